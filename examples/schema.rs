@@ -1,19 +1,119 @@
 use std::env::current_dir;
 use std::fs::create_dir_all;
 
-use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
+use cosmwasm_schema::{export_schema, schema_for, write_api};
 
-use moni::msg::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
+use moni::msg::{
+    ActivePhaseResponse, ActiveTierResponse, AiMetadataResponse, AllowlistStatusResponse,
+    ArchivedRoundsResponse, AuctionInfo, AuctionsResponse, BlocklistResponse, BlocklistedResponse,
+    BurnedResponse, CanMintResponse, ClaimsResponse, CollectionInfoResponse, CollectionResponse,
+    CollectionStatsResponse, CollectionsResponse, ConfigResponse, ContractInfoResponse,
+    CurrentPriceResponse, Cw20HookMsg, Cw721HookMsg, DepositResponse, DropScheduleResponse,
+    DropsResponse, EscrowResponse, ExecuteMsg, ExpiredTokensResponse, GiftInfoResponse,
+    HolderSnapshotResponse, InstantiateData, InstantiateMsg, ListingInfo, ListingsResponse,
+    MigrateMsg, MintAck, MintCreditsResponse, MintGateResponse, MintHooksResponse, MintPacket,
+    MintResponse, MintsOfResponse, MintsResponse, NftDetailsResponse, NonceUsedResponse,
+    OperatorsResponse, OracleQueryMsg, PaymentTokensResponse, PendingMintResponse,
+    PendingOwnerResponse, PendingPaymentTokenResponse, PermissionsResponse, PhaseAllowlistResponse,
+    PhasesResponse, PriceQuoteResponse, PricesResponse, ProvenanceResponse, QueryMsg,
+    RaffleResponse, RaffleTicketsResponse, ReceiptInfo, ReceiptsResponse, ReferrerResponse,
+    ReferrersResponse, RelayerResponse, RentalInfo, RentalsResponse, RevealedResponse,
+    RoyaltyInfoResponse, ScheduleResponse, StakeInfo, StakesResponse, SudoMsg, SupplyInfoResponse,
+    TokenCodeIdsResponse, TokenExpiryResponse, TokenPricesResponse, TraitTableResponse,
+    VestingResponse, WaitlistResponse,
+};
 
 fn main() {
+    // Combined `moni.json` and `raw/` schemas for ts-codegen. Clears the old schema files,
+    // so it has to run before the individual exports below.
+    write_api! {
+        instantiate: InstantiateMsg,
+        execute: ExecuteMsg,
+        query: QueryMsg,
+        migrate: MigrateMsg,
+        sudo: SudoMsg,
+    }
+
     let mut out_dir = current_dir().unwrap();
     out_dir.push("schema");
     create_dir_all(&out_dir).unwrap();
-    remove_schemas(&out_dir).unwrap();
 
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
-    export_schema(&schema_for!(ExecuteMsg), &out_dir);
+    export_schema(&schema_for!(Cw20HookMsg), &out_dir);
+    export_schema(&schema_for!(Cw721HookMsg), &out_dir);
+    export_schema(&schema_for!(SudoMsg), &out_dir);
+    export_schema(&schema_for!(OracleQueryMsg), &out_dir);
     export_schema(&schema_for!(ConfigResponse), &out_dir);
-}
\ No newline at end of file
+    export_schema(&schema_for!(PendingOwnerResponse), &out_dir);
+    export_schema(&schema_for!(SupplyInfoResponse), &out_dir);
+    export_schema(&schema_for!(MintsOfResponse), &out_dir);
+    export_schema(&schema_for!(MintsResponse), &out_dir);
+    export_schema(&schema_for!(RoyaltyInfoResponse), &out_dir);
+    export_schema(&schema_for!(CurrentPriceResponse), &out_dir);
+    export_schema(&schema_for!(ActiveTierResponse), &out_dir);
+    export_schema(&schema_for!(PhasesResponse), &out_dir);
+    export_schema(&schema_for!(ActivePhaseResponse), &out_dir);
+    export_schema(&schema_for!(RevealedResponse), &out_dir);
+    export_schema(&schema_for!(ListingInfo), &out_dir);
+    export_schema(&schema_for!(ListingsResponse), &out_dir);
+    export_schema(&schema_for!(PaymentTokensResponse), &out_dir);
+    export_schema(&schema_for!(GiftInfoResponse), &out_dir);
+    export_schema(&schema_for!(CollectionResponse), &out_dir);
+    export_schema(&schema_for!(CollectionsResponse), &out_dir);
+    export_schema(&schema_for!(RentalInfo), &out_dir);
+    export_schema(&schema_for!(RentalsResponse), &out_dir);
+    export_schema(&schema_for!(WaitlistResponse), &out_dir);
+    export_schema(&schema_for!(PricesResponse), &out_dir);
+    export_schema(&schema_for!(ClaimsResponse), &out_dir);
+    export_schema(&schema_for!(PhaseAllowlistResponse), &out_dir);
+    export_schema(&schema_for!(AllowlistStatusResponse), &out_dir);
+    export_schema(&schema_for!(MintGateResponse), &out_dir);
+    export_schema(&schema_for!(StakeInfo), &out_dir);
+    export_schema(&schema_for!(StakesResponse), &out_dir);
+    export_schema(&schema_for!(VestingResponse), &out_dir);
+    export_schema(&schema_for!(EscrowResponse), &out_dir);
+    export_schema(&schema_for!(TraitTableResponse), &out_dir);
+    export_schema(&schema_for!(PendingMintResponse), &out_dir);
+    export_schema(&schema_for!(CollectionStatsResponse), &out_dir);
+    export_schema(&schema_for!(MintHooksResponse), &out_dir);
+    export_schema(&schema_for!(OperatorsResponse), &out_dir);
+    export_schema(&schema_for!(PermissionsResponse), &out_dir);
+    export_schema(&schema_for!(PendingPaymentTokenResponse), &out_dir);
+    export_schema(&schema_for!(BlocklistedResponse), &out_dir);
+    export_schema(&schema_for!(BlocklistResponse), &out_dir);
+    export_schema(&schema_for!(PriceQuoteResponse), &out_dir);
+    export_schema(&schema_for!(CanMintResponse), &out_dir);
+    export_schema(&schema_for!(DropsResponse), &out_dir);
+    export_schema(&schema_for!(BurnedResponse), &out_dir);
+    export_schema(&schema_for!(RelayerResponse), &out_dir);
+    export_schema(&schema_for!(TokenExpiryResponse), &out_dir);
+    export_schema(&schema_for!(ExpiredTokensResponse), &out_dir);
+    export_schema(&schema_for!(ContractInfoResponse), &out_dir);
+    export_schema(&schema_for!(TokenCodeIdsResponse), &out_dir);
+    export_schema(&schema_for!(ReceiptInfo), &out_dir);
+    export_schema(&schema_for!(ReceiptsResponse), &out_dir);
+    export_schema(&schema_for!(RaffleResponse), &out_dir);
+    export_schema(&schema_for!(RaffleTicketsResponse), &out_dir);
+    export_schema(&schema_for!(AuctionInfo), &out_dir);
+    export_schema(&schema_for!(AuctionsResponse), &out_dir);
+    export_schema(&schema_for!(DropScheduleResponse), &out_dir);
+    export_schema(&schema_for!(TokenPricesResponse), &out_dir);
+    export_schema(&schema_for!(CollectionInfoResponse), &out_dir);
+    export_schema(&schema_for!(MintResponse), &out_dir);
+    export_schema(&schema_for!(InstantiateData), &out_dir);
+    export_schema(&schema_for!(NftDetailsResponse), &out_dir);
+    export_schema(&schema_for!(AiMetadataResponse), &out_dir);
+    export_schema(&schema_for!(HolderSnapshotResponse), &out_dir);
+    export_schema(&schema_for!(NonceUsedResponse), &out_dir);
+    export_schema(&schema_for!(ScheduleResponse), &out_dir);
+    export_schema(&schema_for!(ArchivedRoundsResponse), &out_dir);
+    export_schema(&schema_for!(MintCreditsResponse), &out_dir);
+    export_schema(&schema_for!(DepositResponse), &out_dir);
+    export_schema(&schema_for!(ProvenanceResponse), &out_dir);
+    export_schema(&schema_for!(MintPacket), &out_dir);
+    export_schema(&schema_for!(MintAck), &out_dir);
+    export_schema(&schema_for!(ReferrerResponse), &out_dir);
+    export_schema(&schema_for!(ReferrersResponse), &out_dir);
+}
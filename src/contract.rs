@@ -1,24 +1,112 @@
-use std::marker::PhantomData;
+use std::collections::BTreeSet;
 
+#[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult, Uint128, SubMsg, WasmMsg, to_binary, ReplyOn, Addr, Empty};
-use cw2::set_contract_version;
+use cosmwasm_std::{
+    from_binary, from_slice, to_binary, to_vec, Addr, Api, Attribute, BankMsg, Binary, Coin,
+    ContractResult, CosmosMsg, Decimal, Deps, DepsMut, Empty, Env, Event, HexBinary, IbcTimeout,
+    MessageInfo, Order, QueryRequest, Reply, ReplyOn, Response, StdError, StdResult, Storage,
+    SubMsg, SubMsgResult, SystemResult, Timestamp, Uint128, WasmMsg, WasmQuery,
+};
+use cw2::{get_contract_version, set_contract_version, ContractVersion};
+use semver::Version;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 
-use cw721_base::{Extension, InstantiateMsg as Cw721InstantiateMsg, MintMsg, ExecuteMsg as Cw721ExecuteMsg};
+use cw721_base::{
+    ExecuteMsg as Cw721ExecuteMsg, Extension, InstantiateMsg as Cw721InstantiateMsg, MintMsg,
+};
 
-use cw721_base::helpers::Cw721Contract;
+use cw721::{
+    AllNftInfoResponse, ContractInfoResponse, Cw721QueryMsg, Cw721ReceiveMsg, TokensResponse,
+};
 
-use cw_utils::parse_reply_instantiate_data;
+use cw_storage_plus::{Bound, Map};
+use cw_utils::{parse_reply_instantiate_data, Expiration};
 
 use crate::error::ContractError;
-use crate::msg::{ConfigResponse, Cw20ReceiveMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
-use crate::state::{Config, CONFIG};
+use crate::ibc::settle_ibc_mint;
+use crate::msg::{
+    ActivePhaseResponse, ActiveTierResponse, AdminPayload, AiMetadataResponse, AiOracleExecuteMsg,
+    AiOracleMsg, AllowlistEntryMsg, AllowlistStatusResponse, ArchivedRoundInfo,
+    ArchivedRoundsResponse, AuctionInfo, AuctionsResponse, BlocklistResponse, BlocklistedResponse,
+    BurnedResponse, CanMintResponse, ClaimsResponse, CollectionInfoMsg, CollectionInfoResponse,
+    CollectionResponse, CollectionRoyaltyInfo, CollectionStatsResponse, CollectionsResponse,
+    ConfigResponse, CreatorMsg, CurrencyPrice, CurrentPriceResponse, Cw20BalanceResponse,
+    Cw20ExecuteMsg, Cw20HookMsg, Cw20QueryMsg, Cw20ReceiveMsg, Cw20TokenInfoResponse,
+    Cw4GroupExecuteMsg, Cw4Member, Cw4MemberResponse, Cw4QueryMsg, Cw721HookMsg,
+    Cw721OwnershipAction, Cw721OwnershipMsg, Cw721OwnershipQueryMsg, Cw721OwnershipResponse,
+    Cw721UriExtensionMsg, DepositResponse, DropInfo, DropScheduleResponse, DropsResponse,
+    EscrowResponse, ExecuteMsg, ExpiredTokensResponse, GiftInfoResponse, HolderSnapshotResponse,
+    Ics721BridgeMsg, Ics721OutgoingMsg, InstantiateData, InstantiateMsg, ListingInfo,
+    ListingsResponse, MembershipMsg, MerkleProof, MigrateMsg, MintBlocker, MintCreditsResponse,
+    MintGateMsg, MintGateResponse, MintHookMsg, MintHooksResponse, MintInfo, MintIntent,
+    MintOptions, MintResponse, MintsOfResponse, MintsResponse, NftDetailsResponse, NoisCallback,
+    NoisProxyExecuteMsg, NonTransferableInstantiateMsg, NonceUsedResponse, OperatorInfo,
+    OperatorsResponse, OraclePriceResponse, OracleQueryMsg, PartnerDiscountMsg, PaymentSplitMsg,
+    PaymentTokenInfo, PaymentTokensResponse, PendingMintResponse, PendingOwnerResponse,
+    PendingPaymentTokenResponse, PermissionsResponse, PhaseAllowlistEntryMsg, PhaseAllowlistInfo,
+    PhaseAllowlistResponse, PhaseInfo, PhasesResponse, PriceOracleMsg, PriceQuoteResponse,
+    PricesResponse, ProtocolFeeMsg, ProvenanceResponse, QueryMsg, RaffleResponse,
+    RaffleTicketsResponse, RandomnessMsg, ReceiptInfo, ReceiptsResponse, ReferrerResponse,
+    ReferrersResponse, RelayerResponse, RentalInfo, RentalsResponse, RevealedResponse,
+    RoyaltyInfoResponse, RoyaltyMsg, SaleStatus, ScheduleResponse, SignedAdminMsg,
+    SignedMintPermit, SimulateSwapOperationsResponse, SnapshotHolder, SortOrder, StakeInfo,
+    StakesResponse, SudoMsg, SupplyInfoResponse, SwapOperation, SwapRouteMsg, SwapRouterMsg,
+    SwapRouterQueryMsg, TokenCodeIdsResponse, TokenExpiryResponse, TokenPriceInfo,
+    TokenPricesResponse, TradeInMsg, TraitTableResponse, VestingMsg, VestingResponse, Voucher,
+    WaitlistInfo, WaitlistResponse,
+};
+use crate::pagination::{clamp_limit, range_bounds};
+use crate::state::{
+    mints, AiMetadataStatus, AiOracle, AllowlistEntry, ArchivedRound, Auction, AuctionBid,
+    BlockMints, Collection, CollectionInfo, Config, ConfigV0_2, Creator, CreatorClaim, Currency,
+    Cw721Version, DropRound, EscrowedPayment, Gift, HolderSnapshot, Ics721Bridge, Listing,
+    Membership, Metadata, MintCommitment, MintGate, MintRecord, OperatorPerms, PartnerDiscount,
+    PaymentSplit, PendingMint, PendingOwner, PendingPaymentToken, Permission, Phase,
+    PhaseAllowlistEntry, PriceCurve, PriceOracle, PriceTier, ProtocolFee, Raffle, Randomness,
+    Receipt, Rental, Royalty, Stake, Staking, SwapRoute, TokenIdTemplate, TokenUriMode, TradeIn,
+    Trait, TraitCategory, UpgradeRule, Vesting, WaitlistEntry, AI_METADATA, ALLOWLIST,
+    ARCHIVED_ROUNDS, AUCTIONS, BLOCKLIST, BLOCK_MINTS, BURNED, CLAIMS_OWED, COLLECTIONS,
+    COLLECTION_COUNT, COLLECTION_INFO, CONFIG, CONFIG_V0_1, CONFIG_V0_2, CREATOR_CLAIMS, DEPOSITS,
+    DROP_ROUNDS, DROP_ROUND_COUNT, DROP_ROUND_MINTS, ESCROWED_PAYMENTS, ESCROWED_PAYMENTS_V0_2,
+    ESCROW_HELD, GIFTS, GIFTS_V0_2, HOLDER_SNAPSHOT, INTENT_KEYS, LATEST_RANDOMNESS, LISTINGS,
+    MAX_MEMO_LENGTH, MAX_METADATA_ATTRIBUTES, MAX_METADATA_FIELD_LENGTH, MAX_MINT_HOOKS,
+    MAX_ROYALTY_BPS, MINTS_PER_ADDRESS, MINTS_V0_2, MINT_COMMITMENTS, MINT_CREDITS, MINT_HOOKS,
+    OPERATORS, PAYMENT_TOKENS, PENDING_MINTS, PENDING_OWNER, PENDING_PAYMENT_TOKEN, PHASES,
+    PHASE_ALLOWLISTS, PHASE_CLAIMED, PHASE_MINTS, PLEDGES, RAFFLE, RAFFLE_TICKETS,
+    RANDOMNESS_JOB_COUNT, RECEIPTS, REDEEMED_VOUCHERS, REFERRALS, RELAYERS, RENTALS,
+    REVOKED_TOKENS, ROYALTY, SHUFFLED_IDS, SHUFFLED_IDS_V0_2, SNAPSHOT_HOLDERS, STAKES,
+    TOKEN_CODE_IDS, TOKEN_EXPIRY, TOKEN_METADATA, TOKEN_METADATA_V0_2, TOKEN_PRICES,
+    TRADE_IN_DEPOSITS, TRAIT_TABLE, UPGRADE_DEPOSITS, USED_ADMIN_NONCES, USED_INTENTS,
+    USED_PERMITS, VESTING_WITHDRAWN, WAITLIST, WAITLIST_NEXT,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:aura-nft";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 const INSTANTIATE_TOKEN_REPLY_ID: u64 = 1;
+/// Replies for factory collections carry `INSTANTIATE_COLLECTION_REPLY_ID + collection_id`.
+const INSTANTIATE_COLLECTION_REPLY_ID: u64 = 1_000;
+/// Child mints reply with `MINT_REPLY_ID + token index`, above every collection id.
+const MINT_REPLY_ID: u64 = 1 << 40;
+
+/// Keeps a single batch mint transaction comfortably inside block gas limits.
+pub(crate) const MAX_BATCH_MINTS: usize = 50;
+
+const DEFAULT_CW721_LABEL: &str = "Instantiate fixed price NFT contract";
+
+/// Typed lifecycle events for indexers; the chain emits them as `wasm-<type>`.
+const MINT_EVENT: &str = "moni/mint";
+const PHASE_CHANGE_EVENT: &str = "moni/phase_change";
+const CONFIG_UPDATE_EVENT: &str = "moni/config_update";
+const WITHDRAW_EVENT: &str = "moni/withdraw";
+
+/// Delay between `UpdatePaymentToken` and the switch, so in-flight sends of the old token
+/// still mint and buyers can see the change coming.
+const PAYMENT_TOKEN_TIMELOCK_SECONDS: u64 = 2 * 24 * 60 * 60;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -33,12 +121,142 @@ pub fn instantiate(
         return Err(ContractError::InvalidUnitPrice {});
     }
 
-    if msg.max_tokens == 0 {
+    if let Some(denom) = &msg.native_denom {
+        validate_native_denom(denom)?;
+    }
+
+    let token_id_template = msg
+        .token_id_template
+        .as_deref()
+        .map(parse_token_id_template)
+        .transpose()?;
+    let vesting = msg
+        .vesting
+        .map(|vesting| validate_vesting(&env, vesting))
+        .transpose()?;
+    let mint_gate = msg
+        .mint_gate
+        .as_ref()
+        .map(|gate| validate_mint_gate(deps.api, gate))
+        .transpose()?;
+    let membership = msg
+        .membership
+        .as_ref()
+        .map(|membership| validate_membership(deps.api, membership))
+        .transpose()?;
+    let ai_oracle = msg
+        .ai_oracle
+        .map(|oracle| validate_ai_oracle(deps.api, oracle))
+        .transpose()?;
+    let partner_discount = msg
+        .partner_discount
+        .as_ref()
+        .map(|discount| validate_partner_discount(deps.api, discount))
+        .transpose()?;
+    let protocol_fee = msg
+        .protocol_fee
+        .as_ref()
+        .map(|fee| validate_protocol_fee(deps.api, fee))
+        .transpose()?;
+    let creators = validate_creators(deps.api, msg.creators.as_deref().unwrap_or_default())?;
+
+    let open_edition = msg.open_edition.unwrap_or(false);
+    if open_edition != (msg.max_tokens == 0) {
         return Err(ContractError::InvalidMaxTokens {});
     }
 
-    let config = Config {
-        cw721_address: None,
+    // Shuffling draws from a fixed id range.
+    if open_edition && msg.shuffle_token_ids.unwrap_or(false) {
+        return Err(ContractError::InvalidMaxTokens {});
+    }
+
+    // The reply is what links the child cw721, so it must at least fire on success.
+    if matches!(
+        msg.cw721_reply_on,
+        Some(ReplyOn::Never) | Some(ReplyOn::Error)
+    ) {
+        return Err(ContractError::InvalidReplyOn {});
+    }
+
+    if msg.referral_bps.unwrap_or(0) > MAX_ROYALTY_BPS {
+        return Err(ContractError::InvalidReferralBps {});
+    }
+
+    if msg.refund_bps.unwrap_or(0) > MAX_ROYALTY_BPS {
+        return Err(ContractError::InvalidRefundBps {});
+    }
+
+    if msg.market_fee_bps.unwrap_or(0) > MAX_ROYALTY_BPS {
+        return Err(ContractError::InvalidMarketFee {});
+    }
+
+    if msg.metadata_code_id.is_some()
+        && (msg.soulbound == Some(true) || msg.onchain_metadata == Some(false))
+    {
+        return Err(ContractError::ConflictingCw721Variant {});
+    }
+
+    if msg.commit_reveal_blocks == Some(0) {
+        return Err(ContractError::InvalidCommitRevealBlocks {});
+    }
+
+    if msg
+        .burn_bps
+        .is_some_and(|bps| bps == 0 || bps > MAX_ROYALTY_BPS)
+    {
+        return Err(ContractError::InvalidBurnBps {});
+    }
+
+    if msg
+        .renewal
+        .as_ref()
+        .is_some_and(|renewal| renewal.period == 0)
+    {
+        return Err(ContractError::InvalidRenewal {});
+    }
+
+    if let Some(crowdfund) = &msg.crowdfund {
+        if crowdfund.soft_cap == 0
+            || (!open_edition && crowdfund.soft_cap > msg.max_tokens)
+            || crowdfund.deadline <= env.block.time
+        {
+            return Err(ContractError::InvalidCrowdfund {});
+        }
+    }
+
+    if !open_edition && msg.reserved_supply.unwrap_or(0) > msg.max_tokens {
+        return Err(ContractError::InvalidReservedSupply {});
+    }
+
+    if let (Some(start), Some(end)) = (msg.start_time, msg.end_time) {
+        if start >= end {
+            return Err(ContractError::InvalidMintWindow {});
+        }
+    }
+
+    if let Some(presale) = &msg.presale {
+        if msg
+            .start_time
+            .is_some_and(|start| presale.end_time <= start)
+            || msg.end_time.is_some_and(|end| presale.end_time > end)
+            || presale.price.is_some_and(|price| price.is_zero())
+        {
+            return Err(ContractError::InvalidPresale {});
+        }
+    }
+
+    let existing_cw721 = msg
+        .cw721_address
+        .map(|addr| validate_cw721(deps.as_ref(), &addr))
+        .transpose()?;
+    let cw721_version = match &existing_cw721 {
+        Some(cw721) => detect_cw721_version(deps.as_ref(), cw721),
+        None => msg.cw721_version.unwrap_or_default(),
+    };
+
+    let token_info = query_cw20_info(deps.as_ref(), &msg.cw20_address)?;
+    let mut config = Config {
+        cw721_address: existing_cw721.clone(),
         cw20_address: msg.cw20_address,
         unit_price: msg.unit_price,
         max_tokens: msg.max_tokens,
@@ -48,36 +266,220 @@ pub fn instantiate(
         token_uri: msg.token_uri.clone(),
         extension: msg.extension.clone(),
         unused_token_id: 0,
+        treasury: msg
+            .treasury
+            .map(|treasury| deps.api.addr_validate(&treasury))
+            .transpose()?,
+        paused: false,
+        start_time: msg.start_time,
+        end_time: msg.end_time,
+        token_uri_mode: msg.token_uri_mode.unwrap_or_default(),
+        shuffle_token_ids: msg.shuffle_token_ids.unwrap_or(false),
+        reserved_supply: msg.reserved_supply,
+        reserved_minted: 0,
+        strict_payment: msg.strict_payment.unwrap_or(false),
+        mint_limit_per_address: msg.mint_limit_per_address,
+        token_code_id: msg.metadata_code_id.unwrap_or(msg.token_code_id),
+        cw721_instantiate_error: None,
+        price_curve: msg.price_curve,
+        price_tiers: vec![],
+        presale: msg.presale,
+        referral_bps: msg.referral_bps,
+        payment_splits: vec![],
+        onchain_metadata: msg.metadata_code_id.is_some() || msg.onchain_metadata.unwrap_or(false),
+        placeholder_uri: msg.placeholder_uri,
+        revealed: false,
+        frozen: false,
+        refund_bps: msg.refund_bps,
+        total_refunded: Uint128::zero(),
+        upgrade_rule: None,
+        upgrades_minted: 0,
+        market_fee_bps: msg.market_fee_bps.unwrap_or(0),
+        crowdfund: msg.crowdfund,
+        crowdfund_sold: 0,
+        crowdfund_settled: false,
+        price_oracle: None,
+        voucher_signer: msg.voucher_signer,
+        max_mints_per_tx: msg.max_mints_per_tx,
+        max_mints_per_block: msg.max_mints_per_block,
+        cw721_salt: msg.cw721_salt,
+        cw721_gas_limit: msg.cw721_gas_limit,
+        cw721_reply_on: msg.cw721_reply_on.unwrap_or(ReplyOn::Always),
+        ibc_counterparty_port: None,
+        ibc_connection_id: None,
+        ibc_counterparty_channel: None,
+        ics721_bridge: None,
+        soulbound: msg.soulbound.unwrap_or(false),
+        cw721_admin: msg
+            .cw721_admin
+            .map(|admin| deps.api.addr_validate(&admin))
+            .transpose()?,
+        cw721_label: msg
+            .cw721_label
+            .unwrap_or_else(|| String::from(DEFAULT_CW721_LABEL)),
+        cw721_funds: msg.cw721_funds.unwrap_or_default(),
+        closed: false,
+        custom_metadata: false,
+        open_edition: msg.open_edition.unwrap_or(false),
+        final_supply: None,
+        supply_governor: msg
+            .supply_governor
+            .map(|governor| deps.api.addr_validate(&governor))
+            .transpose()?,
+        waitlist: msg.waitlist.unwrap_or(false),
+        waitlist_escrow: Uint128::zero(),
+        native_denom: msg.native_denom,
+        native_price: None,
+        creators,
+        allowlist_count: 0,
+        merkle_root: None,
+        merkle_version: 0,
+        partner_discount,
+        mint_gate,
+        staking: None,
+        staking_pool: Uint128::zero(),
+        vesting,
+        reveal_escrow: msg.reveal_escrow.unwrap_or(false),
+        token_id_template,
+        randomness: None,
+        pending_mints: 0,
+        unique_minters: 0,
+        retired: false,
+        renounced: false,
+        failed_mints: 0,
+        drop_reserved: 0,
+        protocol_fee,
+        burn_bps: msg.burn_bps,
+        cw20_symbol: Some(token_info.symbol),
+        cw20_decimals: Some(token_info.decimals),
+        commit_reveal_blocks: msg.commit_reveal_blocks,
+        gatekeeper: msg.gatekeeper,
+        owner_key: None,
+        renewal: msg.renewal,
+        trade_in: None,
+        raffle_reserved: 0,
+        raffle_escrow: Uint128::zero(),
+        auction_escrow: Uint128::zero(),
+        cw721_version,
+        membership,
+        swap_route: None,
+        ai_oracle,
+        allowed_uri_prefixes: vec![],
+        deposit_escrow: Uint128::zero(),
     };
+    if config.reveal_escrow && config.placeholder_uri.is_none() {
+        return Err(ContractError::RevealNotConfigured {});
+    }
+
+    if let Some(oracle) = msg.price_oracle {
+        config.price_oracle = Some(validate_price_oracle(deps.as_ref(), oracle)?);
+    }
 
     CONFIG.save(deps.storage, &config)?;
 
-    let sub_msg: Vec<SubMsg> = vec![SubMsg {
-        msg: WasmMsg::Instantiate {
-            code_id: msg.token_code_id,
-            msg: to_binary(&Cw721InstantiateMsg {
-                name: msg.name.clone(),
-                symbol: msg.symbol,
-                minter: env.contract.address.to_string(),
-            })?,
-            funds: vec![],
-            admin: None,
-            label: String::from("Instantiate fixed price NFT contract"),
-        }
-        .into(),
-        id: INSTANTIATE_TOKEN_REPLY_ID,
-        gas_limit: None,
-        reply_on: ReplyOn::Success,
-    }];
+    if let Some(royalty) = msg.royalty {
+        ROYALTY.save(deps.storage, &validate_royalty(deps.api, royalty)?)?;
+    }
+    if let Some(info) = msg.collection_info {
+        let info = validate_collection_info(deps.api, &config, info)?;
+        COLLECTION_INFO.save(deps.storage, &info)?;
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "instantiate")
+        .add_attribute("owner", &config.owner)
+        .add_attribute("cw20_address", &config.cw20_address)
+        .add_attribute("price", config.unit_price)
+        .add_attribute("max_tokens", config.max_tokens.to_string());
+
+    if let Some(cw721) = existing_cw721 {
+        return Ok(res
+            .set_data(to_binary(&InstantiateData {
+                cw721_address: cw721.clone(),
+            })?)
+            .add_attribute("cw721_address", cw721));
+    }
+
+    Ok(res.add_submessage(instantiate_cw721_submsg(&env, &config)?))
+}
+
+/// Checks that `addr` answers cw721 `ContractInfo` queries.
+fn validate_cw721(deps: Deps, addr: &str) -> Result<Addr, ContractError> {
+    let addr = deps.api.addr_validate(addr)?;
+    let _: ContractInfoResponse = deps
+        .querier
+        .query_wasm_smart(&addr, &Cw721QueryMsg::ContractInfo {})
+        .map_err(|_| ContractError::InvalidCw721 {})?;
+    Ok(addr)
+}
+
+/// Compatibility shim for collections this contract did not instantiate: only cw721-base 0.16
+/// and later answer cw-ownable's `Ownership` query.
+fn detect_cw721_version(deps: Deps, addr: &Addr) -> Cw721Version {
+    let ownership: StdResult<Cw721OwnershipResponse> = deps
+        .querier
+        .query_wasm_smart(addr, &Cw721OwnershipQueryMsg::Ownership {});
+    match ownership {
+        Ok(_) => Cw721Version::Ownable,
+        Err(_) => Cw721Version::Legacy,
+    }
+}
 
-    Ok(Response::new().add_submessages(sub_msg))
+fn instantiate_cw721_submsg(env: &Env, config: &Config) -> StdResult<SubMsg> {
+    let msg = if config.soulbound {
+        to_binary(&NonTransferableInstantiateMsg {
+            admin: Some(env.contract.address.to_string()),
+            name: config.name.clone(),
+            symbol: config.symbol.clone(),
+            minter: env.contract.address.to_string(),
+        })?
+    } else {
+        to_binary(&Cw721InstantiateMsg {
+            name: config.name.clone(),
+            symbol: config.symbol.clone(),
+            minter: env.contract.address.to_string(),
+        })?
+    };
+    let admin = config.cw721_admin.as_ref().map(Addr::to_string);
+    let label = config.cw721_label.clone();
+    let funds = config.cw721_funds.clone();
+    let msg = match &config.cw721_salt {
+        Some(salt) => WasmMsg::Instantiate2 {
+            admin,
+            code_id: config.token_code_id,
+            label,
+            msg,
+            funds,
+            salt: salt.clone(),
+        },
+        None => WasmMsg::Instantiate {
+            admin,
+            code_id: config.token_code_id,
+            msg,
+            funds,
+            label,
+        },
+    };
+    Ok(SubMsg {
+        msg: msg.into(),
+        id: INSTANTIATE_TOKEN_REPLY_ID,
+        gas_limit: config.cw721_gas_limit,
+        reply_on: config.cw721_reply_on.clone(),
+    })
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    if msg.id >= MINT_REPLY_ID {
+        return reply_mint(deps, msg.id - MINT_REPLY_ID, msg.result);
+    }
+    if msg.id >= INSTANTIATE_COLLECTION_REPLY_ID {
+        return reply_collection(deps, msg);
+    }
+
     let mut config: Config = CONFIG.load(deps.storage)?;
 
-    if config.cw721_address != None {
+    if config.cw721_address.is_some() {
         return Err(ContractError::Cw721AlreadyLinked {});
     }
 
@@ -85,25 +487,412 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
         return Err(ContractError::InvalidTokenReplyId {});
     }
 
+    if let SubMsgResult::Err(err) = &msg.result {
+        config.cw721_instantiate_error = Some(err.clone());
+        CONFIG.save(deps.storage, &config)?;
+        return Ok(Response::new()
+            .add_attribute("action", "cw721_instantiate_failed")
+            .add_attribute("error", err));
+    }
+
     let reply = parse_reply_instantiate_data(msg).unwrap();
-    config.cw721_address = Addr::unchecked(reply.contract_address).into();
+    let cw721 = Addr::unchecked(reply.contract_address);
+    config.cw721_address = Some(cw721.clone());
+    config.cw721_instantiate_error = None;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .set_data(to_binary(&InstantiateData {
+            cw721_address: cw721.clone(),
+        })?)
+        .add_attribute("action", "link_cw721")
+        .add_attribute("cw721_address", cw721)
+        .add_attribute("cw721_code_id", config.token_code_id.to_string()))
+}
+
+/// Settles a child mint. A confirmed mint notifies the mint hooks. A rejected one (e.g. a
+/// duplicate id after a migration) is rolled back, crowdfund pledge included, and the buyer
+/// gets back what this contract kept of their payment for that token, while the rest of the
+/// batch goes through.
+fn reply_mint(
+    deps: DepsMut,
+    token_id: u64,
+    result: SubMsgResult,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let record = mints()
+        .may_load(deps.storage, token_id)?
+        .ok_or(ContractError::InvalidTokenReplyId {})?;
+    let err = match result {
+        SubMsgResult::Ok(_) => {
+            let ack = settle_ibc_mint(deps.storage, &config, token_id, None)?;
+            let hooks = mint_hook_msgs(
+                deps.storage,
+                config.token_id(token_id),
+                record.buyer.as_str(),
+                record.price,
+            )?;
+            let mut res = Response::new().add_messages(hooks);
+            if let Some(membership) = &config.membership {
+                res = res.add_message(membership_msg(deps.storage, membership, &record.buyer)?);
+            }
+            if let Some(oracle) = &config.ai_oracle {
+                AI_METADATA.save(deps.storage, token_id, &AiMetadataStatus::Pending)?;
+                res = res.add_message(WasmMsg::Execute {
+                    contract_addr: oracle.contract.to_string(),
+                    msg: to_binary(&AiOracleExecuteMsg::Request {
+                        service: oracle.service.clone(),
+                        input: Some(config.token_id(token_id)),
+                        threshold: oracle.threshold,
+                    })?,
+                    funds: vec![],
+                });
+            }
+            if let Some(ack) = ack {
+                res = res.set_data(ack);
+            }
+            return Ok(res);
+        }
+        SubMsgResult::Err(err) => err,
+    };
+    let ack = settle_ibc_mint(deps.storage, &config, token_id, Some(&err))?;
+    mints().remove(deps.storage, token_id)?;
+    TOKEN_EXPIRY.remove(deps.storage, token_id);
+    RECEIPTS.remove(deps.storage, token_id);
+    config.failed_mints += 1;
+    if let Some(mut pledge) = PLEDGES.may_load(deps.storage, &record.buyer)? {
+        if let Some(index) = pledge.token_ids.iter().position(|id| *id == token_id) {
+            pledge.token_ids.remove(index);
+            pledge.paid = pledge.paid.saturating_sub(record.price);
+            config.crowdfund_sold -= 1;
+            if pledge.token_ids.is_empty() {
+                PLEDGES.remove(deps.storage, &record.buyer);
+            } else {
+                PLEDGES.save(deps.storage, &record.buyer, &pledge)?;
+            }
+        }
+    }
+    let bought = MINTS_PER_ADDRESS
+        .may_load(deps.storage, &record.buyer)?
+        .unwrap_or_default()
+        .saturating_sub(1);
+    MINTS_PER_ADDRESS.save(deps.storage, &record.buyer, &bought)?;
+    if bought == 0 {
+        config.unique_minters = config.unique_minters.saturating_sub(1);
+    }
     CONFIG.save(deps.storage, &config)?;
+    let currency = record.currency(&config.cw20_address);
+    if let Some(escrow) = ESCROWED_PAYMENTS.may_load(deps.storage, token_id)? {
+        ESCROWED_PAYMENTS.remove(deps.storage, token_id);
+        ESCROW_HELD.update(deps.storage, &escrow.currency.to_string(), |held| {
+            StdResult::Ok(held.unwrap_or_default().checked_sub(escrow.amount)?)
+        })?;
+    }
+
+    let mut res = Response::new()
+        .add_attribute("action", "mint_failed")
+        .add_attribute("token_id", config.token_id(token_id))
+        .add_attribute("buyer", &record.buyer)
+        .add_attribute("error", err);
+    let refund = record.price - record.paid_out;
+    if !refund.is_zero() {
+        res = res
+            .add_message(transfer_msg(&currency, record.buyer.as_str(), refund)?)
+            .add_attribute("refund", refund);
+    }
+    if let Some(ack) = ack {
+        res = res.set_data(ack);
+    }
+    Ok(res)
+}
+
+fn reply_collection(deps: DepsMut, msg: Reply) -> Result<Response, ContractError> {
+    let collection_id = u32::try_from(msg.id - INSTANTIATE_COLLECTION_REPLY_ID)
+        .map_err(|_| ContractError::InvalidTokenReplyId {})?;
+    let mut collection = COLLECTIONS
+        .may_load(deps.storage, collection_id)?
+        .ok_or(ContractError::InvalidTokenReplyId {})?;
+    if collection.cw721_address.is_some() {
+        return Err(ContractError::Cw721AlreadyLinked {});
+    }
 
-    Ok(Response::new())
+    let reply =
+        parse_reply_instantiate_data(msg).map_err(|err| StdError::generic_err(err.to_string()))?;
+    let cw721 = Addr::unchecked(reply.contract_address);
+    collection.cw721_address = Some(cw721.clone());
+    COLLECTIONS.save(deps.storage, collection_id, &collection)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "link_collection_cw721")
+        .add_attribute("collection_id", collection_id.to_string())
+        .add_attribute("cw721_address", cw721))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
-    match msg {
-        //No migrate implemented
+pub fn migrate(mut deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::CannotMigrate {
+            previous_contract: stored.contract,
+        });
+    }
+
+    let stored_version: Version =
+        stored
+            .version
+            .parse()
+            .map_err(|_| ContractError::CannotMigrateVersion {
+                previous_version: stored.version.clone(),
+            })?;
+    let current_version: Version = CONTRACT_VERSION.parse().unwrap();
+    if stored_version > current_version {
+        return Err(ContractError::CannotMigrateVersion {
+            previous_version: stored.version,
+        });
+    }
+
+    if stored_version < Version::new(0, 2, 0) {
+        migrate_config_from_v0_1(deps.storage)?;
+    }
+    if stored_version < Version::new(0, 3, 0) {
+        migrate_config_from_v0_2(deps.branch())?;
+        migrate_token_ids_from_v0_2(deps.storage)?;
+    }
+    backfill_mint_indexes(deps.storage)?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+/// Mint records written before the buyer or time index existed are re-saved so they show up
+/// in `TokensByBuyer` and `MintsBetween`. A no-op once both indexes have entries.
+fn backfill_mint_indexes(storage: &mut dyn Storage) -> StdResult<()> {
+    let indexes = mints().idx;
+    let indexed = indexes
+        .buyer
+        .range_raw(storage, None, None, Order::Ascending)
+        .next()
+        .is_some()
+        && indexes
+            .time
+            .range_raw(storage, None, None, Order::Ascending)
+            .next()
+            .is_some();
+    if indexed {
+        return Ok(());
+    }
+    let records = mints()
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (token_id, record) in records {
+        mints().save(storage, token_id, &record)?;
+    }
+    Ok(())
+}
+
+/// 0.3.0 widened token ids and supply counters to u64. JSON values read back as is, but
+/// maps keyed by token id change key encoding and are rewritten.
+fn migrate_token_ids_from_v0_2(storage: &mut dyn Storage) -> StdResult<()> {
+    rekey_token_map(storage, SHUFFLED_IDS_V0_2, SHUFFLED_IDS, |slot| slot as u64)?;
+    let records = MINTS_V0_2
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (token_id, record) in records {
+        MINTS_V0_2.remove(storage, token_id);
+        mints().save(storage, token_id as u64, &record)?;
+    }
+    rekey_token_map(storage, TOKEN_METADATA_V0_2, TOKEN_METADATA, |metadata| {
+        metadata
+    })?;
+    rekey_token_map(storage, GIFTS_V0_2, GIFTS, |gift| gift)?;
+    rekey_token_map(
+        storage,
+        ESCROWED_PAYMENTS_V0_2,
+        ESCROWED_PAYMENTS,
+        |payment| payment,
+    )
+}
+
+fn rekey_token_map<T, U>(
+    storage: &mut dyn Storage,
+    old: Map<u32, T>,
+    new: Map<u64, U>,
+    convert: impl Fn(T) -> U,
+) -> StdResult<()>
+where
+    T: Serialize + DeserializeOwned,
+    U: Serialize + DeserializeOwned,
+{
+    let entries = old
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (token_id, value) in entries {
+        old.remove(storage, token_id);
+        new.save(storage, token_id as u64, &convert(value))?;
     }
+    Ok(())
+}
+
+/// 0.2.0 added an optional treasury to `Config`.
+fn migrate_config_from_v0_1(storage: &mut dyn Storage) -> StdResult<()> {
+    let old = CONFIG_V0_1.load(storage)?;
+    let config = ConfigV0_2 {
+        owner: old.owner,
+        cw20_address: old.cw20_address,
+        cw721_address: old.cw721_address,
+        max_tokens: old.max_tokens,
+        unit_price: old.unit_price,
+        name: old.name,
+        symbol: old.symbol,
+        token_uri: old.token_uri,
+        extension: old.extension,
+        unused_token_id: old.unused_token_id,
+        treasury: None,
+    };
+    CONFIG_V0_2.save(storage, &config)
+}
+
+/// Fills in every field added since 0.2.0 with its instantiate default, and `token_code_id`
+/// with the code of the linked child. A config that already loads in the current shape is
+/// left alone.
+fn migrate_config_from_v0_2(deps: DepsMut) -> StdResult<()> {
+    if CONFIG.load(deps.storage).is_ok() {
+        return Ok(());
+    }
+    let old = CONFIG_V0_2.load(deps.storage)?;
+    // Zero, which `UnknownCodeId` refuses, until a code id is supplied.
+    let token_code_id = match &old.cw721_address {
+        Some(cw721) => deps.querier.query_wasm_contract_info(cw721)?.code_id,
+        None => 0,
+    };
+    let config = Config {
+        owner: old.owner,
+        cw20_address: old.cw20_address,
+        cw721_address: old.cw721_address,
+        max_tokens: old.max_tokens.into(),
+        unit_price: old.unit_price,
+        name: old.name,
+        symbol: old.symbol,
+        token_uri: old.token_uri,
+        extension: old.extension,
+        unused_token_id: old.unused_token_id.into(),
+        treasury: old.treasury,
+        paused: false,
+        start_time: None,
+        end_time: None,
+        token_uri_mode: TokenUriMode::Fixed,
+        shuffle_token_ids: false,
+        reserved_supply: None,
+        reserved_minted: 0,
+        strict_payment: false,
+        mint_limit_per_address: None,
+        token_code_id,
+        cw721_instantiate_error: None,
+        price_curve: None,
+        price_tiers: vec![],
+        presale: None,
+        referral_bps: None,
+        payment_splits: vec![],
+        onchain_metadata: false,
+        placeholder_uri: None,
+        revealed: false,
+        frozen: false,
+        refund_bps: None,
+        total_refunded: Uint128::zero(),
+        upgrade_rule: None,
+        upgrades_minted: 0,
+        market_fee_bps: 0,
+        crowdfund: None,
+        crowdfund_sold: 0,
+        crowdfund_settled: false,
+        price_oracle: None,
+        voucher_signer: None,
+        max_mints_per_tx: None,
+        max_mints_per_block: None,
+        cw721_salt: None,
+        cw721_gas_limit: None,
+        cw721_reply_on: ReplyOn::Always,
+        ibc_counterparty_port: None,
+        ibc_connection_id: None,
+        ibc_counterparty_channel: None,
+        ics721_bridge: None,
+        soulbound: false,
+        cw721_admin: None,
+        cw721_label: String::from(DEFAULT_CW721_LABEL),
+        cw721_funds: vec![],
+        closed: false,
+        custom_metadata: false,
+        open_edition: false,
+        final_supply: None,
+        supply_governor: None,
+        waitlist: false,
+        waitlist_escrow: Uint128::zero(),
+        native_denom: None,
+        native_price: None,
+        creators: vec![],
+        allowlist_count: 0,
+        merkle_root: None,
+        merkle_version: 0,
+        partner_discount: None,
+        mint_gate: None,
+        staking: None,
+        staking_pool: Uint128::zero(),
+        vesting: None,
+        reveal_escrow: false,
+        token_id_template: None,
+        randomness: None,
+        pending_mints: 0,
+        unique_minters: 0,
+        retired: false,
+        renounced: false,
+        failed_mints: 0,
+        drop_reserved: 0,
+        protocol_fee: None,
+        burn_bps: None,
+        cw20_symbol: None,
+        cw20_decimals: None,
+        commit_reveal_blocks: None,
+        gatekeeper: None,
+        owner_key: None,
+        renewal: None,
+        trade_in: None,
+        raffle_reserved: 0,
+        raffle_escrow: Uint128::zero(),
+        auction_escrow: Uint128::zero(),
+        cw721_version: Cw721Version::Legacy,
+        membership: None,
+        swap_route: None,
+        ai_oracle: None,
+        allowed_uri_prefixes: vec![],
+        deposit_escrow: Uint128::zero(),
+    };
+    CONFIG.save(deps.storage, &config)
+}
+
+/// Privileged messages from the chain (e.g. governance) that bypass the owner key.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, _env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let res = match msg {
+        SudoMsg::UpdateUnitPrice { price } => update_unit_price(deps.storage, config, price)?,
+        SudoMsg::Pause {} => set_paused(deps.storage, config, true)?,
+        SudoMsg::Resume {} => set_paused(deps.storage, config, false)?,
+        SudoMsg::UpdateMaxTokens { max_tokens } => {
+            set_max_tokens(deps.storage, config, max_tokens)?
+        }
+    };
+    Ok(res.add_attribute("authority", "sudo"))
 }
 
 /// Handling contract execution
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
@@ -112,173 +901,17126 @@ pub fn execute(
             sender,
             amount,
             msg,
-        }) => execute_receive(deps, info, sender, amount, msg),
+        }) => execute_receive(deps, env, info, sender, amount, msg),
+        ExecuteMsg::UpdateUnitPrice { price } => execute_update_unit_price(deps, info, price),
+        ExecuteMsg::ProposeNewOwner { owner, expiry } => {
+            execute_propose_new_owner(deps, env, info, owner, expiry)
+        }
+        ExecuteMsg::CancelOwnershipProposal {} => execute_cancel_ownership_proposal(deps, info),
+        ExecuteMsg::AcceptOwnership {} => execute_accept_ownership(deps, env, info),
+        ExecuteMsg::Withdraw {
+            amount,
+            recipient,
+            token,
+        } => execute_withdraw(deps, env, info, amount, recipient, token),
+        ExecuteMsg::SweepFunds { asset, recipient } => {
+            execute_sweep_funds(deps, env, info, asset, recipient)
+        }
+        ExecuteMsg::Pause {} => execute_set_paused(deps, info, true),
+        ExecuteMsg::Resume {} => execute_set_paused(deps, info, false),
+        ExecuteMsg::ReserveMint {
+            quantity,
+            recipient,
+        } => execute_reserve_mint(deps, env, info, quantity, recipient),
+        ExecuteMsg::Airdrop { recipients } => execute_airdrop(deps, env, info, recipients),
+        ExecuteMsg::RetryCw721Instantiate { code_id } => {
+            execute_retry_cw721_instantiate(deps, env, info, code_id)
+        }
+        ExecuteMsg::SetCw721Address { address } => execute_set_cw721_address(deps, info, address),
+        ExecuteMsg::MintWithAllowance { quantity } => {
+            execute_mint_with_allowance(deps, env, info, quantity)
+        }
+        ExecuteMsg::SetPriceTiers { tiers } => execute_set_price_tiers(deps, info, tiers),
+        ExecuteMsg::UpdateTokenPrices { set, unset } => {
+            execute_update_token_prices(deps, info, set, unset)
+        }
+        ExecuteMsg::AddToAllowlist { entries } => execute_add_to_allowlist(deps, info, entries),
+        ExecuteMsg::RemoveFromAllowlist { addresses } => {
+            execute_remove_from_allowlist(deps, info, addresses)
+        }
+        ExecuteMsg::SetReferralBps { bps } => execute_set_referral_bps(deps, info, bps),
+        ExecuteMsg::UpdatePaymentSplits { splits } => {
+            execute_update_payment_splits(deps, info, splits)
+        }
+        ExecuteMsg::SetPhase { id, phase } => execute_set_phase(deps, info, id, phase),
+        ExecuteMsg::RemovePhase { id } => execute_remove_phase(deps, info, id),
+        ExecuteMsg::SetTokenMetadata { token_id, metadata } => {
+            execute_set_token_metadata(deps, info, token_id, metadata)
+        }
+        ExecuteMsg::UpdateTokenUri { token_id, uri } => {
+            execute_update_token_uri(deps, env, info, token_id, uri)
+        }
+        ExecuteMsg::Reveal { base_uri } => execute_reveal(deps, info, base_uri),
+        ExecuteMsg::Freeze {} => execute_freeze(deps, info),
+        ExecuteMsg::Shutdown {} => execute_shutdown(deps, env, info),
+        ExecuteMsg::ReceiveNft(msg) => execute_receive_nft(deps, env, info, msg),
+        ExecuteMsg::SetRefundBps { bps } => execute_set_refund_bps(deps, info, bps),
+        ExecuteMsg::SetUpgradeRule { rule } => execute_set_upgrade_rule(deps, info, rule),
+        ExecuteMsg::CancelUpgrade {} => execute_cancel_upgrade(deps, info),
+        ExecuteMsg::CancelListing { token_id } => execute_cancel_listing(deps, info, token_id),
+        ExecuteMsg::SettleCrowdfund {} => execute_settle_crowdfund(deps, info),
+        ExecuteMsg::ClaimPledge {} => execute_claim_pledge(deps, env, info),
+        ExecuteMsg::AddPaymentToken { address, price } => {
+            execute_add_payment_token(deps, info, address, price)
+        }
+        ExecuteMsg::RemovePaymentToken { address } => {
+            execute_remove_payment_token(deps, info, address)
+        }
+        ExecuteMsg::SetPriceOracle { oracle } => execute_set_price_oracle(deps, info, oracle),
+        ExecuteMsg::SetIcs721Bridge { bridge } => execute_set_ics721_bridge(deps, info, bridge),
+        ExecuteMsg::SetVoucherSigner { pubkey } => execute_set_voucher_signer(deps, info, pubkey),
+        ExecuteMsg::CloneCollection {
+            max_tokens,
+            unit_price,
+            token_uri,
+        } => execute_clone_collection(deps, env, info, max_tokens, unit_price, token_uri),
+        ExecuteMsg::CreateCollection {
+            name,
+            symbol,
+            unit_price,
+            max_tokens,
+            token_uri,
+            token_code_id,
+        } => execute_create_collection(
+            deps,
+            env,
+            info,
+            Collection {
+                name,
+                symbol,
+                cw721_address: None,
+                unit_price,
+                max_tokens,
+                minted: 0,
+                token_uri,
+            },
+            token_code_id,
+        ),
+        ExecuteMsg::WithdrawRental { token_id } => {
+            execute_withdraw_rental(deps, env, info, token_id)
+        }
+        ExecuteMsg::SetIbcCounterparty {
+            port_id,
+            connection_id,
+            channel_id,
+        } => execute_set_ibc_counterparty(deps, info, port_id, connection_id, channel_id),
+        ExecuteMsg::SetCustomMetadata { allowed } => {
+            execute_set_custom_metadata(deps, info, allowed)
+        }
+        ExecuteMsg::SetAllowedUriPrefixes { prefixes } => {
+            execute_set_allowed_uri_prefixes(deps, info, prefixes)
+        }
+        ExecuteMsg::Finalize {} => execute_finalize(deps, env),
+        ExecuteMsg::ReduceSupply { new_max, freeze } => {
+            execute_reduce_supply(deps, info, new_max, freeze.unwrap_or(false))
+        }
+        ExecuteMsg::IncreaseSupply { new_max } => execute_increase_supply(deps, info, new_max),
+        ExecuteMsg::FillWaitlist { limit } => execute_fill_waitlist(deps, env, limit),
+        ExecuteMsg::CancelWaitlist { position } => execute_cancel_waitlist(deps, info, position),
+        ExecuteMsg::MintNative { quantity } => {
+            execute_mint_native(deps, env, info, quantity.unwrap_or(1))
+        }
+        ExecuteMsg::UpdatePrices { prices } => execute_update_prices(deps, info, prices),
+        ExecuteMsg::ClaimCreatorShare {} => execute_claim_creator_share(deps, info),
+        ExecuteMsg::AddToPhaseAllowlist { phase_id, entries } => {
+            execute_add_to_phase_allowlist(deps, info, phase_id, entries)
+        }
+        ExecuteMsg::RemoveFromPhaseAllowlist {
+            phase_id,
+            addresses,
+        } => execute_remove_from_phase_allowlist(deps, info, phase_id, addresses),
+        ExecuteMsg::UpdateAllowlist { add, remove } => {
+            execute_update_allowlist(deps, info, add, remove)
+        }
+        ExecuteMsg::UpdateMerkleRoot { root } => execute_update_merkle_root(deps, info, root),
+        ExecuteMsg::SetPartnerDiscount { discount } => {
+            execute_set_partner_discount(deps, info, discount)
+        }
+        ExecuteMsg::SetMintGate { gate } => execute_set_mint_gate(deps, info, gate),
+        ExecuteMsg::AiOracleCallback {
+            token_id,
+            token_uri,
+        } => execute_ai_oracle_callback(deps, env, info, token_id, token_uri),
+        ExecuteMsg::SetSwapRoute { route } => execute_set_swap_route(deps, info, route),
+        ExecuteMsg::SetMembership { membership } => execute_set_membership(deps, info, membership),
+        ExecuteMsg::SnapshotHolders { restart, limit } => {
+            execute_snapshot_holders(deps, env, info, restart, limit)
+        }
+        ExecuteMsg::SetStakingRewards {
+            reward_token,
+            reward_per_block,
+        } => execute_set_staking_rewards(deps, env, info, reward_token, reward_per_block),
+        ExecuteMsg::ClaimStakingRewards { token_ids } => {
+            execute_claim_staking_rewards(deps, env, info, token_ids)
+        }
+        ExecuteMsg::Unstake { token_id } => execute_unstake(deps, env, info, token_id),
+        ExecuteMsg::SetTraitTable { traits } => execute_set_trait_table(deps, info, traits),
+        ExecuteMsg::SetRandomness { randomness } => execute_set_randomness(deps, info, randomness),
+        ExecuteMsg::NoisReceive { callback } => execute_nois_receive(deps, env, info, callback),
+        ExecuteMsg::AddMintHook { addr } => execute_add_mint_hook(deps, info, addr),
+        ExecuteMsg::RemoveMintHook { addr } => execute_remove_mint_hook(deps, info, addr),
+        ExecuteMsg::Cw721Execute { msg } => execute_cw721_passthrough(deps, info, msg),
+        ExecuteMsg::TransferMinter { new_minter, expiry } => {
+            execute_transfer_minter(deps, info, new_minter, expiry)
+        }
+        ExecuteMsg::RenounceOwnership {} => execute_renounce_ownership(deps, info),
+        ExecuteMsg::SetOperator { address, perms } => {
+            execute_set_operator(deps, info, address, perms)
+        }
+        ExecuteMsg::RemoveOperator { address } => execute_remove_operator(deps, info, address),
+        ExecuteMsg::UpdatePaymentToken { address } => {
+            execute_update_payment_token(deps, env, info, address)
+        }
+        ExecuteMsg::ApplyPaymentToken {} => execute_apply_payment_token(deps, env),
+        ExecuteMsg::UpdateBlocklist { add, remove } => {
+            execute_update_blocklist(deps, info, add, remove)
+        }
+        ExecuteMsg::Claim {} => execute_claim(deps, env, info),
+        ExecuteMsg::MintFromBalance { quantity } => {
+            execute_mint_from_balance(deps, env, info, quantity)
+        }
+        ExecuteMsg::RefundDeposit { amount } => execute_refund_deposit(deps, info, amount),
+        ExecuteMsg::GrantCredits { address, amount } => {
+            execute_grant_credits(deps, info, address, amount)
+        }
+        ExecuteMsg::RedeemCredits { quantity } => execute_redeem_credits(deps, env, info, quantity),
+        ExecuteMsg::AddDrop {
+            name,
+            start_time,
+            end_time,
+            unit_price,
+            supply,
+            per_wallet_cap,
+        } => {
+            let round = DropRound {
+                name,
+                start_time,
+                end_time,
+                unit_price,
+                supply,
+                minted: 0,
+                per_wallet_cap,
+            };
+            execute_add_drops(deps, info, vec![round])
+        }
+        ExecuteMsg::ScheduleDrops {
+            name,
+            first_start,
+            interval,
+            duration,
+            rounds,
+            unit_price,
+            supply,
+            per_wallet_cap,
+        } => execute_schedule_drops(
+            deps,
+            info,
+            name,
+            first_start,
+            interval,
+            duration,
+            rounds,
+            unit_price,
+            supply,
+            per_wallet_cap,
+        ),
+        ExecuteMsg::UpdateCollectionInfo {
+            collection_info,
+            royalty,
+        } => execute_update_collection_info(deps, info, collection_info, royalty),
+        ExecuteMsg::UpdateProtocolFee { fee } => execute_update_protocol_fee(deps, info, fee),
+        ExecuteMsg::SetBurnBps { bps } => execute_set_burn_bps(deps, info, bps),
+        ExecuteMsg::CommitMint { commitment } => execute_commit_mint(deps, env, info, commitment),
+        ExecuteMsg::SetGatekeeper { pubkey } => execute_set_gatekeeper(deps, info, pubkey),
+        ExecuteMsg::UpdateRelayers { add, remove } => {
+            execute_update_relayers(deps, info, add, remove)
+        }
+        ExecuteMsg::SetIntentKey { pubkey } => execute_set_intent_key(deps, info, pubkey),
+        ExecuteMsg::RevokeExpired { token_ids } => {
+            execute_revoke_expired(deps, env, info, token_ids)
+        }
+        ExecuteMsg::MigrateCw721 { new_code_id, msg } => {
+            execute_migrate_cw721(deps, info, new_code_id, msg)
+        }
+        ExecuteMsg::UpdateTokenCodeId { code_id } => {
+            execute_update_token_code_id(deps, info, code_id)
+        }
+        ExecuteMsg::SetTradeIn { trade_in } => execute_set_trade_in(deps, info, trade_in),
+        ExecuteMsg::CancelTradeIn { collection } => execute_cancel_trade_in(deps, info, collection),
+        ExecuteMsg::StartRaffle {
+            end_time,
+            ticket_price,
+            winners,
+        } => execute_start_raffle(deps, env, info, end_time, ticket_price, winners),
+        ExecuteMsg::DrawRaffle {} => execute_draw_raffle(deps, env, info),
+        ExecuteMsg::ClaimRaffle {} => execute_claim_raffle(deps, env, info),
+        ExecuteMsg::CreateAuction {
+            token_id,
+            token_uri,
+            reserve_price,
+            end_time,
+        } => execute_create_auction(
+            deps,
+            env,
+            info,
+            token_id,
+            token_uri,
+            reserve_price,
+            end_time,
+        ),
+        ExecuteMsg::SettleAuction { token_id } => execute_settle_auction(deps, env, info, token_id),
+        ExecuteMsg::SetOwnerKey { pubkey } => execute_set_owner_key(deps, info, pubkey),
+        ExecuteMsg::ExecuteSigned { payload, signature } => {
+            execute_signed(deps, env, payload, signature)
+        }
     }
 }
 
 pub fn execute_receive(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     sender: String,
     amount: Uint128,
-    _msg: Binary,
+    msg: Binary,
 ) -> Result<Response, ContractError> {
-    let mut config = CONFIG.load(deps.storage)?;
-    if config.cw20_address != info.sender {
+    let config = CONFIG.load(deps.storage)?;
+    let token = info.sender;
+    let hook: Cw20HookMsg = if msg.is_empty() {
+        Cw20HookMsg::Mint(MintOptions::default())
+    } else {
+        from_binary(&msg).map_err(|_| ContractError::InvalidHookMsg {})?
+    };
+    if let Cw20HookMsg::FundStakingRewards {} = hook {
+        return execute_fund_staking_rewards(deps, config, token, amount);
+    }
+    if config.cw20_address != token && !PAYMENT_TOKENS.has(deps.storage, &token) {
         return Err(ContractError::UnauthorizedTokenContract {});
     }
 
-    if config.cw721_address == None {
-        return Err(ContractError::Uninitialized {});
+    if matches!(
+        hook,
+        Cw20HookMsg::Mint(_) | Cw20HookMsg::MintMax(_) | Cw20HookMsg::RelayMint { .. }
+    ) && config.commit_reveal_blocks.is_some()
+    {
+        return Err(ContractError::CommitmentRequired {});
     }
 
-    if config.unused_token_id >= config.max_tokens {
-        return Err(ContractError::SoldOut {});
+    match hook {
+        Cw20HookMsg::Mint(options) => execute_mint(deps, env, token, sender, amount, options),
+        Cw20HookMsg::RevealMint { secret, options } => {
+            consume_commitment(deps.storage, deps.api, &env, &config, &sender, &secret)?;
+            execute_mint(deps, env, token, sender, amount, options)
+        }
+        Cw20HookMsg::MintMax(options) => {
+            execute_mint_max(deps, env, config, token, sender, amount, options)
+        }
+        Cw20HookMsg::RelayMint { intent, signature } => {
+            let relayer = deps.api.addr_validate(&sender)?;
+            if !RELAYERS.has(deps.storage, &relayer) {
+                return Err(ContractError::Unauthorized {});
+            }
+            use_mint_intent(deps.storage, deps.api, &env, &intent, &signature)?;
+            let options = MintOptions {
+                quantity: Some(intent.quantity),
+                ..MintOptions::default()
+            };
+            let res = execute_mint(deps, env, token, intent.recipient, amount, options)?;
+            Ok(res.add_attribute("relayer", relayer))
+        }
+        Cw20HookMsg::Buy { token_id } => {
+            if config.cw20_address != token {
+                return Err(ContractError::UnauthorizedTokenContract {});
+            }
+            execute_buy(deps, config, sender, amount, token_id)
+        }
+        Cw20HookMsg::RedeemVoucher { voucher, signature } => {
+            if config.cw20_address != token {
+                return Err(ContractError::UnauthorizedTokenContract {});
+            }
+            execute_redeem_voucher(deps, env, config, sender, amount, voucher, signature)
+        }
+        Cw20HookMsg::Bid { token_id } => {
+            if config.cw20_address != token {
+                return Err(ContractError::UnauthorizedTokenContract {});
+            }
+            execute_bid(deps, env, config, sender, amount, token_id)
+        }
+        Cw20HookMsg::BuyTickets { quantity } => {
+            if config.cw20_address != token {
+                return Err(ContractError::UnauthorizedTokenContract {});
+            }
+            execute_buy_tickets(deps, env, config, sender, amount, quantity)
+        }
+        Cw20HookMsg::Renew { token_id } => {
+            if config.cw20_address != token {
+                return Err(ContractError::UnauthorizedTokenContract {});
+            }
+            execute_renew(deps, env, config, sender, amount, token_id)
+        }
+        Cw20HookMsg::Rent { token_id, days } => {
+            if config.cw20_address != token {
+                return Err(ContractError::UnauthorizedTokenContract {});
+            }
+            execute_rent(deps, env, config, sender, amount, token_id, days)
+        }
+        Cw20HookMsg::Deposit {} => {
+            if config.cw20_address != token {
+                return Err(ContractError::UnauthorizedTokenContract {});
+            }
+            execute_deposit(deps, config, sender, amount)
+        }
+        Cw20HookMsg::FundStakingRewards {} => unreachable!("handled above"),
     }
+}
 
-    if amount != config.unit_price {
-        return Err(ContractError::WrongPaymentAmount {});
+/// Mints to `sender`, who has already paid `amount` of the cw20 `token`. Tokens other than
+/// `cw20_address` are charged their flat `PAYMENT_TOKENS` price, but the sale's access rules
+/// (presale, allowlist-only phases) still apply.
+pub fn execute_mint(
+    deps: DepsMut,
+    env: Env,
+    token: Addr,
+    sender: String,
+    amount: Uint128,
+    options: MintOptions,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let quantity = options.quantity.unwrap_or(1);
+
+    if config.retired {
+        return Err(ContractError::Retired {});
+    }
+    if config.closed {
+        return Err(ContractError::SaleClosed {});
     }
 
-    let mint_msg = Cw721ExecuteMsg::<Extension, Empty>::Mint(MintMsg::<Extension> {
-        token_id: config.unused_token_id.to_string(),
-        owner: sender,
-        token_uri: config.token_uri.clone().into(),
-        extension: config.extension.clone(),
-    });
+    if config.paused {
+        return Err(ContractError::ContractPaused {});
+    }
 
-    match config.cw721_address.clone() {
-        Some(cw721) => {
-            let callback =
-                Cw721Contract::<Empty, Empty>(cw721, PhantomData, PhantomData).call(mint_msg)?;
-            config.unused_token_id += 1;
-            CONFIG.save(deps.storage, &config)?;
+    assert_not_blocklisted(deps.as_ref(), &sender)?;
+    if let Some(recipient) = &options.recipient {
+        assert_not_blocklisted(deps.as_ref(), recipient)?;
+    }
 
-            Ok(Response::new().add_message(callback))
+    if let Some(collection_id) = options.collection_id {
+        if token != config.cw20_address {
+            return Err(ContractError::PaymentTokenNotAccepted {});
         }
-        None => Err(ContractError::Cw721NotLinked {}),
+        let recipient = options.recipient.unwrap_or_else(|| sender.clone());
+        return execute_collection_mint(
+            deps,
+            config,
+            collection_id,
+            sender,
+            recipient,
+            amount,
+            quantity,
+        );
     }
-}
 
+    if let Some(drop_id) = options.drop_id {
+        if token != config.cw20_address {
+            return Err(ContractError::PaymentTokenNotAccepted {});
+        }
+        let recipient = options.recipient.unwrap_or_else(|| sender.clone());
+        return execute_drop_mint(
+            deps, &env, config, drop_id, sender, recipient, amount, quantity,
+        );
+    }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetConfig {} => to_binary(&query_config(deps)?),
+    if let Some(start) = config.start_time {
+        if env.block.time < start {
+            return Err(ContractError::MintNotStarted {});
+        }
     }
-}
 
-fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
-    let config = CONFIG.load(deps.storage)?;
-    Ok(ConfigResponse {
-        owner: config.owner,
-        cw20_address: config.cw20_address,
-        cw721_address: config.cw721_address,
-        max_tokens: config.max_tokens,
-        unit_price: config.unit_price,
-        name: config.name,
-        symbol: config.symbol,
-        token_uri: config.token_uri,
-        extension: config.extension,
-        unused_token_id: config.unused_token_id,
-    })
-}
+    if let Some(end) = config.end_time {
+        if env.block.time >= end {
+            if config.open_edition && config.final_supply.is_none() {
+                // The first late mint closes the edition and gets its payment back.
+                let res = finalize_edition(deps.storage, config)?;
+                return Ok(res.add_message(cw20_transfer_msg(&token, &sender, amount)?));
+            }
+            return Err(ContractError::MintEnded {});
+        }
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MOCK_CONTRACT_ADDR};
-    use cosmwasm_std::{from_binary, to_binary, SubMsgResponse, SubMsgResult};
-    use prost::Message;
+    if config.cw721_address.is_none() {
+        return Err(ContractError::Uninitialized {});
+    }
 
-    const NFT_CONTRACT_ADDR: &str = "nftcontract";
+    if quantity == 0 || quantity as usize > MAX_BATCH_MINTS {
+        return Err(ContractError::InvalidQuantity {});
+    }
 
-    #[derive(Clone, PartialEq, Message)]
-    struct MsgInstantiateContractResponse {
-        #[prost(string, tag = "1")]
-        pub contract_address: ::prost::alloc::string::String,
-        #[prost(bytes, tag = "2")]
-        pub data: ::prost::alloc::vec::Vec<u8>,
+    let available = config.remaining_supply();
+    if available < quantity as u64 {
+        if config.waitlist && token == config.cw20_address {
+            return join_waitlist(deps, config, sender, amount, quantity);
+        }
+        if available > 0 && options.allow_partial == Some(true) {
+            let available = available as u32;
+            return execute_partial_mint(
+                deps, env, &config, token, sender, amount, options, available,
+            );
+        }
+        return Err(ContractError::SoldOut {});
+    }
+
+    if config.max_mints_per_tx.is_some_and(|max| quantity > max) {
+        return Err(ContractError::TooManyMintsPerTx {});
+    }
+
+    let buyer = deps.api.addr_validate(&sender)?;
+    assert_mint_gate(deps.as_ref(), &config, &buyer)?;
+    use_mint_permit(
+        deps.storage,
+        deps.api,
+        &env,
+        &config,
+        &buyer,
+        options.permit.as_ref(),
+    )?;
+    if let Some(max) = config.max_mints_per_block {
+        let minted_this_block = BLOCK_MINTS
+            .may_load(deps.storage, &buyer)?
+            .filter(|mints| mints.height == env.block.height)
+            .map_or(0, |mints| mints.count);
+        if minted_this_block + quantity > max {
+            return Err(ContractError::BlockMintLimitReached {});
+        }
+        let mints = BlockMints {
+            height: env.block.height,
+            count: minted_this_block + quantity,
+        };
+        BLOCK_MINTS.save(deps.storage, &buyer, &mints)?;
+    }
+    let bought = MINTS_PER_ADDRESS
+        .may_load(deps.storage, &buyer)?
+        .unwrap_or_default();
+    if let Some(limit) = config.mint_limit_per_address {
+        if bought + quantity > limit {
+            return Err(ContractError::MintLimitReached {});
+        }
+    }
+
+    let phase = active_phase(deps.storage, &env)?;
+    let phase_bought = match &phase {
+        Some((id, _)) => PHASE_MINTS
+            .may_load(deps.storage, (*id, &buyer))?
+            .unwrap_or_default(),
+        None => 0,
+    };
+    if let Some((
+        _,
+        Phase {
+            per_wallet_cap: Some(cap),
+            ..
+        },
+    )) = &phase
+    {
+        if phase_bought + quantity > *cap {
+            return Err(ContractError::MintLimitReached {});
+        }
+    }
+    if let Some((id, _)) = &phase {
+        let entry_limit = PHASE_ALLOWLISTS
+            .may_load(deps.storage, (*id, &buyer))?
+            .and_then(|entry| entry.mint_limit);
+        if entry_limit.is_some_and(|limit| phase_bought + quantity > limit) {
+            return Err(ContractError::MintLimitReached {});
+        }
+    }
+    let merkle_verified = match &options.merkle_proof {
+        Some(proof) => {
+            verify_merkle_proof(&config, &buyer, proof)?;
+            true
+        }
+        None => false,
+    };
+    let restricted = match &phase {
+        Some((_, phase)) => phase.allowlist_only,
+        None => config
+            .presale
+            .as_ref()
+            .is_some_and(|presale| env.block.time < presale.end_time),
+    };
+    if restricted {
+        let allocation = live_allowlist_entry(deps.storage, &buyer, env.block.time)?
+            .and_then(|entry| entry.mint_limit);
+        if allocation.is_some_and(|limit| bought + quantity > limit) {
+            return Err(ContractError::MintLimitReached {});
+        }
+    }
+
+    let escrow = config
+        .crowdfund
+        .clone()
+        .filter(|_| !config.crowdfund_settled);
+    if let Some(crowdfund) = &escrow {
+        if env.block.time >= crowdfund.deadline {
+            return Err(ContractError::MintEnded {});
+        }
+    }
+
+    let mut price = mint_price(
+        deps.storage,
+        &env,
+        &config,
+        phase.as_ref().map(|(id, phase)| (*id, phase)),
+        &buyer,
+        quantity,
+        merkle_verified,
+    )?;
+    let payment_token = (token != config.cw20_address).then(|| token.clone());
+    if payment_token.is_some() {
+        // Pledges are refunded in the primary token only.
+        if escrow.is_some() {
+            return Err(ContractError::PaymentTokenNotAccepted {});
+        }
+        price = PAYMENT_TOKENS
+            .load(deps.storage, &token)?
+            .checked_mul(Uint128::from(quantity))?;
+    } else if let Some(oracle) = &config.price_oracle {
+        let quote = oracle_price(deps.as_ref(), &env, &token, oracle, quantity)?;
+        let band = quote.multiply_ratio(oracle.tolerance_bps, MAX_ROYALTY_BPS);
+        if amount + band < quote || amount > quote + band {
+            return Err(ContractError::WrongPaymentAmount {});
+        }
+        // Anything within the band is accepted as payment in full.
+        price = amount;
+    }
+    let partner = config
+        .partner_discount
+        .as_ref()
+        .filter(|_| payment_token.is_some() || config.price_oracle.is_none());
+    let partner_holder = match partner {
+        Some(partner) => holds_cw721(deps.as_ref(), &partner.collection, &buyer)?,
+        None => false,
+    };
+    if let (Some(partner), true) = (partner, partner_holder) {
+        price -= price.multiply_ratio(partner.discount_bps, MAX_ROYALTY_BPS);
+    }
+    validate_payment(&config, amount, price)?;
+    if options
+        .memo
+        .as_ref()
+        .is_some_and(|memo| memo.len() > MAX_MEMO_LENGTH)
+    {
+        return Err(ContractError::MemoTooLong {});
+    }
+    if let Some(metadata) = &options.metadata {
+        if !config.custom_metadata {
+            return Err(ContractError::CustomMetadataDisabled {});
+        }
+        validate_custom_metadata(metadata)?;
+    }
+    if let Some(token_uri) = &options.token_uri {
+        validate_buyer_token_uri(&config, token_uri)?;
+    }
+    let recipient = match options.recipient {
+        Some(recipient) => deps.api.addr_validate(&recipient)?,
+        None => buyer.clone(),
+    };
+    // Forwarded tokens are minted here first, then sent into the bridge.
+    let forward = match options.remote_recipient {
+        Some(_) if escrow.is_some() || recipient != buyer => {
+            return Err(ContractError::RecipientNotSupported {})
+        }
+        Some(receiver) => {
+            let bridge = config
+                .ics721_bridge
+                .clone()
+                .ok_or(ContractError::Ics721Disabled {})?;
+            Some((bridge, receiver))
+        }
+        None => None,
+    };
+    // Escrowed tokens stay with this contract until the crowdfund is settled, and pledges
+    // are only ever claimed by the buyer.
+    let holder = match escrow {
+        Some(_) if recipient != buyer => return Err(ContractError::RecipientNotSupported {}),
+        Some(_) => env.contract.address.clone(),
+        None if forward.is_some() => env.contract.address.clone(),
+        None => recipient.clone(),
+    };
+
+    let refund = amount - price;
+    let unit_paid = price.multiply_ratio(1u128, quantity);
+    let mut res = Response::new()
+        .add_attribute("action", "mint")
+        .add_attribute("buyer", &buyer)
+        .add_attribute("price", price)
+        .add_attribute("quantity", quantity.to_string());
+    if recipient != buyer {
+        res = res.add_attribute("recipient", &recipient);
+    }
+    if let Some(payment_token) = &payment_token {
+        res = res.add_attribute("payment_token", payment_token);
+    }
+    if let Some((_, receiver)) = &forward {
+        res = res.add_attribute("remote_recipient", receiver);
+    }
+    if partner.is_some() {
+        res = res.add_attribute("partner_holder", partner_holder.to_string());
+    }
+    let mut token_ids = Vec::with_capacity(quantity as usize);
+    let mut random_job = None;
+    if let Some(randomness) = config.randomness.clone() {
+        if escrow.is_some()
+            || forward.is_some()
+            || options.metadata.is_some()
+            || options.token_uri.is_some()
+            || options.memo.is_some()
+        {
+            return Err(ContractError::RandomMintUnsupported {});
+        }
+        let job = PendingMint {
+            buyer: buyer.clone(),
+            recipient: holder.clone(),
+            quantity,
+            unit_paid,
+            payment_token: payment_token.clone(),
+            paid_out: Uint128::zero(),
+        };
+        let (job_id, request) = request_randomness(deps.storage, &mut config, &randomness, job)?;
+        res = res.add_message(request).add_attribute("job_id", &job_id);
+        random_job = Some(job_id);
+    } else {
+        let mut batch = mint_batch(
+            deps.storage,
+            &env,
+            &mut config,
+            &buyer,
+            &holder,
+            unit_paid,
+            payment_token.clone().map(Currency::Cw20),
+            quantity,
+        )?;
+        for (&token_id, msg) in batch.token_ids.iter().zip(batch.msgs.iter_mut()) {
+            if let Some(token_uri) = &options.token_uri {
+                msg.msg = cw721_mint_msg(
+                    &config,
+                    config.token_id(token_id),
+                    &holder,
+                    token_uri.clone(),
+                    options.metadata.as_deref().cloned(),
+                )?;
+            } else if let Some(metadata) = &options.metadata {
+                msg.msg =
+                    build_mint_msg(&config, token_id, &holder, Some(metadata.as_ref().clone()))?;
+            }
+            hold_until_reveal(
+                deps.storage,
+                &config,
+                token_id,
+                &buyer,
+                &Currency::Cw20(token.clone()),
+                unit_paid,
+            )?;
+            if let Some(memo) = &options.memo {
+                let gift = Gift {
+                    from: buyer.clone(),
+                    memo: memo.clone(),
+                };
+                GIFTS.save(deps.storage, token_id, &gift)?;
+                let mut record = mints().load(deps.storage, token_id)?;
+                record.memo = Some(memo.clone());
+                mints().save(deps.storage, token_id, &record)?;
+            }
+        }
+        token_ids.extend_from_slice(&batch.token_ids);
+        res = batch.into_response(res)?;
+        if let Some((bridge, receiver)) = &forward {
+            for &token_id in &token_ids {
+                res = res.add_message(ics721_send_msg(
+                    &env,
+                    &config,
+                    bridge,
+                    receiver,
+                    &config.token_id(token_id),
+                )?);
+            }
+        }
+    }
+    if escrow.is_some() {
+        let mut pledge = PLEDGES.may_load(deps.storage, &buyer)?.unwrap_or_default();
+        pledge.token_ids.extend(&token_ids);
+        pledge.paid += price;
+        PLEDGES.save(deps.storage, &buyer, &pledge)?;
+        config.crowdfund_sold += quantity as u64;
+        res = res.add_attribute("escrowed", "true");
+    }
+    record_address_mints(deps.storage, &mut config, &buyer, bought, quantity)?;
+    CONFIG.save(deps.storage, &config)?;
+    if let Some((id, _)) = &phase {
+        PHASE_MINTS.save(deps.storage, (*id, &buyer), &(phase_bought + quantity))?;
+        res = res.add_attribute("phase_id", id.to_string());
+    }
+
+    res = res.add_attribute("cw721_address", config.cw721_address.clone().unwrap());
+
+    // Referral rewards are not paid out of escrowed payments.
+    let mut paid_out = Uint128::zero();
+    let referrer = options.referrer.filter(|_| !escrows_until_reveal(&config));
+    if let (Some(referrer), Some(bps), None) = (referrer, config.referral_bps, &escrow) {
+        let referrer = deps.api.addr_validate(&referrer)?;
+        if referrer == buyer {
+            return Err(ContractError::SelfReferral {});
+        }
+        let reward = price.multiply_ratio(bps, MAX_ROYALTY_BPS);
+        let mut stats = REFERRALS
+            .may_load(deps.storage, &referrer)?
+            .unwrap_or_default();
+        stats.referrals += quantity;
+        stats.earned += reward;
+        REFERRALS.save(deps.storage, &referrer, &stats)?;
+
+        if !reward.is_zero() {
+            res = res.add_message(cw20_transfer_msg(&token, referrer.as_str(), reward)?);
+        }
+        paid_out += reward;
+        res = res
+            .add_attribute("referrer", referrer)
+            .add_attribute("referral_reward", reward);
+    }
+
+    if let Some((fee, msg)) = protocol_fee_msg(&config, &Currency::Cw20(token.clone()), price)? {
+        res = res.add_message(msg).add_attribute("protocol_fee", fee);
+        paid_out += fee;
+    }
+    if let Some((burned, msg)) =
+        burn_msg(deps.storage, &config, &Currency::Cw20(token.clone()), price)?
+    {
+        res = res.add_message(msg).add_attribute("burned", burned);
+        paid_out += burned;
+    }
+    match random_job {
+        Some(job_id) => {
+            PENDING_MINTS.update(deps.storage, &job_id, |job| -> StdResult<_> {
+                let mut job = job.ok_or_else(|| StdError::not_found("PendingMint"))?;
+                job.paid_out = paid_out;
+                Ok(job)
+            })?;
+        }
+        None => record_payouts(deps.storage, &token_ids, price, paid_out)?,
+    }
+
+    if !refund.is_zero() {
+        res = res
+            .add_message(cw20_transfer_msg(&token, &sender, refund)?)
+            .add_attribute("refund", refund);
+    }
+    Ok(res)
+}
+
+pub fn execute_claim(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if config.retired {
+        return Err(ContractError::Retired {});
+    }
+    if config.closed {
+        return Err(ContractError::SaleClosed {});
+    }
+    if config.paused {
+        return Err(ContractError::ContractPaused {});
+    }
+    assert_not_blocklisted(deps.as_ref(), info.sender.as_str())?;
+    if config
+        .start_time
+        .is_some_and(|start| env.block.time < start)
+    {
+        return Err(ContractError::MintNotStarted {});
+    }
+    if config.end_time.is_some_and(|end| env.block.time >= end) {
+        return Err(ContractError::MintEnded {});
+    }
+    if config.cw721_address.is_none() {
+        return Err(ContractError::Uninitialized {});
+    }
+    if config.crowdfund.is_some() && !config.crowdfund_settled {
+        return Err(ContractError::CrowdfundPending {});
+    }
+    if config.randomness.is_some() {
+        return Err(ContractError::RandomMintUnsupported {});
+    }
+    if config.remaining_supply() == 0 {
+        return Err(ContractError::SoldOut {});
+    }
+
+    let (id, phase, free_supply) = match active_phase(deps.storage, &env) {
+        Ok(Some((id, phase))) => match phase.free_supply {
+            Some(free_supply) => (id, phase, free_supply),
+            None => return Err(ContractError::NoActiveClaim {}),
+        },
+        Ok(None) | Err(ContractError::NoActivePhase {}) => {
+            return Err(ContractError::NoActiveClaim {})
+        }
+        Err(err) => return Err(err),
+    };
+    let claimer = info.sender;
+    assert_mint_gate(deps.as_ref(), &config, &claimer)?;
+    let entry = PHASE_ALLOWLISTS.may_load(deps.storage, (id, &claimer))?;
+    if phase.allowlist_only
+        && entry.is_none()
+        && live_allowlist_entry(deps.storage, &claimer, env.block.time)?.is_none()
+    {
+        return Err(ContractError::NotAllowlisted {});
+    }
+    let claimed_by = PHASE_MINTS
+        .may_load(deps.storage, (id, &claimer))?
+        .unwrap_or_default();
+    let caps = [
+        phase.per_wallet_cap,
+        entry.and_then(|entry| entry.mint_limit),
+    ];
+    if caps.into_iter().flatten().any(|cap| claimed_by >= cap) {
+        return Err(ContractError::MintLimitReached {});
+    }
+    let claimed = PHASE_CLAIMED
+        .may_load(deps.storage, id)?
+        .unwrap_or_default();
+    if claimed >= free_supply {
+        return Err(ContractError::FreeSupplyExhausted {});
+    }
+
+    let (token_id, msgs, event) = mint_next(
+        deps.storage,
+        &env,
+        &mut config,
+        &claimer,
+        &claimer,
+        Uint128::zero(),
+        None,
+    )?;
+    PHASE_MINTS.save(deps.storage, (id, &claimer), &(claimed_by + 1))?;
+    PHASE_CLAIMED.save(deps.storage, id, &(claimed + 1))?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .set_data(mint_data(vec![config.token_id(token_id)], &claimer)?)
+        .add_submessages(msgs)
+        .add_event(event)
+        .add_attribute("action", "claim")
+        .add_attribute("claimer", &claimer)
+        .add_attribute("phase_id", id.to_string())
+        .add_attribute("token_id", config.token_id(token_id)))
+}
+
+fn execute_deposit(
+    deps: DepsMut,
+    mut config: Config,
+    sender: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    if config.retired {
+        return Err(ContractError::Retired {});
+    }
+    if amount.is_zero() {
+        return Err(ContractError::WrongPaymentAmount {});
+    }
+    let depositor = deps.api.addr_validate(&sender)?;
+    assert_not_blocklisted(deps.as_ref(), depositor.as_str())?;
+
+    let balance = DEPOSITS.update(deps.storage, &depositor, |balance| {
+        StdResult::Ok(balance.unwrap_or_default().checked_add(amount)?)
+    })?;
+    config.deposit_escrow += amount;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "deposit")
+        .add_attribute("depositor", depositor)
+        .add_attribute("amount", amount)
+        .add_attribute("balance", balance))
+}
+
+/// Pays the quoted price out of the deposit and hands over to `execute_mint`, which sees
+/// exactly that amount as sent, so nothing is refunded.
+pub fn execute_mint_from_balance(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    quantity: u32,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if config.commit_reveal_blocks.is_some() {
+        return Err(ContractError::CommitmentRequired {});
+    }
+    let buyer = info.sender;
+    let token = config.cw20_address.clone();
+    let quote = price_quote(
+        deps.as_ref(),
+        &env,
+        &config,
+        &token,
+        &buyer,
+        quantity,
+        false,
+    )?;
+    let balance = DEPOSITS.may_load(deps.storage, &buyer)?.unwrap_or_default();
+    if balance < quote.price {
+        return Err(ContractError::InsufficientDeposit {});
+    }
+    debit_deposit(deps.storage, &mut config, &buyer, balance, quote.price)?;
+
+    let options = MintOptions {
+        quantity: Some(quantity),
+        ..MintOptions::default()
+    };
+    let res = execute_mint(deps, env, token, buyer.to_string(), quote.price, options)?;
+    Ok(res.add_attribute("deposit_left", balance - quote.price))
+}
+
+pub fn execute_refund_deposit(
+    deps: DepsMut,
+    info: MessageInfo,
+    amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let depositor = info.sender;
+    let balance = DEPOSITS
+        .may_load(deps.storage, &depositor)?
+        .unwrap_or_default();
+    let amount = amount.unwrap_or(balance);
+    if amount.is_zero() {
+        return Err(ContractError::NothingToWithdraw {});
+    }
+    if amount > balance {
+        return Err(ContractError::InsufficientDeposit {});
+    }
+    debit_deposit(deps.storage, &mut config, &depositor, balance, amount)?;
+
+    Ok(Response::new()
+        .add_message(cw20_transfer_msg(
+            &config.cw20_address,
+            depositor.as_str(),
+            amount,
+        )?)
+        .add_attribute("action", "refund_deposit")
+        .add_attribute("depositor", depositor)
+        .add_attribute("amount", amount))
+}
+
+/// Takes `amount` out of `depositor`'s `balance` and the escrowed total, saving `config`.
+fn debit_deposit(
+    storage: &mut dyn Storage,
+    config: &mut Config,
+    depositor: &Addr,
+    balance: Uint128,
+    amount: Uint128,
+) -> StdResult<()> {
+    match balance - amount {
+        left if left.is_zero() => DEPOSITS.remove(storage, depositor),
+        left => DEPOSITS.save(storage, depositor, &left)?,
+    }
+    config.deposit_escrow -= amount;
+    CONFIG.save(storage, config)
+}
+
+pub fn execute_grant_credits(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    amount: u32,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    if amount == 0 {
+        return Err(ContractError::InvalidQuantity {});
+    }
+
+    let address = deps.api.addr_validate(&address)?;
+    let credits = MINT_CREDITS.update(deps.storage, &address, |credits| -> StdResult<_> {
+        credits
+            .unwrap_or_default()
+            .checked_add(amount)
+            .ok_or_else(|| StdError::generic_err("mint credits overflow"))
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "grant_credits")
+        .add_attribute("address", address)
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("credits", credits.to_string()))
+}
+
+/// Credits are granted by the owner, so the buyer-side checks (allowlists, per-address
+/// limits, gates) do not apply; the sale itself must still be open.
+pub fn execute_redeem_credits(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    quantity: u32,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if config.retired {
+        return Err(ContractError::Retired {});
+    }
+    if config.closed {
+        return Err(ContractError::SaleClosed {});
+    }
+    if config.paused {
+        return Err(ContractError::ContractPaused {});
+    }
+    assert_not_blocklisted(deps.as_ref(), info.sender.as_str())?;
+    if config
+        .start_time
+        .is_some_and(|start| env.block.time < start)
+    {
+        return Err(ContractError::MintNotStarted {});
+    }
+    if config.end_time.is_some_and(|end| env.block.time >= end) {
+        return Err(ContractError::MintEnded {});
+    }
+    if config.cw721_address.is_none() {
+        return Err(ContractError::Uninitialized {});
+    }
+    if config.crowdfund.is_some() && !config.crowdfund_settled {
+        return Err(ContractError::CrowdfundPending {});
+    }
+    if config.randomness.is_some() {
+        return Err(ContractError::RandomMintUnsupported {});
+    }
+    if quantity == 0 || quantity as usize > MAX_BATCH_MINTS {
+        return Err(ContractError::InvalidQuantity {});
+    }
+    if config.remaining_supply() < quantity as u64 {
+        return Err(ContractError::SoldOut {});
+    }
+
+    let holder = info.sender;
+    let credits = MINT_CREDITS
+        .may_load(deps.storage, &holder)?
+        .unwrap_or_default();
+    if credits < quantity {
+        return Err(ContractError::InsufficientCredits {});
+    }
+    match credits - quantity {
+        0 => MINT_CREDITS.remove(deps.storage, &holder),
+        left => MINT_CREDITS.save(deps.storage, &holder, &left)?,
+    }
+
+    let bought = MINTS_PER_ADDRESS
+        .may_load(deps.storage, &holder)?
+        .unwrap_or_default();
+    let batch = mint_batch(
+        deps.storage,
+        &env,
+        &mut config,
+        &holder,
+        &holder,
+        Uint128::zero(),
+        None,
+        quantity,
+    )?;
+    record_address_mints(deps.storage, &mut config, &holder, bought, quantity)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    let res = Response::new()
+        .add_attribute("action", "redeem_credits")
+        .add_attribute("holder", &holder)
+        .add_attribute("quantity", quantity.to_string())
+        .add_attribute("credits_left", (credits - quantity).to_string());
+    Ok(batch.into_response(res)?)
+}
+
+/// Works out the largest affordable quantity, then mints it through `execute_mint` with
+/// exactly its price. When nothing fits, one token is attempted with the full amount so the
+/// error names the rule that failed.
+pub fn execute_mint_max(
+    deps: DepsMut,
+    env: Env,
+    config: Config,
+    token: Addr,
+    sender: String,
+    amount: Uint128,
+    mut options: MintOptions,
+) -> Result<Response, ContractError> {
+    if options.collection_id.is_some() || options.drop_id.is_some() {
+        return Err(ContractError::InvalidHookMsg {});
+    }
+    let buyer = deps.api.addr_validate(&sender)?;
+    let max = max_mintable(deps.as_ref(), &env, &config, &buyer)?;
+    let merkle_verified = options.merkle_proof.is_some();
+    let mut quantity = 0;
+    let mut price = Uint128::zero();
+    for candidate in 1..=max {
+        let quote = price_quote(
+            deps.as_ref(),
+            &env,
+            &config,
+            &token,
+            &buyer,
+            candidate,
+            merkle_verified,
+        );
+        match quote {
+            Ok(quote) if quote.price <= amount => {
+                quantity = candidate;
+                price = quote.price;
+            }
+            _ => break,
+        }
+    }
+    if quantity == 0 {
+        options.quantity = Some(1);
+        return execute_mint(deps, env, token, sender, amount, options);
+    }
+
+    options.quantity = Some(quantity);
+    let res = execute_mint(deps, env, token.clone(), sender.clone(), price, options)?;
+    refund_remainder(res, &token, &sender, amount - price)
+}
+
+/// Mints the `available` tokens left of a larger batch at their own price and refunds the
+/// rest of `amount`.
+#[allow(clippy::too_many_arguments)]
+fn execute_partial_mint(
+    deps: DepsMut,
+    env: Env,
+    config: &Config,
+    token: Addr,
+    sender: String,
+    amount: Uint128,
+    mut options: MintOptions,
+    available: u32,
+) -> Result<Response, ContractError> {
+    let buyer = deps.api.addr_validate(&sender)?;
+    let merkle_verified = options.merkle_proof.is_some();
+    let quote = price_quote(
+        deps.as_ref(),
+        &env,
+        config,
+        &token,
+        &buyer,
+        available,
+        merkle_verified,
+    )?;
+    if amount < quote.price {
+        return Err(ContractError::WrongPaymentAmount {});
+    }
+
+    options.quantity = Some(available);
+    let res = execute_mint(
+        deps,
+        env,
+        token.clone(),
+        sender.clone(),
+        quote.price,
+        options,
+    )?;
+    let res = res.add_attribute("partial_fill", available.to_string());
+    refund_remainder(res, &token, &sender, amount - quote.price)
+}
+
+fn refund_remainder(
+    res: Response,
+    token: &Addr,
+    sender: &str,
+    remainder: Uint128,
+) -> Result<Response, ContractError> {
+    if remainder.is_zero() {
+        return Ok(res);
+    }
+    Ok(res
+        .add_message(cw20_transfer_msg(token, sender, remainder)?)
+        .add_attribute("refund", remainder))
+}
+
+/// Most tokens `buyer` could mint in one transaction right now, from the remaining supply
+/// and every per-address, per-phase and per-transaction limit.
+fn max_mintable(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    buyer: &Addr,
+) -> Result<u32, ContractError> {
+    let mut max = (MAX_BATCH_MINTS as u64).min(config.remaining_supply()) as u32;
+    let mut cap = |limit: Option<u32>, used: u32| {
+        if let Some(limit) = limit {
+            max = max.min(limit.saturating_sub(used));
+        }
+    };
+    cap(config.max_mints_per_tx, 0);
+    let bought = MINTS_PER_ADDRESS
+        .may_load(deps.storage, buyer)?
+        .unwrap_or_default();
+    cap(config.mint_limit_per_address, bought);
+    if let Some(block_max) = config.max_mints_per_block {
+        let minted_this_block = BLOCK_MINTS
+            .may_load(deps.storage, buyer)?
+            .filter(|mints| mints.height == env.block.height)
+            .map_or(0, |mints| mints.count);
+        cap(Some(block_max), minted_this_block);
+    }
+    let phase = active_phase(deps.storage, env)?;
+    if let Some((id, phase)) = &phase {
+        let phase_bought = PHASE_MINTS
+            .may_load(deps.storage, (*id, buyer))?
+            .unwrap_or_default();
+        let entry = PHASE_ALLOWLISTS.may_load(deps.storage, (*id, buyer))?;
+        cap(phase.per_wallet_cap, phase_bought);
+        cap(entry.and_then(|entry| entry.mint_limit), phase_bought);
+    }
+    let restricted = match &phase {
+        Some((_, phase)) => phase.allowlist_only,
+        None => config
+            .presale
+            .as_ref()
+            .is_some_and(|presale| env.block.time < presale.end_time),
+    };
+    if restricted {
+        let allocation = live_allowlist_entry(deps.storage, buyer, env.block.time)?
+            .and_then(|entry| entry.mint_limit);
+        cap(allocation, bought);
+    }
+    Ok(max)
+}
+
+pub fn execute_commit_mint(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    commitment: HexBinary,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config.commit_reveal_blocks.is_none() {
+        return Err(ContractError::CommitRevealDisabled {});
+    }
+    if commitment.len() != 32 {
+        return Err(ContractError::InvalidCommitment {});
+    }
+    let pending = MintCommitment {
+        commitment,
+        height: env.block.height,
+    };
+    MINT_COMMITMENTS.save(deps.storage, &info.sender, &pending)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "commit_mint")
+        .add_attribute("buyer", info.sender)
+        .add_attribute("height", pending.height.to_string()))
+}
+
+/// Checks `secret` against the sender's commitment once it is old enough, and removes it.
+fn consume_commitment(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    env: &Env,
+    config: &Config,
+    sender: &str,
+    secret: &Binary,
+) -> Result<(), ContractError> {
+    let delay = config
+        .commit_reveal_blocks
+        .ok_or(ContractError::CommitRevealDisabled {})?;
+    let buyer = api.addr_validate(sender)?;
+    let pending = MINT_COMMITMENTS
+        .may_load(storage, &buyer)?
+        .ok_or(ContractError::NoCommitment {})?;
+    if env.block.height < pending.height + delay {
+        return Err(ContractError::CommitmentNotMatured {});
+    }
+    let mut hasher = Sha256::new();
+    hasher.update(buyer.as_bytes());
+    hasher.update(secret.as_slice());
+    if hasher.finalize().as_slice() != pending.commitment.as_slice() {
+        return Err(ContractError::InvalidCommitment {});
+    }
+    MINT_COMMITMENTS.remove(storage, &buyer);
+    Ok(())
+}
+
+pub fn execute_mint_native(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    quantity: u32,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if config.commit_reveal_blocks.is_some() {
+        return Err(ContractError::CommitmentRequired {});
+    }
+    // Native mints carry no options to hold a permit.
+    if config.gatekeeper.is_some() {
+        return Err(ContractError::PermitRequired {});
+    }
+    let denom = config
+        .native_denom
+        .clone()
+        .ok_or(ContractError::NativePaymentsDisabled {})?;
+    // Exact match only: another `ibc/` hash is another asset, whatever it is called.
+    let paid = match info.funds.as_slice() {
+        [coin] if coin.denom == denom => coin.amount,
+        _ => return Err(ContractError::InvalidDenom {}),
+    };
+
+    if config.retired {
+        return Err(ContractError::Retired {});
+    }
+    if config.closed {
+        return Err(ContractError::SaleClosed {});
+    }
+    if config.paused {
+        return Err(ContractError::ContractPaused {});
+    }
+    if config
+        .start_time
+        .is_some_and(|start| env.block.time < start)
+    {
+        return Err(ContractError::MintNotStarted {});
+    }
+    if config.end_time.is_some_and(|end| env.block.time >= end) {
+        return Err(ContractError::MintEnded {});
+    }
+    if config.cw721_address.is_none() {
+        return Err(ContractError::Uninitialized {});
+    }
+    // Pledges are refunded in the primary token only.
+    if config.crowdfund.is_some() && !config.crowdfund_settled {
+        return Err(ContractError::CrowdfundPending {});
+    }
+    if quantity == 0 || quantity as usize > MAX_BATCH_MINTS {
+        return Err(ContractError::InvalidQuantity {});
+    }
+    if config.remaining_supply() < quantity as u64 {
+        return Err(ContractError::SoldOut {});
+    }
+    if config.max_mints_per_tx.is_some_and(|max| quantity > max) {
+        return Err(ContractError::TooManyMintsPerTx {});
+    }
+    assert_not_blocklisted(deps.as_ref(), info.sender.as_str())?;
+    assert_mint_gate(deps.as_ref(), &config, &info.sender)?;
+    let bought = MINTS_PER_ADDRESS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    if config
+        .mint_limit_per_address
+        .is_some_and(|limit| bought + quantity > limit)
+    {
+        return Err(ContractError::MintLimitReached {});
+    }
+    let price = match config.native_price {
+        Some(unit_price) => unit_price.checked_mul(Uint128::from(quantity))?,
+        None => price_for(deps.storage, &config, quantity)?,
+    };
+    validate_payment(&config, paid, price)?;
+
+    let unit_paid = price.multiply_ratio(1u128, quantity);
+    let mut res = Response::new()
+        .add_attribute("action", "mint")
+        .add_attribute("buyer", &info.sender)
+        .add_attribute("price", price)
+        .add_attribute("denom", &denom)
+        .add_attribute("quantity", quantity.to_string());
+    let batch = mint_batch(
+        deps.storage,
+        &env,
+        &mut config,
+        &info.sender,
+        &info.sender,
+        unit_paid,
+        Some(Currency::Native(denom.clone())),
+        quantity,
+    )?;
+    let token_ids = batch.token_ids.clone();
+    for &token_id in &batch.token_ids {
+        hold_until_reveal(
+            deps.storage,
+            &config,
+            token_id,
+            &info.sender,
+            &Currency::Native(denom.clone()),
+            unit_paid,
+        )?;
+    }
+    res = batch.into_response(res)?;
+    record_address_mints(deps.storage, &mut config, &info.sender, bought, quantity)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    if let Some((fee, msg)) = protocol_fee_msg(&config, &Currency::Native(denom.clone()), price)? {
+        res = res.add_message(msg).add_attribute("protocol_fee", fee);
+        record_payouts(deps.storage, &token_ids, price, fee)?;
+    }
+
+    let refund = paid - price;
+    if !refund.is_zero() {
+        res = res
+            .add_message(BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: vec![Coin::new(refund.u128(), denom)],
+            })
+            .add_attribute("refund", refund);
+    }
+    Ok(res)
+}
+
+/// Queues a sold-out purchase at the current `unit_price`, refunding any excess.
+fn join_waitlist(
+    deps: DepsMut,
+    mut config: Config,
+    sender: String,
+    amount: Uint128,
+    quantity: u32,
+) -> Result<Response, ContractError> {
+    if config.crowdfund.is_some() && !config.crowdfund_settled {
+        return Err(ContractError::CrowdfundPending {});
+    }
+    let buyer = deps.api.addr_validate(&sender)?;
+    assert_mint_gate(deps.as_ref(), &config, &buyer)?;
+    let bought = MINTS_PER_ADDRESS
+        .may_load(deps.storage, &buyer)?
+        .unwrap_or_default();
+    if config
+        .mint_limit_per_address
+        .is_some_and(|limit| bought + quantity > limit)
+    {
+        return Err(ContractError::MintLimitReached {});
+    }
+    let price = config.unit_price.checked_mul(Uint128::from(quantity))?;
+    validate_payment(&config, amount, price)?;
+
+    let position = WAITLIST_NEXT.may_load(deps.storage)?.unwrap_or_default();
+    let entry = WaitlistEntry {
+        buyer: buyer.clone(),
+        quantity,
+        paid: price,
+    };
+    WAITLIST.save(deps.storage, position, &entry)?;
+    WAITLIST_NEXT.save(deps.storage, &(position + 1))?;
+    config.waitlist_escrow += price;
+    CONFIG.save(deps.storage, &config)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "join_waitlist")
+        .add_attribute("buyer", &buyer)
+        .add_attribute("position", position.to_string())
+        .add_attribute("quantity", quantity.to_string());
+    let refund = amount - price;
+    if !refund.is_zero() {
+        res = res
+            .add_message(cw20_transfer_msg(&config.cw20_address, &sender, refund)?)
+            .add_attribute("refund", refund);
+    }
+    Ok(res)
+}
+
+pub fn execute_fill_waitlist(
+    deps: DepsMut,
+    env: Env,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if config.retired {
+        return Err(ContractError::Retired {});
+    }
+    if config.closed {
+        return Err(ContractError::SaleClosed {});
+    }
+    if config.paused {
+        return Err(ContractError::ContractPaused {});
+    }
+    if config.cw721_address.is_none() {
+        return Err(ContractError::Uninitialized {});
+    }
+
+    let limit = clamp_limit(limit);
+    let entries = WAITLIST
+        .range(deps.storage, None, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut res = Response::new().add_attribute("action", "fill_waitlist");
+    let mut filled = 0u32;
+    let mut released = Uint128::zero();
+    let mut token_ids = vec![];
+    for (position, entry) in entries {
+        // Strictly first come, first served: later entries wait behind one that does not fit.
+        if config.remaining_supply() < entry.quantity as u64 {
+            break;
+        }
+        let unit_paid = entry.paid.multiply_ratio(1u128, entry.quantity);
+        let batch = mint_batch(
+            deps.storage,
+            &env,
+            &mut config,
+            &entry.buyer,
+            &entry.buyer,
+            unit_paid,
+            None,
+            entry.quantity,
+        )?;
+        for &token_id in &batch.token_ids {
+            hold_until_reveal(
+                deps.storage,
+                &config,
+                token_id,
+                &entry.buyer,
+                &Currency::Cw20(config.cw20_address.clone()),
+                unit_paid,
+            )?;
+        }
+        token_ids.extend(&batch.token_ids);
+        res = batch.into_response(res)?;
+        let bought = MINTS_PER_ADDRESS
+            .may_load(deps.storage, &entry.buyer)?
+            .unwrap_or_default();
+        record_address_mints(
+            deps.storage,
+            &mut config,
+            &entry.buyer,
+            bought,
+            entry.quantity,
+        )?;
+        WAITLIST.remove(deps.storage, position);
+        config.waitlist_escrow -= entry.paid;
+        released += entry.paid;
+        filled += 1;
+    }
+    let primary = Currency::Cw20(config.cw20_address.clone());
+    let mut paid_out = Uint128::zero();
+    if let Some((fee, msg)) = protocol_fee_msg(&config, &primary, released)? {
+        res = res.add_message(msg).add_attribute("protocol_fee", fee);
+        paid_out += fee;
+    }
+    if let Some((burned, msg)) = burn_msg(deps.storage, &config, &primary, released)? {
+        res = res.add_message(msg).add_attribute("burned", burned);
+        paid_out += burned;
+    }
+    record_payouts(deps.storage, &token_ids, released, paid_out)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(res.add_attribute("filled", filled.to_string()))
+}
+
+pub fn execute_cancel_waitlist(
+    deps: DepsMut,
+    info: MessageInfo,
+    position: u64,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let entry = WAITLIST
+        .may_load(deps.storage, position)?
+        .ok_or(ContractError::WaitlistEntryNotFound {})?;
+    if entry.buyer != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    WAITLIST.remove(deps.storage, position);
+    config.waitlist_escrow -= entry.paid;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_message(cw20_transfer_msg(
+            &config.cw20_address,
+            entry.buyer.as_str(),
+            entry.paid,
+        )?)
+        .add_attribute("action", "cancel_waitlist")
+        .add_attribute("position", position.to_string())
+        .add_attribute("refund", entry.paid))
+}
+
+/// Handles a token of this collection returned by `msg.sender`, which now holds it here.
+pub fn execute_receive_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: Cw721ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config
+        .trade_in
+        .as_ref()
+        .is_some_and(|trade_in| trade_in.collection == info.sender)
+    {
+        return execute_trade_in(deps, env, config, msg.sender, msg.token_id);
+    }
+    if config.cw721_address.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let hook: Cw721HookMsg = from_binary(&msg.msg).map_err(|_| ContractError::InvalidHookMsg {})?;
+    match hook {
+        Cw721HookMsg::Refund {} => execute_refund(deps, config, msg.sender, msg.token_id),
+        Cw721HookMsg::Upgrade {} => execute_upgrade(deps, config, msg.sender, msg.token_id),
+        Cw721HookMsg::List { price } => execute_list(deps, msg.sender, msg.token_id, price),
+        Cw721HookMsg::OfferRental { daily_price } => {
+            execute_offer_rental(deps, msg.sender, msg.token_id, daily_price)
+        }
+        Cw721HookMsg::Stake {} => execute_stake(deps, env, config, msg.sender, msg.token_id),
+        Cw721HookMsg::RefundEscrow {} => {
+            execute_refund_escrow(deps, config, msg.sender, msg.token_id)
+        }
+    }
+}
+
+fn execute_refund(
+    deps: DepsMut,
+    mut config: Config,
+    holder: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let bps = config.refund_bps.ok_or(ContractError::RefundDisabled {})?;
+    let id = config
+        .parse_token_id(&token_id)
+        .ok_or(ContractError::InvalidTokenId {})?;
+    let record = mints()
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::InvalidTokenId {})?;
+
+    let refund = record.price.multiply_ratio(bps, MAX_ROYALTY_BPS);
+    if let Some(payment) = ESCROWED_PAYMENTS.may_load(deps.storage, id)? {
+        release_escrow(deps.storage, id, &payment)?;
+    }
+    config.total_refunded += refund;
+    CONFIG.save(deps.storage, &config)?;
+
+    let cw721 = config.cw721_address.clone().unwrap();
+    let mut res = Response::new()
+        .add_message(cw721_execute_msg(
+            &cw721,
+            &Cw721ExecuteMsg::<Extension, Empty>::Burn {
+                token_id: token_id.clone(),
+            },
+        )?)
+        .add_attribute("action", "refund")
+        .add_attribute("holder", &holder)
+        .add_attribute("token_id", token_id)
+        .add_attribute("refund", refund);
+    if !refund.is_zero() {
+        let token = record.payment_token.unwrap_or(config.cw20_address);
+        res = res.add_message(cw20_transfer_msg(&token, &holder, refund)?);
+    }
+    Ok(res)
+}
+
+fn execute_refund_escrow(
+    deps: DepsMut,
+    config: Config,
+    holder: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    if !config.reveal_escrow {
+        return Err(ContractError::RefundDisabled {});
+    }
+    if config.revealed {
+        return Err(ContractError::AlreadyRevealed {});
+    }
+    let id = config
+        .parse_token_id(&token_id)
+        .ok_or(ContractError::InvalidTokenId {})?;
+    let payment = ESCROWED_PAYMENTS
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::EscrowNotFound {})?;
+    release_escrow(deps.storage, id, &payment)?;
+
+    let cw721 = config
+        .cw721_address
+        .ok_or(ContractError::Cw721NotLinked {})?;
+    Ok(Response::new()
+        .add_message(cw721_execute_msg(
+            &cw721,
+            &Cw721ExecuteMsg::<Extension, Empty>::Burn {
+                token_id: token_id.clone(),
+            },
+        )?)
+        .add_message(transfer_msg(&payment.currency, &holder, payment.amount)?)
+        .add_attribute("action", "refund_escrow")
+        .add_attribute("holder", holder)
+        .add_attribute("token_id", token_id)
+        .add_attribute("refund", payment.amount))
+}
+
+fn execute_upgrade(
+    deps: DepsMut,
+    mut config: Config,
+    holder: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let rule = config
+        .upgrade_rule
+        .clone()
+        .ok_or(ContractError::UpgradeDisabled {})?;
+    let holder = deps.api.addr_validate(&holder)?;
+
+    let mut deposits = UPGRADE_DEPOSITS
+        .may_load(deps.storage, &holder)?
+        .unwrap_or_default();
+    deposits.push(token_id.clone());
+
+    let res = Response::new()
+        .add_attribute("action", "upgrade")
+        .add_attribute("holder", &holder)
+        .add_attribute("token_id", token_id);
+    if (deposits.len() as u32) < rule.tokens_required {
+        UPGRADE_DEPOSITS.save(deps.storage, &holder, &deposits)?;
+        return Ok(res.add_attribute("deposited", deposits.len().to_string()));
+    }
+    UPGRADE_DEPOSITS.remove(deps.storage, &holder);
+
+    let cw721 = config.cw721_address.clone().unwrap();
+    let burns = deposits
+        .into_iter()
+        .map(|token_id| {
+            cw721_execute_msg(
+                &cw721,
+                &Cw721ExecuteMsg::<Extension, Empty>::Burn { token_id },
+            )
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let upgraded_id = format!("upgrade-{}", config.upgrades_minted);
+    let mint = cw721_mint_msg(
+        &config,
+        upgraded_id.clone(),
+        &holder,
+        rule.token_uri,
+        rule.metadata,
+    )?;
+    config.upgrades_minted += 1;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(res
+        .add_messages(burns)
+        .add_message(mint)
+        .add_attribute("upgraded_token_id", upgraded_id))
+}
+
+/// Deposits a token of the trade-in collection and, once the holder has deposited
+/// `tokens_per_mint` of them, mints them one token of the sale for free.
+fn execute_trade_in(
+    deps: DepsMut,
+    env: Env,
+    mut config: Config,
+    holder: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let trade_in = config.trade_in.clone().unwrap();
+    let holder = deps.api.addr_validate(&holder)?;
+    let key = (&trade_in.collection, &holder);
+
+    let mut deposits = TRADE_IN_DEPOSITS
+        .may_load(deps.storage, key)?
+        .unwrap_or_default();
+    deposits.push(token_id.clone());
+
+    let res = Response::new()
+        .add_attribute("action", "trade_in")
+        .add_attribute("holder", &holder)
+        .add_attribute("token_id", token_id);
+    if (deposits.len() as u32) < trade_in.tokens_per_mint {
+        TRADE_IN_DEPOSITS.save(deps.storage, key, &deposits)?;
+        return Ok(res.add_attribute("deposited", deposits.len().to_string()));
+    }
+    TRADE_IN_DEPOSITS.remove(deps.storage, key);
+
+    if config.retired {
+        return Err(ContractError::Retired {});
+    }
+    if config.closed {
+        return Err(ContractError::SaleClosed {});
+    }
+    if config.paused {
+        return Err(ContractError::ContractPaused {});
+    }
+    assert_not_blocklisted(deps.as_ref(), holder.as_str())?;
+    if config
+        .start_time
+        .is_some_and(|start| env.block.time < start)
+    {
+        return Err(ContractError::MintNotStarted {});
+    }
+    if config.end_time.is_some_and(|end| env.block.time >= end) {
+        return Err(ContractError::MintEnded {});
+    }
+    if config.cw721_address.is_none() {
+        return Err(ContractError::Uninitialized {});
+    }
+    if config.crowdfund.is_some() && !config.crowdfund_settled {
+        return Err(ContractError::CrowdfundPending {});
+    }
+    if config.randomness.is_some() {
+        return Err(ContractError::RandomMintUnsupported {});
+    }
+    if config.remaining_supply() == 0 {
+        return Err(ContractError::SoldOut {});
+    }
+    let bought = MINTS_PER_ADDRESS
+        .may_load(deps.storage, &holder)?
+        .unwrap_or_default();
+    if config
+        .mint_limit_per_address
+        .is_some_and(|limit| bought >= limit)
+    {
+        return Err(ContractError::MintLimitReached {});
+    }
+
+    let (minted, msgs, event) = mint_next(
+        deps.storage,
+        &env,
+        &mut config,
+        &holder,
+        &holder,
+        Uint128::zero(),
+        None,
+    )?;
+    record_address_mints(deps.storage, &mut config, &holder, bought, 1)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    let collection = trade_in.collection;
+    let treasury = config.treasury.as_ref().unwrap_or(&config.owner);
+    let disposals = deposits
+        .into_iter()
+        .map(|token_id| {
+            if trade_in.burn {
+                cw721_execute_msg(
+                    &collection,
+                    &Cw721ExecuteMsg::<Extension, Empty>::Burn { token_id },
+                )
+            } else {
+                cw721_execute_msg(
+                    &collection,
+                    &Cw721ExecuteMsg::<Extension, Empty>::TransferNft {
+                        recipient: treasury.to_string(),
+                        token_id,
+                    },
+                )
+            }
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(res
+        .set_data(mint_data(vec![config.token_id(minted)], &holder)?)
+        .add_submessages(msgs)
+        .add_event(event)
+        .add_messages(disposals)
+        .add_attribute("minted_token_id", config.token_id(minted)))
+}
+
+pub fn execute_cancel_trade_in(
+    deps: DepsMut,
+    info: MessageInfo,
+    collection: String,
+) -> Result<Response, ContractError> {
+    let collection = deps.api.addr_validate(&collection)?;
+    let key = (&collection, &info.sender);
+    let deposits = TRADE_IN_DEPOSITS
+        .may_load(deps.storage, key)?
+        .ok_or(ContractError::NothingDeposited {})?;
+    TRADE_IN_DEPOSITS.remove(deps.storage, key);
+
+    let returns = deposits
+        .into_iter()
+        .map(|token_id| {
+            cw721_execute_msg(
+                &collection,
+                &Cw721ExecuteMsg::<Extension, Empty>::TransferNft {
+                    recipient: info.sender.to_string(),
+                    token_id,
+                },
+            )
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(Response::new()
+        .add_messages(returns)
+        .add_attribute("action", "cancel_trade_in")
+        .add_attribute("holder", info.sender))
+}
+
+pub fn execute_cancel_upgrade(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let deposits = UPGRADE_DEPOSITS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::NothingDeposited {})?;
+    UPGRADE_DEPOSITS.remove(deps.storage, &info.sender);
+
+    let cw721 = config
+        .cw721_address
+        .ok_or(ContractError::Cw721NotLinked {})?;
+    let returns = deposits
+        .into_iter()
+        .map(|token_id| {
+            cw721_execute_msg(
+                &cw721,
+                &Cw721ExecuteMsg::<Extension, Empty>::TransferNft {
+                    recipient: info.sender.to_string(),
+                    token_id,
+                },
+            )
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(Response::new()
+        .add_messages(returns)
+        .add_attribute("action", "cancel_upgrade")
+        .add_attribute("holder", info.sender))
+}
+
+fn execute_list(
+    deps: DepsMut,
+    seller: String,
+    token_id: String,
+    price: Uint128,
+) -> Result<Response, ContractError> {
+    if price.is_zero() {
+        return Err(ContractError::InvalidListingPrice {});
+    }
+    let seller = deps.api.addr_validate(&seller)?;
+    LISTINGS.save(
+        deps.storage,
+        &token_id,
+        &Listing {
+            seller: seller.clone(),
+            price,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "list")
+        .add_attribute("seller", seller)
+        .add_attribute("token_id", token_id)
+        .add_attribute("price", price))
+}
+
+pub fn execute_cancel_listing(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let listing = LISTINGS
+        .may_load(deps.storage, &token_id)?
+        .ok_or(ContractError::ListingNotFound {})?;
+    if listing.seller != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    LISTINGS.remove(deps.storage, &token_id);
+
+    Ok(Response::new()
+        .add_message(cw721_transfer_msg(&config, &listing.seller, &token_id)?)
+        .add_attribute("action", "cancel_listing")
+        .add_attribute("token_id", token_id))
+}
+
+/// Settles a listing paid with `amount`: the royalty and market fee come out of the price,
+/// the seller gets the rest and the token goes to the buyer.
+fn execute_buy(
+    deps: DepsMut,
+    config: Config,
+    buyer: String,
+    amount: Uint128,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let listing = LISTINGS
+        .may_load(deps.storage, &token_id)?
+        .ok_or(ContractError::ListingNotFound {})?;
+    if amount < listing.price {
+        return Err(ContractError::WrongPaymentAmount {});
+    }
+    let buyer = deps.api.addr_validate(&buyer)?;
+    LISTINGS.remove(deps.storage, &token_id);
+
+    let fee = listing
+        .price
+        .multiply_ratio(config.market_fee_bps, MAX_ROYALTY_BPS);
+    let mut proceeds = listing.price.checked_sub(fee)?;
+    let mut res = Response::new()
+        .add_message(cw721_transfer_msg(&config, &buyer, &token_id)?)
+        .add_attribute("action", "buy")
+        .add_attribute("buyer", &buyer)
+        .add_attribute("seller", &listing.seller)
+        .add_attribute("token_id", token_id)
+        .add_attribute("price", listing.price)
+        .add_attribute("market_fee", fee);
+
+    if let Some(royalty) = ROYALTY.may_load(deps.storage)? {
+        let royalty_amount = listing.price.multiply_ratio(royalty.bps, MAX_ROYALTY_BPS);
+        let royalty_amount = royalty_amount.min(proceeds);
+        proceeds -= royalty_amount;
+        if !royalty_amount.is_zero() {
+            res = res.add_message(cw20_transfer_msg(
+                &config.cw20_address,
+                royalty.payment_address.as_str(),
+                royalty_amount,
+            )?);
+        }
+        res = res.add_attribute("royalty", royalty_amount);
+    }
+    if !proceeds.is_zero() {
+        res = res.add_message(cw20_transfer_msg(
+            &config.cw20_address,
+            listing.seller.as_str(),
+            proceeds,
+        )?);
+    }
+
+    let refund = amount - listing.price;
+    if !refund.is_zero() {
+        res = res
+            .add_message(cw20_transfer_msg(
+                &config.cw20_address,
+                buyer.as_str(),
+                refund,
+            )?)
+            .add_attribute("refund", refund);
+    }
+    Ok(res)
+}
+
+/// Checks and consumes the buyer's gatekeeper permit. A no-op without a gatekeeper.
+fn use_mint_permit(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    env: &Env,
+    config: &Config,
+    buyer: &Addr,
+    permit: Option<&SignedMintPermit>,
+) -> Result<(), ContractError> {
+    let gatekeeper = match &config.gatekeeper {
+        Some(gatekeeper) => gatekeeper,
+        None => return Ok(()),
+    };
+    let SignedMintPermit { permit, signature } = permit.ok_or(ContractError::PermitRequired {})?;
+
+    let mut payload = env.contract.address.as_bytes().to_vec();
+    payload.extend_from_slice(&to_binary(permit)?);
+    let hash = Sha256::digest(&payload);
+    let valid = api
+        .secp256k1_verify(&hash, signature, gatekeeper)
+        .map_err(|_| ContractError::InvalidPermit {})?;
+    if !valid || permit.address != buyer.as_str() {
+        return Err(ContractError::InvalidPermit {});
+    }
+    if env.block.time >= permit.expiry {
+        return Err(ContractError::PermitExpired {});
+    }
+    if USED_PERMITS.has(storage, (buyer, permit.nonce)) {
+        return Err(ContractError::PermitUsed {});
+    }
+    USED_PERMITS.save(storage, (buyer, permit.nonce), &Empty {})?;
+    Ok(())
+}
+
+/// Checks the recipient's signature over `intent` and consumes its nonce.
+fn use_mint_intent(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    env: &Env,
+    intent: &MintIntent,
+    signature: &Binary,
+) -> Result<(), ContractError> {
+    let recipient = api.addr_validate(&intent.recipient)?;
+    let pubkey = INTENT_KEYS
+        .may_load(storage, &recipient)?
+        .ok_or(ContractError::NoIntentKey {})?;
+
+    let mut payload = env.contract.address.as_bytes().to_vec();
+    payload.extend_from_slice(&to_binary(intent)?);
+    let hash = Sha256::digest(&payload);
+    let valid = api
+        .secp256k1_verify(&hash, signature, &pubkey)
+        .map_err(|_| ContractError::InvalidIntentSignature {})?;
+    if !valid {
+        return Err(ContractError::InvalidIntentSignature {});
+    }
+    if USED_INTENTS.has(storage, (&recipient, intent.nonce)) {
+        return Err(ContractError::IntentUsed {});
+    }
+    USED_INTENTS.save(storage, (&recipient, intent.nonce), &Empty {})?;
+    Ok(())
+}
+
+fn execute_redeem_voucher(
+    deps: DepsMut,
+    env: Env,
+    config: Config,
+    sender: String,
+    amount: Uint128,
+    voucher: Voucher,
+    signature: Binary,
+) -> Result<Response, ContractError> {
+    let signer = config
+        .voucher_signer
+        .as_ref()
+        .ok_or(ContractError::VouchersDisabled {})?;
+    if config.retired {
+        return Err(ContractError::Retired {});
+    }
+    if config.closed {
+        return Err(ContractError::SaleClosed {});
+    }
+    if config.paused {
+        return Err(ContractError::ContractPaused {});
+    }
+
+    let mut payload = env.contract.address.as_bytes().to_vec();
+    payload.extend_from_slice(&to_binary(&voucher)?);
+    let hash = Sha256::digest(&payload);
+    let valid = deps
+        .api
+        .secp256k1_verify(&hash, &signature, signer)
+        .map_err(|_| ContractError::InvalidVoucherSignature {})?;
+    if !valid {
+        return Err(ContractError::InvalidVoucherSignature {});
+    }
+
+    if env.block.time >= voucher.expiry {
+        return Err(ContractError::VoucherExpired {});
+    }
+    if config.parse_token_id(&voucher.token_id).is_some() {
+        return Err(ContractError::InvalidTokenId {});
+    }
+    if REDEEMED_VOUCHERS.has(deps.storage, &voucher.token_id) {
+        return Err(ContractError::VoucherRedeemed {});
+    }
+    assert_not_blocklisted(deps.as_ref(), &sender)?;
+    assert_not_blocklisted(deps.as_ref(), &voucher.recipient)?;
+    validate_payment(&config, amount, voucher.price)?;
+    REDEEMED_VOUCHERS.save(deps.storage, &voucher.token_id, &env.block.time)?;
+
+    // Voucher ids sit outside the numbered supply, so there is no mint record to roll back
+    // and the child mint goes out as a plain message: if it is rejected, so is the redemption.
+    let recipient = deps.api.addr_validate(&voucher.recipient)?;
+    let mint = cw721_mint_msg(
+        &config,
+        voucher.token_id.clone(),
+        &recipient,
+        voucher.token_uri,
+        None,
+    )?;
+    let hooks = mint_hook_msgs(
+        deps.storage,
+        voucher.token_id.clone(),
+        &sender,
+        voucher.price,
+    )?;
+    let event = mint_event(&voucher.token_id, &sender, &recipient, voucher.price, None);
+    let mut res = Response::new()
+        .add_message(mint)
+        .add_messages(hooks)
+        .add_event(event)
+        .add_attribute("action", "redeem_voucher")
+        .add_attribute("buyer", &sender)
+        .add_attribute("recipient", recipient)
+        .add_attribute("token_id", voucher.token_id)
+        .add_attribute("price", voucher.price);
+
+    if let Some((fee, msg)) = protocol_fee_msg(
+        &config,
+        &Currency::Cw20(config.cw20_address.clone()),
+        voucher.price,
+    )? {
+        res = res.add_message(msg).add_attribute("protocol_fee", fee);
+    }
+    if let Some((burned, msg)) = burn_msg(
+        deps.storage,
+        &config,
+        &Currency::Cw20(config.cw20_address.clone()),
+        voucher.price,
+    )? {
+        res = res.add_message(msg).add_attribute("burned", burned);
+    }
+
+    let refund = amount - voucher.price;
+    if !refund.is_zero() {
+        res = res
+            .add_message(cw20_transfer_msg(&config.cw20_address, &sender, refund)?)
+            .add_attribute("refund", refund);
+    }
+    Ok(res)
+}
+
+fn execute_collection_mint(
+    deps: DepsMut,
+    config: Config,
+    collection_id: u32,
+    sender: String,
+    recipient: String,
+    amount: Uint128,
+    quantity: u32,
+) -> Result<Response, ContractError> {
+    let mut collection = COLLECTIONS
+        .may_load(deps.storage, collection_id)?
+        .ok_or(ContractError::CollectionNotFound {})?;
+    let cw721 = collection
+        .cw721_address
+        .clone()
+        .ok_or(ContractError::Uninitialized {})?;
+    if quantity == 0 || quantity as usize > MAX_BATCH_MINTS {
+        return Err(ContractError::InvalidQuantity {});
+    }
+    if collection.max_tokens - collection.minted < quantity as u64 {
+        return Err(ContractError::SoldOut {});
+    }
+    let price = collection.unit_price.checked_mul(Uint128::from(quantity))?;
+    validate_payment(&config, amount, price)?;
+    let recipient = deps.api.addr_validate(&recipient)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "mint")
+        .add_attribute("collection_id", collection_id.to_string())
+        .add_attribute("buyer", &sender)
+        .add_attribute("price", price)
+        .add_attribute("quantity", quantity.to_string());
+    for token_id in collection.minted..collection.minted + quantity as u64 {
+        res = res
+            .add_message(cw721_execute_msg(
+                &cw721,
+                &Cw721ExecuteMsg::<Extension, Empty>::Mint(MintMsg {
+                    token_id: token_id.to_string(),
+                    owner: recipient.to_string(),
+                    token_uri: Some(collection.token_uri.clone()),
+                    extension: None,
+                }),
+            )?)
+            .add_attribute("token_id", token_id.to_string());
+    }
+    collection.minted += quantity as u64;
+    COLLECTIONS.save(deps.storage, collection_id, &collection)?;
+
+    if let Some((fee, msg)) =
+        protocol_fee_msg(&config, &Currency::Cw20(config.cw20_address.clone()), price)?
+    {
+        res = res.add_message(msg).add_attribute("protocol_fee", fee);
+    }
+    if let Some((burned, msg)) = burn_msg(
+        deps.storage,
+        &config,
+        &Currency::Cw20(config.cw20_address.clone()),
+        price,
+    )? {
+        res = res.add_message(msg).add_attribute("burned", burned);
+    }
+
+    let refund = amount - price;
+    if !refund.is_zero() {
+        res = res
+            .add_message(cw20_transfer_msg(&config.cw20_address, &sender, refund)?)
+            .add_attribute("refund", refund);
+    }
+    Ok(res)
+}
+
+/// Most rounds a single `ScheduleDrops` may create.
+const MAX_SCHEDULED_ROUNDS: u32 = 52;
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_schedule_drops(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+    first_start: Timestamp,
+    interval: u64,
+    duration: u64,
+    rounds: u32,
+    unit_price: Uint128,
+    supply: u64,
+    per_wallet_cap: Option<u32>,
+) -> Result<Response, ContractError> {
+    if duration == 0 || duration > interval || rounds == 0 || rounds > MAX_SCHEDULED_ROUNDS {
+        return Err(ContractError::InvalidDrop {});
+    }
+    let rounds = (0..rounds as u64)
+        .map(|n| {
+            let offset = n.checked_mul(interval);
+            Ok(DropRound {
+                name: format!("{} #{}", name, n + 1),
+                start_time: drop_time(first_start, offset)?,
+                end_time: Some(drop_time(
+                    first_start,
+                    offset.and_then(|offset| offset.checked_add(duration)),
+                )?),
+                unit_price,
+                supply,
+                minted: 0,
+                per_wallet_cap,
+            })
+        })
+        .collect::<Result<_, ContractError>>()?;
+    execute_add_drops(deps, info, rounds)
+}
+
+/// `start` plus `seconds`; `InvalidDrop` when the offset overflowed or lands past the last
+/// representable time.
+fn drop_time(start: Timestamp, seconds: Option<u64>) -> Result<Timestamp, ContractError> {
+    seconds
+        .and_then(|seconds| seconds.checked_mul(1_000_000_000))
+        .and_then(|nanos| start.nanos().checked_add(nanos))
+        .map(Timestamp::from_nanos)
+        .ok_or(ContractError::InvalidDrop {})
+}
+
+pub fn execute_add_drops(
+    deps: DepsMut,
+    info: MessageInfo,
+    rounds: Vec<DropRound>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    let first_id = DROP_ROUND_COUNT.may_load(deps.storage)?.unwrap_or_default();
+    let mut supply = 0;
+    for (id, round) in (first_id..).zip(&rounds) {
+        if round.supply == 0
+            || round.unit_price.is_zero()
+            || round.per_wallet_cap == Some(0)
+            || round.end_time.is_some_and(|end| end <= round.start_time)
+        {
+            return Err(ContractError::InvalidDrop {});
+        }
+        DROP_ROUNDS.save(deps.storage, id, round)?;
+        supply += round.supply;
+    }
+    DROP_ROUND_COUNT.save(deps.storage, &(first_id + rounds.len() as u32))?;
+
+    config.drop_reserved += supply;
+    let max_tokens = config.max_tokens + supply;
+    let res = set_max_tokens(deps.storage, config, max_tokens)?;
+    let res = Response::new()
+        .add_events(res.events)
+        .add_attribute("action", "add_drop")
+        .add_attribute("drop_id", first_id.to_string());
+    let res = match rounds.len() {
+        1 => res,
+        count => res.add_attribute("rounds", count.to_string()),
+    };
+    Ok(res
+        .add_attribute("supply", supply.to_string())
+        .add_attribute("max_tokens", max_tokens.to_string()))
+}
+
+/// Mints from a drop round. Per-address limits of the main sale still apply; phases,
+/// allowlists and price tiers don't.
+#[allow(clippy::too_many_arguments)]
+fn execute_drop_mint(
+    deps: DepsMut,
+    env: &Env,
+    mut config: Config,
+    drop_id: u32,
+    sender: String,
+    recipient: String,
+    amount: Uint128,
+    quantity: u32,
+) -> Result<Response, ContractError> {
+    let mut round = DROP_ROUNDS
+        .may_load(deps.storage, drop_id)?
+        .ok_or(ContractError::DropNotFound {})?;
+    if !round.is_active(env.block.time) {
+        return Err(ContractError::DropNotActive {});
+    }
+    if config.cw721_address.is_none() {
+        return Err(ContractError::Uninitialized {});
+    }
+    if config.randomness.is_some() {
+        return Err(ContractError::RandomMintUnsupported {});
+    }
+    if quantity == 0 || quantity as usize > MAX_BATCH_MINTS {
+        return Err(ContractError::InvalidQuantity {});
+    }
+    if round.supply - round.minted < quantity as u64 {
+        return Err(ContractError::SoldOut {});
+    }
+
+    let buyer = deps.api.addr_validate(&sender)?;
+    assert_mint_gate(deps.as_ref(), &config, &buyer)?;
+    let bought = MINTS_PER_ADDRESS
+        .may_load(deps.storage, &buyer)?
+        .unwrap_or_default();
+    if config
+        .mint_limit_per_address
+        .is_some_and(|limit| bought + quantity > limit)
+    {
+        return Err(ContractError::MintLimitReached {});
+    }
+    let round_bought = DROP_ROUND_MINTS
+        .may_load(deps.storage, (drop_id, &buyer))?
+        .unwrap_or_default();
+    if round
+        .per_wallet_cap
+        .is_some_and(|cap| round_bought + quantity > cap)
+    {
+        return Err(ContractError::MintLimitReached {});
+    }
+
+    let price = round.unit_price.checked_mul(Uint128::from(quantity))?;
+    validate_payment(&config, amount, price)?;
+    let recipient = deps.api.addr_validate(&recipient)?;
+
+    // Hand the round's reserved supply back to `remaining_supply` before minting from it.
+    config.drop_reserved -= quantity as u64;
+    let mut res = Response::new()
+        .add_attribute("action", "mint")
+        .add_attribute("drop_id", drop_id.to_string())
+        .add_attribute("buyer", &buyer)
+        .add_attribute("price", price)
+        .add_attribute("quantity", quantity.to_string());
+    let batch = mint_batch(
+        deps.storage,
+        env,
+        &mut config,
+        &buyer,
+        &recipient,
+        round.unit_price,
+        None,
+        quantity,
+    )?;
+    let token_ids = batch.token_ids.clone();
+    res = batch.into_response(res)?;
+    round.minted += quantity as u64;
+    DROP_ROUNDS.save(deps.storage, drop_id, &round)?;
+    DROP_ROUND_MINTS.save(deps.storage, (drop_id, &buyer), &(round_bought + quantity))?;
+    record_address_mints(deps.storage, &mut config, &buyer, bought, quantity)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    let mut paid_out = Uint128::zero();
+    if let Some((fee, msg)) =
+        protocol_fee_msg(&config, &Currency::Cw20(config.cw20_address.clone()), price)?
+    {
+        res = res.add_message(msg).add_attribute("protocol_fee", fee);
+        paid_out += fee;
+    }
+    if let Some((burned, msg)) = burn_msg(
+        deps.storage,
+        &config,
+        &Currency::Cw20(config.cw20_address.clone()),
+        price,
+    )? {
+        res = res.add_message(msg).add_attribute("burned", burned);
+        paid_out += burned;
+    }
+    record_payouts(deps.storage, &token_ids, price, paid_out)?;
+
+    let refund = amount - price;
+    if !refund.is_zero() {
+        res = res
+            .add_message(cw20_transfer_msg(&config.cw20_address, &sender, refund)?)
+            .add_attribute("refund", refund);
+    }
+    Ok(res)
+}
+
+fn execute_offer_rental(
+    deps: DepsMut,
+    lender: String,
+    token_id: String,
+    daily_price: Uint128,
+) -> Result<Response, ContractError> {
+    if daily_price.is_zero() {
+        return Err(ContractError::InvalidListingPrice {});
+    }
+    let lender = deps.api.addr_validate(&lender)?;
+    let rental = Rental {
+        lender: lender.clone(),
+        daily_price,
+        renter: None,
+        expires: None,
+    };
+    RENTALS.save(deps.storage, &token_id, &rental)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "offer_rental")
+        .add_attribute("lender", lender)
+        .add_attribute("token_id", token_id)
+        .add_attribute("daily_price", daily_price))
+}
+
+fn execute_renew(
+    deps: DepsMut,
+    env: Env,
+    config: Config,
+    sender: String,
+    amount: Uint128,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let renewal = config
+        .renewal
+        .as_ref()
+        .ok_or(ContractError::RenewalDisabled {})?;
+    let index = config
+        .parse_token_id(&token_id)
+        .ok_or(ContractError::InvalidTokenId {})?;
+    let expiry = TOKEN_EXPIRY
+        .may_load(deps.storage, index)?
+        .ok_or(ContractError::InvalidTokenId {})?;
+    if REVOKED_TOKENS.has(deps.storage, index) {
+        return Err(ContractError::TokenRevoked {});
+    }
+    if amount < renewal.price {
+        return Err(ContractError::WrongPaymentAmount {});
+    }
+
+    let expiry = expiry.max(env.block.time).plus_seconds(renewal.period);
+    TOKEN_EXPIRY.save(deps.storage, index, &expiry)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "renew")
+        .add_attribute("token_id", token_id)
+        .add_attribute("expiry", expiry.seconds().to_string());
+    let refund = amount - renewal.price;
+    if !refund.is_zero() {
+        res = res
+            .add_message(cw20_transfer_msg(&config.cw20_address, &sender, refund)?)
+            .add_attribute("refund", refund);
+    }
+    Ok(res)
+}
+
+fn execute_buy_tickets(
+    deps: DepsMut,
+    env: Env,
+    mut config: Config,
+    sender: String,
+    amount: Uint128,
+    quantity: u32,
+) -> Result<Response, ContractError> {
+    let mut raffle = RAFFLE
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoRaffle {})?;
+    if config.retired {
+        return Err(ContractError::Retired {});
+    }
+    if config.closed {
+        return Err(ContractError::SaleClosed {});
+    }
+    if config.paused {
+        return Err(ContractError::ContractPaused {});
+    }
+    if env.block.time >= raffle.end_time {
+        return Err(ContractError::MintEnded {});
+    }
+    assert_not_blocklisted(deps.as_ref(), &sender)?;
+    let holder = deps.api.addr_validate(&sender)?;
+    let mut tickets = RAFFLE_TICKETS
+        .may_load(deps.storage, &holder)?
+        .unwrap_or_default();
+    if quantity == 0 {
+        return Err(ContractError::InvalidQuantity {});
+    }
+    // Claims mint every winning ticket at once, so holdings are capped like a batch mint.
+    if tickets.len() + quantity as usize > MAX_BATCH_MINTS {
+        return Err(ContractError::MintLimitReached {});
+    }
+    let price = raffle.ticket_price.checked_mul(Uint128::from(quantity))?;
+    if amount < price {
+        return Err(ContractError::WrongPaymentAmount {});
+    }
+
+    tickets.extend(raffle.tickets_sold..raffle.tickets_sold + quantity);
+    raffle.tickets_sold += quantity;
+    RAFFLE_TICKETS.save(deps.storage, &holder, &tickets)?;
+    RAFFLE.save(deps.storage, &raffle)?;
+    config.raffle_escrow += price;
+    CONFIG.save(deps.storage, &config)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "buy_tickets")
+        .add_attribute("holder", &holder)
+        .add_attribute("quantity", quantity.to_string());
+    let refund = amount - price;
+    if !refund.is_zero() {
+        res = res
+            .add_message(cw20_transfer_msg(&config.cw20_address, &sender, refund)?)
+            .add_attribute("refund", refund);
+    }
+    Ok(res)
+}
+
+fn execute_bid(
+    deps: DepsMut,
+    env: Env,
+    mut config: Config,
+    sender: String,
+    amount: Uint128,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let mut auction = AUCTIONS
+        .may_load(deps.storage, &token_id)?
+        .ok_or(ContractError::AuctionNotFound {})?;
+    if config.paused {
+        return Err(ContractError::ContractPaused {});
+    }
+    if env.block.time >= auction.end_time {
+        return Err(ContractError::AuctionEnded {});
+    }
+    assert_not_blocklisted(deps.as_ref(), &sender)?;
+    if amount < auction.reserve_price
+        || auction
+            .highest_bid
+            .as_ref()
+            .is_some_and(|bid| amount <= bid.amount)
+    {
+        return Err(ContractError::BidTooLow {});
+    }
+
+    let bidder = deps.api.addr_validate(&sender)?;
+    let previous = auction.highest_bid.replace(AuctionBid {
+        bidder: bidder.clone(),
+        amount,
+    });
+    AUCTIONS.save(deps.storage, &token_id, &auction)?;
+    config.auction_escrow += amount;
+
+    let mut res = Response::new()
+        .add_attribute("action", "bid")
+        .add_attribute("token_id", token_id)
+        .add_attribute("bidder", bidder)
+        .add_attribute("amount", amount);
+    if let Some(previous) = previous {
+        config.auction_escrow -= previous.amount;
+        res = res
+            .add_message(cw20_transfer_msg(
+                &config.cw20_address,
+                previous.bidder.as_str(),
+                previous.amount,
+            )?)
+            .add_attribute("refunded", previous.bidder);
+    }
+    CONFIG.save(deps.storage, &config)?;
+    Ok(res)
+}
+
+fn execute_rent(
+    deps: DepsMut,
+    env: Env,
+    config: Config,
+    renter: String,
+    amount: Uint128,
+    token_id: String,
+    days: u32,
+) -> Result<Response, ContractError> {
+    let mut rental = RENTALS
+        .may_load(deps.storage, &token_id)?
+        .ok_or(ContractError::RentalNotFound {})?;
+    if rental.is_rented(env.block.time) {
+        return Err(ContractError::TokenRented {});
+    }
+    if days == 0 {
+        return Err(ContractError::InvalidQuantity {});
+    }
+    let price = rental.daily_price.checked_mul(Uint128::from(days))?;
+    if amount < price {
+        return Err(ContractError::WrongPaymentAmount {});
+    }
+
+    let renter = deps.api.addr_validate(&renter)?;
+    let expires = env.block.time.plus_seconds(days as u64 * 86_400);
+    rental.renter = Some(renter.clone());
+    rental.expires = Some(expires);
+    RENTALS.save(deps.storage, &token_id, &rental)?;
+
+    let mut res = Response::new()
+        .add_message(cw20_transfer_msg(
+            &config.cw20_address,
+            rental.lender.as_str(),
+            price,
+        )?)
+        .add_attribute("action", "rent")
+        .add_attribute("renter", &renter)
+        .add_attribute("token_id", token_id)
+        .add_attribute("expires", expires.seconds().to_string());
+    let refund = amount - price;
+    if !refund.is_zero() {
+        res = res
+            .add_message(cw20_transfer_msg(
+                &config.cw20_address,
+                renter.as_str(),
+                refund,
+            )?)
+            .add_attribute("refund", refund);
+    }
+    Ok(res)
+}
+
+pub fn execute_withdraw_rental(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let rental = RENTALS
+        .may_load(deps.storage, &token_id)?
+        .ok_or(ContractError::RentalNotFound {})?;
+    if rental.lender != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if rental.is_rented(env.block.time) {
+        return Err(ContractError::TokenRented {});
+    }
+    RENTALS.remove(deps.storage, &token_id);
+
+    Ok(Response::new()
+        .add_message(cw721_transfer_msg(&config, &rental.lender, &token_id)?)
+        .add_attribute("action", "withdraw_rental")
+        .add_attribute("token_id", token_id))
+}
+
+fn execute_stake(
+    deps: DepsMut,
+    env: Env,
+    config: Config,
+    owner: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let staking = config.staking.ok_or(ContractError::StakingDisabled {})?;
+    let owner = deps.api.addr_validate(&owner)?;
+    let stake = Stake {
+        owner: owner.clone(),
+        staked_height: env.block.height,
+        reward_index: staking.index_at(env.block.height)?,
+    };
+    STAKES.save(deps.storage, &token_id, &stake)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "stake")
+        .add_attribute("owner", owner)
+        .add_attribute("token_id", token_id))
+}
+
+fn execute_fund_staking_rewards(
+    deps: DepsMut,
+    mut config: Config,
+    token: Addr,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let staking = config
+        .staking
+        .as_ref()
+        .ok_or(ContractError::StakingDisabled {})?;
+    if staking.reward_token != token {
+        return Err(ContractError::UnauthorizedTokenContract {});
+    }
+    config.staking_pool += amount;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "fund_staking_rewards")
+        .add_attribute("amount", amount)
+        .add_attribute("pool", config.staking_pool))
+}
+
+pub fn execute_set_staking_rewards(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    reward_token: String,
+    reward_per_block: Uint128,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    let reward_token = deps.api.addr_validate(&reward_token)?;
+
+    let reward_index = match &config.staking {
+        Some(staking) => {
+            if staking.reward_token != reward_token && !config.staking_pool.is_zero() {
+                return Err(ContractError::InvalidStakingConfig {});
+            }
+            staking.index_at(env.block.height)?
+        }
+        None => Uint128::zero(),
+    };
+    config.staking = Some(Staking {
+        reward_token: reward_token.clone(),
+        reward_per_block,
+        reward_index,
+        index_height: env.block.height,
+    });
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_staking_rewards")
+        .add_attribute("reward_token", reward_token)
+        .add_attribute("reward_per_block", reward_per_block))
+}
+
+/// Rewards accrued by `stake` and the index to record once they are paid. Payouts are capped
+/// at `pool`; anything beyond it is forfeited.
+fn pending_stake_rewards(
+    config: &Config,
+    stake: &Stake,
+    height: u64,
+    pool: Uint128,
+) -> StdResult<(Uint128, Uint128)> {
+    match &config.staking {
+        Some(staking) => {
+            let index = staking.index_at(height)?;
+            let accrued = index.saturating_sub(stake.reward_index);
+            Ok((accrued.min(pool), index))
+        }
+        None => Ok((Uint128::zero(), stake.reward_index)),
+    }
+}
+
+pub fn execute_claim_staking_rewards(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_ids: Vec<String>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let mut total = Uint128::zero();
+    for token_id in &token_ids {
+        let mut stake = STAKES
+            .may_load(deps.storage, token_id)?
+            .ok_or(ContractError::StakeNotFound {})?;
+        if stake.owner != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+        let (reward, index) =
+            pending_stake_rewards(&config, &stake, env.block.height, config.staking_pool)?;
+        config.staking_pool -= reward;
+        total += reward;
+        stake.reward_index = index;
+        STAKES.save(deps.storage, token_id, &stake)?;
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "claim_staking_rewards")
+        .add_attribute("owner", &info.sender)
+        .add_attribute("reward", total);
+    if let Some(staking) = config.staking.as_ref().filter(|_| !total.is_zero()) {
+        res = res.add_message(cw20_transfer_msg(
+            &staking.reward_token,
+            info.sender.as_str(),
+            total,
+        )?);
+    }
+    Ok(res)
+}
+
+pub fn execute_unstake(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let stake = STAKES
+        .may_load(deps.storage, &token_id)?
+        .ok_or(ContractError::StakeNotFound {})?;
+    if stake.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let (reward, _) =
+        pending_stake_rewards(&config, &stake, env.block.height, config.staking_pool)?;
+    config.staking_pool -= reward;
+    CONFIG.save(deps.storage, &config)?;
+    STAKES.remove(deps.storage, &token_id);
+
+    let mut res = Response::new()
+        .add_message(cw721_transfer_msg(&config, &stake.owner, &token_id)?)
+        .add_attribute("action", "unstake")
+        .add_attribute("token_id", token_id)
+        .add_attribute("reward", reward);
+    if let Some(staking) = config.staking.as_ref().filter(|_| !reward.is_zero()) {
+        res = res.add_message(cw20_transfer_msg(
+            &staking.reward_token,
+            stake.owner.as_str(),
+            reward,
+        )?);
+    }
+    Ok(res)
+}
+
+pub fn execute_reserve_mint(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    quantity: u32,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    authorize(deps.storage, &config, &info.sender, Permission::Airdrop)?;
+    assert_free_mint_allowed(&config, quantity)?;
+
+    let recipient = deps.api.addr_validate(&recipient)?;
+    let mut res = Response::new()
+        .add_attribute("action", "reserve_mint")
+        .add_attribute("recipient", &recipient)
+        .add_attribute("quantity", quantity.to_string());
+    let batch = mint_batch(
+        deps.storage,
+        &env,
+        &mut config,
+        &recipient,
+        &recipient,
+        Uint128::zero(),
+        None,
+        quantity,
+    )?;
+    res = batch.into_response(res)?;
+    config.reserved_minted += quantity as u64;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(res)
+}
+
+/// Pulls `unit_price * quantity` from the sender's cw20 allowance and mints.
+pub fn execute_mint_with_allowance(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    quantity: u32,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config.commit_reveal_blocks.is_some() {
+        return Err(ContractError::CommitmentRequired {});
+    }
+    let phase = active_phase(deps.storage, &env)?;
+    let price = mint_price(
+        deps.storage,
+        &env,
+        &config,
+        phase.as_ref().map(|(id, phase)| (*id, phase)),
+        &info.sender,
+        quantity,
+        false,
+    )?;
+
+    let options = MintOptions {
+        quantity: Some(quantity),
+        ..MintOptions::default()
+    };
+    let mut res = execute_mint(
+        deps,
+        env.clone(),
+        config.cw20_address.clone(),
+        info.sender.to_string(),
+        price,
+        options,
+    )?;
+    let transfer_from = WasmMsg::Execute {
+        contract_addr: config.cw20_address.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+            owner: info.sender.to_string(),
+            recipient: env.contract.address.to_string(),
+            amount: price,
+        })?,
+        funds: vec![],
+    };
+    // Collect the payment before any mint message runs.
+    res.messages.insert(0, SubMsg::new(transfer_from));
+    Ok(res.add_attribute("payment", "allowance"))
+}
+
+pub fn execute_update_unit_price(
+    deps: DepsMut,
+    info: MessageInfo,
+    price: Uint128,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    authorize(deps.storage, &config, &info.sender, Permission::UpdatePrice)?;
+    update_unit_price(deps.storage, config, price)
+}
+
+fn update_unit_price(
+    storage: &mut dyn Storage,
+    mut config: Config,
+    price: Uint128,
+) -> Result<Response, ContractError> {
+    assert_not_frozen(&config)?;
+    if price == Uint128::new(0) {
+        return Err(ContractError::InvalidUnitPrice {});
+    }
+
+    let old_price = config.unit_price;
+    config.unit_price = price;
+    CONFIG.save(storage, &config)?;
+
+    Ok(Response::new()
+        .add_event(config_update_event("unit_price", price))
+        .add_attribute("action", "update_unit_price")
+        .add_attribute("old_price", old_price)
+        .add_attribute("new_price", price))
+}
+
+pub fn execute_set_price_tiers(
+    deps: DepsMut,
+    info: MessageInfo,
+    tiers: Vec<PriceTier>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    authorize(deps.storage, &config, &info.sender, Permission::UpdatePrice)?;
+    assert_not_frozen(&config)?;
+
+    if tiers
+        .iter()
+        .any(|tier| tier.supply == 0 || tier.price.is_zero())
+    {
+        return Err(ContractError::InvalidPriceTiers {});
+    }
+
+    config.price_tiers = tiers;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_event(config_update_event(
+            "price_tiers",
+            config.price_tiers.len().to_string(),
+        ))
+        .add_attribute("action", "set_price_tiers")
+        .add_attribute("tiers", config.price_tiers.len().to_string()))
+}
+
+pub fn execute_update_token_prices(
+    deps: DepsMut,
+    info: MessageInfo,
+    set: Vec<(String, Uint128)>,
+    unset: Vec<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    authorize(deps.storage, &config, &info.sender, Permission::UpdatePrice)?;
+    assert_not_frozen(&config)?;
+    if config.shuffle_token_ids {
+        return Err(ContractError::TokenPricesUnsupported {});
+    }
+
+    let unsold_index = |token_id: &str| match config.parse_token_id(token_id) {
+        Some(index) if index >= config.unused_token_id => Ok(index),
+        _ => Err(ContractError::InvalidTokenId {}),
+    };
+    for (token_id, price) in &set {
+        if price.is_zero() {
+            return Err(ContractError::InvalidTokenPrice {});
+        }
+        TOKEN_PRICES.save(deps.storage, unsold_index(token_id)?, price)?;
+    }
+    for token_id in &unset {
+        TOKEN_PRICES.remove(deps.storage, unsold_index(token_id)?);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "update_token_prices")
+        .add_attribute("set", set.len().to_string())
+        .add_attribute("unset", unset.len().to_string()))
+}
+
+pub fn execute_add_to_allowlist(
+    deps: DepsMut,
+    info: MessageInfo,
+    entries: Vec<AllowlistEntryMsg>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    authorize(
+        deps.storage,
+        &config,
+        &info.sender,
+        Permission::ManageAllowlist,
+    )?;
+
+    let count = entries.len();
+    for entry in entries {
+        let address = deps.api.addr_validate(&entry.address)?;
+        let existing = ALLOWLIST.may_load(deps.storage, &address)?;
+        if existing.is_none() {
+            config.allowlist_count += 1;
+        }
+        let entry = AllowlistEntry {
+            price: entry.price,
+            mint_limit: existing.and_then(|existing| existing.mint_limit),
+            expires: entry.expires,
+        };
+        ALLOWLIST.save(deps.storage, &address, &entry)?;
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_to_allowlist")
+        .add_attribute("count", count.to_string()))
+}
+
+pub fn execute_remove_from_allowlist(
+    deps: DepsMut,
+    info: MessageInfo,
+    addresses: Vec<String>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    authorize(
+        deps.storage,
+        &config,
+        &info.sender,
+        Permission::ManageAllowlist,
+    )?;
+
+    let count = addresses.len();
+    for address in addresses {
+        let address = deps.api.addr_validate(&address)?;
+        if ALLOWLIST.has(deps.storage, &address) {
+            ALLOWLIST.remove(deps.storage, &address);
+            config.allowlist_count -= 1;
+        }
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_from_allowlist")
+        .add_attribute("count", count.to_string()))
+}
+
+pub fn execute_update_allowlist(
+    deps: DepsMut,
+    info: MessageInfo,
+    add: Vec<(String, u32)>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    authorize(
+        deps.storage,
+        &config,
+        &info.sender,
+        Permission::ManageAllowlist,
+    )?;
+
+    let mut seen = BTreeSet::new();
+    for (address, mint_limit) in &add {
+        let address = deps.api.addr_validate(address)?;
+        if *mint_limit == 0 {
+            return Err(ContractError::InvalidQuantity {});
+        }
+        if !seen.insert(address.clone()) {
+            return Err(ContractError::DuplicateAllowlistEntry {});
+        }
+        let existing = ALLOWLIST.may_load(deps.storage, &address)?;
+        if existing.is_none() {
+            config.allowlist_count += 1;
+        }
+        let entry = AllowlistEntry {
+            price: existing.as_ref().and_then(|existing| existing.price),
+            mint_limit: Some(*mint_limit),
+            expires: existing.and_then(|existing| existing.expires),
+        };
+        ALLOWLIST.save(deps.storage, &address, &entry)?;
+    }
+    for address in &remove {
+        let address = deps.api.addr_validate(address)?;
+        if !seen.insert(address.clone()) {
+            return Err(ContractError::DuplicateAllowlistEntry {});
+        }
+        if ALLOWLIST.has(deps.storage, &address) {
+            ALLOWLIST.remove(deps.storage, &address);
+            config.allowlist_count -= 1;
+        }
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_allowlist")
+        .add_attribute("added", add.len().to_string())
+        .add_attribute("removed", remove.len().to_string())
+        .add_attribute("allowlist_count", config.allowlist_count.to_string()))
+}
+
+pub fn execute_update_merkle_root(
+    deps: DepsMut,
+    info: MessageInfo,
+    root: Option<Binary>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    authorize(
+        deps.storage,
+        &config,
+        &info.sender,
+        Permission::ManageAllowlist,
+    )?;
+    if root.as_ref().is_some_and(|root| root.len() != 32) {
+        return Err(ContractError::InvalidMerkleRoot {});
+    }
+
+    config.merkle_root = root;
+    config.merkle_version += 1;
+    CONFIG.save(deps.storage, &config)?;
+
+    let root = config
+        .merkle_root
+        .as_ref()
+        .map_or_else(|| String::from("none"), Binary::to_base64);
+    Ok(Response::new()
+        .add_attribute("action", "update_merkle_root")
+        .add_attribute("root", root)
+        .add_attribute("version", config.merkle_version.to_string()))
+}
+
+pub fn execute_set_mint_gate(
+    deps: DepsMut,
+    info: MessageInfo,
+    gate: Option<MintGateMsg>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    config.mint_gate = gate
+        .as_ref()
+        .map(|gate| validate_mint_gate(deps.api, gate))
+        .transpose()?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_mint_gate")
+        .add_attribute("gated", config.mint_gate.is_some().to_string()))
+}
+
+pub fn execute_set_swap_route(
+    deps: DepsMut,
+    info: MessageInfo,
+    route: Option<SwapRouteMsg>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    config.swap_route = match route {
+        Some(route) => {
+            if route.max_slippage_bps > MAX_ROYALTY_BPS {
+                return Err(ContractError::InvalidSwapRoute {});
+            }
+            let ask = match route.ask {
+                Currency::Cw20(token) => Currency::Cw20(deps.api.addr_validate(token.as_str())?),
+                Currency::Native(denom) => {
+                    validate_native_denom(&denom)?;
+                    Currency::Native(denom)
+                }
+            };
+            Some(SwapRoute {
+                router: deps.api.addr_validate(&route.router)?,
+                ask,
+                max_slippage_bps: route.max_slippage_bps,
+            })
+        }
+        None => None,
+    };
+    CONFIG.save(deps.storage, &config)?;
+
+    let res = Response::new().add_attribute("action", "set_swap_route");
+    Ok(match &config.swap_route {
+        Some(route) => res
+            .add_attribute("router", &route.router)
+            .add_attribute("ask", route.ask.to_string()),
+        None => res,
+    })
+}
+
+pub fn execute_set_membership(
+    deps: DepsMut,
+    info: MessageInfo,
+    membership: Option<MembershipMsg>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    config.membership = membership
+        .as_ref()
+        .map(|membership| validate_membership(deps.api, membership))
+        .transpose()?;
+    CONFIG.save(deps.storage, &config)?;
+
+    let res = Response::new().add_attribute("action", "set_membership");
+    Ok(match &config.membership {
+        Some(membership) => res.add_attribute("group", &membership.group),
+        None => res,
+    })
+}
+
+pub fn execute_snapshot_holders(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    restart: bool,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    let cw721 = config
+        .cw721_address
+        .ok_or(ContractError::Uninitialized {})?;
+
+    let mut snapshot = match HOLDER_SNAPSHOT.may_load(deps.storage)? {
+        Some(snapshot) if !restart => snapshot,
+        _ => {
+            SNAPSHOT_HOLDERS.clear(deps.storage);
+            HolderSnapshot {
+                height: env.block.height,
+                last_token: None,
+                complete: false,
+            }
+        }
+    };
+    if snapshot.complete {
+        return Err(ContractError::SnapshotComplete {});
+    }
+
+    let limit = clamp_limit(limit);
+    let page: TokensResponse = deps.querier.query_wasm_smart(
+        &cw721,
+        &Cw721QueryMsg::AllTokens {
+            start_after: snapshot.last_token.clone(),
+            limit: Some(limit as u32),
+        },
+    )?;
+    for token_id in &page.tokens {
+        let owner: cw721::OwnerOfResponse = deps.querier.query_wasm_smart(
+            &cw721,
+            &Cw721QueryMsg::OwnerOf {
+                token_id: token_id.clone(),
+                include_expired: None,
+            },
+        )?;
+        let owner = deps.api.addr_validate(&owner.owner)?;
+        SNAPSHOT_HOLDERS.update(deps.storage, &owner, |tokens| -> StdResult<_> {
+            Ok(tokens.unwrap_or_default() + 1)
+        })?;
+    }
+    snapshot.complete = page.tokens.len() < limit;
+    if let Some(last) = page.tokens.last() {
+        snapshot.last_token = Some(last.clone());
+    }
+    HOLDER_SNAPSHOT.save(deps.storage, &snapshot)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "snapshot_holders")
+        .add_attribute("height", snapshot.height.to_string())
+        .add_attribute("tokens", page.tokens.len().to_string())
+        .add_attribute("complete", snapshot.complete.to_string()))
+}
+
+pub fn execute_set_trade_in(
+    deps: DepsMut,
+    info: MessageInfo,
+    trade_in: Option<TradeInMsg>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    assert_not_frozen(&config)?;
+
+    if let Some(trade_in) = &trade_in {
+        if trade_in.tokens_per_mint == 0 || trade_in.tokens_per_mint as usize > MAX_BATCH_MINTS {
+            return Err(ContractError::InvalidTradeIn {});
+        }
+    }
+    config.trade_in = trade_in
+        .map(|trade_in| -> StdResult<_> {
+            Ok(TradeIn {
+                collection: deps.api.addr_validate(&trade_in.collection)?,
+                tokens_per_mint: trade_in.tokens_per_mint,
+                burn: trade_in.burn,
+            })
+        })
+        .transpose()?;
+    if config
+        .trade_in
+        .as_ref()
+        .map(|trade_in| &trade_in.collection)
+        == config.cw721_address.as_ref()
+    {
+        return Err(ContractError::InvalidTradeIn {});
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    let mut res = Response::new().add_attribute("action", "set_trade_in");
+    if let Some(trade_in) = &config.trade_in {
+        res = res
+            .add_attribute("collection", &trade_in.collection)
+            .add_attribute("tokens_per_mint", trade_in.tokens_per_mint.to_string());
+    }
+    Ok(res)
+}
+
+pub fn execute_set_partner_discount(
+    deps: DepsMut,
+    info: MessageInfo,
+    discount: Option<PartnerDiscountMsg>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    assert_not_frozen(&config)?;
+
+    config.partner_discount = discount
+        .as_ref()
+        .map(|discount| validate_partner_discount(deps.api, discount))
+        .transpose()?;
+    CONFIG.save(deps.storage, &config)?;
+
+    let mut res = Response::new().add_attribute("action", "set_partner_discount");
+    if let Some(partner) = &config.partner_discount {
+        res = res
+            .add_attribute("collection", &partner.collection)
+            .add_attribute("discount_bps", partner.discount_bps.to_string());
+    }
+    Ok(res)
+}
+
+pub fn execute_update_protocol_fee(
+    deps: DepsMut,
+    info: MessageInfo,
+    fee: Option<ProtocolFeeMsg>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if config
+        .protocol_fee
+        .as_ref()
+        .is_none_or(|fee| fee.collector != info.sender)
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    config.protocol_fee = fee
+        .as_ref()
+        .map(|fee| validate_protocol_fee(deps.api, fee))
+        .transpose()?;
+    CONFIG.save(deps.storage, &config)?;
+
+    let value = config
+        .protocol_fee
+        .as_ref()
+        .map_or_else(|| String::from("none"), |fee| fee.bps.to_string());
+    let mut res = Response::new()
+        .add_event(config_update_event("protocol_fee", value))
+        .add_attribute("action", "update_protocol_fee");
+    if let Some(fee) = &config.protocol_fee {
+        res = res
+            .add_attribute("collector", &fee.collector)
+            .add_attribute("bps", fee.bps.to_string());
+    }
+    Ok(res)
+}
+
+pub fn execute_set_burn_bps(
+    deps: DepsMut,
+    info: MessageInfo,
+    bps: Option<u16>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    assert_not_frozen(&config)?;
+    if bps.is_some_and(|bps| bps == 0 || bps > MAX_ROYALTY_BPS) {
+        return Err(ContractError::InvalidBurnBps {});
+    }
+
+    config.burn_bps = bps;
+    CONFIG.save(deps.storage, &config)?;
+
+    let value = bps.map_or_else(|| String::from("none"), |bps| bps.to_string());
+    Ok(Response::new()
+        .add_event(config_update_event("burn_bps", value.clone()))
+        .add_attribute("action", "set_burn_bps")
+        .add_attribute("burn_bps", value))
+}
+
+pub fn execute_set_referral_bps(
+    deps: DepsMut,
+    info: MessageInfo,
+    bps: Option<u16>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    if bps.unwrap_or(0) > MAX_ROYALTY_BPS {
+        return Err(ContractError::InvalidReferralBps {});
+    }
+    config.referral_bps = bps;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_event(config_update_event(
+            "referral_bps",
+            bps.unwrap_or(0).to_string(),
+        ))
+        .add_attribute("action", "set_referral_bps")
+        .add_attribute("bps", bps.unwrap_or(0).to_string()))
+}
+
+pub fn execute_set_phase(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: u8,
+    phase: Phase,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    assert_not_frozen(&config)?;
+
+    let free = phase.free_supply.is_some();
+    if phase.end_time.is_some_and(|end| end <= phase.start_time)
+        || phase.price.is_some_and(|price| price.is_zero()) != free
+        || phase.free_supply == Some(0)
+        || phase.per_wallet_cap == Some(0)
+    {
+        return Err(ContractError::InvalidPhase {});
+    }
+    for item in PHASES.range(deps.storage, None, None, Order::Ascending) {
+        let (other_id, other) = item?;
+        let starts_before_other_ends = other.end_time.is_none_or(|end| phase.start_time < end);
+        let ends_after_other_starts = phase.end_time.is_none_or(|end| other.start_time < end);
+        if other_id != id && starts_before_other_ends && ends_after_other_starts {
+            return Err(ContractError::InvalidPhase {});
+        }
+    }
+    PHASES.save(deps.storage, id, &phase)?;
+
+    let event = phase_change_event("set")
+        .add_attribute("phase_id", id.to_string())
+        .add_attribute("name", &phase.name)
+        .add_attribute("start_time", phase.start_time.seconds().to_string());
+    Ok(Response::new()
+        .add_event(event)
+        .add_attribute("action", "set_phase")
+        .add_attribute("phase_id", id.to_string())
+        .add_attribute("name", phase.name))
+}
+
+pub fn execute_remove_phase(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: u8,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    if !PHASES.has(deps.storage, id) {
+        return Err(ContractError::PhaseNotFound {});
+    }
+    PHASES.remove(deps.storage, id);
+
+    Ok(Response::new()
+        .add_event(phase_change_event("removed").add_attribute("phase_id", id.to_string()))
+        .add_attribute("action", "remove_phase")
+        .add_attribute("phase_id", id.to_string()))
+}
+
+pub fn execute_add_to_phase_allowlist(
+    deps: DepsMut,
+    info: MessageInfo,
+    phase_id: u8,
+    entries: Vec<PhaseAllowlistEntryMsg>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    authorize(
+        deps.storage,
+        &config,
+        &info.sender,
+        Permission::ManageAllowlist,
+    )?;
+    if !PHASES.has(deps.storage, phase_id) {
+        return Err(ContractError::PhaseNotFound {});
+    }
+
+    let count = entries.len();
+    for entry in entries {
+        if entry.price.is_some_and(|price| price.is_zero()) || entry.mint_limit == Some(0) {
+            return Err(ContractError::InvalidPhase {});
+        }
+        let address = deps.api.addr_validate(&entry.address)?;
+        PHASE_ALLOWLISTS.save(
+            deps.storage,
+            (phase_id, &address),
+            &PhaseAllowlistEntry {
+                price: entry.price,
+                mint_limit: entry.mint_limit,
+            },
+        )?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "add_to_phase_allowlist")
+        .add_attribute("phase_id", phase_id.to_string())
+        .add_attribute("count", count.to_string()))
+}
+
+pub fn execute_remove_from_phase_allowlist(
+    deps: DepsMut,
+    info: MessageInfo,
+    phase_id: u8,
+    addresses: Vec<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    authorize(
+        deps.storage,
+        &config,
+        &info.sender,
+        Permission::ManageAllowlist,
+    )?;
+
+    let count = addresses.len();
+    for address in addresses {
+        let address = deps.api.addr_validate(&address)?;
+        PHASE_ALLOWLISTS.remove(deps.storage, (phase_id, &address));
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_from_phase_allowlist")
+        .add_attribute("phase_id", phase_id.to_string())
+        .add_attribute("count", count.to_string()))
+}
+
+pub fn execute_set_randomness(
+    deps: DepsMut,
+    info: MessageInfo,
+    randomness: Option<RandomnessMsg>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    if randomness.is_none() && config.pending_mints > 0 {
+        return Err(ContractError::RandomnessPending {});
+    }
+
+    config.randomness = randomness
+        .map(|randomness| -> StdResult<_> {
+            Ok(Randomness {
+                proxy: deps.api.addr_validate(&randomness.proxy)?,
+                fee: randomness.fee,
+            })
+        })
+        .transpose()?;
+    CONFIG.save(deps.storage, &config)?;
+
+    let mut res = Response::new().add_attribute("action", "set_randomness");
+    if let Some(randomness) = &config.randomness {
+        res = res.add_attribute("proxy", &randomness.proxy);
+    }
+    Ok(res)
+}
+
+pub fn execute_nois_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    callback: NoisCallback,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if config
+        .randomness
+        .as_ref()
+        .map(|randomness| &randomness.proxy)
+        != Some(&info.sender)
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+    if callback.job_id == RAFFLE_JOB_ID {
+        let mut raffle = RAFFLE
+            .may_load(deps.storage)?
+            .filter(|raffle| raffle.drawing)
+            .ok_or(ContractError::RandomnessJobNotFound {})?;
+        raffle.drawing = false;
+        raffle.seed = Some(callback.randomness);
+        RAFFLE.save(deps.storage, &raffle)?;
+        return Ok(Response::new()
+            .add_attribute("action", "nois_receive")
+            .add_attribute("job_id", callback.job_id));
+    }
+    let job = PENDING_MINTS
+        .may_load(deps.storage, &callback.job_id)?
+        .ok_or(ContractError::RandomnessJobNotFound {})?;
+    PENDING_MINTS.remove(deps.storage, &callback.job_id);
+    LATEST_RANDOMNESS.save(deps.storage, &callback.randomness)?;
+    config.pending_mints -= job.quantity as u64;
+
+    let mut res = Response::new()
+        .add_attribute("action", "nois_receive")
+        .add_attribute("job_id", &callback.job_id)
+        .add_attribute("buyer", &job.buyer);
+    let currency = Currency::Cw20(
+        job.payment_token
+            .clone()
+            .unwrap_or_else(|| config.cw20_address.clone()),
+    );
+    let batch = mint_batch(
+        deps.storage,
+        &env,
+        &mut config,
+        &job.buyer,
+        &job.recipient,
+        job.unit_paid,
+        job.payment_token.clone().map(Currency::Cw20),
+        job.quantity,
+    )?;
+    let total = job.unit_paid * Uint128::from(job.quantity);
+    record_payouts(deps.storage, &batch.token_ids, total, job.paid_out)?;
+    for &token_id in &batch.token_ids {
+        hold_until_reveal(
+            deps.storage,
+            &config,
+            token_id,
+            &job.buyer,
+            &currency,
+            job.unit_paid,
+        )?;
+    }
+    res = batch.into_response(res)?;
+    CONFIG.save(deps.storage, &config)?;
+    Ok(res)
+}
+
+/// Records `job` and builds the proxy request for its beacon. The tokens stay reserved in
+/// `pending_mints` until the callback mints them.
+fn request_randomness(
+    storage: &mut dyn Storage,
+    config: &mut Config,
+    randomness: &Randomness,
+    job: PendingMint,
+) -> StdResult<(String, CosmosMsg)> {
+    let id = RANDOMNESS_JOB_COUNT.may_load(storage)?.unwrap_or_default();
+    RANDOMNESS_JOB_COUNT.save(storage, &(id + 1))?;
+    let job_id = format!("mint-{id}");
+    config.pending_mints += job.quantity as u64;
+    PENDING_MINTS.save(storage, &job_id, &job)?;
+
+    let request = WasmMsg::Execute {
+        contract_addr: randomness.proxy.to_string(),
+        msg: to_binary(&NoisProxyExecuteMsg::GetNextRandomness {
+            job_id: job_id.clone(),
+        })?,
+        funds: randomness.fee.clone(),
+    };
+    Ok((job_id, request.into()))
+}
+
+pub fn execute_set_trait_table(
+    deps: DepsMut,
+    info: MessageInfo,
+    traits: Vec<TraitCategory>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    assert_not_frozen(&config)?;
+
+    if !config.onchain_metadata {
+        return Err(ContractError::OnchainMetadataDisabled {});
+    }
+    let mut trait_types = BTreeSet::new();
+    if traits.len() > MAX_METADATA_ATTRIBUTES
+        || traits.iter().any(|category| {
+            !trait_types.insert(category.trait_type.as_str())
+                || category.trait_type.is_empty()
+                || category.values.is_empty()
+                || category.values.iter().any(|value| value.weight == 0)
+        })
+    {
+        return Err(ContractError::InvalidTraitTable {});
+    }
+    TRAIT_TABLE.save(deps.storage, &traits)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_trait_table")
+        .add_attribute("categories", traits.len().to_string()))
+}
+
+pub fn execute_set_token_metadata(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_id: u64,
+    metadata: Metadata,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    assert_not_frozen(&config)?;
+
+    if !config.onchain_metadata {
+        return Err(ContractError::OnchainMetadataDisabled {});
+    }
+    if !config.open_edition && token_id >= config.max_tokens {
+        return Err(ContractError::InvalidTokenId {});
+    }
+    if mints().has(deps.storage, token_id) {
+        return Err(ContractError::TokenAlreadyMinted {});
+    }
+    TOKEN_METADATA.save(deps.storage, token_id, &metadata)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_token_metadata")
+        .add_attribute("token_id", token_id.to_string()))
+}
+
+pub fn execute_set_custom_metadata(
+    deps: DepsMut,
+    info: MessageInfo,
+    allowed: bool,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    assert_not_frozen(&config)?;
+
+    if allowed && !config.onchain_metadata {
+        return Err(ContractError::OnchainMetadataDisabled {});
+    }
+    config.custom_metadata = allowed;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_custom_metadata")
+        .add_attribute("allowed", allowed.to_string()))
+}
+
+pub fn execute_set_allowed_uri_prefixes(
+    deps: DepsMut,
+    info: MessageInfo,
+    prefixes: Vec<String>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    assert_not_frozen(&config)?;
+
+    if prefixes
+        .iter()
+        .any(|prefix| prefix.is_empty() || prefix.len() > MAX_METADATA_FIELD_LENGTH)
+    {
+        return Err(ContractError::InvalidMetadata {});
+    }
+    config.allowed_uri_prefixes = prefixes;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_allowed_uri_prefixes")
+        .add_attribute("count", config.allowed_uri_prefixes.len().to_string()))
+}
+
+/// A buyer-supplied URI must stay inside one of the allowed prefixes, so `..` segments that
+/// could climb out of them are refused.
+fn validate_buyer_token_uri(config: &Config, token_uri: &str) -> Result<(), ContractError> {
+    let allowed = config
+        .allowed_uri_prefixes
+        .iter()
+        .any(|prefix| token_uri.starts_with(prefix.as_str()));
+    if !allowed || token_uri.len() > MAX_METADATA_FIELD_LENGTH || token_uri.contains("..") {
+        return Err(ContractError::TokenUriNotAllowed {});
+    }
+    Ok(())
+}
+
+/// Bounds buyer-supplied metadata so a single mint cannot bloat the collection's storage.
+fn validate_custom_metadata(metadata: &Metadata) -> Result<(), ContractError> {
+    let attributes = metadata.attributes.as_deref().unwrap_or_default();
+    if attributes.len() > MAX_METADATA_ATTRIBUTES {
+        return Err(ContractError::InvalidMetadata {});
+    }
+    let fields = [
+        &metadata.image,
+        &metadata.image_data,
+        &metadata.external_url,
+        &metadata.description,
+        &metadata.name,
+        &metadata.background_color,
+        &metadata.animation_url,
+        &metadata.youtube_url,
+    ];
+    let too_long = |field: &str| field.len() > MAX_METADATA_FIELD_LENGTH;
+    if fields
+        .iter()
+        .any(|field| field.as_deref().is_some_and(too_long))
+        || attributes.iter().any(|attr| {
+            too_long(&attr.trait_type)
+                || too_long(&attr.value)
+                || attr.display_type.as_deref().is_some_and(too_long)
+        })
+    {
+        return Err(ContractError::InvalidMetadata {});
+    }
+    Ok(())
+}
+
+pub fn execute_update_token_uri(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: String,
+    uri: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    assert_not_frozen(&config)?;
+
+    let id = config
+        .parse_token_id(&token_id)
+        .ok_or(ContractError::InvalidTokenId {})?;
+    let update = set_token_uri(deps.storage, &env, &config, id, &uri)?;
+    Ok(Response::new()
+        .add_message(update)
+        .add_attribute("action", "update_token_uri")
+        .add_attribute("token_id", token_id)
+        .add_attribute("uri", uri))
+}
+
+pub fn execute_ai_oracle_callback(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: String,
+    token_uri: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config
+        .ai_oracle
+        .as_ref()
+        .is_none_or(|oracle| oracle.contract != info.sender)
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+    let id = config
+        .parse_token_id(&token_id)
+        .ok_or(ContractError::InvalidTokenId {})?;
+    if AI_METADATA.may_load(deps.storage, id)? != Some(AiMetadataStatus::Pending) {
+        return Err(ContractError::AiMetadataNotPending {});
+    }
+    AI_METADATA.save(deps.storage, id, &AiMetadataStatus::Completed)?;
+    let update = set_token_uri(deps.storage, &env, &config, id, &token_uri)?;
+
+    Ok(Response::new()
+        .add_message(update)
+        .add_attribute("action", "ai_oracle_callback")
+        .add_attribute("token_id", token_id)
+        .add_attribute("uri", token_uri))
+}
+
+/// Records `uri` as mint `id`'s URI and builds the child update that applies it.
+fn set_token_uri(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &Config,
+    id: u64,
+    uri: &str,
+) -> Result<WasmMsg, ContractError> {
+    if uri.is_empty() || uri.len() > MAX_METADATA_FIELD_LENGTH {
+        return Err(ContractError::InvalidTokenUri {});
+    }
+    let mut record = mints()
+        .may_load(storage, id)?
+        .ok_or(ContractError::InvalidTokenId {})?;
+    let cw721 = config
+        .cw721_address
+        .clone()
+        .ok_or(ContractError::Cw721NotLinked {})?;
+
+    record.token_uri = Some(uri.to_string());
+    record.uri_updated = Some(env.block.time);
+    mints().save(storage, id, &record)?;
+
+    Ok(WasmMsg::Execute {
+        contract_addr: cw721.into_string(),
+        msg: to_binary(
+            &Cw721ExecuteMsg::<Extension, Cw721UriExtensionMsg>::Extension {
+                msg: Cw721UriExtensionMsg::UpdateTokenUri {
+                    token_id: config.token_id(id),
+                    token_uri: uri.to_string(),
+                },
+            },
+        )?,
+        funds: vec![],
+    })
+}
+
+pub fn execute_reveal(
+    deps: DepsMut,
+    info: MessageInfo,
+    base_uri: String,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    assert_not_frozen(&config)?;
+
+    if config.placeholder_uri.is_none() {
+        return Err(ContractError::RevealNotConfigured {});
+    }
+    if config.revealed {
+        return Err(ContractError::AlreadyRevealed {});
+    }
+
+    config.revealed = true;
+    config.token_uri = base_uri.clone();
+    config.token_uri_mode = TokenUriMode::Base;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "reveal")
+        .add_attribute("base_uri", base_uri)
+        .add_attribute("minted", config.unused_token_id.to_string())
+        .add_attribute("escrow_released", config.reveal_escrow.to_string()))
+}
+
+pub fn execute_freeze(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    assert_not_frozen(&config)?;
+
+    config.frozen = true;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", "freeze"))
+}
+
+pub fn execute_reduce_supply(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_max: u64,
+    freeze: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    if new_max > config.max_tokens {
+        return Err(ContractError::InvalidMaxTokens {});
+    }
+
+    let old_max_tokens = config.max_tokens;
+    set_max_tokens(deps.storage, config, new_max)?;
+    if freeze {
+        CONFIG.update(deps.storage, |mut config| -> StdResult<_> {
+            config.frozen = true;
+            Ok(config)
+        })?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "reduce_supply")
+        .add_attribute("old_max_tokens", old_max_tokens.to_string())
+        .add_attribute("new_max_tokens", new_max.to_string())
+        .add_attribute("frozen", freeze.to_string()))
+}
+
+pub fn execute_increase_supply(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_max: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config.renounced {
+        return Err(ContractError::OwnershipRenounced {});
+    }
+    if config.supply_governor.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if new_max <= config.max_tokens {
+        return Err(ContractError::InvalidMaxTokens {});
+    }
+    Ok(set_max_tokens(deps.storage, config, new_max)?.add_attribute("authority", "governor"))
+}
+
+pub fn execute_shutdown(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    if config.closed {
+        return Err(ContractError::SaleClosed {});
+    }
+    if config.crowdfund.is_some() && !config.crowdfund_settled {
+        return Err(ContractError::CrowdfundPending {});
+    }
+
+    config.closed = true;
+    CONFIG.save(deps.storage, &config)?;
+
+    let mut tokens = vec![Currency::Cw20(config.cw20_address.clone())];
+    for item in PAYMENT_TOKENS.keys(deps.storage, None, None, Order::Ascending) {
+        tokens.push(Currency::Cw20(item?));
+    }
+    if let Some(denom) = &config.native_denom {
+        tokens.push(Currency::Native(denom.clone()));
+    }
+    let mut res = Response::new()
+        .add_event(phase_change_event("closed"))
+        .add_attribute("action", "shutdown");
+    for token in tokens {
+        let balance = match &config.vesting {
+            Some(vesting) => vested_amounts(deps.as_ref(), &env, &config, vesting, &token)?.2,
+            None => available_balance(deps.as_ref(), &env, &config, &token)?,
+        };
+        if !balance.is_zero() {
+            if config.vesting.is_some() {
+                record_vested_withdrawal(deps.storage, &token, balance)?;
+            }
+            res = res.add_event(withdraw_event(&token, balance));
+            res = add_payouts(deps.storage, res, &config, &token, balance)?;
+        }
+    }
+    Ok(res)
+}
+
+pub fn execute_finalize(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let end = match config.end_time {
+        Some(end) if config.open_edition => end,
+        _ => return Err(ContractError::InvalidMintWindow {}),
+    };
+    if env.block.time < end {
+        return Err(ContractError::MintNotEnded {});
+    }
+    if config.final_supply.is_some() {
+        return Err(ContractError::EditionFinalized {});
+    }
+    finalize_edition(deps.storage, config)
+}
+
+fn finalize_edition(
+    storage: &mut dyn Storage,
+    mut config: Config,
+) -> Result<Response, ContractError> {
+    config.final_supply = Some(config.unused_token_id);
+    CONFIG.save(storage, &config)?;
+    Ok(Response::new()
+        .add_attribute("action", "finalize")
+        .add_attribute("final_supply", config.unused_token_id.to_string()))
+}
+
+pub fn execute_set_refund_bps(
+    deps: DepsMut,
+    info: MessageInfo,
+    bps: Option<u16>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    if bps.unwrap_or(0) > MAX_ROYALTY_BPS {
+        return Err(ContractError::InvalidRefundBps {});
+    }
+    config.refund_bps = bps;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_event(config_update_event(
+            "refund_bps",
+            bps.map_or(String::from("none"), |bps| bps.to_string()),
+        ))
+        .add_attribute("action", "set_refund_bps")
+        .add_attribute(
+            "bps",
+            bps.map_or(String::from("none"), |bps| bps.to_string()),
+        ))
+}
+
+pub fn execute_set_upgrade_rule(
+    deps: DepsMut,
+    info: MessageInfo,
+    rule: Option<UpgradeRule>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    assert_not_frozen(&config)?;
+
+    if let Some(rule) = &rule {
+        if rule.tokens_required == 0 || rule.tokens_required as usize > MAX_BATCH_MINTS {
+            return Err(ContractError::InvalidUpgradeRule {});
+        }
+    }
+    config.upgrade_rule = rule;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", "set_upgrade_rule"))
+}
+
+pub fn execute_settle_crowdfund(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    let crowdfund = config
+        .crowdfund
+        .as_ref()
+        .ok_or(ContractError::InvalidCrowdfund {})?;
+    if config.crowdfund_settled {
+        return Err(ContractError::InvalidCrowdfund {});
+    }
+    if config.crowdfund_sold < crowdfund.soft_cap {
+        return Err(ContractError::CrowdfundTargetNotMet {});
+    }
+    config.crowdfund_settled = true;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "settle_crowdfund")
+        .add_attribute("sold", config.crowdfund_sold.to_string()))
+}
+
+pub fn execute_claim_pledge(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let crowdfund = config
+        .crowdfund
+        .clone()
+        .ok_or(ContractError::InvalidCrowdfund {})?;
+    let pledge = PLEDGES
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::NothingDeposited {})?;
+
+    let failed = env.block.time >= crowdfund.deadline && config.crowdfund_sold < crowdfund.soft_cap;
+    if !config.crowdfund_settled && !failed {
+        return Err(ContractError::CrowdfundPending {});
+    }
+    PLEDGES.remove(deps.storage, &info.sender);
+
+    let mut res = Response::new()
+        .add_attribute("holder", &info.sender)
+        .add_attribute("quantity", pledge.token_ids.len().to_string());
+    if config.crowdfund_settled {
+        for token_id in pledge.token_ids {
+            res = res.add_message(cw721_transfer_msg(
+                &config,
+                &info.sender,
+                &config.token_id(token_id),
+            )?);
+        }
+        return Ok(res.add_attribute("action", "claim_pledge"));
+    }
+
+    let cw721 = config
+        .cw721_address
+        .clone()
+        .ok_or(ContractError::Cw721NotLinked {})?;
+    for token_id in &pledge.token_ids {
+        res = res.add_message(cw721_execute_msg(
+            &cw721,
+            &Cw721ExecuteMsg::<Extension, Empty>::Burn {
+                token_id: config.token_id(*token_id),
+            },
+        )?);
+    }
+    config.crowdfund_sold -= pledge.token_ids.len() as u64;
+    CONFIG.save(deps.storage, &config)?;
+    if !pledge.paid.is_zero() {
+        res = res.add_message(cw20_transfer_msg(
+            &config.cw20_address,
+            info.sender.as_str(),
+            pledge.paid,
+        )?);
+    }
+
+    Ok(res
+        .add_attribute("action", "refund_pledge")
+        .add_attribute("refund", pledge.paid))
+}
+
+pub fn execute_add_payment_token(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    price: Uint128,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    assert_not_frozen(&config)?;
+
+    if price.is_zero() {
+        return Err(ContractError::InvalidUnitPrice {});
+    }
+    let token = deps.api.addr_validate(&address)?;
+    if token == config.cw20_address {
+        return Err(ContractError::PaymentTokenNotAccepted {});
+    }
+    PAYMENT_TOKENS.save(deps.storage, &token, &price)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_payment_token")
+        .add_attribute("token", token)
+        .add_attribute("price", price))
+}
+
+pub fn execute_update_prices(
+    deps: DepsMut,
+    info: MessageInfo,
+    prices: Vec<CurrencyPrice>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    authorize(deps.storage, &config, &info.sender, Permission::UpdatePrice)?;
+    assert_not_frozen(&config)?;
+
+    let mut res = Response::new().add_attribute("action", "update_prices");
+    for CurrencyPrice { currency, price } in prices {
+        if price.is_zero() {
+            return Err(ContractError::InvalidUnitPrice {});
+        }
+        match &currency {
+            Currency::Cw20(address) => {
+                let token = deps.api.addr_validate(address.as_str())?;
+                if token == config.cw20_address {
+                    config.unit_price = price;
+                } else {
+                    PAYMENT_TOKENS.save(deps.storage, &token, &price)?;
+                }
+            }
+            Currency::Native(denom) => {
+                if config.native_denom.as_ref() != Some(denom) {
+                    return Err(ContractError::PaymentTokenNotAccepted {});
+                }
+                config.native_price = Some(price);
+            }
+        }
+        res = res
+            .add_attribute("currency", currency.to_string())
+            .add_attribute("price", price);
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(res)
+}
+
+pub fn execute_remove_payment_token(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    let token = deps.api.addr_validate(&address)?;
+    if !PAYMENT_TOKENS.has(deps.storage, &token) {
+        return Err(ContractError::PaymentTokenNotAccepted {});
+    }
+    PAYMENT_TOKENS.remove(deps.storage, &token);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_payment_token")
+        .add_attribute("token", token))
+}
+
+pub fn execute_add_mint_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    let hook = deps.api.addr_validate(&addr)?;
+    if MINT_HOOKS.has(deps.storage, &hook) {
+        return Err(ContractError::MintHookAlreadyRegistered {});
+    }
+    let count = MINT_HOOKS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .count();
+    if count >= MAX_MINT_HOOKS {
+        return Err(ContractError::TooManyMintHooks {});
+    }
+    MINT_HOOKS.save(deps.storage, &hook, &Empty {})?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_mint_hook")
+        .add_attribute("hook", hook))
+}
+
+pub fn execute_remove_mint_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    let hook = deps.api.addr_validate(&addr)?;
+    if !MINT_HOOKS.has(deps.storage, &hook) {
+        return Err(ContractError::MintHookNotFound {});
+    }
+    MINT_HOOKS.remove(deps.storage, &hook);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_mint_hook")
+        .add_attribute("hook", hook))
+}
+
+pub fn execute_cw721_passthrough(
+    deps: DepsMut,
+    info: MessageInfo,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    let cw721 = config
+        .cw721_address
+        .clone()
+        .ok_or(ContractError::Cw721NotLinked {})?;
+
+    let token_id = match from_binary::<Cw721ExecuteMsg<Extension, Empty>>(&msg) {
+        Ok(Cw721ExecuteMsg::Mint(_)) => return Err(ContractError::MintPassthroughNotAllowed {}),
+        // An operator could move every token held in custody.
+        Ok(Cw721ExecuteMsg::ApproveAll { .. }) => return Err(ContractError::TokenInCustody {}),
+        Ok(Cw721ExecuteMsg::TransferNft { token_id, .. })
+        | Ok(Cw721ExecuteMsg::SendNft { token_id, .. })
+        | Ok(Cw721ExecuteMsg::Approve { token_id, .. })
+        | Ok(Cw721ExecuteMsg::Revoke { token_id, .. })
+        | Ok(Cw721ExecuteMsg::Burn { token_id }) => Some(token_id),
+        _ => None,
+    };
+    if let Some(token_id) = token_id {
+        if held_in_custody(deps.storage, &config, &cw721, &token_id)? {
+            return Err(ContractError::TokenInCustody {});
+        }
+    }
+
+    Ok(Response::new()
+        .add_message(WasmMsg::Execute {
+            contract_addr: cw721.into_string(),
+            msg,
+            funds: vec![],
+        })
+        .add_attribute("action", "cw721_execute"))
+}
+
+/// Whether this contract holds child token `token_id` for someone else: staked, rented out,
+/// listed, pledged to the crowdfund or deposited towards an upgrade or trade-in.
+fn held_in_custody(
+    storage: &dyn Storage,
+    config: &Config,
+    cw721: &Addr,
+    token_id: &str,
+) -> StdResult<bool> {
+    if STAKES.has(storage, token_id)
+        || RENTALS.has(storage, token_id)
+        || LISTINGS.has(storage, token_id)
+    {
+        return Ok(true);
+    }
+    if let Some(index) = config.parse_token_id(token_id) {
+        for pledge in PLEDGES.range(storage, None, None, Order::Ascending) {
+            if pledge?.1.token_ids.contains(&index) {
+                return Ok(true);
+            }
+        }
+    }
+    for deposits in UPGRADE_DEPOSITS.range(storage, None, None, Order::Ascending) {
+        if deposits?.1.iter().any(|id| id == token_id) {
+            return Ok(true);
+        }
+    }
+    for deposits in TRADE_IN_DEPOSITS
+        .prefix(cw721)
+        .range(storage, None, None, Order::Ascending)
+    {
+        if deposits?.1.iter().any(|id| id == token_id) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+pub fn execute_transfer_minter(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_minter: String,
+    expiry: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    if config.retired {
+        return Err(ContractError::Retired {});
+    }
+    if config.pending_mints > 0 {
+        return Err(ContractError::RandomnessPending {});
+    }
+    let cw721 = config
+        .cw721_address
+        .clone()
+        .ok_or(ContractError::Cw721NotLinked {})?;
+    if config.cw721_version == Cw721Version::Legacy {
+        return Err(ContractError::Cw721OwnershipUnsupported {});
+    }
+    let new_minter = deps.api.addr_validate(&new_minter)?;
+
+    config.retired = true;
+    CONFIG.save(deps.storage, &config)?;
+
+    let transfer = WasmMsg::Execute {
+        contract_addr: cw721.into_string(),
+        msg: to_binary(&Cw721OwnershipMsg::UpdateOwnership(
+            Cw721OwnershipAction::TransferOwnership {
+                new_owner: new_minter.to_string(),
+                expiry,
+            },
+        ))?,
+        funds: vec![],
+    };
+    Ok(Response::new()
+        .add_message(transfer)
+        .add_event(phase_change_event("retired").add_attribute("new_minter", &new_minter))
+        .add_attribute("action", "transfer_minter")
+        .add_attribute("new_minter", new_minter))
+}
+
+pub fn execute_set_operator(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    perms: OperatorPerms,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    let operator = deps.api.addr_validate(&address)?;
+    OPERATORS.save(deps.storage, &operator, &perms)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_operator")
+        .add_attribute("operator", operator)
+        .add_attribute("can_pause", perms.can_pause.to_string())
+        .add_attribute(
+            "can_manage_allowlist",
+            perms.can_manage_allowlist.to_string(),
+        )
+        .add_attribute("can_airdrop", perms.can_airdrop.to_string())
+        .add_attribute("can_update_price", perms.can_update_price.to_string())
+        .add_attribute("can_withdraw", perms.can_withdraw.to_string()))
+}
+
+pub fn execute_remove_operator(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    let operator = deps.api.addr_validate(&address)?;
+    if !OPERATORS.has(deps.storage, &operator) {
+        return Err(ContractError::OperatorNotFound {});
+    }
+    OPERATORS.remove(deps.storage, &operator);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_operator")
+        .add_attribute("operator", operator))
+}
+
+pub fn execute_update_payment_token(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    assert_payment_token_switchable(&config)?;
+
+    let address = deps.api.addr_validate(&address)?;
+    if address == config.cw20_address || PAYMENT_TOKENS.has(deps.storage, &address) {
+        return Err(ContractError::PaymentTokenNotAccepted {});
+    }
+    query_cw20_info(deps.as_ref(), &address)?;
+    let pending = PendingPaymentToken {
+        address,
+        effective_at: env.block.time.plus_seconds(PAYMENT_TOKEN_TIMELOCK_SECONDS),
+    };
+    PENDING_PAYMENT_TOKEN.save(deps.storage, &pending)?;
+
+    let event = config_update_event("pending_payment_token", pending.address.as_str())
+        .add_attribute("effective_at", pending.effective_at.seconds().to_string());
+    Ok(Response::new()
+        .add_event(event)
+        .add_attribute("action", "update_payment_token")
+        .add_attribute("token", pending.address)
+        .add_attribute("effective_at", pending.effective_at.seconds().to_string()))
+}
+
+pub fn execute_apply_payment_token(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let pending = PENDING_PAYMENT_TOKEN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingPaymentToken {})?;
+    if env.block.time < pending.effective_at {
+        return Err(ContractError::PaymentTokenTimelocked {});
+    }
+    assert_payment_token_switchable(&config)?;
+
+    let token_info = query_cw20_info(deps.as_ref(), &pending.address)?;
+    let previous = config.cw20_address;
+    config.cw20_address = pending.address;
+    config.cw20_symbol = Some(token_info.symbol);
+    config.cw20_decimals = Some(token_info.decimals);
+    CONFIG.save(deps.storage, &config)?;
+    PENDING_PAYMENT_TOKEN.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_event(config_update_event(
+            "payment_token",
+            config.cw20_address.as_str(),
+        ))
+        .add_attribute("action", "apply_payment_token")
+        .add_attribute("previous_token", previous)
+        .add_attribute("token", config.cw20_address))
+}
+
+/// Reads `TokenInfo` from `token`, failing when it is not a cw20 contract.
+fn query_cw20_info(deps: Deps, token: &Addr) -> Result<Cw20TokenInfoResponse, ContractError> {
+    deps.querier
+        .query_wasm_smart(token, &Cw20QueryMsg::TokenInfo {})
+        .map_err(|_| ContractError::InvalidPaymentToken {})
+}
+
+/// Waitlist and mint deposits and crowdfund pledges are refunded in `cw20_address`, so it
+/// cannot change while any are outstanding.
+fn assert_payment_token_switchable(config: &Config) -> Result<(), ContractError> {
+    if !config.waitlist_escrow.is_zero()
+        || !config.deposit_escrow.is_zero()
+        || (config.crowdfund.is_some() && !config.crowdfund_settled)
+    {
+        return Err(ContractError::PaymentTokenInUse {});
+    }
+    Ok(())
+}
+
+pub fn execute_update_blocklist(
+    deps: DepsMut,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    for address in &add {
+        let address = deps.api.addr_validate(address)?;
+        BLOCKLIST.save(deps.storage, &address, &Empty {})?;
+    }
+    for address in &remove {
+        let address = deps.api.addr_validate(address)?;
+        BLOCKLIST.remove(deps.storage, &address);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "update_blocklist")
+        .add_attribute("added", add.len().to_string())
+        .add_attribute("removed", remove.len().to_string()))
+}
+
+pub fn execute_set_price_oracle(
+    deps: DepsMut,
+    info: MessageInfo,
+    oracle: Option<PriceOracleMsg>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    assert_not_frozen(&config)?;
+
+    config.price_oracle = oracle
+        .map(|oracle| validate_price_oracle(deps.as_ref(), oracle))
+        .transpose()?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", "set_price_oracle"))
+}
+
+pub fn execute_set_ics721_bridge(
+    deps: DepsMut,
+    info: MessageInfo,
+    bridge: Option<Ics721BridgeMsg>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    config.ics721_bridge = match bridge {
+        Some(bridge) => {
+            if bridge.channel_id.is_empty() || bridge.timeout_seconds == 0 {
+                return Err(ContractError::InvalidIcs721Bridge {});
+            }
+            Some(Ics721Bridge {
+                bridge: deps.api.addr_validate(&bridge.bridge)?,
+                channel_id: bridge.channel_id,
+                timeout_seconds: bridge.timeout_seconds,
+            })
+        }
+        None => None,
+    };
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", "set_ics721_bridge"))
+}
+
+pub fn execute_set_voucher_signer(
+    deps: DepsMut,
+    info: MessageInfo,
+    pubkey: Option<Binary>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    config.voucher_signer = pubkey;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", "set_voucher_signer"))
+}
+
+pub fn execute_set_gatekeeper(
+    deps: DepsMut,
+    info: MessageInfo,
+    pubkey: Option<Binary>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    config.gatekeeper = pubkey;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", "set_gatekeeper"))
+}
+
+pub fn execute_update_relayers(
+    deps: DepsMut,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    for address in &add {
+        let address = deps.api.addr_validate(address)?;
+        RELAYERS.save(deps.storage, &address, &Empty {})?;
+    }
+    for address in &remove {
+        let address = deps.api.addr_validate(address)?;
+        RELAYERS.remove(deps.storage, &address);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "update_relayers")
+        .add_attribute("added", add.len().to_string())
+        .add_attribute("removed", remove.len().to_string()))
+}
+
+pub fn execute_set_intent_key(
+    deps: DepsMut,
+    info: MessageInfo,
+    pubkey: Option<Binary>,
+) -> Result<Response, ContractError> {
+    match pubkey {
+        Some(pubkey) => INTENT_KEYS.save(deps.storage, &info.sender, &pubkey)?,
+        None => INTENT_KEYS.remove(deps.storage, &info.sender),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_intent_key")
+        .add_attribute("address", info.sender))
+}
+
+pub fn execute_revoke_expired(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_ids: Vec<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    if !config
+        .renewal
+        .as_ref()
+        .is_some_and(|renewal| renewal.revocable)
+    {
+        return Err(ContractError::RenewalNotRevocable {});
+    }
+
+    for token_id in &token_ids {
+        let index = config
+            .parse_token_id(token_id)
+            .ok_or(ContractError::InvalidTokenId {})?;
+        let expiry = TOKEN_EXPIRY
+            .may_load(deps.storage, index)?
+            .ok_or(ContractError::InvalidTokenId {})?;
+        if env.block.time < expiry {
+            return Err(ContractError::TokenNotExpired {});
+        }
+        REVOKED_TOKENS.save(deps.storage, index, &Empty {})?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_expired")
+        .add_attribute("revoked", token_ids.len().to_string()))
+}
+
+pub fn execute_migrate_cw721(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_code_id: u64,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    let cw721 = config
+        .cw721_address
+        .ok_or(ContractError::Cw721NotLinked {})?;
+
+    Ok(Response::new()
+        .add_message(WasmMsg::Migrate {
+            contract_addr: cw721.to_string(),
+            new_code_id,
+            msg,
+        })
+        .add_attribute("action", "migrate_cw721")
+        .add_attribute("cw721", cw721)
+        .add_attribute("new_code_id", new_code_id.to_string()))
+}
+
+pub fn execute_update_token_code_id(
+    deps: DepsMut,
+    info: MessageInfo,
+    code_id: u64,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    set_token_code_id(deps.storage, &mut config, code_id)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_event(config_update_event("token_code_id", code_id.to_string()))
+        .add_attribute("action", "update_token_code_id")
+        .add_attribute("code_id", code_id.to_string()))
+}
+
+/// Records `code_id` in the code id history, seeding it with the current code id for
+/// contracts instantiated before the history was kept.
+fn set_token_code_id(
+    storage: &mut dyn Storage,
+    config: &mut Config,
+    code_id: u64,
+) -> StdResult<()> {
+    let mut history = TOKEN_CODE_IDS
+        .may_load(storage)?
+        .unwrap_or_else(|| vec![config.token_code_id]);
+    if history.last() != Some(&code_id) {
+        history.push(code_id);
+    }
+    TOKEN_CODE_IDS.save(storage, &history)?;
+    config.token_code_id = code_id;
+    Ok(())
+}
+
+pub fn execute_start_raffle(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    end_time: Timestamp,
+    ticket_price: Uint128,
+    winners: u32,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    assert_not_frozen(&config)?;
+
+    if RAFFLE.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::RaffleExists {});
+    }
+    if winners == 0 || ticket_price.is_zero() || end_time <= env.block.time {
+        return Err(ContractError::InvalidRaffle {});
+    }
+    if config.cw721_address.is_none() {
+        return Err(ContractError::Uninitialized {});
+    }
+    if config.remaining_supply() < winners as u64 {
+        return Err(ContractError::SoldOut {});
+    }
+
+    let raffle = Raffle {
+        end_time,
+        ticket_price,
+        winners,
+        tickets_sold: 0,
+        drawing: false,
+        seed: None,
+    };
+    RAFFLE.save(deps.storage, &raffle)?;
+    config.raffle_reserved += winners as u64;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "start_raffle")
+        .add_attribute("winners", winners.to_string())
+        .add_attribute("ticket_price", ticket_price))
+}
+
+/// Job id of the raffle draw's randomness request.
+const RAFFLE_JOB_ID: &str = "raffle";
+
+/// Seeds the draw from the randomness provider when configured. Without one the seed comes
+/// from block data, which a validator could influence.
+/// Without a randomness provider the seed comes from the block, which a participant could
+/// pick, so only the owner may draw.
+pub fn execute_draw_raffle(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let mut raffle = RAFFLE
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoRaffle {})?;
+    if env.block.time < raffle.end_time {
+        return Err(ContractError::RaffleOpen {});
+    }
+    if raffle.drawing || raffle.seed.is_some() {
+        return Err(ContractError::RaffleDrawn {});
+    }
+    if config.randomness.is_none() {
+        assert_owner(&config, &info.sender)?;
+    }
+
+    // Supply reserved beyond the tickets sold goes back to the sale.
+    let unsold = raffle.winners.saturating_sub(raffle.tickets_sold);
+    raffle.winners -= unsold;
+    config.raffle_reserved -= unsold as u64;
+    CONFIG.save(deps.storage, &config)?;
+
+    let res = Response::new()
+        .add_attribute("action", "draw_raffle")
+        .add_attribute("tickets_sold", raffle.tickets_sold.to_string());
+    match &config.randomness {
+        Some(randomness) => {
+            raffle.drawing = true;
+            RAFFLE.save(deps.storage, &raffle)?;
+            Ok(res.add_message(WasmMsg::Execute {
+                contract_addr: randomness.proxy.to_string(),
+                msg: to_binary(&NoisProxyExecuteMsg::GetNextRandomness {
+                    job_id: String::from(RAFFLE_JOB_ID),
+                })?,
+                funds: randomness.fee.clone(),
+            }))
+        }
+        None => {
+            let mut hasher = Sha256::new();
+            hasher.update(env.block.height.to_be_bytes());
+            hasher.update(env.block.time.nanos().to_be_bytes());
+            hasher.update(raffle.tickets_sold.to_be_bytes());
+            raffle.seed = Some(HexBinary::from(hasher.finalize().to_vec()));
+            RAFFLE.save(deps.storage, &raffle)?;
+            Ok(res)
+        }
+    }
+}
+
+pub fn execute_claim_raffle(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let raffle = RAFFLE
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoRaffle {})?;
+    if raffle.seed.is_none() {
+        return Err(ContractError::RaffleNotDrawn {});
+    }
+    let holder = info.sender;
+    let tickets = RAFFLE_TICKETS
+        .may_load(deps.storage, &holder)?
+        .ok_or(ContractError::NoTickets {})?;
+    RAFFLE_TICKETS.remove(deps.storage, &holder);
+
+    let wins = tickets
+        .iter()
+        .filter(|ticket| raffle.is_winner(**ticket))
+        .count() as u32;
+    let paid = raffle
+        .ticket_price
+        .checked_mul(Uint128::from(tickets.len() as u128))?;
+    config.raffle_escrow -= paid;
+    config.raffle_reserved -= wins as u64;
+
+    let mut res = Response::new()
+        .add_attribute("action", "claim_raffle")
+        .add_attribute("holder", &holder)
+        .add_attribute("wins", wins.to_string());
+    if wins > 0 {
+        let bought = MINTS_PER_ADDRESS
+            .may_load(deps.storage, &holder)?
+            .unwrap_or_default();
+        let batch = mint_batch(
+            deps.storage,
+            &env,
+            &mut config,
+            &holder,
+            &holder,
+            raffle.ticket_price,
+            None,
+            wins,
+        )?;
+        res = batch.into_response(res)?;
+        record_address_mints(deps.storage, &mut config, &holder, bought, wins)?;
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    let refund = raffle
+        .ticket_price
+        .checked_mul(Uint128::from(tickets.len() as u32 - wins))?;
+    if !refund.is_zero() {
+        res = res
+            .add_message(cw20_transfer_msg(
+                &config.cw20_address,
+                holder.as_str(),
+                refund,
+            )?)
+            .add_attribute("refund", refund);
+    }
+    Ok(res)
+}
+
+pub fn execute_create_auction(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: String,
+    token_uri: String,
+    reserve_price: Uint128,
+    end_time: Timestamp,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    if config.parse_token_id(&token_id).is_some() || AUCTIONS.has(deps.storage, &token_id) {
+        return Err(ContractError::InvalidTokenId {});
+    }
+    if reserve_price.is_zero() || end_time <= env.block.time {
+        return Err(ContractError::InvalidAuction {});
+    }
+    if config.cw721_address.is_none() {
+        return Err(ContractError::Uninitialized {});
+    }
+    let auction = Auction {
+        token_uri,
+        reserve_price,
+        end_time,
+        highest_bid: None,
+        settled: false,
+    };
+    AUCTIONS.save(deps.storage, &token_id, &auction)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_auction")
+        .add_attribute("token_id", token_id)
+        .add_attribute("reserve_price", reserve_price)
+        .add_attribute("end_time", end_time.seconds().to_string()))
+}
+
+pub fn execute_settle_auction(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let mut auction = AUCTIONS
+        .may_load(deps.storage, &token_id)?
+        .ok_or(ContractError::AuctionNotFound {})?;
+    let is_winner = auction
+        .highest_bid
+        .as_ref()
+        .is_some_and(|bid| bid.bidder == info.sender);
+    if !is_winner {
+        assert_owner(&config, &info.sender)?;
+    }
+    if env.block.time < auction.end_time {
+        return Err(ContractError::AuctionOpen {});
+    }
+    if auction.settled {
+        return Err(ContractError::AuctionSettled {});
+    }
+    auction.settled = true;
+    AUCTIONS.save(deps.storage, &token_id, &auction)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "settle_auction")
+        .add_attribute("token_id", &token_id);
+    let bid = match auction.highest_bid {
+        Some(bid) => bid,
+        None => return Ok(res),
+    };
+    config.auction_escrow -= bid.amount;
+    CONFIG.save(deps.storage, &config)?;
+
+    let mint = cw721_mint_msg(
+        &config,
+        token_id.clone(),
+        &bid.bidder,
+        auction.token_uri,
+        None,
+    )?;
+    let hooks = mint_hook_msgs(
+        deps.storage,
+        token_id.clone(),
+        bid.bidder.as_str(),
+        bid.amount,
+    )?;
+    let event = mint_event(
+        &token_id,
+        bid.bidder.as_str(),
+        &bid.bidder,
+        bid.amount,
+        None,
+    );
+    res = res
+        .add_message(mint)
+        .add_messages(hooks)
+        .add_event(event)
+        .add_attribute("winner", &bid.bidder)
+        .add_attribute("price", bid.amount);
+
+    let currency = Currency::Cw20(config.cw20_address.clone());
+    if let Some((fee, msg)) = protocol_fee_msg(&config, &currency, bid.amount)? {
+        res = res.add_message(msg).add_attribute("protocol_fee", fee);
+    }
+    if let Some((burned, msg)) = burn_msg(deps.storage, &config, &currency, bid.amount)? {
+        res = res.add_message(msg).add_attribute("burned", burned);
+    }
+    Ok(res)
+}
+
+pub fn execute_update_collection_info(
+    deps: DepsMut,
+    info: MessageInfo,
+    collection_info: CollectionInfoMsg,
+    royalty: Option<RoyaltyMsg>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    assert_not_frozen(&config)?;
+
+    let collection_info = validate_collection_info(deps.api, &config, collection_info)?;
+    COLLECTION_INFO.save(deps.storage, &collection_info)?;
+    match royalty {
+        Some(royalty) => ROYALTY.save(deps.storage, &validate_royalty(deps.api, royalty)?)?,
+        None => ROYALTY.remove(deps.storage),
+    }
+
+    Ok(Response::new()
+        .add_event(config_update_event(
+            "collection_info",
+            collection_info.creator.to_string(),
+        ))
+        .add_attribute("action", "update_collection_info")
+        .add_attribute("creator", collection_info.creator))
+}
+
+pub fn execute_set_owner_key(
+    deps: DepsMut,
+    info: MessageInfo,
+    pubkey: Option<Binary>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    config.owner_key = pubkey;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", "set_owner_key"))
+}
+
+/// Verifies the owner key's signature over `payload`, consumes its nonce and runs the action
+/// with the owner as sender, so it is subject to the same checks as a direct call.
+pub fn execute_signed(
+    deps: DepsMut,
+    env: Env,
+    payload: AdminPayload,
+    signature: Binary,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let owner_key = config
+        .owner_key
+        .as_ref()
+        .ok_or(ContractError::NoOwnerKey {})?;
+
+    let mut data = env.contract.address.as_bytes().to_vec();
+    data.extend_from_slice(&to_binary(&payload)?);
+    let hash = Sha256::digest(&data);
+    let valid = deps
+        .api
+        .secp256k1_verify(&hash, &signature, owner_key)
+        .map_err(|_| ContractError::InvalidAdminSignature {})?;
+    if !valid {
+        return Err(ContractError::InvalidAdminSignature {});
+    }
+    if env.block.time >= payload.expiry {
+        return Err(ContractError::AdminPayloadExpired {});
+    }
+    if USED_ADMIN_NONCES.has(deps.storage, payload.nonce) {
+        return Err(ContractError::AdminNonceUsed {});
+    }
+    USED_ADMIN_NONCES.save(deps.storage, payload.nonce, &Empty {})?;
+
+    let info = MessageInfo {
+        sender: config.owner,
+        funds: vec![],
+    };
+    let res = match payload.msg {
+        SignedAdminMsg::UpdateUnitPrice { price } => execute_update_unit_price(deps, info, price)?,
+        SignedAdminMsg::Withdraw {
+            amount,
+            recipient,
+            token,
+        } => execute_withdraw(deps, env, info, amount, recipient, token)?,
+    };
+    Ok(res.add_attribute("signed_nonce", payload.nonce.to_string()))
+}
+
+pub fn execute_set_ibc_counterparty(
+    deps: DepsMut,
+    info: MessageInfo,
+    port_id: Option<String>,
+    connection_id: Option<String>,
+    channel_id: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    if port_id.is_some() && connection_id.is_none() {
+        return Err(ContractError::InvalidIbcChannel {});
+    }
+
+    config.ibc_counterparty_port = port_id;
+    config.ibc_connection_id = connection_id;
+    config.ibc_counterparty_channel = channel_id;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", "set_ibc_counterparty"))
+}
+
+pub fn execute_clone_collection(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    max_tokens: u64,
+    unit_price: Option<Uint128>,
+    token_uri: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    assert_not_frozen(&config)?;
+    let cw721 = config
+        .cw721_address
+        .take()
+        .ok_or(ContractError::Uninitialized {})?;
+    if max_tokens == 0 {
+        return Err(ContractError::InvalidMaxTokens {});
+    }
+    if unit_price.is_some_and(|price| price.is_zero()) {
+        return Err(ContractError::InvalidUnitPrice {});
+    }
+    // Pending mints would land on the new child.
+    if config.pending_mints > 0 {
+        return Err(ContractError::MintsPending {});
+    }
+
+    let (round_id, first_token) = current_round(deps.storage)?;
+    ARCHIVED_ROUNDS.save(
+        deps.storage,
+        round_id,
+        &ArchivedRound {
+            cw721_address: cw721.clone(),
+            first_token,
+            end_token: config.unused_token_id,
+            unit_price: config.unit_price,
+            token_uri: config.token_uri.clone(),
+            archived_at: env.block.time,
+        },
+    )?;
+
+    if let Some(price) = unit_price {
+        config.unit_price = price;
+    }
+    if let Some(token_uri) = token_uri {
+        config.token_uri = token_uri;
+    }
+    // Instantiate2 addresses must not repeat, so each round gets its own salt.
+    if let Some(salt) = &config.cw721_salt {
+        let mut salt = salt.to_vec();
+        salt.extend_from_slice(&(round_id + 1).to_be_bytes());
+        config.cw721_salt = Some(Binary::from(salt));
+    }
+    config.cw721_instantiate_error = None;
+    if config.token_code_id == 0 {
+        return Err(ContractError::UnknownCodeId {});
+    }
+    let instantiate = instantiate_cw721_submsg(&env, &config)?;
+
+    let new_max =
+        config.unused_token_id + config.drop_reserved + config.raffle_reserved + max_tokens;
+    let res = set_max_tokens(deps.storage, config, new_max)?;
+    Ok(Response::new()
+        .add_events(res.events)
+        .add_submessage(instantiate)
+        .add_attribute("action", "clone_collection")
+        .add_attribute("archived_round", round_id.to_string())
+        .add_attribute("previous_cw721", cw721)
+        .add_attribute("max_tokens", max_tokens.to_string()))
+}
+
+/// Id of the running round and its first mint index.
+fn current_round(storage: &dyn Storage) -> StdResult<(u32, u64)> {
+    Ok(ARCHIVED_ROUNDS
+        .range(storage, None, None, Order::Descending)
+        .next()
+        .transpose()?
+        .map_or((0, 0), |(id, round)| (id + 1, round.end_token)))
+}
+
+/// Child cw721 holding the token at mint index `id`: the archived round's collection for
+/// tokens minted before the running round.
+fn cw721_of(storage: &dyn Storage, config: &Config, id: u64) -> StdResult<Option<Addr>> {
+    let (_, first_token) = current_round(storage)?;
+    if id >= first_token {
+        return Ok(config.cw721_address.clone());
+    }
+    for item in ARCHIVED_ROUNDS.range(storage, None, None, Order::Ascending) {
+        let (_, round) = item?;
+        if id < round.end_token {
+            return Ok(Some(round.cw721_address));
+        }
+    }
+    Ok(None)
+}
+
+pub fn execute_create_collection(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: Collection,
+    token_code_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    if collection.unit_price.is_zero() {
+        return Err(ContractError::InvalidUnitPrice {});
+    }
+    if collection.max_tokens == 0 {
+        return Err(ContractError::InvalidMaxTokens {});
+    }
+
+    let collection_id = COLLECTION_COUNT.may_load(deps.storage)?.unwrap_or_default();
+    COLLECTION_COUNT.save(deps.storage, &(collection_id + 1))?;
+    COLLECTIONS.save(deps.storage, collection_id, &collection)?;
+
+    let instantiate = SubMsg::reply_on_success(
+        WasmMsg::Instantiate {
+            code_id: token_code_id.unwrap_or(config.token_code_id),
+            msg: to_binary(&Cw721InstantiateMsg {
+                name: collection.name.clone(),
+                symbol: collection.symbol.clone(),
+                minter: env.contract.address.to_string(),
+            })?,
+            funds: vec![],
+            admin: None,
+            label: format!("Instantiate fixed price NFT collection {}", collection_id),
+        },
+        INSTANTIATE_COLLECTION_REPLY_ID + collection_id as u64,
+    );
+
+    Ok(Response::new()
+        .add_submessage(instantiate)
+        .add_attribute("action", "create_collection")
+        .add_attribute("collection_id", collection_id.to_string())
+        .add_attribute("name", collection.name))
+}
+
+pub fn execute_propose_new_owner(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    expiry: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    if let Some(expiry) = expiry {
+        if expiry.is_expired(&env.block) {
+            return Err(ContractError::InvalidExpiration {});
+        }
+    }
+
+    let pending = PendingOwner {
+        owner: deps.api.addr_validate(&owner)?,
+        expiry,
+    };
+    PENDING_OWNER.save(deps.storage, &pending)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "propose_new_owner")
+        .add_attribute("pending_owner", pending.owner))
+}
+
+pub fn execute_cancel_ownership_proposal(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    if PENDING_OWNER.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::NoPendingOwner {});
+    }
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response::new().add_attribute("action", "cancel_ownership_proposal"))
+}
+
+pub fn execute_accept_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let pending = PENDING_OWNER
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingOwner {})?;
+    if pending.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(expiry) = pending.expiry {
+        if expiry.is_expired(&env.block) {
+            return Err(ContractError::OwnershipProposalExpired {});
+        }
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+    let previous_owner = config.owner;
+    config.owner = pending.owner;
+    config.owner_key = None;
+    CONFIG.save(deps.storage, &config)?;
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("previous_owner", previous_owner)
+        .add_attribute("owner", config.owner))
+}
+
+pub fn execute_renounce_ownership(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    config.renounced = true;
+    CONFIG.save(deps.storage, &config)?;
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_event(config_update_event("owner", "none"))
+        .add_attribute("action", "renounce_ownership")
+        .add_attribute("previous_owner", config.owner))
+}
+
+pub fn execute_withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Option<Uint128>,
+    recipient: Option<String>,
+    token: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if recipient.is_some() {
+        assert_owner(&config, &info.sender)?;
+    } else if !config.renounced {
+        authorize(deps.storage, &config, &info.sender, Permission::Withdraw)?;
+    }
+    let token = parse_currency(deps.as_ref(), &config, token)?;
+
+    if recipient.is_some() && !(config.payment_splits.is_empty() && config.creators.is_empty()) {
+        return Err(ContractError::PaymentSplitsConfigured {});
+    }
+
+    if config.crowdfund.is_some() && !config.crowdfund_settled {
+        return Err(ContractError::CrowdfundPending {});
+    }
+
+    let claimable = config
+        .vesting
+        .as_ref()
+        .map(|vesting| vested_amounts(deps.as_ref(), &env, &config, vesting, &token))
+        .transpose()?
+        .map(|(_, _, claimable)| claimable);
+    let amount = match amount {
+        Some(amount) if claimable.is_some_and(|claimable| amount > claimable) => {
+            return Err(ContractError::NotVested {});
+        }
+        Some(amount) => {
+            if amount > available_balance(deps.as_ref(), &env, &config, &token)? {
+                return Err(ContractError::NothingToWithdraw {});
+            }
+            amount
+        }
+        None => match claimable {
+            Some(claimable) => claimable,
+            None => available_balance(deps.as_ref(), &env, &config, &token)?,
+        },
+    };
+    if amount.is_zero() {
+        return Err(ContractError::NothingToWithdraw {});
+    }
+    if config.vesting.is_some() {
+        record_vested_withdrawal(deps.storage, &token, amount)?;
+    }
+
+    let event = withdraw_event(&token, amount);
+    let res = Response::new()
+        .add_attribute("action", "withdraw")
+        .add_attribute("token", token.to_string())
+        .add_attribute("amount", amount);
+
+    if let Some(recipient) = recipient {
+        let recipient = deps.api.addr_validate(&recipient)?;
+        return Ok(res
+            .add_message(transfer_msg(&token, recipient.as_str(), amount)?)
+            .add_event(event.add_attribute("recipient", &recipient))
+            .add_attribute("recipient", recipient));
+    }
+    let swap = config.swap_route.as_ref().filter(|route| {
+        route.ask != token && config.payment_splits.is_empty() && config.creators.is_empty()
+    });
+    if let Some(route) = swap {
+        let treasury = config.treasury.as_ref().unwrap_or(&config.owner);
+        return Ok(res
+            .add_message(swap_msg(deps.as_ref(), route, &token, amount, treasury)?)
+            .add_event(event.add_attribute("swapped_to", route.ask.to_string()))
+            .add_attribute("recipient", treasury)
+            .add_attribute("swapped_to", route.ask.to_string()));
+    }
+    Ok(add_payouts(
+        deps.storage,
+        res.add_event(event),
+        &config,
+        &token,
+        amount,
+    )?)
+}
+
+pub fn execute_sweep_funds(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    asset: Currency,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    let asset = match asset {
+        Currency::Cw20(address) => Currency::Cw20(deps.api.addr_validate(address.as_str())?),
+        Currency::Native(denom) => Currency::Native(denom),
+    };
+    let accepted = match &asset {
+        Currency::Cw20(address) => {
+            *address == config.cw20_address || PAYMENT_TOKENS.has(deps.storage, address)
+        }
+        Currency::Native(denom) => config.native_denom.as_ref() == Some(denom),
+    };
+    if accepted {
+        return Err(ContractError::NotSweepable {});
+    }
+    let recipient = deps.api.addr_validate(&recipient)?;
+
+    let amount = available_balance(deps.as_ref(), &env, &config, &asset)?;
+    if amount.is_zero() {
+        return Err(ContractError::NothingToWithdraw {});
+    }
+
+    Ok(Response::new()
+        .add_message(transfer_msg(&asset, recipient.as_str(), amount)?)
+        .add_attribute("action", "sweep_funds")
+        .add_attribute("token", asset.to_string())
+        .add_attribute("amount", amount)
+        .add_attribute("recipient", recipient))
+}
+
+/// Swaps `amount` of `offer` into `route.ask` for `to`, rejecting any output more than
+/// `max_slippage_bps` below what the router simulates now.
+fn swap_msg(
+    deps: Deps,
+    route: &SwapRoute,
+    offer: &Currency,
+    amount: Uint128,
+    to: &Addr,
+) -> StdResult<CosmosMsg> {
+    let operations = vec![SwapOperation::OraiSwap {
+        offer_asset_info: offer.clone().into(),
+        ask_asset_info: route.ask.clone().into(),
+    }];
+    let simulated: SimulateSwapOperationsResponse = deps.querier.query_wasm_smart(
+        &route.router,
+        &SwapRouterQueryMsg::SimulateSwapOperations {
+            offer_amount: amount,
+            operations: operations.clone(),
+        },
+    )?;
+    let minimum_receive = simulated
+        .amount
+        .multiply_ratio(MAX_ROYALTY_BPS - route.max_slippage_bps, MAX_ROYALTY_BPS);
+    let swap = to_binary(&SwapRouterMsg::ExecuteSwapOperations {
+        operations,
+        minimum_receive: Some(minimum_receive),
+        to: Some(to.to_string()),
+    })?;
+    Ok(match offer {
+        Currency::Cw20(token) => WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: route.router.to_string(),
+                amount,
+                msg: swap,
+            })?,
+            funds: vec![],
+        },
+        Currency::Native(denom) => WasmMsg::Execute {
+            contract_addr: route.router.to_string(),
+            msg: swap,
+            funds: vec![Coin::new(amount.u128(), denom)],
+        },
+    }
+    .into())
+}
+
+/// Pays `amount` of `token` to the treasury (or owner), or across the payment splits.
+/// With creators configured, their shares are credited for `ClaimCreatorShare` instead.
+fn add_payouts(
+    storage: &mut dyn Storage,
+    mut res: Response,
+    config: &Config,
+    token: &Currency,
+    amount: Uint128,
+) -> StdResult<Response> {
+    if !config.creators.is_empty() {
+        let shares: Vec<PaymentSplit> = config
+            .creators
+            .iter()
+            .map(|creator| PaymentSplit {
+                address: creator.address.clone(),
+                weight: creator.bps as u32,
+            })
+            .collect();
+        let key = token.to_string();
+        for (address, share) in split_amount(&shares, amount) {
+            CREATOR_CLAIMS.update(storage, (&address, &key), |claim| -> StdResult<_> {
+                let mut claim = claim.unwrap_or(CreatorClaim {
+                    currency: token.clone(),
+                    amount: Uint128::zero(),
+                });
+                claim.amount += share;
+                Ok(claim)
+            })?;
+            res = res
+                .add_attribute("creator", address)
+                .add_attribute("share", share);
+        }
+        CLAIMS_OWED.update(storage, &key, |owed| -> StdResult<_> {
+            Ok(owed.unwrap_or_default() + amount)
+        })?;
+        return Ok(res);
+    }
+
+    if config.payment_splits.is_empty() {
+        let recipient = config
+            .treasury
+            .clone()
+            .unwrap_or_else(|| config.owner.clone());
+        return Ok(res
+            .add_message(transfer_msg(token, recipient.as_str(), amount)?)
+            .add_attribute("recipient", recipient));
+    }
+
+    for (address, share) in split_amount(&config.payment_splits, amount) {
+        if share.is_zero() {
+            continue;
+        }
+        res = res
+            .add_message(transfer_msg(token, address.as_str(), share)?)
+            .add_attribute("recipient", address)
+            .add_attribute("share", share);
+    }
+    Ok(res)
+}
+
+/// Divides `amount` proportionally to the split weights. Rounding dust goes to the first
+/// recipient so the full amount is always paid out.
+fn split_amount(splits: &[PaymentSplit], amount: Uint128) -> Vec<(Addr, Uint128)> {
+    let total_weight: u128 = splits.iter().map(|split| split.weight as u128).sum();
+    let mut shares: Vec<(Addr, Uint128)> = splits
+        .iter()
+        .map(|split| {
+            (
+                split.address.clone(),
+                amount.multiply_ratio(split.weight as u128, total_weight),
+            )
+        })
+        .collect();
+    let paid: Uint128 = shares.iter().map(|(_, share)| *share).sum();
+    if let Some((_, first)) = shares.first_mut() {
+        *first += amount - paid;
+    }
+    shares
+}
+
+pub fn execute_update_payment_splits(
+    deps: DepsMut,
+    info: MessageInfo,
+    splits: Vec<PaymentSplitMsg>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    let mut validated: Vec<PaymentSplit> = Vec::with_capacity(splits.len());
+    for split in splits {
+        let address = deps.api.addr_validate(&split.address)?;
+        if split.weight == 0 || validated.iter().any(|s| s.address == address) {
+            return Err(ContractError::InvalidPaymentSplits {});
+        }
+        validated.push(PaymentSplit {
+            address,
+            weight: split.weight,
+        });
+    }
+
+    config.payment_splits = validated;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_event(config_update_event(
+            "payment_splits",
+            config.payment_splits.len().to_string(),
+        ))
+        .add_attribute("action", "update_payment_splits")
+        .add_attribute("splits", config.payment_splits.len().to_string()))
+}
+
+pub fn execute_claim_creator_share(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let claims = CREATOR_CLAIMS
+        .prefix(&info.sender)
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    if claims.is_empty() {
+        return Err(ContractError::NothingToWithdraw {});
+    }
+
+    let mut res = Response::new().add_attribute("action", "claim_creator_share");
+    for (key, claim) in claims {
+        CREATOR_CLAIMS.remove(deps.storage, (&info.sender, &key));
+        CLAIMS_OWED.update(deps.storage, &key, |owed| -> StdResult<_> {
+            Ok(owed.unwrap_or_default().checked_sub(claim.amount)?)
+        })?;
+        res = res
+            .add_message(transfer_msg(
+                &claim.currency,
+                info.sender.as_str(),
+                claim.amount,
+            )?)
+            .add_attribute("currency", key)
+            .add_attribute("amount", claim.amount);
+    }
+    Ok(res)
+}
+
+pub fn execute_set_paused(
+    deps: DepsMut,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    authorize(deps.storage, &config, &info.sender, Permission::Pause)?;
+    set_paused(deps.storage, config, paused)
+}
+
+fn set_paused(
+    storage: &mut dyn Storage,
+    mut config: Config,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    config.paused = paused;
+    CONFIG.save(storage, &config)?;
+
+    let (action, change) = if paused {
+        ("pause", "paused")
+    } else {
+        ("resume", "resumed")
+    };
+    Ok(Response::new()
+        .add_event(phase_change_event(change))
+        .add_attribute("action", action))
+}
+
+/// Changes `max_tokens`, keeping the shuffled id pool consistent with the new supply.
+fn set_max_tokens(
+    storage: &mut dyn Storage,
+    mut config: Config,
+    max_tokens: u64,
+) -> Result<Response, ContractError> {
+    assert_not_frozen(&config)?;
+    if config.open_edition
+        || max_tokens == 0
+        || max_tokens
+            < config.unused_token_id
+                + config.pending_mints
+                + config.drop_reserved
+                + config.raffle_reserved
+    {
+        return Err(ContractError::InvalidMaxTokens {});
+    }
+
+    if config.shuffle_token_ids {
+        let remaining = config.max_tokens - config.unused_token_id;
+        let new_remaining = max_tokens - config.unused_token_id;
+        // New slots must point at the new ids, not at the (possibly minted) ids their
+        // slot numbers would map to by default.
+        for slot in remaining..new_remaining {
+            SHUFFLED_IDS.save(storage, slot, &(config.max_tokens + slot - remaining))?;
+        }
+        for slot in new_remaining..remaining {
+            SHUFFLED_IDS.remove(storage, slot);
+        }
+    }
+
+    let old_max_tokens = config.max_tokens;
+    config.max_tokens = max_tokens;
+    CONFIG.save(storage, &config)?;
+
+    Ok(Response::new()
+        .add_event(config_update_event("max_tokens", max_tokens.to_string()))
+        .add_attribute("action", "update_max_tokens")
+        .add_attribute("old_max_tokens", old_max_tokens.to_string())
+        .add_attribute("new_max_tokens", max_tokens.to_string()))
+}
+
+pub fn execute_airdrop(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipients: Vec<String>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    authorize(deps.storage, &config, &info.sender, Permission::Airdrop)?;
+
+    if recipients.len() > MAX_BATCH_MINTS {
+        return Err(ContractError::TooManyRecipients {
+            max: MAX_BATCH_MINTS,
+        });
+    }
+    let quantity = recipients.len() as u32;
+    assert_free_mint_allowed(&config, quantity)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "airdrop")
+        .add_attribute("quantity", quantity.to_string());
+    let mut batch = MintBatch::with_capacity(recipients.len());
+    for recipient in recipients {
+        let recipient = deps.api.addr_validate(&recipient)?;
+        let (token_id, msgs, event) = mint_next(
+            deps.storage,
+            &env,
+            &mut config,
+            &recipient,
+            &recipient,
+            Uint128::zero(),
+            None,
+        )?;
+        batch.push(&config, token_id, msgs, event);
+        batch
+            .attributes
+            .push(Attribute::new("recipient", recipient));
+    }
+    res = batch.into_response(res)?;
+    config.reserved_minted += quantity as u64;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(res)
+}
+
+/// Checks that the owner may mint `quantity` unpaid tokens right now.
+fn assert_free_mint_allowed(config: &Config, quantity: u32) -> Result<(), ContractError> {
+    if config.retired {
+        return Err(ContractError::Retired {});
+    }
+    if config.closed {
+        return Err(ContractError::SaleClosed {});
+    }
+
+    if quantity == 0 {
+        return Err(ContractError::InvalidQuantity {});
+    }
+
+    if config.cw721_address.is_none() {
+        return Err(ContractError::Uninitialized {});
+    }
+
+    if config.remaining_supply() < quantity as u64 {
+        return Err(ContractError::SoldOut {});
+    }
+
+    if let Some(reserved_supply) = config.reserved_supply {
+        if reserved_supply - config.reserved_minted < quantity as u64 {
+            return Err(ContractError::ReservedSupplyExceeded {});
+        }
+    }
+
+    Ok(())
+}
+
+pub fn execute_retry_cw721_instantiate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    code_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    if config.cw721_address.is_some() {
+        return Err(ContractError::Cw721AlreadyLinked {});
+    }
+
+    if let Some(code_id) = code_id {
+        set_token_code_id(deps.storage, &mut config, code_id)?;
+    }
+    if config.token_code_id == 0 {
+        return Err(ContractError::UnknownCodeId {});
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_submessage(instantiate_cw721_submsg(&env, &config)?)
+        .add_attribute("action", "retry_cw721_instantiate")
+        .add_attribute("code_id", config.token_code_id.to_string()))
+}
+
+pub fn execute_set_cw721_address(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    if config.cw721_address.is_some() {
+        return Err(ContractError::Cw721AlreadyLinked {});
+    }
+
+    let address = validate_cw721(deps.as_ref(), &address)?;
+    config.cw721_version = detect_cw721_version(deps.as_ref(), &address);
+    config.cw721_address = Some(address.clone());
+    config.cw721_instantiate_error = None;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_cw721_address")
+        .add_attribute("cw721_address", address))
+}
+
+/// Adds `quantity` to the tokens `buyer` has minted, counting first-time minters.
+pub(crate) fn record_address_mints(
+    storage: &mut dyn Storage,
+    config: &mut Config,
+    buyer: &Addr,
+    bought: u32,
+    quantity: u32,
+) -> StdResult<()> {
+    if bought == 0 {
+        config.unique_minters += 1;
+    }
+    MINTS_PER_ADDRESS.save(storage, buyer, &(bought + quantity))
+}
+
+/// Builds the cw721 mint for the next token, records it and advances the mint counter.
+/// `payment` is what `price` was paid in when other than `cw20_address`. Returns the
+/// assigned token id; the caller is responsible for persisting `config`.
+pub(crate) fn mint_next(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &mut Config,
+    buyer: &Addr,
+    recipient: &Addr,
+    price: Uint128,
+    payment: Option<Currency>,
+) -> Result<(u64, Vec<SubMsg>, Event), ContractError> {
+    let phase_id = active_phase(storage, env)?.map(|(id, _)| id);
+    let (token_id, msg, event) = mint_token(
+        storage, env, config, buyer, recipient, price, payment, phase_id,
+    )?;
+    Ok((token_id, vec![msg], event))
+}
+
+/// `MintResponse` data for mints of `token_ids` to `recipient`.
+pub(crate) fn mint_data(token_ids: Vec<String>, recipient: &Addr) -> StdResult<Binary> {
+    to_binary(&MintResponse {
+        token_id: token_ids.first().cloned().unwrap_or_default(),
+        token_ids,
+        recipient: recipient.to_string(),
+    })
+}
+
+/// Submessages, events and `token_id` attributes of several mints, gathered into
+/// preallocated vectors so the response is extended once. cw721-base has no batched mint,
+/// so every token keeps its own reply-tracked submessage.
+pub(crate) struct MintBatch {
+    pub token_ids: Vec<u64>,
+    /// Set when every token goes to one address; the response data is then a `MintResponse`.
+    pub recipient: Option<Addr>,
+    pub msgs: Vec<SubMsg>,
+    pub events: Vec<Event>,
+    pub attributes: Vec<Attribute>,
+}
+
+impl MintBatch {
+    pub fn with_capacity(capacity: usize) -> Self {
+        MintBatch {
+            token_ids: Vec::with_capacity(capacity),
+            recipient: None,
+            msgs: Vec::with_capacity(capacity),
+            events: Vec::with_capacity(capacity),
+            attributes: Vec::with_capacity(capacity),
+        }
+    }
+
+    fn push(&mut self, config: &Config, token_id: u64, msgs: Vec<SubMsg>, event: Event) {
+        self.token_ids.push(token_id);
+        self.msgs.extend(msgs);
+        self.events.push(event);
+        self.attributes
+            .push(Attribute::new("token_id", config.token_id(token_id)));
+    }
+
+    pub fn into_response(self, mut res: Response) -> StdResult<Response> {
+        if let Some(recipient) = &self.recipient {
+            let token_ids = self
+                .attributes
+                .iter()
+                .filter(|attr| attr.key == "token_id")
+                .map(|attr| attr.value.clone())
+                .collect();
+            res = res.set_data(mint_data(token_ids, recipient)?);
+        }
+        Ok(res
+            .add_submessages(self.msgs)
+            .add_events(self.events)
+            .add_attributes(self.attributes))
+    }
+}
+
+/// Mints `quantity` tokens to `recipient` at `price` each, resolving the active phase once
+/// for the whole batch.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn mint_batch(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &mut Config,
+    buyer: &Addr,
+    recipient: &Addr,
+    price: Uint128,
+    payment: Option<Currency>,
+    quantity: u32,
+) -> Result<MintBatch, ContractError> {
+    let phase_id = active_phase(storage, env)?.map(|(id, _)| id);
+    let mut batch = MintBatch::with_capacity(quantity as usize);
+    batch.recipient = Some(recipient.clone());
+    for _ in 0..quantity {
+        let (token_id, msg, event) = mint_token(
+            storage,
+            env,
+            config,
+            buyer,
+            recipient,
+            price,
+            payment.clone(),
+            phase_id,
+        )?;
+        batch.push(config, token_id, vec![msg], event);
+    }
+    Ok(batch)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn mint_token(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &mut Config,
+    buyer: &Addr,
+    recipient: &Addr,
+    price: Uint128,
+    payment: Option<Currency>,
+    phase_id: Option<u8>,
+) -> Result<(u64, SubMsg, Event), ContractError> {
+    let token_id = assign_token_id(storage, env, config, buyer)?;
+    let receipt = Receipt {
+        payer: buyer.clone(),
+        recipient: recipient.clone(),
+        amount: price,
+        currency: payment
+            .clone()
+            .unwrap_or_else(|| Currency::Cw20(config.cw20_address.clone())),
+        phase_id,
+        height: env.block.height,
+    };
+    RECEIPTS.save(storage, token_id, &receipt)?;
+    let (payment_token, denom) = match payment {
+        Some(Currency::Cw20(token)) => (Some(token), None),
+        Some(Currency::Native(denom)) => (None, Some(denom)),
+        None => (None, None),
+    };
+    let event = mint_event(
+        &config.token_id(token_id),
+        buyer.as_str(),
+        recipient,
+        price,
+        payment_token.as_ref(),
+    );
+    mints().save(
+        storage,
+        token_id,
+        &MintRecord {
+            buyer: buyer.clone(),
+            time: env.block.time,
+            height: env.block.height,
+            price,
+            paid_out: Uint128::zero(),
+            payment_token,
+            denom,
+            token_uri: None,
+            uri_updated: None,
+            memo: None,
+        },
+    )?;
+    if let Some(renewal) = &config.renewal {
+        let expiry = env.block.time.plus_seconds(renewal.period);
+        TOKEN_EXPIRY.save(storage, token_id, &expiry)?;
+    }
+
+    let metadata = if config.onchain_metadata {
+        let mut metadata = match TOKEN_METADATA.may_load(storage, token_id)? {
+            Some(metadata) => metadata,
+            None => Metadata {
+                name: Some(format!("{} #{}", config.name, token_id)),
+                ..Metadata::default()
+            },
+        };
+        assign_traits(storage, env, token_id, &mut metadata)?;
+        Some(metadata)
+    } else {
+        None
+    };
+    let callback = build_mint_msg(config, token_id, recipient, metadata)?;
+    // The records above are provisional until `reply_mint` confirms the child minted the
+    // token, or rolls them back and refunds the buyer.
+    let msg = SubMsg::reply_always(callback, MINT_REPLY_ID + token_id);
+
+    Ok((token_id, msg, event))
+}
+
+fn mint_event(
+    token_id: &str,
+    buyer: &str,
+    recipient: &Addr,
+    price: Uint128,
+    payment_token: Option<&Addr>,
+) -> Event {
+    let event = Event::new(MINT_EVENT)
+        .add_attribute("token_id", token_id)
+        .add_attribute("buyer", buyer)
+        .add_attribute("recipient", recipient)
+        .add_attribute("price", price);
+    match payment_token {
+        Some(token) => event.add_attribute("payment_token", token),
+        None => event,
+    }
+}
+
+fn phase_change_event(change: &str) -> Event {
+    Event::new(PHASE_CHANGE_EVENT).add_attribute("change", change)
+}
+
+fn config_update_event(field: &str, value: impl Into<String>) -> Event {
+    Event::new(CONFIG_UPDATE_EVENT)
+        .add_attribute("field", field)
+        .add_attribute("value", value)
+}
+
+fn withdraw_event(token: &Currency, amount: Uint128) -> Event {
+    Event::new(WITHDRAW_EVENT)
+        .add_attribute("token", token.to_string())
+        .add_attribute("amount", amount)
+}
+
+/// One `MintHookExecuteMsg` per registered hook for the freshly minted `token_id`.
+fn mint_hook_msgs(
+    storage: &dyn Storage,
+    token_id: String,
+    buyer: &str,
+    price: Uint128,
+) -> StdResult<Vec<CosmosMsg>> {
+    MINT_HOOKS
+        .keys(storage, None, None, Order::Ascending)
+        .map(|hook| {
+            MintHookMsg {
+                token_id: token_id.clone(),
+                buyer: buyer.to_string(),
+                price,
+            }
+            .into_cosmos_msg(hook?)
+        })
+        .collect()
+}
+
+/// Sets `buyer`'s weight in the membership group to match every token they have bought.
+fn membership_msg(
+    storage: &dyn Storage,
+    membership: &Membership,
+    buyer: &Addr,
+) -> StdResult<CosmosMsg> {
+    let bought = MINTS_PER_ADDRESS
+        .may_load(storage, buyer)?
+        .unwrap_or_default();
+    let msg = Cw4GroupExecuteMsg::UpdateMembers {
+        remove: vec![],
+        add: vec![Cw4Member {
+            addr: buyer.to_string(),
+            weight: u64::from(bought).saturating_mul(membership.weight_per_mint),
+        }],
+    };
+    Ok(WasmMsg::Execute {
+        contract_addr: membership.group.to_string(),
+        msg: to_binary(&msg)?,
+        funds: vec![],
+    }
+    .into())
+}
+
+/// Checks that `paid` covers `price`, and matches it exactly when `strict_payment` is set.
+pub fn validate_payment(
+    config: &Config,
+    paid: Uint128,
+    price: Uint128,
+) -> Result<(), ContractError> {
+    if paid < price || (config.strict_payment && paid > price) {
+        return Err(ContractError::WrongPaymentAmount {});
+    }
+    Ok(())
+}
+
+/// Picks the index of the next mint and advances the mint counter. The caller is
+/// responsible for persisting `config`.
+pub fn assign_token_id(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &mut Config,
+    buyer: &Addr,
+) -> StdResult<u64> {
+    let token_id = next_token_id(storage, env, config, buyer.as_str())?;
+    config.unused_token_id += 1;
+    Ok(token_id)
+}
+
+/// The child cw721 `Mint` of mint `token_id` to `owner`, with the collection's token URI.
+pub fn build_mint_msg(
+    config: &Config,
+    token_id: u64,
+    owner: &Addr,
+    metadata: Option<Metadata>,
+) -> Result<CosmosMsg, ContractError> {
+    cw721_mint_msg(
+        config,
+        config.token_id(token_id),
+        owner,
+        token_uri_for(config, token_id),
+        metadata,
+    )
+}
+
+/// Builds the child cw721 `Mint`. Collections with `onchain_metadata` take `metadata` as the
+/// extension; all others get the shared `config.extension`.
+fn cw721_mint_msg(
+    config: &Config,
+    token_id: String,
+    owner: &Addr,
+    token_uri: String,
+    metadata: Option<Metadata>,
+) -> Result<CosmosMsg, ContractError> {
+    let cw721 = config
+        .cw721_address
+        .clone()
+        .ok_or(ContractError::Cw721NotLinked {})?;
+
+    let msg = match metadata {
+        Some(metadata) if config.onchain_metadata => cw721_execute_msg(
+            &cw721,
+            &Cw721ExecuteMsg::<Option<Metadata>, Empty>::Mint(MintMsg {
+                token_id,
+                owner: owner.to_string(),
+                token_uri: Some(token_uri),
+                extension: Some(metadata),
+            }),
+        )?,
+        _ => cw721_execute_msg(
+            &cw721,
+            &Cw721ExecuteMsg::<Extension, Empty>::Mint(MintMsg {
+                token_id,
+                owner: owner.to_string(),
+                token_uri: Some(token_uri),
+                extension: config.extension.clone(),
+            }),
+        )?,
+    };
+    Ok(msg)
+}
+
+/// Picks the id for the next mint. Sequential collections use the mint counter directly;
+/// shuffled ones draw uniformly from the ids that have not been minted yet.
+fn next_token_id(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &Config,
+    buyer: &str,
+) -> StdResult<u64> {
+    if !config.shuffle_token_ids {
+        return Ok(config.unused_token_id);
+    }
+
+    let remaining = config.max_tokens - config.unused_token_id;
+    let beacon = LATEST_RANDOMNESS.may_load(storage)?;
+    let slot = mint_seed(env, beacon.as_ref(), buyer, config.unused_token_id) % remaining;
+    let last = remaining - 1;
+
+    let token_id = SHUFFLED_IDS.may_load(storage, slot)?.unwrap_or(slot);
+    let last_id = SHUFFLED_IDS.may_load(storage, last)?.unwrap_or(last);
+    if slot != last {
+        SHUFFLED_IDS.save(storage, slot, &last_id)?;
+    }
+    SHUFFLED_IDS.remove(storage, last);
+
+    Ok(token_id)
+}
+
+/// Draws a value from every trait table category the metadata does not already set.
+/// CosmWasm does not expose the block hash, so draws are seeded from the block height and
+/// time together with the token id, plus the latest randomness beacon when there is one.
+fn assign_traits(
+    storage: &dyn Storage,
+    env: &Env,
+    token_id: u64,
+    metadata: &mut Metadata,
+) -> StdResult<()> {
+    let table = TRAIT_TABLE.may_load(storage)?.unwrap_or_default();
+    if table.is_empty() {
+        return Ok(());
+    }
+    let beacon = LATEST_RANDOMNESS.may_load(storage)?;
+    let attributes = metadata.attributes.get_or_insert_with(Vec::new);
+    for (index, category) in table.iter().enumerate() {
+        if attributes
+            .iter()
+            .any(|attribute| attribute.trait_type == category.trait_type)
+        {
+            continue;
+        }
+        let total: u64 = category
+            .values
+            .iter()
+            .map(|value| value.weight as u64)
+            .sum();
+        let mut roll = trait_seed(env, beacon.as_ref(), token_id, index) % total;
+        for value in &category.values {
+            if roll < value.weight as u64 {
+                attributes.push(Trait {
+                    display_type: None,
+                    trait_type: category.trait_type.clone(),
+                    value: value.value.clone(),
+                });
+                break;
+            }
+            roll -= value.weight as u64;
+        }
+    }
+    Ok(())
+}
+
+fn trait_seed(env: &Env, beacon: Option<&HexBinary>, token_id: u64, category: usize) -> u64 {
+    let mut hasher = Sha256::new();
+    if let Some(beacon) = beacon {
+        hasher.update(beacon.as_slice());
+    }
+    hasher.update(env.block.height.to_be_bytes());
+    hasher.update(env.block.time.nanos().to_be_bytes());
+    hasher.update(token_id.to_be_bytes());
+    hasher.update((category as u64).to_be_bytes());
+    let digest = hasher.finalize();
+    u64::from_be_bytes(digest[0..8].try_into().unwrap())
+}
+
+fn mint_seed(env: &Env, beacon: Option<&HexBinary>, buyer: &str, nonce: u64) -> u64 {
+    let mut hasher = Sha256::new();
+    if let Some(beacon) = beacon {
+        hasher.update(beacon.as_slice());
+    }
+    hasher.update(env.block.height.to_be_bytes());
+    hasher.update(env.block.time.nanos().to_be_bytes());
+    if let Some(tx) = &env.transaction {
+        hasher.update(tx.index.to_be_bytes());
+    }
+    hasher.update(buyer.as_bytes());
+    hasher.update(nonce.to_be_bytes());
+    let digest = hasher.finalize();
+    u64::from_be_bytes(digest[0..8].try_into().unwrap())
+}
+
+fn token_uri_for(config: &Config, token_id: u64) -> String {
+    if let (Some(placeholder), false) = (&config.placeholder_uri, config.revealed) {
+        return placeholder.clone();
+    }
+    match config.token_uri_mode {
+        TokenUriMode::Fixed => config.token_uri.clone(),
+        TokenUriMode::Base => format!(
+            "{}/{}.json",
+            config.token_uri.trim_end_matches('/'),
+            token_id
+        ),
+    }
+}
+
+/// The phase active at the current block. Fails with `NoActivePhase` when a schedule is
+/// configured but none of its phases is running.
+fn active_phase(storage: &dyn Storage, env: &Env) -> Result<Option<(u8, Phase)>, ContractError> {
+    let mut scheduled = false;
+    for item in PHASES.range(storage, None, None, Order::Ascending) {
+        let (id, phase) = item?;
+        if phase.is_active(env.block.time) {
+            return Ok(Some((id, phase)));
+        }
+        scheduled = true;
+    }
+    if scheduled {
+        return Err(ContractError::NoActivePhase {});
+    }
+    Ok(None)
+}
+
+/// Converts the oracle's USD price for `quantity` tokens into units of `token`.
+fn oracle_price(
+    deps: Deps,
+    env: &Env,
+    token: &Addr,
+    oracle: &PriceOracle,
+    quantity: u32,
+) -> Result<Uint128, ContractError> {
+    let res: OraclePriceResponse = deps.querier.query_wasm_smart(
+        &oracle.address,
+        &OracleQueryMsg::Price {
+            denom: token.to_string(),
+        },
+    )?;
+    if res.updated_at.plus_seconds(oracle.max_staleness) < env.block.time {
+        return Err(ContractError::StalePrice {});
+    }
+    Ok(oracle
+        .usd_price
+        .checked_mul(Uint128::from(quantity))?
+        .mul_floor(res.rate))
+}
+
+fn validate_royalty(api: &dyn Api, royalty: RoyaltyMsg) -> Result<Royalty, ContractError> {
+    if royalty.bps > MAX_ROYALTY_BPS {
+        return Err(ContractError::InvalidRoyalty {});
+    }
+    Ok(Royalty {
+        payment_address: api.addr_validate(&royalty.payment_address)?,
+        bps: royalty.bps,
+    })
+}
+
+fn validate_collection_info(
+    api: &dyn Api,
+    config: &Config,
+    info: CollectionInfoMsg,
+) -> Result<CollectionInfo, ContractError> {
+    let fields = [&info.description, &info.image, &info.external_link];
+    if fields.iter().any(|field| {
+        field
+            .as_deref()
+            .is_some_and(|field| field.is_empty() || field.len() > MAX_METADATA_FIELD_LENGTH)
+    }) {
+        return Err(ContractError::InvalidCollectionInfo {});
+    }
+    Ok(CollectionInfo {
+        creator: match info.creator {
+            Some(creator) => api.addr_validate(&creator)?,
+            None => config.owner.clone(),
+        },
+        description: info.description,
+        image: info.image,
+        external_link: info.external_link,
+        explicit_content: info.explicit_content,
+    })
+}
+
+fn validate_price_oracle(deps: Deps, oracle: PriceOracleMsg) -> Result<PriceOracle, ContractError> {
+    if oracle.usd_price.is_zero() || oracle.tolerance_bps > MAX_ROYALTY_BPS {
+        return Err(ContractError::InvalidPriceOracle {});
+    }
+    Ok(PriceOracle {
+        address: deps.api.addr_validate(&oracle.address)?,
+        usd_price: oracle.usd_price,
+        max_staleness: oracle.max_staleness,
+        tolerance_bps: oracle.tolerance_bps,
+    })
+}
+
+/// What `buyer` owes for the next `quantity` tokens. During the presale only allowlisted
+/// addresses may mint, at their entry price or the presale price when one is set. The same
+/// applies to allowlist-only phases, with the phase price in place of the presale price.
+/// A phase allowlist entry takes precedence over the global entry. A verified Merkle proof
+/// stands in for a global entry without a price.
+fn mint_price(
+    storage: &dyn Storage,
+    env: &Env,
+    config: &Config,
+    phase: Option<(u8, &Phase)>,
+    buyer: &Addr,
+    quantity: u32,
+    merkle_verified: bool,
+) -> Result<Uint128, ContractError> {
+    if let Some((id, phase)) = phase {
+        if phase.free_supply.is_some() {
+            return Err(ContractError::FreePhase {});
+        }
+        let entry_price = match PHASE_ALLOWLISTS.may_load(storage, (id, buyer))? {
+            Some(entry) => entry.price,
+            None if phase.allowlist_only => allowlist_price(storage, env, buyer, merkle_verified)?,
+            None => None,
+        };
+        if let Some(price) = entry_price.or(phase.price) {
+            return Ok(price.checked_mul(Uint128::from(quantity))?);
+        }
+    }
+
+    match &config.presale {
+        Some(presale) if env.block.time < presale.end_time => {
+            match allowlist_price(storage, env, buyer, merkle_verified)?.or(presale.price) {
+                Some(price) => Ok(price.checked_mul(Uint128::from(quantity))?),
+                None => Ok(price_for(storage, config, quantity)?),
+            }
+        }
+        _ => Ok(price_for(storage, config, quantity)?),
+    }
+}
+
+/// Entry price of an allowlisted `buyer`, failing when it is not allowlisted.
+fn allowlist_price(
+    storage: &dyn Storage,
+    env: &Env,
+    buyer: &Addr,
+    merkle_verified: bool,
+) -> Result<Option<Uint128>, ContractError> {
+    match live_allowlist_entry(storage, buyer, env.block.time)? {
+        Some(entry) => Ok(entry.price),
+        None if merkle_verified => Ok(None),
+        None => Err(ContractError::NotAllowlisted {}),
+    }
+}
+
+/// Global allowlist entry of `address`, unless it has expired by `time`.
+fn live_allowlist_entry(
+    storage: &dyn Storage,
+    address: &Addr,
+    time: Timestamp,
+) -> StdResult<Option<AllowlistEntry>> {
+    Ok(ALLOWLIST
+        .may_load(storage, address)?
+        .filter(|entry| !entry.is_expired(time)))
+}
+
+/// Total price of the next `quantity` tokens, honouring explicit per-token prices.
+pub(crate) fn price_for(
+    storage: &dyn Storage,
+    config: &Config,
+    quantity: u32,
+) -> StdResult<Uint128> {
+    (config.unused_token_id..config.unused_token_id + quantity as u64)
+        .try_fold(Uint128::zero(), |total, index| {
+            Ok(total.checked_add(token_price(storage, config, index)?)?)
+        })
+}
+
+/// Explicit price of the token at `index`, falling back to `price_at`.
+fn token_price(storage: &dyn Storage, config: &Config, index: u64) -> StdResult<Uint128> {
+    match TOKEN_PRICES.may_load(storage, index)? {
+        Some(price) => Ok(price),
+        None => price_at(config, index),
+    }
+}
+
+/// Price of the token minted when `index` tokens have already been minted.
+fn price_at(config: &Config, index: u64) -> StdResult<Uint128> {
+    if let Some((_, tier, _)) = tier_at(config, index) {
+        return Ok(tier.price);
+    }
+
+    match &config.price_curve {
+        None => Ok(config.unit_price),
+        Some(PriceCurve::Linear { increment }) => Ok(config
+            .unit_price
+            .checked_add(increment.checked_mul(Uint128::from(index))?)?),
+        Some(PriceCurve::Exponential { growth_bps }) => {
+            let exponent = u32::try_from(index)
+                .map_err(|_| StdError::generic_err("price curve exponent overflow"))?;
+            let factor = Decimal::from_ratio(10_000u128 + *growth_bps as u128, 10_000u128)
+                .checked_pow(exponent)?;
+            Ok(config.unit_price * factor)
+        }
+    }
+}
+
+/// Returns the tier containing token `index`, along with its position and how many tokens
+/// remain in it from that index on.
+fn tier_at(config: &Config, index: u64) -> Option<(usize, &PriceTier, u64)> {
+    let mut start = 0u64;
+    for (position, tier) in config.price_tiers.iter().enumerate() {
+        let end = start.saturating_add(tier.supply);
+        if index < end {
+            return Some((position, tier, end - index));
+        }
+        start = end;
+    }
+    None
+}
+
+/// Sends `token_id`, held by this contract, into the ICS-721 bridge for `receiver`.
+fn ics721_send_msg(
+    env: &Env,
+    config: &Config,
+    bridge: &Ics721Bridge,
+    receiver: &str,
+    token_id: &str,
+) -> Result<CosmosMsg, ContractError> {
+    let cw721 = config
+        .cw721_address
+        .clone()
+        .ok_or(ContractError::Cw721NotLinked {})?;
+    let msg = Ics721OutgoingMsg {
+        receiver: receiver.to_string(),
+        channel_id: bridge.channel_id.clone(),
+        timeout: IbcTimeout::with_timestamp(env.block.time.plus_seconds(bridge.timeout_seconds)),
+        memo: None,
+    };
+    Ok(cw721_execute_msg(
+        &cw721,
+        &Cw721ExecuteMsg::<Extension, Empty>::SendNft {
+            contract: bridge.bridge.to_string(),
+            token_id: token_id.to_string(),
+            msg: to_binary(&msg)?,
+        },
+    )?)
+}
+
+fn cw721_transfer_msg(
+    config: &Config,
+    recipient: &Addr,
+    token_id: &str,
+) -> Result<CosmosMsg, ContractError> {
+    let cw721 = config
+        .cw721_address
+        .clone()
+        .ok_or(ContractError::Cw721NotLinked {})?;
+    Ok(cw721_execute_msg(
+        &cw721,
+        &Cw721ExecuteMsg::<Extension, Empty>::TransferNft {
+            recipient: recipient.to_string(),
+            token_id: token_id.to_string(),
+        },
+    )?)
+}
+
+/// Executes `msg` on the cw721 at `contract`. `T` is the mint extension the child expects.
+fn cw721_execute_msg<T: Serialize>(
+    contract: &Addr,
+    msg: &Cw721ExecuteMsg<T, Empty>,
+) -> StdResult<CosmosMsg> {
+    Ok(WasmMsg::Execute {
+        contract_addr: contract.to_string(),
+        msg: to_binary(msg)?,
+        funds: vec![],
+    }
+    .into())
+}
+
+fn cw20_transfer_msg(token: &Addr, recipient: &str, amount: Uint128) -> StdResult<CosmosMsg> {
+    Ok(WasmMsg::Execute {
+        contract_addr: token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Transfer {
+            recipient: recipient.to_string(),
+            amount,
+        })?,
+        funds: vec![],
+    }
+    .into())
+}
+
+fn transfer_msg(token: &Currency, recipient: &str, amount: Uint128) -> StdResult<CosmosMsg> {
+    match token {
+        Currency::Cw20(address) => cw20_transfer_msg(address, recipient, amount),
+        Currency::Native(denom) => Ok(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![Coin::new(amount.u128(), denom)],
+        }
+        .into()),
+    }
+}
+
+/// This contract's `token` balance, less any waitlist escrow, unclaimed creator shares,
+/// staking reward pool and payments escrowed until reveal.
+fn available_balance(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    token: &Currency,
+) -> StdResult<Uint128> {
+    let mut owed = CLAIMS_OWED
+        .may_load(deps.storage, &token.to_string())?
+        .unwrap_or_default();
+    if escrows_until_reveal(config) {
+        owed += ESCROW_HELD
+            .may_load(deps.storage, &token.to_string())?
+            .unwrap_or_default();
+    }
+    let balance = match token {
+        Currency::Native(denom) => {
+            deps.querier
+                .query_balance(&env.contract.address, denom)?
+                .amount
+        }
+        Currency::Cw20(address) => {
+            let res: Cw20BalanceResponse = deps.querier.query_wasm_smart(
+                address,
+                &Cw20QueryMsg::Balance {
+                    address: env.contract.address.to_string(),
+                },
+            )?;
+            let mut balance = res.balance;
+            if *address == config.cw20_address {
+                balance = balance.saturating_sub(
+                    config.waitlist_escrow
+                        + config.raffle_escrow
+                        + config.auction_escrow
+                        + config.deposit_escrow,
+                );
+            }
+            if config
+                .staking
+                .as_ref()
+                .is_some_and(|staking| staking.reward_token == *address)
+            {
+                balance = balance.saturating_sub(config.staking_pool);
+            }
+            balance
+        }
+    };
+    Ok(balance.saturating_sub(owed))
+}
+
+/// Whether payments taken now may still be refunded in full: crowdfund pledges, or anything
+/// held until reveal.
+fn payment_escrowed(config: &Config) -> bool {
+    (config.crowdfund.is_some() && !config.crowdfund_settled) || escrows_until_reveal(config)
+}
+
+/// The protocol fee on a payment of `price` in `currency`, with the message forwarding it.
+/// Escrowed payments are not charged.
+fn protocol_fee_msg(
+    config: &Config,
+    currency: &Currency,
+    price: Uint128,
+) -> StdResult<Option<(Uint128, CosmosMsg)>> {
+    let fee = match &config.protocol_fee {
+        Some(fee) if !payment_escrowed(config) => price.multiply_ratio(fee.bps, MAX_ROYALTY_BPS),
+        _ => return Ok(None),
+    };
+    if fee.is_zero() {
+        return Ok(None);
+    }
+    let collector = config.protocol_fee.as_ref().unwrap().collector.as_str();
+    Ok(Some((fee, transfer_msg(currency, collector, fee)?)))
+}
+
+/// Burns `burn_bps` of a primary cw20 payment of `price` and adds it to `BURNED`. Other
+/// currencies and escrowed payments are left alone.
+fn burn_msg(
+    storage: &mut dyn Storage,
+    config: &Config,
+    currency: &Currency,
+    price: Uint128,
+) -> StdResult<Option<(Uint128, CosmosMsg)>> {
+    let primary = matches!(currency, Currency::Cw20(token) if *token == config.cw20_address);
+    let amount = match config.burn_bps {
+        Some(bps) if primary && !payment_escrowed(config) => {
+            price.multiply_ratio(bps, MAX_ROYALTY_BPS)
+        }
+        _ => return Ok(None),
+    };
+    if amount.is_zero() {
+        return Ok(None);
+    }
+    let burned = BURNED.may_load(storage)?.unwrap_or_default();
+    BURNED.save(storage, &(burned + amount))?;
+    let msg = WasmMsg::Execute {
+        contract_addr: config.cw20_address.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
+        funds: vec![],
+    };
+    Ok(Some((amount, msg.into())))
+}
+
+/// Spreads `paid_out` of a payment of `total` over the records of `token_ids`, in proportion
+/// to their price and rounded up, so refunds of failed mints never exceed what was kept.
+fn record_payouts(
+    storage: &mut dyn Storage,
+    token_ids: &[u64],
+    total: Uint128,
+    paid_out: Uint128,
+) -> StdResult<()> {
+    if paid_out.is_zero() {
+        return Ok(());
+    }
+    for &token_id in token_ids {
+        let mut record = mints().load(storage, token_id)?;
+        record.paid_out = record.price - record.price.multiply_ratio(total - paid_out, total);
+        mints().save(storage, token_id, &record)?;
+    }
+    Ok(())
+}
+
+fn escrows_until_reveal(config: &Config) -> bool {
+    config.reveal_escrow && !config.revealed
+}
+
+/// Escrows what was paid for `token_id` while `reveal_escrow` is pending.
+fn hold_until_reveal(
+    storage: &mut dyn Storage,
+    config: &Config,
+    token_id: u64,
+    buyer: &Addr,
+    currency: &Currency,
+    amount: Uint128,
+) -> StdResult<()> {
+    if !escrows_until_reveal(config) || amount.is_zero() {
+        return Ok(());
+    }
+    let payment = EscrowedPayment {
+        buyer: buyer.clone(),
+        currency: currency.clone(),
+        amount,
+    };
+    ESCROWED_PAYMENTS.save(storage, token_id, &payment)?;
+    ESCROW_HELD.update(storage, &currency.to_string(), |held| -> StdResult<_> {
+        Ok(held.unwrap_or_default() + amount)
+    })?;
+    Ok(())
+}
+
+fn release_escrow(
+    storage: &mut dyn Storage,
+    token_id: u64,
+    payment: &EscrowedPayment,
+) -> StdResult<()> {
+    ESCROWED_PAYMENTS.remove(storage, token_id);
+    ESCROW_HELD.update(
+        storage,
+        &payment.currency.to_string(),
+        |held| -> StdResult<_> { Ok(held.unwrap_or_default().saturating_sub(payment.amount)) },
+    )?;
+    Ok(())
+}
+
+/// Longest accepted token id template prefix or suffix, in bytes.
+const MAX_TOKEN_ID_AFFIX_LENGTH: usize = 64;
+
+fn parse_token_id_template(template: &str) -> Result<TokenIdTemplate, ContractError> {
+    let (prefix, rest) = template
+        .split_once("{n")
+        .ok_or(ContractError::InvalidTokenIdTemplate {})?;
+    let (spec, suffix) = rest
+        .split_once('}')
+        .ok_or(ContractError::InvalidTokenIdTemplate {})?;
+    let width = match spec.strip_prefix(":0") {
+        None if spec.is_empty() => 0,
+        Some(width) => width
+            .parse::<u8>()
+            .ok()
+            .filter(|width| (1..=20).contains(width))
+            .ok_or(ContractError::InvalidTokenIdTemplate {})?,
+        None => return Err(ContractError::InvalidTokenIdTemplate {}),
+    };
+    let affix_ok =
+        |affix: &str| affix.len() <= MAX_TOKEN_ID_AFFIX_LENGTH && !affix.contains(['{', '}']);
+    if !affix_ok(prefix) || !affix_ok(suffix) {
+        return Err(ContractError::InvalidTokenIdTemplate {});
+    }
+    Ok(TokenIdTemplate {
+        prefix: prefix.to_string(),
+        suffix: suffix.to_string(),
+        width,
+    })
+}
+
+fn validate_vesting(env: &Env, vesting: VestingMsg) -> Result<Vesting, ContractError> {
+    if vesting.duration_seconds == 0 || vesting.cliff_seconds > vesting.duration_seconds {
+        return Err(ContractError::InvalidVesting {});
+    }
+    Ok(Vesting {
+        start: vesting.start.unwrap_or(env.block.time),
+        cliff_seconds: vesting.cliff_seconds,
+        duration_seconds: vesting.duration_seconds,
+    })
+}
+
+/// `(vested, withdrawn, claimable)` proceeds of `token`. Everything withdrawn so far plus the
+/// available balance is vested on the schedule.
+fn vested_amounts(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    vesting: &Vesting,
+    token: &Currency,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    let withdrawn = VESTING_WITHDRAWN
+        .may_load(deps.storage, &token.to_string())?
+        .unwrap_or_default();
+    let available = available_balance(deps, env, config, token)?;
+    let vested = vesting.vested(withdrawn + available, env.block.time);
+    Ok((vested, withdrawn, vested.saturating_sub(withdrawn)))
+}
+
+fn record_vested_withdrawal(
+    storage: &mut dyn Storage,
+    token: &Currency,
+    amount: Uint128,
+) -> StdResult<()> {
+    VESTING_WITHDRAWN.update(storage, &token.to_string(), |withdrawn| -> StdResult<_> {
+        Ok(withdrawn.unwrap_or_default() + amount)
+    })?;
+    Ok(())
+}
+
+/// The cw20 address or `native_denom` named by `token`, defaulting to `cw20_address`.
+fn parse_currency(deps: Deps, config: &Config, token: Option<String>) -> StdResult<Currency> {
+    Ok(match token {
+        Some(denom) if config.native_denom.as_ref() == Some(&denom) => Currency::Native(denom),
+        Some(token) => Currency::Cw20(deps.api.addr_validate(&token)?),
+        None => Currency::Cw20(config.cw20_address.clone()),
+    })
+}
+
+fn validate_creators(
+    api: &dyn Api,
+    creators: &[CreatorMsg],
+) -> Result<Vec<Creator>, ContractError> {
+    let mut validated: Vec<Creator> = Vec::with_capacity(creators.len());
+    for creator in creators {
+        let address = api.addr_validate(&creator.address)?;
+        if creator.bps == 0 || validated.iter().any(|c| c.address == address) {
+            return Err(ContractError::InvalidCreators {});
+        }
+        validated.push(Creator {
+            address,
+            bps: creator.bps,
+        });
+    }
+    let total: u32 = validated.iter().map(|creator| creator.bps as u32).sum();
+    if !validated.is_empty() && total != MAX_ROYALTY_BPS as u32 {
+        return Err(ContractError::InvalidCreators {});
+    }
+    Ok(validated)
+}
+
+fn verify_merkle_proof(
+    config: &Config,
+    address: &Addr,
+    proof: &MerkleProof,
+) -> Result<(), ContractError> {
+    let root = config
+        .merkle_root
+        .as_ref()
+        .ok_or(ContractError::InvalidMerkleProof {})?;
+    if proof.root_version != config.merkle_version {
+        return Err(ContractError::StaleMerkleProof {});
+    }
+    let mut hash: [u8; 32] = Sha256::digest(address.as_bytes()).into();
+    for sibling in &proof.proof {
+        let sibling: [u8; 32] = sibling
+            .as_slice()
+            .try_into()
+            .map_err(|_| ContractError::InvalidMerkleProof {})?;
+        let (first, second) = if hash <= sibling {
+            (hash, sibling)
+        } else {
+            (sibling, hash)
+        };
+        hash = Sha256::new()
+            .chain_update(first)
+            .chain_update(second)
+            .finalize()
+            .into();
+    }
+    if hash.as_slice() != root.as_slice() {
+        return Err(ContractError::InvalidMerkleProof {});
+    }
+    Ok(())
+}
+
+fn validate_partner_discount(
+    api: &dyn Api,
+    discount: &PartnerDiscountMsg,
+) -> Result<PartnerDiscount, ContractError> {
+    if discount.discount_bps == 0 || discount.discount_bps > MAX_ROYALTY_BPS {
+        return Err(ContractError::InvalidPartnerDiscount {});
+    }
+    Ok(PartnerDiscount {
+        collection: api.addr_validate(&discount.collection)?,
+        discount_bps: discount.discount_bps,
+    })
+}
+
+fn validate_protocol_fee(
+    api: &dyn Api,
+    fee: &ProtocolFeeMsg,
+) -> Result<ProtocolFee, ContractError> {
+    if fee.bps == 0 || fee.bps > MAX_ROYALTY_BPS {
+        return Err(ContractError::InvalidProtocolFee {});
+    }
+    Ok(ProtocolFee {
+        collector: api.addr_validate(&fee.collector)?,
+        bps: fee.bps,
+    })
+}
+
+fn validate_mint_gate(api: &dyn Api, gate: &MintGateMsg) -> Result<MintGate, ContractError> {
+    Ok(match gate {
+        MintGateMsg::Cw721 { collection } => MintGate::Cw721 {
+            collection: api.addr_validate(collection)?,
+        },
+        MintGateMsg::Cw20 { token, min_balance } => {
+            if min_balance.is_zero() {
+                return Err(ContractError::InvalidMintGate {});
+            }
+            MintGate::Cw20 {
+                token: api.addr_validate(token)?,
+                min_balance: *min_balance,
+            }
+        }
+        MintGateMsg::Cw4 { group, min_weight } => MintGate::Cw4 {
+            group: api.addr_validate(group)?,
+            min_weight: *min_weight,
+        },
+    })
+}
+
+fn validate_membership(
+    api: &dyn Api,
+    membership: &MembershipMsg,
+) -> Result<Membership, ContractError> {
+    if membership.weight_per_mint == 0 {
+        return Err(ContractError::InvalidMembership {});
+    }
+    Ok(Membership {
+        group: api.addr_validate(&membership.group)?,
+        weight_per_mint: membership.weight_per_mint,
+    })
+}
+
+fn validate_ai_oracle(api: &dyn Api, oracle: AiOracleMsg) -> StdResult<AiOracle> {
+    Ok(AiOracle {
+        contract: api.addr_validate(&oracle.contract)?,
+        service: oracle.service,
+        threshold: oracle.threshold,
+    })
+}
+
+pub(crate) fn assert_not_blocklisted(deps: Deps, address: &str) -> Result<(), ContractError> {
+    if BLOCKLIST.has(deps.storage, &deps.api.addr_validate(address)?) {
+        return Err(ContractError::Blocklisted {});
+    }
+    Ok(())
+}
+
+fn assert_mint_gate(deps: Deps, config: &Config, buyer: &Addr) -> Result<(), ContractError> {
+    let eligible = match &config.mint_gate {
+        None => true,
+        Some(MintGate::Cw721 { collection }) => holds_cw721(deps, collection, buyer)?,
+        Some(MintGate::Cw20 { token, min_balance }) => {
+            let res: Cw20BalanceResponse = deps.querier.query_wasm_smart(
+                token,
+                &Cw20QueryMsg::Balance {
+                    address: buyer.to_string(),
+                },
+            )?;
+            res.balance >= *min_balance
+        }
+        Some(MintGate::Cw4 { group, min_weight }) => {
+            let res: Cw4MemberResponse = deps.querier.query_wasm_smart(
+                group,
+                &Cw4QueryMsg::Member {
+                    addr: buyer.to_string(),
+                    at_height: None,
+                },
+            )?;
+            res.weight
+                .is_some_and(|weight| weight >= min_weight.unwrap_or_default())
+        }
+    };
+    if !eligible {
+        return Err(ContractError::NotEligible {});
+    }
+    Ok(())
+}
+
+/// Whether `owner` holds any token of `collection`.
+fn holds_cw721(deps: Deps, collection: &Addr, owner: &Addr) -> StdResult<bool> {
+    let res: TokensResponse = deps.querier.query_wasm_smart(
+        collection,
+        &Cw721QueryMsg::Tokens {
+            owner: owner.to_string(),
+            start_after: None,
+            limit: Some(1),
+        },
+    )?;
+    Ok(!res.tokens.is_empty())
+}
+
+/// Accepts `ibc/` followed by an upper-case hex hash, or a plain SDK denom.
+fn validate_native_denom(denom: &str) -> Result<(), ContractError> {
+    let valid = match denom.strip_prefix("ibc/") {
+        Some(hash) => {
+            hash.len() == 64
+                && hash
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || ('A'..='F').contains(&c))
+        }
+        None => {
+            (3..=128).contains(&denom.len())
+                && denom.starts_with(|c: char| c.is_ascii_alphabetic())
+                && denom
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "/:._-".contains(c))
+        }
+    };
+    if !valid {
+        return Err(ContractError::InvalidDenom {});
+    }
+    Ok(())
+}
+
+fn assert_not_frozen(config: &Config) -> Result<(), ContractError> {
+    if config.frozen {
+        return Err(ContractError::ConfigFrozen {});
+    }
+    Ok(())
+}
+
+/// Authorization for every delegable handler: passes for the owner, and for operators
+/// holding `permission`. Owner-only handlers use `assert_owner` directly.
+fn authorize(
+    storage: &dyn Storage,
+    config: &Config,
+    sender: &Addr,
+    permission: Permission,
+) -> Result<(), ContractError> {
+    if effective_perms(storage, config, sender)?.allows(permission) {
+        return Ok(());
+    }
+    assert_owner(config, sender)?;
+    Err(ContractError::Unauthorized {})
+}
+
+/// Everything for the owner, the granted flags for operators, nothing once renounced.
+fn effective_perms(
+    storage: &dyn Storage,
+    config: &Config,
+    sender: &Addr,
+) -> StdResult<OperatorPerms> {
+    if config.renounced {
+        return Ok(OperatorPerms::default());
+    }
+    if config.owner == *sender {
+        return Ok(OperatorPerms::all());
+    }
+    Ok(OPERATORS.may_load(storage, sender)?.unwrap_or_default())
+}
+
+fn assert_owner(config: &Config, sender: &Addr) -> Result<(), ContractError> {
+    if config.renounced {
+        return Err(ContractError::OwnershipRenounced {});
+    }
+    if config.owner != *sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetConfig {} => to_binary(&query_config(deps, env)?),
+        QueryMsg::PendingOwner {} => to_binary(&query_pending_owner(deps)?),
+        QueryMsg::SupplyInfo {} => to_binary(&query_supply_info(deps)?),
+        QueryMsg::MintsOf { address } => to_binary(&query_mints_of(deps, address)?),
+        QueryMsg::Deposit { address } => to_binary(&query_deposit(deps, address)?),
+        QueryMsg::MintCredits { address } => to_binary(&query_mint_credits(deps, address)?),
+        QueryMsg::Provenance { token_id } => to_binary(&query_provenance(deps, token_id)?),
+        QueryMsg::NftDetails { token_id } => to_binary(&query_nft_details(deps, token_id)?),
+        QueryMsg::AiMetadata { token_id } => to_binary(&query_ai_metadata(deps, token_id)?),
+        QueryMsg::HolderSnapshot { start_after, limit } => {
+            to_binary(&query_holder_snapshot(deps, start_after, limit)?)
+        }
+        QueryMsg::Mints {
+            start_after,
+            limit,
+            order,
+        } => to_binary(&query_mints(deps, start_after, limit, order)?),
+        QueryMsg::RoyaltyInfo {
+            token_id: _,
+            sale_price,
+        } => to_binary(&query_royalty_info(deps, sale_price)?),
+        QueryMsg::CurrentPrice {} => to_binary(&query_current_price(deps)?),
+        QueryMsg::ActiveTier {} => to_binary(&query_active_tier(deps)?),
+        QueryMsg::Referrer { address } => to_binary(&query_referrer(deps, address)?),
+        QueryMsg::Referrers { start_after, limit } => {
+            to_binary(&query_referrers(deps, start_after, limit)?)
+        }
+        QueryMsg::Phases {} => to_binary(&query_phases(deps)?),
+        QueryMsg::ActivePhase {} => to_binary(&query_active_phase(deps, env)?),
+        QueryMsg::Schedule {} => to_binary(&query_schedule(deps, env)?),
+        QueryMsg::Revealed {} => to_binary(&query_revealed(deps)?),
+        QueryMsg::Listing { token_id } => to_binary(&query_listing(deps, token_id)?),
+        QueryMsg::Listings {
+            start_after,
+            limit,
+            order,
+        } => to_binary(&query_listings(deps, start_after, limit, order)?),
+        QueryMsg::PaymentTokens {} => to_binary(&query_payment_tokens(deps)?),
+        QueryMsg::GiftInfo { token_id } => to_binary(&query_gift_info(deps, token_id)?),
+        QueryMsg::Collection { collection_id } => {
+            to_binary(&query_collection(deps, collection_id)?)
+        }
+        QueryMsg::Collections { start_after, limit } => {
+            to_binary(&query_collections(deps, start_after, limit)?)
+        }
+        QueryMsg::ArchivedRounds { start_after, limit } => {
+            to_binary(&query_archived_rounds(deps, start_after, limit)?)
+        }
+        QueryMsg::Rental { token_id } => to_binary(&query_rental(deps, env, token_id)?),
+        QueryMsg::ActiveRentals { start_after, limit } => {
+            to_binary(&query_active_rentals(deps, env, start_after, limit)?)
+        }
+        QueryMsg::Cw721 { msg } => query_cw721(deps, msg),
+        QueryMsg::Waitlist { start_after, limit } => {
+            to_binary(&query_waitlist(deps, start_after, limit)?)
+        }
+        QueryMsg::Prices {} => to_binary(&query_prices(deps)?),
+        QueryMsg::Claims { address } => to_binary(&query_claims(deps, address)?),
+        QueryMsg::PhaseAllowlist {
+            phase_id,
+            start_after,
+            limit,
+            order,
+        } => to_binary(&query_phase_allowlist(
+            deps,
+            phase_id,
+            start_after,
+            limit,
+            order,
+        )?),
+        QueryMsg::AllowlistStatus { address } => {
+            to_binary(&query_allowlist_status(deps, env, address)?)
+        }
+        QueryMsg::MintGate {} => to_binary(&query_mint_gate(deps)?),
+        QueryMsg::Stake { token_id } => to_binary(&query_stake(deps, env, token_id)?),
+        QueryMsg::Stakes {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_stakes(deps, env, owner, start_after, limit)?),
+        QueryMsg::Vesting { token } => to_binary(&query_vesting(deps, env, token)?),
+        QueryMsg::Escrow { token_id } => to_binary(&query_escrow(deps, token_id)?),
+        QueryMsg::TraitTable {} => to_binary(&query_trait_table(deps)?),
+        QueryMsg::PendingMint { job_id } => to_binary(&query_pending_mint(deps, job_id)?),
+        QueryMsg::CollectionStats {} => to_binary(&query_collection_stats(deps, env)?),
+        QueryMsg::ContractInfo {} => to_binary(&query_contract_info(deps)?),
+        QueryMsg::MintHooks {} => to_binary(&query_mint_hooks(deps)?),
+        QueryMsg::Operator { address } => to_binary(&query_operator(deps, address)?),
+        QueryMsg::Operators { start_after, limit } => {
+            to_binary(&query_operators(deps, start_after, limit)?)
+        }
+        QueryMsg::Permissions { address } => to_binary(&query_permissions(deps, address)?),
+        QueryMsg::PendingPaymentToken {} => to_binary(&query_pending_payment_token(deps)?),
+        QueryMsg::Blocklisted { address } => to_binary(&query_blocklisted(deps, address)?),
+        QueryMsg::Blocklist { start_after, limit } => {
+            to_binary(&query_blocklist(deps, start_after, limit)?)
+        }
+        QueryMsg::TokensByBuyer {
+            address,
+            start_after,
+            limit,
+            order,
+        } => to_binary(&query_tokens_by_buyer(
+            deps,
+            address,
+            start_after,
+            limit,
+            order,
+        )?),
+        QueryMsg::MintsBetween {
+            start,
+            end,
+            start_after,
+            limit,
+        } => to_binary(&query_mints_between(deps, start, end, start_after, limit)?),
+        QueryMsg::PriceQuote { address, quantity } => {
+            to_binary(&query_price_quote(deps, env, address, quantity)?)
+        }
+        QueryMsg::CanMint { address, quantity } => {
+            to_binary(&query_can_mint(deps, env, address, quantity)?)
+        }
+        QueryMsg::Drops {
+            start_after,
+            limit,
+            order,
+        } => to_binary(&query_drops(deps, start_after, limit, order)?),
+        QueryMsg::Burned {} => to_binary(&BurnedResponse {
+            burned: BURNED.may_load(deps.storage)?.unwrap_or_default(),
+        }),
+        QueryMsg::Relayer { address } => to_binary(&query_relayer(deps, address)?),
+        QueryMsg::IntentNonceUsed { address, nonce } => {
+            to_binary(&query_nonce_used(deps, USED_INTENTS, address, nonce)?)
+        }
+        QueryMsg::PermitNonceUsed { address, nonce } => {
+            to_binary(&query_nonce_used(deps, USED_PERMITS, address, nonce)?)
+        }
+        QueryMsg::TokenExpiry { token_id } => to_binary(&query_token_expiry(deps, env, token_id)?),
+        QueryMsg::ExpiredTokens { start_after, limit } => {
+            to_binary(&query_expired_tokens(deps, env, start_after, limit)?)
+        }
+        QueryMsg::TokenCodeIds {} => to_binary(&query_token_code_ids(deps)?),
+        QueryMsg::Receipt { token_id } => to_binary(&query_receipt(deps, token_id)?),
+        QueryMsg::Receipts { start_after, limit } => {
+            to_binary(&query_receipts(deps, start_after, limit)?)
+        }
+        QueryMsg::Raffle {} => to_binary(&RaffleResponse {
+            raffle: RAFFLE.may_load(deps.storage)?,
+        }),
+        QueryMsg::RaffleTickets { address } => to_binary(&query_raffle_tickets(deps, address)?),
+        QueryMsg::Auction { token_id } => to_binary(&AuctionInfo {
+            auction: AUCTIONS.load(deps.storage, &token_id)?,
+            token_id,
+        }),
+        QueryMsg::Auctions { start_after, limit } => {
+            to_binary(&query_auctions(deps, start_after, limit)?)
+        }
+        QueryMsg::DropSchedule {} => to_binary(&query_drop_schedule(deps, env)?),
+        QueryMsg::TokenPrices { start_after, limit } => {
+            to_binary(&query_token_prices(deps, start_after, limit)?)
+        }
+        QueryMsg::CollectionInfo {} => to_binary(&query_collection_info(deps)?),
+    }
+}
+
+fn query_supply_info(deps: Deps) -> StdResult<SupplyInfoResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let remaining = config.remaining_supply();
+    Ok(SupplyInfoResponse {
+        minted: config.minted(),
+        failed: config.failed_mints,
+        remaining,
+        max_tokens: config.max_tokens,
+        sold_out: remaining == 0,
+        open_edition: config.open_edition,
+        final_supply: config.final_supply,
+    })
+}
+
+fn query_contract_info(deps: Deps) -> StdResult<crate::msg::ContractInfoResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let (cw721_code_id, cw721_version) = match &config.cw721_address {
+        Some(cw721) => {
+            let code_id = deps
+                .querier
+                .query_wasm_contract_info(cw721)
+                .ok()
+                .map(|info| info.code_id);
+            let version = deps
+                .querier
+                .query_wasm_raw(cw721, cw2::CONTRACT.as_slice())
+                .ok()
+                .flatten()
+                .and_then(|raw| from_slice::<ContractVersion>(&raw).ok());
+            (code_id, version)
+        }
+        None => (None, None),
+    };
+    Ok(crate::msg::ContractInfoResponse {
+        contract: get_contract_version(deps.storage)?,
+        cw721_code_id,
+        cw721_version,
+    })
+}
+
+fn query_collection_stats(deps: Deps, env: Env) -> StdResult<CollectionStatsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let (num_tokens, info) = match &config.cw721_address {
+        Some(cw721) => {
+            let num_tokens: Option<cw721::NumTokensResponse> = deps
+                .querier
+                .query_wasm_smart(cw721, &Cw721QueryMsg::NumTokens {})
+                .ok();
+            let info: Option<ContractInfoResponse> = deps
+                .querier
+                .query_wasm_smart(cw721, &Cw721QueryMsg::ContractInfo {})
+                .ok();
+            (num_tokens.map(|res| res.count), info)
+        }
+        None => (None, None),
+    };
+
+    let sale_status = if config.closed || config.retired {
+        SaleStatus::Closed
+    } else if config.paused {
+        SaleStatus::Paused
+    } else if config
+        .start_time
+        .is_some_and(|start| env.block.time < start)
+    {
+        SaleStatus::NotStarted
+    } else if config.end_time.is_some_and(|end| env.block.time >= end) {
+        SaleStatus::Ended
+    } else if config.remaining_supply() == 0 {
+        SaleStatus::SoldOut
+    } else {
+        SaleStatus::Active
+    };
+
+    Ok(CollectionStatsResponse {
+        minted: config.minted(),
+        burned: num_tokens.map(|count| config.minted().saturating_sub(count)),
+        num_tokens,
+        unique_holders: config.unique_minters,
+        sale_status,
+        cw20_address: config.cw20_address,
+        cw721_address: config.cw721_address,
+        name: info.as_ref().map(|info| info.name.clone()),
+        symbol: info.map(|info| info.symbol),
+    })
+}
+
+fn query_mints_of(deps: Deps, address: String) -> StdResult<MintsOfResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let address = deps.api.addr_validate(&address)?;
+    let minted = MINTS_PER_ADDRESS
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    Ok(MintsOfResponse {
+        address,
+        minted,
+        remaining: config
+            .mint_limit_per_address
+            .map(|limit| limit.saturating_sub(minted)),
+    })
+}
+
+fn query_provenance(deps: Deps, token_id: String) -> StdResult<ProvenanceResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let id = config
+        .parse_token_id(&token_id)
+        .ok_or_else(|| StdError::generic_err("invalid token id"))?;
+    let record = mints().load(deps.storage, id)?;
+    Ok(ProvenanceResponse {
+        token_id,
+        buyer: record.buyer,
+        price: record.price,
+        payment_token: record.payment_token,
+        height: record.height,
+        time: record.time,
+        token_uri: record.token_uri,
+        uri_updated: record.uri_updated,
+        memo: record.memo,
+    })
+}
+
+fn query_ai_metadata(deps: Deps, token_id: String) -> StdResult<AiMetadataResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let id = config
+        .parse_token_id(&token_id)
+        .ok_or_else(|| StdError::generic_err("invalid token id"))?;
+    let token_uri = mints()
+        .may_load(deps.storage, id)?
+        .and_then(|record| record.token_uri);
+    Ok(AiMetadataResponse {
+        token_id,
+        status: AI_METADATA.may_load(deps.storage, id)?,
+        token_uri,
+    })
+}
+
+fn query_deposit(deps: Deps, address: String) -> StdResult<DepositResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let balance = DEPOSITS
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    Ok(DepositResponse { address, balance })
+}
+
+fn query_mint_credits(deps: Deps, address: String) -> StdResult<MintCreditsResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let credits = MINT_CREDITS
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    Ok(MintCreditsResponse { address, credits })
+}
+
+fn query_nft_details(deps: Deps, token_id: String) -> StdResult<NftDetailsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let id = config
+        .parse_token_id(&token_id)
+        .ok_or_else(|| StdError::generic_err("invalid token id"))?;
+    let cw721 = cw721_of(deps.storage, &config, id)?
+        .ok_or_else(|| StdError::generic_err("cw721 not linked"))?;
+    let receipt = RECEIPTS
+        .may_load(deps.storage, id)?
+        .map(|receipt| receipt_info(&config, id, receipt));
+    let nft: AllNftInfoResponse<Option<Metadata>> = deps.querier.query_wasm_smart(
+        &cw721,
+        &Cw721QueryMsg::AllNftInfo {
+            token_id: token_id.clone(),
+            include_expired: None,
+        },
+    )?;
+    Ok(NftDetailsResponse {
+        provenance: query_provenance(deps, token_id)?,
+        receipt,
+        owner: nft.access.owner,
+        approvals: nft.access.approvals,
+        token_uri: nft.info.token_uri,
+        extension: nft.info.extension,
+    })
+}
+
+fn query_mints(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+    order: Option<SortOrder>,
+) -> StdResult<MintsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let limit = clamp_limit(limit);
+    let (min, max, order) = range_bounds(start_after, order);
+    let mints = mints()
+        .range(deps.storage, min, max, order)
+        .take(limit)
+        .map(|item| {
+            let (token_id, record) = item?;
+            Ok(MintInfo {
+                token_id: config.token_id(token_id),
+                buyer: record.buyer,
+                time: record.time,
+                price: record.price,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(MintsResponse { mints })
+}
+
+fn query_mints_between(
+    deps: Deps,
+    start: Timestamp,
+    end: Timestamp,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<MintsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let limit = clamp_limit(limit);
+    let after = match start_after {
+        Some(token_id) => Some((mints().load(deps.storage, token_id)?.time, token_id)),
+        None => None,
+    };
+    let min = match after {
+        Some((time, token_id)) if time >= start => Bound::exclusive((time.seconds(), token_id)),
+        _ => Bound::inclusive((start.seconds(), 0)),
+    };
+    let max = Bound::inclusive((end.seconds(), u64::MAX));
+    let mints = mints()
+        .idx
+        .time
+        .range(deps.storage, Some(min), Some(max), Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (token_id, record) = item?;
+            Ok(MintInfo {
+                token_id: config.token_id(token_id),
+                buyer: record.buyer,
+                time: record.time,
+                price: record.price,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(MintsResponse { mints })
+}
+
+fn query_tokens_by_buyer(
+    deps: Deps,
+    address: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+    order: Option<SortOrder>,
+) -> StdResult<MintsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let buyer = deps.api.addr_validate(&address)?;
+    let limit = clamp_limit(limit);
+    let (min, max, order) = range_bounds(start_after, order);
+    let mints = mints()
+        .idx
+        .buyer
+        .prefix(buyer)
+        .range(deps.storage, min, max, order)
+        .take(limit)
+        .map(|item| {
+            let (token_id, record) = item?;
+            Ok(MintInfo {
+                token_id: config.token_id(token_id),
+                buyer: record.buyer,
+                time: record.time,
+                price: record.price,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(MintsResponse { mints })
+}
+
+fn query_royalty_info(deps: Deps, sale_price: Uint128) -> StdResult<RoyaltyInfoResponse> {
+    Ok(match ROYALTY.may_load(deps.storage)? {
+        Some(royalty) => RoyaltyInfoResponse {
+            address: royalty.payment_address.into_string(),
+            royalty_amount: sale_price.multiply_ratio(royalty.bps, MAX_ROYALTY_BPS),
+        },
+        None => RoyaltyInfoResponse {
+            address: String::new(),
+            royalty_amount: Uint128::zero(),
+        },
+    })
+}
+
+fn query_current_price(deps: Deps) -> StdResult<CurrentPriceResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(CurrentPriceResponse {
+        current_price: token_price(deps.storage, &config, config.unused_token_id)?,
+        next_price: token_price(deps.storage, &config, config.unused_token_id + 1)?,
+    })
+}
+
+fn query_active_tier(deps: Deps) -> StdResult<ActiveTierResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(match tier_at(&config, config.unused_token_id) {
+        Some((index, tier, remaining)) => ActiveTierResponse {
+            index: Some(index as u32),
+            tier: Some(tier.clone()),
+            remaining_in_tier: remaining,
+        },
+        None => ActiveTierResponse {
+            index: None,
+            tier: None,
+            remaining_in_tier: 0,
+        },
+    })
+}
+
+fn query_referrer(deps: Deps, address: String) -> StdResult<ReferrerResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let stats = REFERRALS
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    Ok(ReferrerResponse {
+        address,
+        referrals: stats.referrals,
+        earned: stats.earned,
+    })
+}
+
+fn query_referrers(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ReferrersResponse> {
+    let limit = clamp_limit(limit);
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let referrers = REFERRALS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (address, stats) = item?;
+            Ok(ReferrerResponse {
+                address,
+                referrals: stats.referrals,
+                earned: stats.earned,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(ReferrersResponse { referrers })
+}
+
+fn query_phases(deps: Deps) -> StdResult<PhasesResponse> {
+    let phases = PHASES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(id, phase)| PhaseInfo { id, phase }))
+        .collect::<StdResult<_>>()?;
+    Ok(PhasesResponse { phases })
+}
+
+fn query_phase_allowlist(
+    deps: Deps,
+    phase_id: u8,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    order: Option<SortOrder>,
+) -> StdResult<PhaseAllowlistResponse> {
+    let limit = clamp_limit(limit);
+    let start = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+    let (min, max, order) = range_bounds(start.as_ref(), order);
+    let entries = PHASE_ALLOWLISTS
+        .prefix(phase_id)
+        .range(deps.storage, min, max, order)
+        .take(limit)
+        .map(|item| {
+            item.map(|(address, entry)| PhaseAllowlistInfo {
+                address,
+                price: entry.price,
+                mint_limit: entry.mint_limit,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(PhaseAllowlistResponse { phase_id, entries })
+}
+
+fn query_allowlist_status(
+    deps: Deps,
+    env: Env,
+    address: String,
+) -> StdResult<AllowlistStatusResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let address = deps.api.addr_validate(&address)?;
+    let phases = PHASES
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let now = env.block.time;
+    let phase = phases
+        .iter()
+        .find(|(_, phase)| phase.is_active(now))
+        .or_else(|| {
+            phases
+                .iter()
+                .filter(|(_, phase)| now < phase.start_time)
+                .min_by_key(|(_, phase)| phase.start_time)
+        });
+
+    let bought = MINTS_PER_ADDRESS
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    let mut remaining = config
+        .mint_limit_per_address
+        .map(|limit| limit.saturating_sub(bought));
+    let global = ALLOWLIST.may_load(deps.storage, &address)?;
+    let on_global = global.as_ref().is_some_and(|entry| !entry.is_expired(now));
+    let (allowlisted, restricted) = match phase {
+        Some((id, phase)) => {
+            let entry = PHASE_ALLOWLISTS.may_load(deps.storage, (*id, &address))?;
+            let phase_bought = PHASE_MINTS
+                .may_load(deps.storage, (*id, &address))?
+                .unwrap_or_default();
+            let caps = [
+                phase.per_wallet_cap,
+                entry.as_ref().and_then(|entry| entry.mint_limit),
+            ];
+            for cap in caps.into_iter().flatten() {
+                let left = cap.saturating_sub(phase_bought);
+                remaining = Some(remaining.map_or(left, |remaining| remaining.min(left)));
+            }
+            (on_global || entry.is_some(), phase.allowlist_only)
+        }
+        None => (
+            on_global,
+            config
+                .presale
+                .as_ref()
+                .is_some_and(|presale| now < presale.end_time),
+        ),
+    };
+    if restricted && !allowlisted {
+        remaining = Some(0);
+    }
+    let price = mint_price(
+        deps.storage,
+        &env,
+        &config,
+        phase.map(|(id, phase)| (*id, phase)),
+        &address,
+        1,
+        false,
+    )
+    .or_else(|_| price_for(deps.storage, &config, 1))?;
+
+    Ok(AllowlistStatusResponse {
+        phase_id: phase.map(|(id, _)| *id),
+        phase_active: phase.is_some_and(|(_, phase)| phase.is_active(now)),
+        allowlisted,
+        remaining,
+        price,
+        expires: global.and_then(|entry| entry.expires),
+    })
+}
+
+/// Mirrors the pricing in `execute_mint` for a primary cw20 payment.
+fn query_price_quote(
+    deps: Deps,
+    env: Env,
+    address: String,
+    quantity: u32,
+) -> StdResult<PriceQuoteResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let buyer = deps.api.addr_validate(&address)?;
+    let merkle_verified = config.merkle_root.is_some();
+    price_quote(
+        deps,
+        &env,
+        &config,
+        &config.cw20_address,
+        &buyer,
+        quantity,
+        merkle_verified,
+    )
+    .map_err(|err| StdError::generic_err(err.to_string()))
+}
+
+/// What `buyer` owes for `quantity` tokens paid in `token`, priced as in `execute_mint`.
+fn price_quote(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    token: &Addr,
+    buyer: &Addr,
+    quantity: u32,
+    merkle_verified: bool,
+) -> Result<PriceQuoteResponse, ContractError> {
+    if quantity == 0 || quantity as usize > MAX_BATCH_MINTS {
+        return Err(ContractError::InvalidQuantity {});
+    }
+    let phase = active_phase(deps.storage, env)?;
+    let primary = *token == config.cw20_address;
+    let mut price = mint_price(
+        deps.storage,
+        env,
+        config,
+        phase.as_ref().map(|(id, phase)| (*id, phase)),
+        buyer,
+        quantity,
+        merkle_verified,
+    )?;
+    if !primary {
+        price = PAYMENT_TOKENS
+            .load(deps.storage, token)?
+            .checked_mul(Uint128::from(quantity))?;
+    } else if let Some(oracle) = &config.price_oracle {
+        price = oracle_price(deps, env, token, oracle, quantity)?;
+    }
+    let mut partner_discount = Uint128::zero();
+    if let Some(partner) = config
+        .partner_discount
+        .as_ref()
+        .filter(|_| !primary || config.price_oracle.is_none())
+    {
+        if holds_cw721(deps, &partner.collection, buyer)? {
+            partner_discount = price.multiply_ratio(partner.discount_bps, MAX_ROYALTY_BPS);
+            price -= partner_discount;
+        }
+    }
+    Ok(PriceQuoteResponse {
+        price,
+        phase_id: phase.map(|(id, _)| id),
+        partner_discount,
+    })
+}
+
+fn query_can_mint(
+    deps: Deps,
+    env: Env,
+    address: String,
+    quantity: u32,
+) -> StdResult<CanMintResponse> {
+    let blocker = mint_blocker(deps, &env, &address, quantity)?;
+    Ok(CanMintResponse {
+        can_mint: blocker.is_none(),
+        blocker,
+    })
+}
+
+/// The checks of `execute_mint`, without taking payment, in the same order.
+fn mint_blocker(
+    deps: Deps,
+    env: &Env,
+    address: &str,
+    quantity: u32,
+) -> StdResult<Option<MintBlocker>> {
+    let config = CONFIG.load(deps.storage)?;
+    let now = env.block.time;
+    if config.retired || config.closed {
+        return Ok(Some(MintBlocker::Closed));
+    }
+    if config.paused {
+        return Ok(Some(MintBlocker::Paused));
+    }
+    if assert_not_blocklisted(deps, address).is_err() {
+        return Ok(Some(MintBlocker::Blocklisted));
+    }
+    if config.start_time.is_some_and(|start| now < start) {
+        return Ok(Some(MintBlocker::NotStarted));
+    }
+    if config.end_time.is_some_and(|end| now >= end) {
+        return Ok(Some(MintBlocker::Ended));
+    }
+    if config.cw721_address.is_none() {
+        return Ok(Some(MintBlocker::Closed));
+    }
+    if quantity == 0 || quantity as usize > MAX_BATCH_MINTS {
+        return Ok(Some(MintBlocker::InvalidQuantity));
+    }
+    if config.remaining_supply() < quantity as u64 {
+        return Ok(Some(MintBlocker::SoldOut));
+    }
+    if config.max_mints_per_tx.is_some_and(|max| quantity > max) {
+        return Ok(Some(MintBlocker::CapReached));
+    }
+
+    let buyer = deps.api.addr_validate(address)?;
+    match assert_mint_gate(deps, &config, &buyer) {
+        Err(ContractError::NotEligible {}) => return Ok(Some(MintBlocker::NotEligible)),
+        Err(err) => return Err(StdError::generic_err(err.to_string())),
+        Ok(()) => {}
+    }
+    if let Some(max) = config.max_mints_per_block {
+        let minted_this_block = BLOCK_MINTS
+            .may_load(deps.storage, &buyer)?
+            .filter(|mints| mints.height == env.block.height)
+            .map_or(0, |mints| mints.count);
+        if minted_this_block + quantity > max {
+            return Ok(Some(MintBlocker::CapReached));
+        }
+    }
+    let bought = MINTS_PER_ADDRESS
+        .may_load(deps.storage, &buyer)?
+        .unwrap_or_default();
+    if config
+        .mint_limit_per_address
+        .is_some_and(|limit| bought + quantity > limit)
+    {
+        return Ok(Some(MintBlocker::CapReached));
+    }
+
+    let phase = match active_phase(deps.storage, env) {
+        Ok(phase) => phase,
+        Err(ContractError::NoActivePhase {}) => return Ok(Some(MintBlocker::WrongPhase)),
+        Err(err) => return Err(StdError::generic_err(err.to_string())),
+    };
+    if phase
+        .as_ref()
+        .is_some_and(|(_, phase)| phase.free_supply.is_some())
+    {
+        return Ok(Some(MintBlocker::WrongPhase));
+    }
+    let mut phase_entry = None;
+    if let Some((id, phase)) = &phase {
+        let phase_bought = PHASE_MINTS
+            .may_load(deps.storage, (*id, &buyer))?
+            .unwrap_or_default();
+        phase_entry = PHASE_ALLOWLISTS.may_load(deps.storage, (*id, &buyer))?;
+        let caps = [
+            phase.per_wallet_cap,
+            phase_entry.as_ref().and_then(|entry| entry.mint_limit),
+        ];
+        if caps
+            .into_iter()
+            .flatten()
+            .any(|cap| phase_bought + quantity > cap)
+        {
+            return Ok(Some(MintBlocker::CapReached));
+        }
+    }
+    let restricted = match &phase {
+        Some((_, phase)) => phase.allowlist_only,
+        None => config
+            .presale
+            .as_ref()
+            .is_some_and(|presale| now < presale.end_time),
+    };
+    if restricted {
+        let entry = live_allowlist_entry(deps.storage, &buyer, now)?;
+        if entry
+            .as_ref()
+            .and_then(|entry| entry.mint_limit)
+            .is_some_and(|limit| bought + quantity > limit)
+        {
+            return Ok(Some(MintBlocker::CapReached));
+        }
+        if entry.is_none() && phase_entry.is_none() && config.merkle_root.is_none() {
+            return Ok(Some(MintBlocker::NotAllowlisted));
+        }
+    }
+    if config
+        .crowdfund
+        .as_ref()
+        .filter(|_| !config.crowdfund_settled)
+        .is_some_and(|crowdfund| now >= crowdfund.deadline)
+    {
+        return Ok(Some(MintBlocker::Ended));
+    }
+    Ok(None)
+}
+
+fn query_mint_gate(deps: Deps) -> StdResult<MintGateResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(MintGateResponse {
+        gate: config.mint_gate,
+    })
+}
+
+fn query_active_phase(deps: Deps, env: Env) -> StdResult<ActivePhaseResponse> {
+    let phase = PHASES
+        .range(deps.storage, None, None, Order::Ascending)
+        .find(|item| {
+            item.as_ref()
+                .map_or(true, |(_, phase)| phase.is_active(env.block.time))
+        })
+        .transpose()?
+        .map(|(id, phase)| PhaseInfo { id, phase });
+    Ok(ActivePhaseResponse { phase })
+}
+
+fn query_schedule(deps: Deps, env: Env) -> StdResult<ScheduleResponse> {
+    let now = env.block.time;
+    let mut current = None;
+    let mut next: Option<PhaseInfo> = None;
+    for item in PHASES.range(deps.storage, None, None, Order::Ascending) {
+        let (id, phase) = item?;
+        if current.is_none() && phase.is_active(now) {
+            current = Some(PhaseInfo { id, phase });
+        } else if phase.start_time > now
+            && next
+                .as_ref()
+                .is_none_or(|next| phase.start_time < next.phase.start_time)
+        {
+            next = Some(PhaseInfo { id, phase });
+        }
+    }
+    let current_ends_at = current.as_ref().and_then(|current| current.phase.end_time);
+    let next_starts_at = next.as_ref().map(|next| next.phase.start_time);
+    let seconds_until = |time: Timestamp| time.seconds().saturating_sub(now.seconds());
+    Ok(ScheduleResponse {
+        seconds_until_end: current_ends_at.map(seconds_until),
+        seconds_until_next: next_starts_at.map(seconds_until),
+        current,
+        current_ends_at,
+        next,
+        next_starts_at,
+    })
+}
+
+fn query_revealed(deps: Deps) -> StdResult<RevealedResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(RevealedResponse {
+        revealed: config.revealed,
+        base_uri: config.revealed.then_some(config.token_uri),
+    })
+}
+
+fn query_listing(deps: Deps, token_id: String) -> StdResult<ListingInfo> {
+    let listing = LISTINGS.load(deps.storage, &token_id)?;
+    Ok(ListingInfo {
+        token_id,
+        seller: listing.seller,
+        price: listing.price,
+    })
+}
+
+fn query_listings(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    order: Option<SortOrder>,
+) -> StdResult<ListingsResponse> {
+    let limit = clamp_limit(limit);
+    let (min, max, order) = range_bounds(start_after.as_deref(), order);
+    let listings = LISTINGS
+        .range(deps.storage, min, max, order)
+        .take(limit)
+        .map(|item| {
+            let (token_id, listing) = item?;
+            Ok(ListingInfo {
+                token_id,
+                seller: listing.seller,
+                price: listing.price,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(ListingsResponse { listings })
+}
+
+fn query_mint_hooks(deps: Deps) -> StdResult<MintHooksResponse> {
+    let hooks = MINT_HOOKS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(MintHooksResponse { hooks })
+}
+
+fn query_operator(deps: Deps, address: String) -> StdResult<OperatorPerms> {
+    let address = deps.api.addr_validate(&address)?;
+    Ok(OPERATORS
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default())
+}
+
+fn query_operators(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<OperatorsResponse> {
+    let limit = clamp_limit(limit);
+    let start = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+    let operators = OPERATORS
+        .range(
+            deps.storage,
+            start.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(address, perms)| OperatorInfo { address, perms }))
+        .collect::<StdResult<_>>()?;
+    Ok(OperatorsResponse { operators })
+}
+
+fn query_permissions(deps: Deps, address: String) -> StdResult<PermissionsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let address = deps.api.addr_validate(&address)?;
+    Ok(PermissionsResponse {
+        is_owner: !config.renounced && config.owner == address,
+        perms: effective_perms(deps.storage, &config, &address)?,
+    })
+}
+
+fn query_token_expiry(deps: Deps, env: Env, token_id: String) -> StdResult<TokenExpiryResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let index = config
+        .parse_token_id(&token_id)
+        .ok_or_else(|| StdError::generic_err("invalid token id"))?;
+    let expiry = TOKEN_EXPIRY.may_load(deps.storage, index)?;
+    Ok(TokenExpiryResponse {
+        expiry,
+        expired: expiry.is_some_and(|expiry| env.block.time >= expiry),
+        revoked: REVOKED_TOKENS.has(deps.storage, index),
+    })
+}
+
+fn query_expired_tokens(
+    deps: Deps,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ExpiredTokensResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let limit = clamp_limit(limit);
+    let start = match start_after {
+        Some(token_id) => Some(Bound::exclusive(
+            config
+                .parse_token_id(&token_id)
+                .ok_or_else(|| StdError::generic_err("invalid token id"))?,
+        )),
+        None => None,
+    };
+    let token_ids = TOKEN_EXPIRY
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter(|item| {
+            item.as_ref()
+                .map_or(true, |(_, expiry)| env.block.time >= *expiry)
+        })
+        .take(limit)
+        .map(|item| item.map(|(index, _)| config.token_id(index)))
+        .collect::<StdResult<_>>()?;
+    Ok(ExpiredTokensResponse { token_ids })
+}
+
+fn query_token_prices(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TokenPricesResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let limit = clamp_limit(limit);
+    let start = match start_after {
+        Some(token_id) => Some(Bound::exclusive(
+            config
+                .parse_token_id(&token_id)
+                .ok_or_else(|| StdError::generic_err("invalid token id"))?,
+        )),
+        None => None,
+    };
+    let prices = TOKEN_PRICES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(index, price)| TokenPriceInfo {
+                token_id: config.token_id(index),
+                price,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(TokenPricesResponse { prices })
+}
+
+fn query_collection_info(deps: Deps) -> StdResult<CollectionInfoResponse> {
+    let info = match COLLECTION_INFO.may_load(deps.storage)? {
+        Some(info) => info,
+        None => CollectionInfo {
+            creator: CONFIG.load(deps.storage)?.owner,
+            description: None,
+            image: None,
+            external_link: None,
+            explicit_content: false,
+        },
+    };
+    let royalty_info = ROYALTY
+        .may_load(deps.storage)?
+        .map(|royalty| CollectionRoyaltyInfo {
+            payment_address: royalty.payment_address,
+            share: Decimal::from_ratio(royalty.bps, MAX_ROYALTY_BPS),
+        });
+    Ok(CollectionInfoResponse {
+        creator: info.creator,
+        description: info.description,
+        image: info.image,
+        external_link: info.external_link,
+        explicit_content: info.explicit_content,
+        royalty_info,
+    })
+}
+
+fn query_token_code_ids(deps: Deps) -> StdResult<TokenCodeIdsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let history = TOKEN_CODE_IDS
+        .may_load(deps.storage)?
+        .unwrap_or_else(|| vec![config.token_code_id]);
+    Ok(TokenCodeIdsResponse {
+        current: config.token_code_id,
+        history,
+    })
+}
+
+fn receipt_info(config: &Config, token_id: u64, receipt: Receipt) -> ReceiptInfo {
+    ReceiptInfo {
+        token_id: config.token_id(token_id),
+        payer: receipt.payer,
+        recipient: receipt.recipient,
+        amount: receipt.amount,
+        currency: receipt.currency,
+        phase_id: receipt.phase_id,
+        height: receipt.height,
+    }
+}
+
+fn query_receipt(deps: Deps, token_id: String) -> StdResult<ReceiptInfo> {
+    let config = CONFIG.load(deps.storage)?;
+    let index = config
+        .parse_token_id(&token_id)
+        .ok_or_else(|| StdError::generic_err("invalid token id"))?;
+    let receipt = RECEIPTS.load(deps.storage, index)?;
+    Ok(receipt_info(&config, index, receipt))
+}
+
+fn query_receipts(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ReceiptsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let limit = clamp_limit(limit);
+    let start = start_after.map(Bound::exclusive);
+    let receipts = RECEIPTS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(index, receipt)| receipt_info(&config, index, receipt)))
+        .collect::<StdResult<_>>()?;
+    Ok(ReceiptsResponse { receipts })
+}
+
+fn query_raffle_tickets(deps: Deps, address: String) -> StdResult<RaffleTicketsResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let tickets = RAFFLE_TICKETS
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    let winning = RAFFLE
+        .may_load(deps.storage)?
+        .filter(|raffle| raffle.seed.is_some())
+        .map(|raffle| {
+            tickets
+                .iter()
+                .copied()
+                .filter(|ticket| raffle.is_winner(*ticket))
+                .collect()
+        });
+    Ok(RaffleTicketsResponse { tickets, winning })
+}
+
+fn query_auctions(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AuctionsResponse> {
+    let limit = clamp_limit(limit);
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let auctions = AUCTIONS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(token_id, auction)| AuctionInfo { token_id, auction }))
+        .collect::<StdResult<_>>()?;
+    Ok(AuctionsResponse { auctions })
+}
+
+fn query_relayer(deps: Deps, address: String) -> StdResult<RelayerResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    Ok(RelayerResponse {
+        relayer: RELAYERS.has(deps.storage, &address),
+    })
+}
+
+fn query_nonce_used(
+    deps: Deps,
+    used: Map<(&Addr, u64), Empty>,
+    address: String,
+    nonce: u64,
+) -> StdResult<NonceUsedResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    Ok(NonceUsedResponse {
+        used: used.has(deps.storage, (&address, nonce)),
+    })
+}
+
+fn query_blocklisted(deps: Deps, address: String) -> StdResult<BlocklistedResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    Ok(BlocklistedResponse {
+        blocklisted: BLOCKLIST.has(deps.storage, &address),
+    })
+}
+
+fn query_blocklist(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<BlocklistResponse> {
+    let limit = clamp_limit(limit);
+    let start = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+    let addresses = BLOCKLIST
+        .keys(
+            deps.storage,
+            start.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+    Ok(BlocklistResponse { addresses })
+}
+
+fn query_holder_snapshot(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<HolderSnapshotResponse> {
+    let limit = clamp_limit(limit);
+    let start = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+    let snapshot = HOLDER_SNAPSHOT.may_load(deps.storage)?;
+    let holders = SNAPSHOT_HOLDERS
+        .range(
+            deps.storage,
+            start.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| {
+            let (address, tokens) = item?;
+            Ok(SnapshotHolder { address, tokens })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(HolderSnapshotResponse {
+        height: snapshot.as_ref().map(|snapshot| snapshot.height),
+        complete: snapshot.is_some_and(|snapshot| snapshot.complete),
+        holders,
+    })
+}
+
+fn query_payment_tokens(deps: Deps) -> StdResult<PaymentTokensResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let primary = PaymentTokenInfo {
+        address: config.cw20_address,
+        unit_price: config.unit_price,
+    };
+    let tokens = std::iter::once(Ok(primary))
+        .chain(
+            PAYMENT_TOKENS
+                .range(deps.storage, None, None, Order::Ascending)
+                .map(|item| {
+                    item.map(|(address, unit_price)| PaymentTokenInfo {
+                        address,
+                        unit_price,
+                    })
+                }),
+        )
+        .collect::<StdResult<_>>()?;
+    Ok(PaymentTokensResponse { tokens })
+}
+
+fn query_prices(deps: Deps) -> StdResult<PricesResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut prices = vec![CurrencyPrice {
+        currency: Currency::Cw20(config.cw20_address.clone()),
+        price: config.unit_price,
+    }];
+    for item in PAYMENT_TOKENS.range(deps.storage, None, None, Order::Ascending) {
+        let (address, price) = item?;
+        prices.push(CurrencyPrice {
+            currency: Currency::Cw20(address),
+            price,
+        });
+    }
+    if let Some(denom) = config.native_denom {
+        prices.push(CurrencyPrice {
+            currency: Currency::Native(denom),
+            price: config.native_price.unwrap_or(config.unit_price),
+        });
+    }
+    Ok(PricesResponse { prices })
+}
+
+fn query_vesting(deps: Deps, env: Env, token: Option<String>) -> StdResult<VestingResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let token = parse_currency(deps, &config, token)?;
+    let (vested, withdrawn, claimable) = match &config.vesting {
+        Some(vesting) => vested_amounts(deps, &env, &config, vesting, &token)?,
+        None => {
+            let available = available_balance(deps, &env, &config, &token)?;
+            (available, Uint128::zero(), available)
+        }
+    };
+    Ok(VestingResponse {
+        vesting: config.vesting,
+        token: token.to_string(),
+        vested,
+        withdrawn,
+        claimable,
+    })
+}
+
+fn query_escrow(deps: Deps, token_id: String) -> StdResult<EscrowResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let id = config
+        .parse_token_id(&token_id)
+        .ok_or_else(|| StdError::generic_err("invalid token id"))?;
+    let payment = ESCROWED_PAYMENTS.may_load(deps.storage, id)?;
+    Ok(EscrowResponse {
+        token_id,
+        refundable: payment.is_some() && escrows_until_reveal(&config),
+        payment,
+    })
+}
+
+fn query_trait_table(deps: Deps) -> StdResult<TraitTableResponse> {
+    Ok(TraitTableResponse {
+        traits: TRAIT_TABLE.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
+fn query_pending_mint(deps: Deps, job_id: String) -> StdResult<PendingMintResponse> {
+    Ok(PendingMintResponse {
+        pending: PENDING_MINTS.may_load(deps.storage, &job_id)?,
+        job_id,
+    })
+}
+
+fn query_claims(deps: Deps, address: String) -> StdResult<ClaimsResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let claims = CREATOR_CLAIMS
+        .prefix(&address)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, claim)| claim))
+        .collect::<StdResult<_>>()?;
+    Ok(ClaimsResponse { address, claims })
+}
+
+fn query_gift_info(deps: Deps, token_id: String) -> StdResult<GiftInfoResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let id = config
+        .parse_token_id(&token_id)
+        .ok_or_else(|| StdError::generic_err("invalid token id"))?;
+    let gift = GIFTS.may_load(deps.storage, id)?;
+    Ok(GiftInfoResponse {
+        token_id,
+        from: gift.as_ref().map(|gift| gift.from.clone()),
+        memo: gift.map(|gift| gift.memo),
+    })
+}
+
+fn query_collection(deps: Deps, collection_id: u32) -> StdResult<CollectionResponse> {
+    Ok(CollectionResponse {
+        collection_id,
+        collection: COLLECTIONS.load(deps.storage, collection_id)?,
+    })
+}
+
+fn query_collections(
+    deps: Deps,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+) -> StdResult<CollectionsResponse> {
+    let limit = clamp_limit(limit);
+    let start = start_after.map(Bound::exclusive);
+    let collections = COLLECTIONS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(collection_id, collection)| CollectionResponse {
+                collection_id,
+                collection,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(CollectionsResponse { collections })
+}
+
+fn query_archived_rounds(
+    deps: Deps,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+) -> StdResult<ArchivedRoundsResponse> {
+    let limit = clamp_limit(limit);
+    let start = start_after.map(Bound::exclusive);
+    let rounds = ARCHIVED_ROUNDS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(round_id, round)| ArchivedRoundInfo { round_id, round }))
+        .collect::<StdResult<_>>()?;
+    Ok(ArchivedRoundsResponse { rounds })
+}
+
+fn query_drops(
+    deps: Deps,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+    order: Option<SortOrder>,
+) -> StdResult<DropsResponse> {
+    let limit = clamp_limit(limit);
+    let (min, max, order) = range_bounds(start_after, order);
+    let drops = DROP_ROUNDS
+        .range(deps.storage, min, max, order)
+        .take(limit)
+        .map(|item| item.map(|(id, round)| DropInfo { id, round }))
+        .collect::<StdResult<_>>()?;
+    Ok(DropsResponse { drops })
+}
+
+fn query_drop_schedule(deps: Deps, env: Env) -> StdResult<DropScheduleResponse> {
+    let now = env.block.time;
+    let mut current = None;
+    let mut next: Option<DropInfo> = None;
+    for item in DROP_ROUNDS.range(deps.storage, None, None, Order::Ascending) {
+        let (id, round) = item?;
+        if current.is_none() && round.is_active(now) {
+            current = Some(DropInfo { id, round });
+        } else if round.start_time > now
+            && next
+                .as_ref()
+                .is_none_or(|next| round.start_time < next.round.start_time)
+        {
+            next = Some(DropInfo { id, round });
+        }
+    }
+    Ok(DropScheduleResponse { current, next })
+}
+
+fn rental_info(env: &Env, token_id: String, rental: Rental) -> RentalInfo {
+    let rented = rental.is_rented(env.block.time);
+    RentalInfo {
+        token_id,
+        lender: rental.lender,
+        daily_price: rental.daily_price,
+        renter: rental.renter.filter(|_| rented),
+        expires: rental.expires.filter(|_| rented),
+    }
+}
+
+fn query_rental(deps: Deps, env: Env, token_id: String) -> StdResult<RentalInfo> {
+    let rental = RENTALS.load(deps.storage, &token_id)?;
+    Ok(rental_info(&env, token_id, rental))
+}
+
+fn query_active_rentals(
+    deps: Deps,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<RentalsResponse> {
+    let limit = clamp_limit(limit);
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let rentals = RENTALS
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter(|item| {
+            item.as_ref()
+                .map_or(true, |(_, rental)| rental.is_rented(env.block.time))
+        })
+        .take(limit)
+        .map(|item| item.map(|(token_id, rental)| rental_info(&env, token_id, rental)))
+        .collect::<StdResult<_>>()?;
+    Ok(RentalsResponse { rentals })
+}
+
+fn stake_info(
+    config: &Config,
+    height: u64,
+    token_id: String,
+    stake: Stake,
+) -> StdResult<StakeInfo> {
+    let (pending, _) = pending_stake_rewards(config, &stake, height, config.staking_pool)?;
+    Ok(StakeInfo {
+        token_id,
+        owner: stake.owner,
+        staked_height: stake.staked_height,
+        pending,
+    })
+}
+
+fn query_stake(deps: Deps, env: Env, token_id: String) -> StdResult<StakeInfo> {
+    let config = CONFIG.load(deps.storage)?;
+    let stake = STAKES.load(deps.storage, &token_id)?;
+    stake_info(&config, env.block.height, token_id, stake)
+}
+
+fn query_stakes(
+    deps: Deps,
+    env: Env,
+    owner: Option<String>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<StakesResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let owner = owner
+        .map(|owner| deps.api.addr_validate(&owner))
+        .transpose()?;
+    let limit = clamp_limit(limit);
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let stakes = STAKES
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter(|item| {
+            item.as_ref().map_or(true, |(_, stake)| {
+                owner.as_ref().is_none_or(|o| stake.owner == *o)
+            })
+        })
+        .take(limit)
+        .map(|item| {
+            item.and_then(|(token_id, stake)| {
+                stake_info(&config, env.block.height, token_id, stake)
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(StakesResponse { stakes })
+}
+
+fn query_waitlist(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<WaitlistResponse> {
+    let limit = clamp_limit(limit);
+    let start = start_after.map(Bound::exclusive);
+    let entries = WAITLIST
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(position, entry)| WaitlistInfo {
+                position,
+                buyer: entry.buyer,
+                quantity: entry.quantity,
+                paid: entry.paid,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(WaitlistResponse { entries })
+}
+
+fn query_cw721(deps: Deps, msg: Binary) -> StdResult<Binary> {
+    let config = CONFIG.load(deps.storage)?;
+    let contract_addr = config
+        .cw721_address
+        .ok_or_else(|| StdError::generic_err("cw721 not initialized"))?;
+    let request: QueryRequest<Empty> = WasmQuery::Smart {
+        contract_addr: contract_addr.into_string(),
+        msg,
+    }
+    .into();
+    match deps.querier.raw_query(&to_vec(&request)?) {
+        SystemResult::Err(err) => Err(StdError::generic_err(format!(
+            "Querier system error: {}",
+            err
+        ))),
+        SystemResult::Ok(ContractResult::Err(err)) => Err(StdError::generic_err(format!(
+            "Querier contract error: {}",
+            err
+        ))),
+        SystemResult::Ok(ContractResult::Ok(res)) => Ok(res),
+    }
+}
+
+fn query_pending_owner(deps: Deps) -> StdResult<PendingOwnerResponse> {
+    let pending = PENDING_OWNER.may_load(deps.storage)?;
+    Ok(PendingOwnerResponse {
+        pending_owner: pending.as_ref().map(|p| p.owner.clone()),
+        expiry: pending.and_then(|p| p.expiry),
+    })
+}
+
+fn query_pending_payment_token(deps: Deps) -> StdResult<PendingPaymentTokenResponse> {
+    let pending = PENDING_PAYMENT_TOKEN.may_load(deps.storage)?;
+    Ok(PendingPaymentTokenResponse {
+        address: pending.as_ref().map(|p| p.address.clone()),
+        effective_at: pending.map(|p| p.effective_at),
+    })
+}
+
+fn query_config(deps: Deps, env: Env) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let phase = active_phase(deps.storage, &env);
+    let current_price = match &phase {
+        Ok(Some((_, phase))) if phase.price.is_some() => phase.price,
+        Ok(_) => match &config.presale {
+            Some(presale) if env.block.time < presale.end_time && presale.price.is_some() => {
+                presale.price
+            }
+            _ => Some(price_for(deps.storage, &config, 1)?),
+        },
+        Err(_) => None,
+    };
+    let active_phase = phase
+        .ok()
+        .flatten()
+        .map(|(id, phase)| PhaseInfo { id, phase });
+    Ok(ConfigResponse {
+        tokens_sold: config.minted().saturating_sub(config.reserved_minted),
+        tokens_remaining: config.remaining_supply(),
+        active_phase,
+        current_price,
+        owner: config.owner,
+        cw20_address: config.cw20_address,
+        cw721_address: config.cw721_address,
+        max_tokens: config.max_tokens,
+        unit_price: config.unit_price,
+        name: config.name,
+        symbol: config.symbol,
+        token_uri: config.token_uri,
+        extension: config.extension,
+        unused_token_id: config.unused_token_id,
+        treasury: config.treasury,
+        paused: config.paused,
+        start_time: config.start_time,
+        end_time: config.end_time,
+        token_uri_mode: config.token_uri_mode,
+        shuffle_token_ids: config.shuffle_token_ids,
+        reserved_supply: config.reserved_supply,
+        reserved_minted: config.reserved_minted,
+        strict_payment: config.strict_payment,
+        mint_limit_per_address: config.mint_limit_per_address,
+        token_code_id: config.token_code_id,
+        cw721_instantiate_error: config.cw721_instantiate_error,
+        price_curve: config.price_curve,
+        price_tiers: config.price_tiers,
+        presale: config.presale,
+        referral_bps: config.referral_bps,
+        payment_splits: config.payment_splits,
+        onchain_metadata: config.onchain_metadata,
+        placeholder_uri: config.placeholder_uri,
+        revealed: config.revealed,
+        frozen: config.frozen,
+        refund_bps: config.refund_bps,
+        total_refunded: config.total_refunded,
+        upgrade_rule: config.upgrade_rule,
+        upgrades_minted: config.upgrades_minted,
+        market_fee_bps: config.market_fee_bps,
+        crowdfund: config.crowdfund,
+        crowdfund_sold: config.crowdfund_sold,
+        crowdfund_settled: config.crowdfund_settled,
+        price_oracle: config.price_oracle,
+        voucher_signer: config.voucher_signer,
+        max_mints_per_tx: config.max_mints_per_tx,
+        max_mints_per_block: config.max_mints_per_block,
+        cw721_salt: config.cw721_salt,
+        cw721_gas_limit: config.cw721_gas_limit,
+        cw721_reply_on: config.cw721_reply_on,
+        ibc_counterparty_port: config.ibc_counterparty_port,
+        ibc_connection_id: config.ibc_connection_id,
+        ibc_counterparty_channel: config.ibc_counterparty_channel,
+        ics721_bridge: config.ics721_bridge,
+        soulbound: config.soulbound,
+        cw721_admin: config.cw721_admin,
+        cw721_label: config.cw721_label,
+        cw721_funds: config.cw721_funds,
+        closed: config.closed,
+        custom_metadata: config.custom_metadata,
+        open_edition: config.open_edition,
+        final_supply: config.final_supply,
+        supply_governor: config.supply_governor,
+        waitlist: config.waitlist,
+        waitlist_escrow: config.waitlist_escrow,
+        native_denom: config.native_denom,
+        native_price: config.native_price,
+        creators: config.creators,
+        allowlist_count: config.allowlist_count,
+        merkle_root: config.merkle_root,
+        merkle_version: config.merkle_version,
+        partner_discount: config.partner_discount,
+        mint_gate: config.mint_gate,
+        staking: config.staking,
+        staking_pool: config.staking_pool,
+        vesting: config.vesting,
+        reveal_escrow: config.reveal_escrow,
+        token_id_template: config.token_id_template,
+        randomness: config.randomness,
+        pending_mints: config.pending_mints,
+        unique_minters: config.unique_minters,
+        retired: config.retired,
+        renounced: config.renounced,
+        failed_mints: config.failed_mints,
+        drop_reserved: config.drop_reserved,
+        protocol_fee: config.protocol_fee,
+        burn_bps: config.burn_bps,
+        cw20_symbol: config.cw20_symbol,
+        cw20_decimals: config.cw20_decimals,
+        commit_reveal_blocks: config.commit_reveal_blocks,
+        gatekeeper: config.gatekeeper,
+        owner_key: config.owner_key,
+        renewal: config.renewal,
+        trade_in: config.trade_in,
+        raffle_reserved: config.raffle_reserved,
+        raffle_escrow: config.raffle_escrow,
+        auction_escrow: config.auction_escrow,
+        cw721_version: config.cw721_version,
+        membership: config.membership,
+        swap_route: config.swap_route,
+        ai_oracle: config.ai_oracle,
+        allowed_uri_prefixes: config.allowed_uri_prefixes,
+        deposit_escrow: config.deposit_escrow,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msg::{
+        AdminPayload, MintIntent, MintPermit, RoyaltyMsg, SignedAdminMsg, TradeInMsg,
+    };
+    use crate::state::Renewal;
+    use crate::state::{ConfigV0_1, Crowdfund, Presale, Trait, WeightedValue};
+    use cosmwasm_std::testing::{
+        mock_env, mock_info, MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR,
+    };
+    use cosmwasm_std::{
+        attr, coins, from_binary, to_binary, ContractResult, SubMsgResponse, SystemError,
+        SystemResult, WasmQuery,
+    };
+    use cosmwasm_std::{OwnedDeps, QuerierResult};
+    use prost::Message;
+
+    const NFT_CONTRACT_ADDR: &str = "nftcontract";
+
+    /// Addresses that answer cw20 `TokenInfo` in `mock_dependencies`.
+    const CW20_CONTRACTS: [&str; 2] = [MOCK_CONTRACT_ADDR, "new_token"];
+
+    /// Mock dependencies where `CW20_CONTRACTS` answer as cw20s, since setting a payment
+    /// token queries it.
+    fn mock_dependencies() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+        let mut deps = cosmwasm_std::testing::mock_dependencies();
+        deps.querier.update_wasm(cw20_token_info);
+        deps
+    }
+
+    fn cw20_token_info(query: &WasmQuery) -> QuerierResult {
+        match query {
+            WasmQuery::Smart { contract_addr, .. }
+                if CW20_CONTRACTS.contains(&contract_addr.as_str()) =>
+            {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&Cw20TokenInfoResponse {
+                        name: String::from("Payment token"),
+                        symbol: String::from("PAY"),
+                        decimals: 6,
+                        total_supply: Uint128::zero(),
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => SystemResult::Err(SystemError::NoSuchContract {
+                addr: String::from("unknown"),
+            }),
+        }
+    }
+
+    /// Like `cw20_token_info`, answering every cw20 `Balance` query with `balance`.
+    fn cw20_with_balance(balance: u128) -> impl Fn(&WasmQuery) -> QuerierResult {
+        move |query| match query {
+            WasmQuery::Smart { msg, .. }
+                if matches!(from_binary(msg), Ok(Cw20QueryMsg::Balance { .. })) =>
+            {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&Cw20BalanceResponse {
+                        balance: Uint128::new(balance),
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => cw20_token_info(query),
+        }
+    }
+
+    /// Answers `ContractInfo` for the child with code id 7, as a migrated config looks it up.
+    fn cw721_code_info(query: &WasmQuery) -> QuerierResult {
+        match query {
+            WasmQuery::ContractInfo { contract_addr } if contract_addr == NFT_CONTRACT_ADDR => {
+                let mut info = cosmwasm_std::ContractInfoResponse::default();
+                info.code_id = 7;
+                SystemResult::Ok(ContractResult::Ok(to_binary(&info).unwrap()))
+            }
+            _ => cw20_token_info(query),
+        }
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    struct MsgInstantiateContractResponse {
+        #[prost(string, tag = "1")]
+        pub contract_address: ::prost::alloc::string::String,
+        #[prost(bytes, tag = "2")]
+        pub data: ::prost::alloc::vec::Vec<u8>,
+    }
+
+    fn default_instantiate_msg() -> InstantiateMsg {
+        InstantiateMsg {
+            owner: Addr::unchecked("owner"),
+            max_tokens: 1,
+            unit_price: Uint128::new(1),
+            name: String::from("FirstFT"),
+            symbol: String::from("FFT"),
+            token_code_id: 10u64,
+            cw20_address: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            token_uri: String::from("https://ipfs.io/ipfs/Q"),
+            extension: None,
+            treasury: None,
+            start_time: None,
+            end_time: None,
+            token_uri_mode: None,
+            shuffle_token_ids: None,
+            reserved_supply: None,
+            strict_payment: None,
+            cw721_address: None,
+            mint_limit_per_address: None,
+            royalty: None,
+            price_curve: None,
+            presale: None,
+            referral_bps: None,
+            onchain_metadata: None,
+            placeholder_uri: None,
+            refund_bps: None,
+            market_fee_bps: None,
+            crowdfund: None,
+            price_oracle: None,
+            voucher_signer: None,
+            max_mints_per_tx: None,
+            max_mints_per_block: None,
+            cw721_salt: None,
+            cw721_gas_limit: None,
+            cw721_reply_on: None,
+            soulbound: None,
+            cw721_admin: None,
+            cw721_label: None,
+            cw721_funds: None,
+            open_edition: None,
+            supply_governor: None,
+            waitlist: None,
+            native_denom: None,
+            creators: None,
+            partner_discount: None,
+            mint_gate: None,
+            vesting: None,
+            reveal_escrow: None,
+            token_id_template: None,
+            protocol_fee: None,
+            burn_bps: None,
+            commit_reveal_blocks: None,
+            gatekeeper: None,
+            renewal: None,
+            metadata_code_id: None,
+            collection_info: None,
+            cw721_version: None,
+            membership: None,
+            ai_oracle: None,
+        }
+    }
+
+    /// Instantiates the contract and simulates a successful cw721 instantiation reply.
+    fn setup_contract(mut deps: DepsMut, msg: InstantiateMsg) {
+        instantiate(deps.branch(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+        let instantiate_reply = MsgInstantiateContractResponse {
+            contract_address: NFT_CONTRACT_ADDR.to_string(),
+            data: vec![],
+        };
+        let reply_msg = Reply {
+            id: INSTANTIATE_TOKEN_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(instantiate_reply.encode_to_vec().into()),
+            }),
+        };
+        reply(deps, mock_env(), reply_msg).unwrap();
+    }
+
+    #[test]
+    fn initialization() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Addr::unchecked("owner"),
+            max_tokens: 1,
+            unit_price: Uint128::new(1),
+            name: String::from("FirstFT"),
+            symbol: String::from("FFT"),
+            token_code_id: 10u64,
+            cw20_address: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            token_uri: String::from("https://ipfs.io/ipfs/Q"),
+            extension: None,
+            treasury: None,
+            start_time: None,
+            end_time: None,
+            token_uri_mode: None,
+            shuffle_token_ids: None,
+            reserved_supply: None,
+            strict_payment: None,
+            cw721_address: None,
+            mint_limit_per_address: None,
+            royalty: None,
+            price_curve: None,
+            presale: None,
+            referral_bps: None,
+            onchain_metadata: None,
+            placeholder_uri: None,
+            refund_bps: None,
+            market_fee_bps: None,
+            crowdfund: None,
+            price_oracle: None,
+            voucher_signer: None,
+            max_mints_per_tx: None,
+            max_mints_per_block: None,
+            cw721_salt: None,
+            cw721_gas_limit: None,
+            cw721_reply_on: None,
+            soulbound: None,
+            cw721_admin: None,
+            cw721_label: None,
+            cw721_funds: None,
+            open_edition: None,
+            supply_governor: None,
+            waitlist: None,
+            native_denom: None,
+            creators: None,
+            partner_discount: None,
+            mint_gate: None,
+            vesting: None,
+            reveal_escrow: None,
+            token_id_template: None,
+            protocol_fee: None,
+            burn_bps: None,
+            commit_reveal_blocks: None,
+            gatekeeper: None,
+            renewal: None,
+            metadata_code_id: None,
+            collection_info: None,
+            cw721_version: None,
+            membership: None,
+            ai_oracle: None,
+        };
+
+        let info = mock_info("owner", &[]);
+        let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+
+        instantiate(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
+
+        assert_eq!(
+            res.messages,
+            vec![SubMsg {
+                msg: WasmMsg::Instantiate {
+                    code_id: msg.token_code_id,
+                    msg: to_binary(&Cw721InstantiateMsg {
+                        name: msg.name.clone(),
+                        symbol: msg.symbol.clone(),
+                        minter: MOCK_CONTRACT_ADDR.to_string(),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                    admin: None,
+                    label: String::from("Instantiate fixed price NFT contract"),
+                }
+                .into(),
+                id: INSTANTIATE_TOKEN_REPLY_ID,
+                gas_limit: None,
+                reply_on: ReplyOn::Always,
+            }]
+        );
+
+        let instantiate_reply = MsgInstantiateContractResponse {
+            contract_address: "nftcontract".to_string(),
+            data: vec![2u8; 32769],
+        };
+        let mut encoded_instantiate_reply =
+            Vec::<u8>::with_capacity(instantiate_reply.encoded_len());
+        instantiate_reply
+            .encode(&mut encoded_instantiate_reply)
+            .unwrap();
+
+        let reply_msg = Reply {
+            id: INSTANTIATE_TOKEN_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(encoded_instantiate_reply.into()),
+            }),
+        };
+        reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+        let query_msg = QueryMsg::GetConfig {};
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let config: Config = from_binary(&res).unwrap();
+        assert_eq!(
+            config,
+            Config {
+                owner: Addr::unchecked("owner"),
+                cw20_address: msg.cw20_address,
+                cw721_address: Some(Addr::unchecked(NFT_CONTRACT_ADDR)),
+                max_tokens: msg.max_tokens,
+                unit_price: msg.unit_price,
+                name: msg.name,
+                symbol: msg.symbol,
+                token_uri: msg.token_uri,
+                extension: None,
+                unused_token_id: 0,
+                treasury: None,
+                paused: false,
+                start_time: None,
+                end_time: None,
+                token_uri_mode: TokenUriMode::Fixed,
+                shuffle_token_ids: false,
+                reserved_supply: None,
+                reserved_minted: 0,
+                strict_payment: false,
+                mint_limit_per_address: None,
+                token_code_id: msg.token_code_id,
+                cw721_instantiate_error: None,
+                price_curve: None,
+                price_tiers: vec![],
+                presale: None,
+                referral_bps: None,
+                payment_splits: vec![],
+                onchain_metadata: false,
+                placeholder_uri: None,
+                revealed: false,
+                frozen: false,
+                refund_bps: None,
+                total_refunded: Uint128::zero(),
+                upgrade_rule: None,
+                upgrades_minted: 0,
+                market_fee_bps: 0,
+                crowdfund: None,
+                crowdfund_sold: 0,
+                crowdfund_settled: false,
+                price_oracle: None,
+                voucher_signer: None,
+                max_mints_per_tx: None,
+                max_mints_per_block: None,
+                cw721_salt: None,
+                cw721_gas_limit: None,
+                cw721_reply_on: ReplyOn::Always,
+                ibc_counterparty_port: None,
+                ibc_connection_id: None,
+                ibc_counterparty_channel: None,
+                ics721_bridge: None,
+                soulbound: false,
+                cw721_admin: None,
+                cw721_label: String::from(DEFAULT_CW721_LABEL),
+                cw721_funds: vec![],
+                closed: false,
+                custom_metadata: false,
+                open_edition: false,
+                final_supply: None,
+                supply_governor: None,
+                waitlist: false,
+                waitlist_escrow: Uint128::zero(),
+                native_denom: None,
+                native_price: None,
+                creators: vec![],
+                allowlist_count: 0,
+                merkle_root: None,
+                merkle_version: 0,
+                partner_discount: None,
+                mint_gate: None,
+                staking: None,
+                staking_pool: Uint128::zero(),
+                vesting: None,
+                reveal_escrow: false,
+                token_id_template: None,
+                randomness: None,
+                pending_mints: 0,
+                unique_minters: 0,
+                retired: false,
+                renounced: false,
+                failed_mints: 0,
+                drop_reserved: 0,
+                protocol_fee: None,
+                burn_bps: None,
+                cw20_symbol: Some(String::from("PAY")),
+                cw20_decimals: Some(6),
+                commit_reveal_blocks: None,
+                gatekeeper: None,
+                owner_key: None,
+                renewal: None,
+                trade_in: None,
+                raffle_reserved: 0,
+                raffle_escrow: Uint128::zero(),
+                auction_escrow: Uint128::zero(),
+                cw721_version: Cw721Version::Legacy,
+                membership: None,
+                swap_route: None,
+                ai_oracle: None,
+                allowed_uri_prefixes: vec![],
+                deposit_escrow: Uint128::zero(),
+            }
+        );
+    }
+
+    #[test]
+    fn update_unit_price() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+
+        let msg = ExecuteMsg::UpdateUnitPrice {
+            price: Uint128::new(5),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let zero = ExecuteMsg::UpdateUnitPrice {
+            price: Uint128::zero(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), zero).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidUnitPrice {}));
+
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        assert_eq!(
+            CONFIG.load(&deps.storage).unwrap().unit_price,
+            Uint128::new(5)
+        );
+    }
+
+    #[test]
+    fn two_step_ownership_transfer() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+
+        let env = mock_env();
+        let propose = ExecuteMsg::ProposeNewOwner {
+            owner: String::from("new_owner"),
+            expiry: Some(Expiration::AtHeight(env.block.height + 10)),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), propose).unwrap();
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::PendingOwner {}).unwrap();
+        let pending: PendingOwnerResponse = from_binary(&res).unwrap();
+        assert_eq!(pending.pending_owner, Some(Addr::unchecked("new_owner")));
+
+        let accept = ExecuteMsg::AcceptOwnership {};
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            accept.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let mut late = env.clone();
+        late.block.height += 10;
+        let err = execute(
+            deps.as_mut(),
+            late,
+            mock_info("new_owner", &[]),
+            accept.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::OwnershipProposalExpired {}));
+
+        execute(deps.as_mut(), env, mock_info("new_owner", &[]), accept).unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.owner, Addr::unchecked("new_owner"));
+        assert!(PENDING_OWNER.may_load(&deps.storage).unwrap().is_none());
+    }
+
+    #[test]
+    fn withdraw_defaults_to_treasury() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.treasury = Some(String::from("treasury"));
+        setup_contract(deps.as_mut(), msg);
+        deps.querier.update_wasm(cw20_with_balance(7));
+
+        let withdraw = ExecuteMsg::Withdraw {
+            amount: Some(Uint128::new(7)),
+            recipient: None,
+            token: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            withdraw.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), withdraw).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("treasury"),
+                    amount: Uint128::new(7),
+                })
+                .unwrap(),
+                funds: vec![],
+            })]
+        );
+    }
+
+    #[test]
+    fn migrate_from_v0_1() {
+        let mut deps = mock_dependencies();
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.1.0").unwrap();
+        let old = ConfigV0_1 {
+            owner: Addr::unchecked("owner"),
+            cw20_address: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            cw721_address: Some(Addr::unchecked(NFT_CONTRACT_ADDR)),
+            max_tokens: 10,
+            unit_price: Uint128::new(1),
+            name: String::from("FirstFT"),
+            symbol: String::from("FFT"),
+            token_uri: String::from("https://ipfs.io/ipfs/Q"),
+            extension: None,
+            unused_token_id: 3,
+        };
+        CONFIG_V0_1.save(&mut deps.storage, &old).unwrap();
+        deps.querier.update_wasm(cw721_code_info);
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.unused_token_id, 3);
+        assert_eq!(config.treasury, None);
+        let version = get_contract_version(&deps.storage).unwrap();
+        assert_eq!(version.version, CONTRACT_VERSION);
+
+        set_contract_version(&mut deps.storage, "crates.io:other", "0.1.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert!(matches!(err, ContractError::CannotMigrate { .. }));
+    }
+
+    #[test]
+    fn migrate_config_from_v0_2() {
+        let mut deps = mock_dependencies();
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.2.0").unwrap();
+        let old = ConfigV0_2 {
+            owner: Addr::unchecked("owner"),
+            cw20_address: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            cw721_address: Some(Addr::unchecked(NFT_CONTRACT_ADDR)),
+            max_tokens: 10,
+            unit_price: Uint128::new(1),
+            name: String::from("FirstFT"),
+            symbol: String::from("FFT"),
+            token_uri: String::from("https://ipfs.io/ipfs/Q"),
+            extension: None,
+            unused_token_id: 3,
+            treasury: Some(Addr::unchecked("treasury")),
+        };
+        CONFIG_V0_2.save(&mut deps.storage, &old).unwrap();
+        CONFIG.load(&deps.storage).unwrap_err();
+        deps.querier.update_wasm(cw721_code_info);
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.token_code_id, 7);
+        assert_eq!(config.max_tokens, 10);
+        assert_eq!(config.unused_token_id, 3);
+        assert_eq!(config.treasury, Some(Addr::unchecked("treasury")));
+        assert_eq!(config.remaining_supply(), 7);
+        let res: SupplyInfoResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::SupplyInfo {}).unwrap())
+                .unwrap();
+        assert_eq!(res.minted, 3);
+
+        // Without a child there is no code id to learn, so a retry has to name one.
+        let mut deps = mock_dependencies();
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.2.0").unwrap();
+        let old = ConfigV0_2 {
+            cw721_address: None,
+            ..old
+        };
+        CONFIG_V0_2.save(&mut deps.storage, &old).unwrap();
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        let info = mock_info("owner", &[]);
+        let retry = ExecuteMsg::RetryCw721Instantiate { code_id: None };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), retry).unwrap_err();
+        assert!(matches!(err, ContractError::UnknownCodeId {}));
+        let retry = ExecuteMsg::RetryCw721Instantiate { code_id: Some(5) };
+        execute(deps.as_mut(), mock_env(), info, retry).unwrap();
+    }
+
+    #[test]
+    fn migrate_token_ids_from_v0_2() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.2.0").unwrap();
+        let record = MintRecord {
+            buyer: Addr::unchecked("buyer"),
+            time: mock_env().block.time,
+            height: 12_345,
+            price: Uint128::new(1),
+            paid_out: Uint128::zero(),
+            payment_token: None,
+            denom: None,
+            token_uri: None,
+            uri_updated: None,
+            memo: None,
+        };
+        MINTS_V0_2.save(&mut deps.storage, 7, &record).unwrap();
+        SHUFFLED_IDS_V0_2.save(&mut deps.storage, 2, &9).unwrap();
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+        assert_eq!(mints().load(&deps.storage, 7).unwrap(), record);
+        assert_eq!(SHUFFLED_IDS.load(&deps.storage, 2).unwrap(), 9);
+        assert_eq!(MINTS_V0_2.may_load(&deps.storage, 7).unwrap(), None);
+    }
+
+    #[test]
+    fn paused_contract_rejects_mints() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::Pause {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::Pause {},
+        )
+        .unwrap();
+
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1),
+            msg: Binary::default(),
+        });
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            receive.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ContractPaused {}));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::Resume {},
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            receive,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn mint_window_is_enforced() {
+        let mut deps = mock_dependencies();
+        let now = mock_env().block.time;
+        let mut msg = default_instantiate_msg();
+        msg.start_time = Some(now.plus_seconds(10));
+        msg.end_time = Some(now.plus_seconds(20));
+        setup_contract(deps.as_mut(), msg);
+
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1),
+            msg: Binary::default(),
+        });
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), receive.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::MintNotStarted {}));
+
+        let mut env = mock_env();
+        env.block.time = now.plus_seconds(20);
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), receive.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::MintEnded {}));
+
+        env.block.time = now.plus_seconds(10);
+        execute(deps.as_mut(), env, info, receive).unwrap();
+    }
+
+    #[test]
+    fn base_token_uri_mode() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.token_uri = String::from("ipfs://QmFolder/");
+        msg.token_uri_mode = Some(TokenUriMode::Base);
+        setup_contract(deps.as_mut(), msg);
+
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1),
+            msg: Binary::default(),
+        });
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            receive,
+        )
+        .unwrap();
+        let mint = Cw721ExecuteMsg::<Extension, Empty>::Mint(MintMsg::<Extension> {
+            token_id: String::from("0"),
+            owner: String::from("buyer"),
+            token_uri: Some(String::from("ipfs://QmFolder/0.json")),
+            extension: None,
+        });
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_always(
+                WasmMsg::Execute {
+                    contract_addr: NFT_CONTRACT_ADDR.to_string(),
+                    msg: to_binary(&mint).unwrap(),
+                    funds: vec![],
+                },
+                MINT_REPLY_ID,
+            )]
+        );
+    }
+
+    #[test]
+    fn shuffled_token_ids_are_unique() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 5;
+        msg.shuffle_token_ids = Some(true);
+        setup_contract(deps.as_mut(), msg);
+
+        let mut env = mock_env();
+        let mut minted = vec![];
+        for _ in 0..5 {
+            env.block.height += 1;
+            let mut config = CONFIG.load(&deps.storage).unwrap();
+            let id = next_token_id(&mut deps.storage, &env, &config, "buyer").unwrap();
+            config.unused_token_id += 1;
+            CONFIG.save(&mut deps.storage, &config).unwrap();
+            minted.push(id);
+        }
+        minted.sort_unstable();
+        assert_eq!(minted, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn reserve_mint_respects_reserved_supply() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 10;
+        msg.reserved_supply = Some(3);
+        setup_contract(deps.as_mut(), msg);
+
+        let reserve = |quantity| ExecuteMsg::ReserveMint {
+            quantity,
+            recipient: String::from("team"),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            reserve(1),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            reserve(2),
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 2);
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            reserve(2),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ReservedSupplyExceeded {}));
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.unused_token_id, 2);
+        assert_eq!(config.reserved_minted, 2);
+    }
+
+    #[test]
+    fn airdrop_mints_one_per_recipient() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 2;
+        setup_contract(deps.as_mut(), msg);
+
+        let airdrop = |recipients: &[&str]| ExecuteMsg::Airdrop {
+            recipients: recipients.iter().map(|r| r.to_string()).collect(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            airdrop(&["alice", "bob", "carol"]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SoldOut {}));
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            airdrop(&["alice", "bob"]),
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().unused_token_id, 2);
+    }
+
+    #[test]
+    fn overpayment_is_refunded() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 2;
+        setup_contract(deps.as_mut(), msg);
+
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(3),
+            msg: Binary::default(),
+        });
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), receive.clone()).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(
+            res.messages[1].msg,
+            cw20_transfer_msg(
+                &Addr::unchecked(MOCK_CONTRACT_ADDR),
+                "buyer",
+                Uint128::new(2)
+            )
+            .unwrap()
+        );
+
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+        config.strict_payment = true;
+        CONFIG.save(&mut deps.storage, &config).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), info, receive).unwrap_err();
+        assert!(matches!(err, ContractError::WrongPaymentAmount {}));
+    }
+
+    #[test]
+    fn link_existing_cw721() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, .. } if contract_addr == NFT_CONTRACT_ADDR => {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&ContractInfoResponse {
+                        name: String::from("FirstFT"),
+                        symbol: String::from("FFT"),
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => cw20_token_info(query),
+        });
+
+        let mut msg = default_instantiate_msg();
+        msg.cw721_address = Some(String::from("notacollection"));
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidCw721 {}));
+
+        msg.cw721_address = Some(String::from(NFT_CONTRACT_ADDR));
+        let res = instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        assert!(res.messages.is_empty());
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(
+            config.cw721_address,
+            Some(Addr::unchecked(NFT_CONTRACT_ADDR))
+        );
+    }
+
+    #[test]
+    fn owner_can_link_cw721_after_lost_reply() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, .. } if contract_addr == NFT_CONTRACT_ADDR => {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&ContractInfoResponse {
+                        name: String::from("FirstFT"),
+                        symbol: String::from("FFT"),
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => cw20_token_info(query),
+        });
+        let owner = mock_info("owner", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            default_instantiate_msg(),
+        )
+        .unwrap();
+
+        let link = |address: &str| ExecuteMsg::SetCw721Address {
+            address: String::from(address),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            link("notacollection"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidCw721 {}));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            link(NFT_CONTRACT_ADDR),
+        )
+        .unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(
+            config.cw721_address,
+            Some(Addr::unchecked(NFT_CONTRACT_ADDR))
+        );
+
+        let err = execute(deps.as_mut(), mock_env(), owner, link(NFT_CONTRACT_ADDR)).unwrap_err();
+        assert!(matches!(err, ContractError::Cw721AlreadyLinked {}));
+    }
+
+    #[test]
+    fn receive_parses_mint_hook() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 3;
+        setup_contract(deps.as_mut(), msg);
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+
+        let malformed = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1),
+            msg: Binary::from(b"not json".to_vec()),
+        });
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), malformed).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidHookMsg {}));
+
+        let mint_two = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(2),
+            msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                quantity: Some(2),
+                ..MintOptions::default()
+            }))
+            .unwrap(),
+        });
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), mint_two.clone()).unwrap();
+        assert_eq!(res.messages.len(), 2);
+
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), mint_two).unwrap_err();
+        assert!(matches!(err, ContractError::SoldOut {}));
+
+        let partial = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(2),
+            msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                quantity: Some(2),
+                allow_partial: Some(true),
+                ..MintOptions::default()
+            }))
+            .unwrap(),
+        });
+        let res = execute(deps.as_mut(), mock_env(), info, partial).unwrap();
+        assert_eq!(
+            res.messages.last().unwrap().msg,
+            cw20_transfer_msg(
+                &Addr::unchecked(MOCK_CONTRACT_ADDR),
+                "buyer",
+                Uint128::new(1)
+            )
+            .unwrap()
+        );
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().unused_token_id, 3);
+    }
+
+    #[test]
+    fn supply_info_tracks_mints() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::SupplyInfo {}).unwrap();
+        let supply: SupplyInfoResponse = from_binary(&res).unwrap();
+        assert_eq!(supply.remaining, 1);
+        assert!(!supply.sold_out);
+
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1),
+            msg: Binary::default(),
+        });
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            receive,
+        )
+        .unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::SupplyInfo {}).unwrap();
+        let supply: SupplyInfoResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            supply,
+            SupplyInfoResponse {
+                minted: 1,
+                failed: 0,
+                remaining: 0,
+                max_tokens: 1,
+                sold_out: true,
+                open_edition: false,
+                final_supply: None,
+            }
+        );
+    }
+
+    #[test]
+    fn open_edition_has_unbounded_supply() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 0;
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMaxTokens {}));
+
+        msg.open_edition = Some(true);
+        setup_contract(deps.as_mut(), msg);
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(3),
+            msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                quantity: Some(3),
+                ..MintOptions::default()
+            }))
+            .unwrap(),
+        });
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        execute(deps.as_mut(), mock_env(), info, receive).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::SupplyInfo {}).unwrap();
+        let supply: SupplyInfoResponse = from_binary(&res).unwrap();
+        assert_eq!(supply.minted, 3);
+        assert_eq!(supply.max_tokens, 0);
+        assert!(supply.open_edition);
+        assert!(!supply.sold_out);
+    }
+
+    #[test]
+    fn per_address_mint_limit() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 10;
+        msg.mint_limit_per_address = Some(2);
+        setup_contract(deps.as_mut(), msg);
+
+        let receive = |quantity| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from("buyer"),
+                amount: Uint128::from(quantity as u128),
+                msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                    quantity: Some(quantity),
+                    ..MintOptions::default()
+                }))
+                .unwrap(),
+            })
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        execute(deps.as_mut(), mock_env(), info.clone(), receive(1)).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), info, receive(2)).unwrap_err();
+        assert!(matches!(err, ContractError::MintLimitReached {}));
+
+        let query_msg = QueryMsg::MintsOf {
+            address: String::from("buyer"),
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let mints: MintsOfResponse = from_binary(&res).unwrap();
+        assert_eq!(mints.minted, 1);
+        assert_eq!(mints.remaining, Some(1));
+    }
+
+    #[test]
+    fn mints_are_recorded_and_paginated() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 4;
+        msg.unit_price = Uint128::new(3);
+        setup_contract(deps.as_mut(), msg);
+
+        let airdrop = ExecuteMsg::Airdrop {
+            recipients: vec![
+                String::from("alice"),
+                String::from("bob"),
+                String::from("carol"),
+            ],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), airdrop).unwrap();
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("dave"),
+            amount: Uint128::new(3),
+            msg: Binary::default(),
+        });
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        execute(deps.as_mut(), mock_env(), info, receive).unwrap();
+
+        let query_msg = QueryMsg::Mints {
+            start_after: Some(0),
+            limit: Some(1),
+            order: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let mints: MintsResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            mints.mints,
+            vec![MintInfo {
+                token_id: String::from("1"),
+                buyer: Addr::unchecked("bob"),
+                time: mock_env().block.time,
+                price: Uint128::zero(),
+            }]
+        );
+        // Paid mints report what the buyer paid.
+        let query_msg = QueryMsg::Mints {
+            start_after: Some(2),
+            limit: None,
+            order: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let mints: MintsResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            mints.mints,
+            vec![MintInfo {
+                token_id: String::from("3"),
+                buyer: Addr::unchecked("dave"),
+                time: mock_env().block.time,
+                price: Uint128::new(3),
+            }]
+        );
+
+        let query_msg = QueryMsg::Provenance {
+            token_id: String::from("2"),
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let provenance: ProvenanceResponse = from_binary(&res).unwrap();
+        assert_eq!(provenance.buyer, Addr::unchecked("carol"));
+        assert_eq!(provenance.price, Uint128::zero());
+        assert_eq!(provenance.height, mock_env().block.height);
+    }
+
+    #[test]
+    fn mint_response_has_attributes() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1),
+            msg: Binary::default(),
+        });
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            receive,
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "mint"),
+                attr("buyer", "buyer"),
+                attr("price", "1"),
+                attr("quantity", "1"),
+                attr("token_id", "0"),
+                attr("cw721_address", NFT_CONTRACT_ADDR),
+            ]
+        );
+    }
+
+    #[test]
+    fn failed_cw721_instantiation_can_be_retried() {
+        let mut deps = mock_dependencies();
+        let msg = default_instantiate_msg();
+        instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+        let failed = Reply {
+            id: INSTANTIATE_TOKEN_REPLY_ID,
+            result: SubMsgResult::Err(String::from("out of gas")),
+        };
+        reply(deps.as_mut(), mock_env(), failed).unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(
+            config.cw721_instantiate_error,
+            Some(String::from("out of gas"))
+        );
+
+        let retry = ExecuteMsg::RetryCw721Instantiate { code_id: Some(11) };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            retry.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), retry).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].reply_on, ReplyOn::Always);
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().token_code_id, 11);
+    }
+
+    #[test]
+    fn mint_with_allowance_pulls_payment_first() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 5;
+        msg.unit_price = Uint128::new(4);
+        setup_contract(deps.as_mut(), msg);
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("buyer", &[]),
+            ExecuteMsg::MintWithAllowance { quantity: 2 },
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 3);
+        assert_eq!(
+            res.messages[0],
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: String::from("buyer"),
+                    recipient: MOCK_CONTRACT_ADDR.to_string(),
+                    amount: Uint128::new(8),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn royalty_info() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.royalty = Some(RoyaltyMsg {
+            payment_address: String::from("creator"),
+            bps: 10_001,
+        });
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidRoyalty {}));
+
+        msg.royalty = Some(RoyaltyMsg {
+            payment_address: String::from("creator"),
+            bps: 500,
+        });
+        setup_contract(deps.as_mut(), msg);
+        let query_msg = QueryMsg::RoyaltyInfo {
+            token_id: String::from("0"),
+            sale_price: Uint128::new(1000),
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let royalty: RoyaltyInfoResponse = from_binary(&res).unwrap();
+        assert_eq!(royalty.address, "creator");
+        assert_eq!(royalty.royalty_amount, Uint128::new(50));
+    }
+
+    #[test]
+    fn bonding_curve_pricing() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 10;
+        msg.unit_price = Uint128::new(100);
+        msg.price_curve = Some(PriceCurve::Linear {
+            increment: Uint128::new(10),
+        });
+        setup_contract(deps.as_mut(), msg);
+
+        let receive = |amount, quantity| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from("buyer"),
+                amount: Uint128::new(amount),
+                msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                    quantity: Some(quantity),
+                    ..MintOptions::default()
+                }))
+                .unwrap(),
+            })
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), receive(200, 2)).unwrap_err();
+        assert!(matches!(err, ContractError::WrongPaymentAmount {}));
+        execute(deps.as_mut(), mock_env(), info, receive(210, 2)).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::CurrentPrice {}).unwrap();
+        let price: CurrentPriceResponse = from_binary(&res).unwrap();
+        assert_eq!(price.current_price, Uint128::new(120));
+        assert_eq!(price.next_price, Uint128::new(130));
+
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+        config.price_curve = Some(PriceCurve::Exponential { growth_bps: 1_000 });
+        assert_eq!(price_at(&config, 2).unwrap(), Uint128::new(121));
+    }
+
+    #[test]
+    fn tiered_pricing() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 10;
+        msg.unit_price = Uint128::new(50);
+        setup_contract(deps.as_mut(), msg);
+
+        let tiers = vec![
+            PriceTier {
+                supply: 2,
+                price: Uint128::new(10),
+            },
+            PriceTier {
+                supply: 3,
+                price: Uint128::new(20),
+            },
+        ];
+        let set_tiers = ExecuteMsg::SetPriceTiers { tiers };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            set_tiers,
+        )
+        .unwrap();
+
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(
+            price_for(&deps.storage, &config, 3).unwrap(),
+            Uint128::new(40)
+        );
+        config.unused_token_id = 4;
+        assert_eq!(
+            price_for(&deps.storage, &config, 2).unwrap(),
+            Uint128::new(70)
+        );
+        CONFIG.save(&mut deps.storage, &config).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::ActiveTier {}).unwrap();
+        let tier: ActiveTierResponse = from_binary(&res).unwrap();
+        assert_eq!(tier.index, Some(1));
+        assert_eq!(tier.remaining_in_tier, 1);
+    }
+
+    #[test]
+    fn presale_allowlist_discounts() {
+        let mut deps = mock_dependencies();
+        let now = mock_env().block.time;
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 10;
+        msg.unit_price = Uint128::new(100);
+        msg.presale = Some(Presale {
+            end_time: now.plus_seconds(60),
+            price: Some(Uint128::new(80)),
+        });
+        setup_contract(deps.as_mut(), msg);
+
+        let add = ExecuteMsg::AddToAllowlist {
+            entries: vec![
+                AllowlistEntryMsg {
+                    address: String::from("friend"),
+                    price: None,
+                    expires: None,
+                },
+                AllowlistEntryMsg {
+                    address: String::from("vip"),
+                    price: Some(Uint128::new(50)),
+                    expires: None,
+                },
+                AllowlistEntryMsg {
+                    address: String::from("partner"),
+                    price: Some(Uint128::new(60)),
+                    expires: Some(now.plus_seconds(30)),
+                },
+            ],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), add).unwrap();
+
+        let receive = |sender: &str, amount| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(amount),
+                msg: Binary::default(),
+            })
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            receive("stranger", 100),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotAllowlisted {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            receive("friend", 80),
+        )
+        .unwrap();
+        execute(deps.as_mut(), mock_env(), info.clone(), receive("vip", 50)).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            receive("partner", 60),
+        )
+        .unwrap();
+
+        let mut lapsed = mock_env();
+        lapsed.block.time = now.plus_seconds(30);
+        let err = execute(
+            deps.as_mut(),
+            lapsed.clone(),
+            info.clone(),
+            receive("partner", 100),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotAllowlisted {}));
+        let query_msg = QueryMsg::AllowlistStatus {
+            address: String::from("partner"),
+        };
+        let status: AllowlistStatusResponse =
+            from_binary(&query(deps.as_ref(), lapsed, query_msg).unwrap()).unwrap();
+        assert!(!status.allowlisted);
+        assert_eq!(status.remaining, Some(0));
+        assert_eq!(status.expires, Some(now.plus_seconds(30)));
+
+        let mut env = mock_env();
+        env.block.time = now.plus_seconds(60);
+        let err =
+            execute(deps.as_mut(), env.clone(), info.clone(), receive("vip", 50)).unwrap_err();
+        assert!(matches!(err, ContractError::WrongPaymentAmount {}));
+        execute(deps.as_mut(), env, info, receive("stranger", 100)).unwrap();
+    }
+
+    #[test]
+    fn referral_rewards() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 10;
+        msg.unit_price = Uint128::new(100);
+        msg.referral_bps = Some(1_000);
+        setup_contract(deps.as_mut(), msg);
+
+        let receive = |referrer: &str| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from("buyer"),
+                amount: Uint128::new(200),
+                msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                    quantity: Some(2),
+                    referrer: Some(referrer.to_string()),
+                    ..MintOptions::default()
+                }))
+                .unwrap(),
+            })
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), receive("buyer")).unwrap_err();
+        assert!(matches!(err, ContractError::SelfReferral {}));
+
+        let res = execute(deps.as_mut(), mock_env(), info, receive("influencer")).unwrap();
+        assert_eq!(
+            res.messages[2].msg,
+            cw20_transfer_msg(
+                &Addr::unchecked(MOCK_CONTRACT_ADDR),
+                "influencer",
+                Uint128::new(20)
+            )
+            .unwrap()
+        );
+
+        let query_msg = QueryMsg::Referrers {
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let referrers: ReferrersResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            referrers.referrers,
+            vec![ReferrerResponse {
+                address: Addr::unchecked("influencer"),
+                referrals: 2,
+                earned: Uint128::new(20),
+            }]
+        );
+    }
+
+    #[test]
+    fn withdraw_distributes_payment_splits() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        deps.querier.update_wasm(cw20_with_balance(100));
+
+        let update = ExecuteMsg::UpdatePaymentSplits {
+            splits: vec![
+                PaymentSplitMsg {
+                    address: String::from("artist"),
+                    weight: 2,
+                },
+                PaymentSplitMsg {
+                    address: String::from("dev"),
+                    weight: 1,
+                },
+            ],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), update).unwrap();
+
+        let withdraw = ExecuteMsg::Withdraw {
+            amount: Some(Uint128::new(100)),
+            recipient: None,
+            token: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), withdraw).unwrap();
+        let token = Addr::unchecked(MOCK_CONTRACT_ADDR);
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(cw20_transfer_msg(&token, "artist", Uint128::new(67)).unwrap()),
+                SubMsg::new(cw20_transfer_msg(&token, "dev", Uint128::new(33)).unwrap()),
+            ]
+        );
+    }
+
+    #[test]
+    fn sudo_manages_parameters() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 3;
+        msg.shuffle_token_ids = Some(true);
+        setup_contract(deps.as_mut(), msg);
+
+        sudo(deps.as_mut(), mock_env(), SudoMsg::Pause {}).unwrap();
+        assert!(CONFIG.load(&deps.storage).unwrap().paused);
+
+        let update = SudoMsg::UpdateUnitPrice {
+            price: Uint128::new(9),
+        };
+        sudo(deps.as_mut(), mock_env(), update).unwrap();
+        assert_eq!(
+            CONFIG.load(&deps.storage).unwrap().unit_price,
+            Uint128::new(9)
+        );
+
+        // Mint two, grow the supply, then drain it: every id must come out exactly once.
+        let mut env = mock_env();
+        let mut minted = vec![];
+        let mut mint_one = |deps: &mut OwnedDeps<_, _, _>, env: &Env| {
+            let mut config = CONFIG.load(&deps.storage).unwrap();
+            let id = next_token_id(&mut deps.storage, env, &config, "buyer").unwrap();
+            config.unused_token_id += 1;
+            CONFIG.save(&mut deps.storage, &config).unwrap();
+            minted.push(id);
+        };
+        for _ in 0..2 {
+            env.block.height += 1;
+            mint_one(&mut deps, &env);
+        }
+        let grow = SudoMsg::UpdateMaxTokens { max_tokens: 5 };
+        sudo(deps.as_mut(), mock_env(), grow).unwrap();
+        for _ in 0..3 {
+            env.block.height += 1;
+            mint_one(&mut deps, &env);
+        }
+        minted.sort_unstable();
+        assert_eq!(minted, vec![0, 1, 2, 3, 4]);
+
+        let shrink = SudoMsg::UpdateMaxTokens { max_tokens: 4 };
+        let err = sudo(deps.as_mut(), mock_env(), shrink).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMaxTokens {}));
+    }
+
+    #[test]
+    fn phased_mint_schedule() {
+        let mut deps = mock_dependencies();
+        let now = mock_env().block.time;
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 10;
+        msg.unit_price = Uint128::new(100);
+        setup_contract(deps.as_mut(), msg);
+
+        let owner = mock_info("owner", &[]);
+        let og = Phase {
+            name: String::from("og"),
+            start_time: now,
+            end_time: Some(now.plus_seconds(60)),
+            price: Some(Uint128::new(50)),
+            per_wallet_cap: Some(1),
+            allowlist_only: true,
+            free_supply: None,
+        };
+        let public = Phase {
+            name: String::from("public"),
+            start_time: now.plus_seconds(60),
+            end_time: None,
+            price: None,
+            per_wallet_cap: None,
+            allowlist_only: false,
+            free_supply: None,
+        };
+        let set = |id, phase: &Phase| ExecuteMsg::SetPhase {
+            id,
+            phase: phase.clone(),
+        };
+        execute(deps.as_mut(), mock_env(), owner.clone(), set(0, &og)).unwrap();
+        execute(deps.as_mut(), mock_env(), owner.clone(), set(1, &public)).unwrap();
+        let overlapping = Phase {
+            start_time: now.plus_seconds(30),
+            ..public.clone()
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            set(2, &overlapping),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPhase {}));
+
+        let add = ExecuteMsg::AddToAllowlist {
+            entries: vec![AllowlistEntryMsg {
+                address: String::from("friend"),
+                price: None,
+                expires: None,
+            }],
+        };
+        execute(deps.as_mut(), mock_env(), owner, add).unwrap();
+
+        let receive = |sender: &str, amount| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(amount),
+                msg: Binary::default(),
+            })
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let mut env = mock_env();
+        env.block.time = now.minus_seconds(1);
+        let err = execute(deps.as_mut(), env, info.clone(), receive("friend", 50)).unwrap_err();
+        assert!(matches!(err, ContractError::NoActivePhase {}));
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            receive("stranger", 50),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotAllowlisted {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            receive("friend", 50),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            receive("friend", 50),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::MintLimitReached {}));
+
+        let mut env = mock_env();
+        env.block.time = now.plus_seconds(60);
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::ActivePhase {}).unwrap();
+        let active: ActivePhaseResponse = from_binary(&res).unwrap();
+        assert_eq!(active.phase.unwrap().id, 1);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            receive("friend", 100),
+        )
+        .unwrap();
+        execute(deps.as_mut(), env, info, receive("stranger", 100)).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Phases {}).unwrap();
+        let phases: PhasesResponse = from_binary(&res).unwrap();
+        assert_eq!(phases.phases.len(), 2);
+
+        let schedule = |env| -> ScheduleResponse {
+            from_binary(&query(deps.as_ref(), env, QueryMsg::Schedule {}).unwrap()).unwrap()
+        };
+        let mut env = mock_env();
+        env.block.time = now.plus_seconds(20);
+        let res = schedule(env.clone());
+        assert_eq!(res.current.unwrap().id, 0);
+        assert_eq!(res.current_ends_at, Some(now.plus_seconds(60)));
+        assert_eq!(res.next.unwrap().id, 1);
+        assert_eq!(res.next_starts_at, Some(now.plus_seconds(60)));
+        assert_eq!(res.seconds_until_end, Some(40));
+        assert_eq!(res.seconds_until_next, Some(40));
+
+        env.block.time = now.plus_seconds(60);
+        let res = schedule(env);
+        assert_eq!(res.current.unwrap().id, 1);
+        assert_eq!(res.current_ends_at, None);
+        assert!(res.next.is_none());
+        assert_eq!(res.seconds_until_end, None);
+    }
+
+    #[test]
+    fn onchain_metadata_per_token() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 2;
+        msg.onchain_metadata = Some(true);
+        setup_contract(deps.as_mut(), msg);
+
+        let metadata = Metadata {
+            name: Some(String::from("Genesis")),
+            attributes: Some(vec![Trait {
+                display_type: None,
+                trait_type: String::from("rarity"),
+                value: String::from("legendary"),
+            }]),
+            ..Metadata::default()
+        };
+        let set = ExecuteMsg::SetTokenMetadata {
+            token_id: 0,
+            metadata: metadata.clone(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), set).unwrap();
+
+        let reserve = ExecuteMsg::ReserveMint {
+            quantity: 2,
+            recipient: String::from("buyer"),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), reserve).unwrap();
+        let expected = |token_id: &str, metadata: Metadata| {
+            let mint = Cw721ExecuteMsg::<Option<Metadata>, Empty>::Mint(MintMsg {
+                token_id: token_id.to_string(),
+                owner: String::from("buyer"),
+                token_uri: Some(String::from("https://ipfs.io/ipfs/Q")),
+                extension: Some(metadata),
+            });
+            SubMsg::reply_always(
+                WasmMsg::Execute {
+                    contract_addr: NFT_CONTRACT_ADDR.to_string(),
+                    msg: to_binary(&mint).unwrap(),
+                    funds: vec![],
+                },
+                MINT_REPLY_ID + token_id.parse::<u64>().unwrap(),
+            )
+        };
+        let generated = Metadata {
+            name: Some(String::from("FirstFT #1")),
+            ..Metadata::default()
+        };
+        assert_eq!(
+            res.messages,
+            vec![expected("0", metadata.clone()), expected("1", generated)]
+        );
+
+        let set = ExecuteMsg::SetTokenMetadata {
+            token_id: 0,
+            metadata,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), set).unwrap_err();
+        assert!(matches!(err, ContractError::TokenAlreadyMinted {}));
+    }
+
+    #[test]
+    fn delayed_reveal() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 2;
+        msg.placeholder_uri = Some(String::from("ipfs://placeholder.json"));
+        setup_contract(deps.as_mut(), msg);
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(token_uri_for(&config, 0), "ipfs://placeholder.json");
+
+        let reveal = ExecuteMsg::Reveal {
+            base_uri: String::from("ipfs://QmReal/"),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            reveal.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            reveal.clone(),
+        )
+        .unwrap();
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(token_uri_for(&config, 1), "ipfs://QmReal/1.json");
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Revealed {}).unwrap();
+        let revealed: RevealedResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            revealed,
+            RevealedResponse {
+                revealed: true,
+                base_uri: Some(String::from("ipfs://QmReal/")),
+            }
+        );
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), reveal).unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyRevealed {}));
+    }
+
+    #[test]
+    fn freeze_locks_collection() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.placeholder_uri = Some(String::from("ipfs://placeholder.json"));
+        setup_contract(deps.as_mut(), msg);
+
+        let owner = mock_info("owner", &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            ExecuteMsg::Freeze {},
+        )
+        .unwrap();
+        assert!(CONFIG.load(&deps.storage).unwrap().frozen);
+
+        let msgs = vec![
+            ExecuteMsg::UpdateUnitPrice {
+                price: Uint128::new(2),
+            },
+            ExecuteMsg::SetPriceTiers { tiers: vec![] },
+            ExecuteMsg::Reveal {
+                base_uri: String::from("ipfs://QmReal"),
+            },
+            ExecuteMsg::Freeze {},
+        ];
+        for msg in msgs {
+            let err = execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap_err();
+            assert!(matches!(err, ContractError::ConfigFrozen {}));
+        }
+        let err = sudo(
+            deps.as_mut(),
+            mock_env(),
+            SudoMsg::UpdateMaxTokens { max_tokens: 5 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ConfigFrozen {}));
+    }
+
+    #[test]
+    fn burn_to_refund() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.unit_price = Uint128::new(100);
+        msg.refund_bps = Some(5_000);
+        setup_contract(deps.as_mut(), msg);
+
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(100),
+            msg: Binary::default(),
+        });
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            receive,
+        )
+        .unwrap();
+
+        let return_nft = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: String::from("holder"),
+            token_id: String::from("0"),
+            msg: to_binary(&Cw721HookMsg::Refund {}).unwrap(),
+        });
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("fake_nft", &[]),
+            return_nft.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(NFT_CONTRACT_ADDR, &[]),
+            return_nft,
+        )
+        .unwrap();
+        let burn = Cw721ExecuteMsg::<Extension, Empty>::Burn {
+            token_id: String::from("0"),
+        };
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(WasmMsg::Execute {
+                    contract_addr: NFT_CONTRACT_ADDR.to_string(),
+                    msg: to_binary(&burn).unwrap(),
+                    funds: vec![],
+                }),
+                SubMsg::new(
+                    cw20_transfer_msg(
+                        &Addr::unchecked(MOCK_CONTRACT_ADDR),
+                        "holder",
+                        Uint128::new(50)
+                    )
+                    .unwrap()
+                ),
+            ]
+        );
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.total_refunded, Uint128::new(50));
+    }
+
+    #[test]
+    fn burn_to_upgrade() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+
+        let rule = UpgradeRule {
+            tokens_required: 2,
+            token_uri: String::from("ipfs://QmUpgraded"),
+            metadata: None,
+        };
+        let set = ExecuteMsg::SetUpgradeRule {
+            rule: Some(rule.clone()),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), set).unwrap();
+
+        let send = |token_id: &str| {
+            ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+                sender: String::from("holder"),
+                token_id: token_id.to_string(),
+                msg: to_binary(&Cw721HookMsg::Upgrade {}).unwrap(),
+            })
+        };
+        let nft = mock_info(NFT_CONTRACT_ADDR, &[]);
+        let res = execute(deps.as_mut(), mock_env(), nft.clone(), send("3")).unwrap();
+        assert!(res.messages.is_empty());
+
+        let res = execute(deps.as_mut(), mock_env(), nft, send("7")).unwrap();
+        let wasm = |msg: Binary| {
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: NFT_CONTRACT_ADDR.to_string(),
+                msg,
+                funds: vec![],
+            })
+        };
+        let burn = |token_id: &str| {
+            to_binary(&Cw721ExecuteMsg::<Extension, Empty>::Burn {
+                token_id: token_id.to_string(),
+            })
+            .unwrap()
+        };
+        let mint = Cw721ExecuteMsg::<Extension, Empty>::Mint(MintMsg::<Extension> {
+            token_id: String::from("upgrade-0"),
+            owner: String::from("holder"),
+            token_uri: Some(rule.token_uri),
+            extension: None,
+        });
+        assert_eq!(
+            res.messages,
+            vec![
+                wasm(burn("3")),
+                wasm(burn("7")),
+                wasm(to_binary(&mint).unwrap())
+            ]
+        );
+        assert!(!UPGRADE_DEPOSITS.has(&deps.storage, &Addr::unchecked("holder")));
+    }
+
+    #[test]
+    fn secondary_market_sale() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.market_fee_bps = Some(200);
+        msg.royalty = Some(RoyaltyMsg {
+            payment_address: String::from("creator"),
+            bps: 500,
+        });
+        setup_contract(deps.as_mut(), msg);
+
+        let list = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: String::from("seller"),
+            token_id: String::from("4"),
+            msg: to_binary(&Cw721HookMsg::List {
+                price: Uint128::new(1_000),
+            })
+            .unwrap(),
+        });
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(NFT_CONTRACT_ADDR, &[]),
+            list,
+        )
+        .unwrap();
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Listings {
+                start_after: None,
+                limit: None,
+                order: None,
+            },
+        )
+        .unwrap();
+        let listings: ListingsResponse = from_binary(&res).unwrap();
+        assert_eq!(listings.listings.len(), 1);
+
+        let buy = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1_000),
+            msg: to_binary(&Cw20HookMsg::Buy {
+                token_id: String::from("4"),
+            })
+            .unwrap(),
+        });
+        let cw20 = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let res = execute(deps.as_mut(), mock_env(), cw20.clone(), buy.clone()).unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        let token = Addr::unchecked(MOCK_CONTRACT_ADDR);
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(cw721_transfer_msg(&config, &Addr::unchecked("buyer"), "4").unwrap()),
+                SubMsg::new(cw20_transfer_msg(&token, "creator", Uint128::new(50)).unwrap()),
+                SubMsg::new(cw20_transfer_msg(&token, "seller", Uint128::new(930)).unwrap()),
+            ]
+        );
+
+        let err = execute(deps.as_mut(), mock_env(), cw20, buy).unwrap_err();
+        assert!(matches!(err, ContractError::ListingNotFound {}));
+    }
+
+    #[test]
+    fn crowdfund_escrow_and_settlement() {
+        let now = mock_env().block.time;
+        let receive = |sender: &str| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(1),
+                msg: Binary::default(),
+            })
+        };
+        let cw20 = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let setup = || {
+            let mut deps = mock_dependencies();
+            let mut msg = default_instantiate_msg();
+            msg.max_tokens = 5;
+            msg.crowdfund = Some(Crowdfund {
+                soft_cap: 2,
+                deadline: now.plus_seconds(100),
+            });
+            setup_contract(deps.as_mut(), msg);
+            deps
+        };
+
+        // Missed target: the buyer gets their payment back and the escrowed token is burned.
+        let mut deps = setup();
+        let res = execute(deps.as_mut(), mock_env(), cw20.clone(), receive("alice")).unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        let escrowed = cw721_mint_msg(
+            &config,
+            String::from("0"),
+            &Addr::unchecked(MOCK_CONTRACT_ADDR),
+            String::from("https://ipfs.io/ipfs/Q"),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_always(escrowed, MINT_REPLY_ID)]
+        );
+        let claim = ExecuteMsg::ClaimPledge {};
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            claim.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::CrowdfundPending {}));
+        let mut late = mock_env();
+        late.block.time = now.plus_seconds(100);
+        let res = execute(deps.as_mut(), late, mock_info("alice", &[]), claim.clone()).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(
+            res.messages[1],
+            SubMsg::new(
+                cw20_transfer_msg(
+                    &Addr::unchecked(MOCK_CONTRACT_ADDR),
+                    "alice",
+                    Uint128::new(1)
+                )
+                .unwrap()
+            )
+        );
+
+        // Target reached: the owner settles and buyers claim their tokens.
+        let mut deps = setup();
+        execute(deps.as_mut(), mock_env(), cw20.clone(), receive("alice")).unwrap();
+        let owner = mock_info("owner", &[]);
+        let settle = ExecuteMsg::SettleCrowdfund {};
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), settle.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::CrowdfundTargetNotMet {}));
+        execute(deps.as_mut(), mock_env(), cw20, receive("bob")).unwrap();
+        execute(deps.as_mut(), mock_env(), owner, settle).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), claim).unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(
+                cw721_transfer_msg(&config, &Addr::unchecked("bob"), "1").unwrap()
+            )]
+        );
+    }
+
+    #[test]
+    fn alternative_payment_tokens() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 2;
+        msg.refund_bps = Some(10_000);
+        setup_contract(deps.as_mut(), msg);
+
+        let add = ExecuteMsg::AddPaymentToken {
+            address: String::from("stable"),
+            price: Uint128::new(25),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), add).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::PaymentTokens {}).unwrap();
+        let tokens: PaymentTokensResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            tokens.tokens,
+            vec![
+                PaymentTokenInfo {
+                    address: Addr::unchecked(MOCK_CONTRACT_ADDR),
+                    unit_price: Uint128::new(1),
+                },
+                PaymentTokenInfo {
+                    address: Addr::unchecked("stable"),
+                    unit_price: Uint128::new(25),
+                },
+            ]
+        );
+
+        let receive = |amount| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from("buyer"),
+                amount: Uint128::new(amount),
+                msg: Binary::default(),
+            })
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("other", &[]),
+            receive(25),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::UnauthorizedTokenContract {}));
+        let stable = mock_info("stable", &[]);
+        let err = execute(deps.as_mut(), mock_env(), stable.clone(), receive(1)).unwrap_err();
+        assert!(matches!(err, ContractError::WrongPaymentAmount {}));
+        execute(deps.as_mut(), mock_env(), stable, receive(25)).unwrap();
+
+        // Buy-backs are paid in the token the mint was paid with.
+        let return_nft = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: String::from("buyer"),
+            token_id: String::from("0"),
+            msg: to_binary(&Cw721HookMsg::Refund {}).unwrap(),
+        });
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(NFT_CONTRACT_ADDR, &[]),
+            return_nft,
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[1],
+            SubMsg::new(
+                cw20_transfer_msg(&Addr::unchecked("stable"), "buyer", Uint128::new(25)).unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn oracle_usd_pricing() {
+        let mut deps = mock_dependencies();
+        let now = mock_env().block.time;
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::Smart { contract_addr, .. } if contract_addr == "oracle" => {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&OraclePriceResponse {
+                        rate: Decimal::percent(250),
+                        updated_at: now.minus_seconds(30),
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => cw20_token_info(query),
+        });
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 3;
+        msg.price_oracle = Some(PriceOracleMsg {
+            address: String::from("oracle"),
+            usd_price: Uint128::new(40),
+            max_staleness: 60,
+            tolerance_bps: 500,
+        });
+        setup_contract(deps.as_mut(), msg);
+
+        let receive = |amount| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from("buyer"),
+                amount: Uint128::new(amount),
+                msg: Binary::default(),
+            })
+        };
+        // 40 USD at 2.5 tokens per USD quotes 100 tokens, accepted within 5%.
+        let cw20 = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let err = execute(deps.as_mut(), mock_env(), cw20.clone(), receive(94)).unwrap_err();
+        assert!(matches!(err, ContractError::WrongPaymentAmount {}));
+        let err = execute(deps.as_mut(), mock_env(), cw20.clone(), receive(106)).unwrap_err();
+        assert!(matches!(err, ContractError::WrongPaymentAmount {}));
+        let res = execute(deps.as_mut(), mock_env(), cw20.clone(), receive(96)).unwrap();
+        assert_eq!(res.messages.len(), 1);
+
+        let mut env = mock_env();
+        env.block.time = now.plus_seconds(31);
+        let err = execute(deps.as_mut(), env, cw20, receive(100)).unwrap_err();
+        assert!(matches!(err, ContractError::StalePrice {}));
+    }
+
+    #[test]
+    fn mint_to_recipient() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+
+        let hook = Cw20HookMsg::Mint(MintOptions {
+            recipient: Some(String::from("friend")),
+            ..MintOptions::default()
+        });
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1),
+            msg: to_binary(&hook).unwrap(),
+        });
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            receive,
+        )
+        .unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        let mint = cw721_mint_msg(
+            &config,
+            String::from("0"),
+            &Addr::unchecked("friend"),
+            config.token_uri.clone(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_always(mint, MINT_REPLY_ID)]
+        );
+        assert!(res.attributes.contains(&attr("recipient", "friend")));
+
+        let record = mints().load(&deps.storage, 0).unwrap();
+        assert_eq!(record.buyer, Addr::unchecked("buyer"));
+        let bought = MINTS_PER_ADDRESS
+            .load(&deps.storage, &Addr::unchecked("buyer"))
+            .unwrap();
+        assert_eq!(bought, 1);
+    }
+
+    #[test]
+    fn gift_memo_is_stored() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+
+        let gift = |memo: String| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from("buyer"),
+                amount: Uint128::new(1),
+                msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                    recipient: Some(String::from("friend")),
+                    memo: Some(memo),
+                    ..MintOptions::default()
+                }))
+                .unwrap(),
+            })
+        };
+        let cw20 = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            cw20.clone(),
+            gift("x".repeat(MAX_MEMO_LENGTH + 1)),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::MemoTooLong {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            cw20,
+            gift(String::from("Happy birthday!")),
+        )
+        .unwrap();
+
+        let token_id = String::from("0");
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GiftInfo { token_id }).unwrap();
+        let info: GiftInfoResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            info,
+            GiftInfoResponse {
+                token_id: String::from("0"),
+                from: Some(Addr::unchecked("buyer")),
+                memo: Some(String::from("Happy birthday!")),
+            }
+        );
+        let token_id = String::from("0");
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Provenance { token_id }).unwrap();
+        let provenance: ProvenanceResponse = from_binary(&res).unwrap();
+        assert_eq!(provenance.memo.as_deref(), Some("Happy birthday!"));
+    }
+
+    #[test]
+    fn signed_voucher_mints() {
+        use k256::ecdsa::signature::Signer;
+        use k256::ecdsa::{Signature, SigningKey};
+        use k256::elliptic_curve::sec1::ToEncodedPoint;
+
+        let key = SigningKey::from_bytes(&[7u8; 32]).unwrap();
+        let pubkey = key.verifying_key().to_encoded_point(true);
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.voucher_signer = Some(Binary::from(pubkey.as_bytes()));
+        setup_contract(deps.as_mut(), msg);
+
+        let voucher = Voucher {
+            token_id: String::from("vip-1"),
+            token_uri: String::from("ipfs://QmVip"),
+            price: Uint128::new(5),
+            recipient: String::from("friend"),
+            expiry: mock_env().block.time.plus_seconds(60),
+        };
+        let sign = |voucher: &Voucher| {
+            let mut payload = MOCK_CONTRACT_ADDR.as_bytes().to_vec();
+            payload.extend_from_slice(&to_binary(voucher).unwrap());
+            let signature: Signature = key.sign(&payload);
+            Binary::from(signature.as_ref())
+        };
+        let redeem = |voucher: Voucher, signature| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from("buyer"),
+                amount: Uint128::new(5),
+                msg: to_binary(&Cw20HookMsg::RedeemVoucher { voucher, signature }).unwrap(),
+            })
+        };
+        let cw20 = mock_info(MOCK_CONTRACT_ADDR, &[]);
+
+        let tampered = Voucher {
+            price: Uint128::new(1),
+            ..voucher.clone()
+        };
+        let msg = redeem(tampered, sign(&voucher));
+        let err = execute(deps.as_mut(), mock_env(), cw20.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidVoucherSignature {}));
+        // Beyond u32, but still in the sale's id sequence.
+        let sale_id = Voucher {
+            token_id: String::from("4294967296"),
+            ..voucher.clone()
+        };
+        let msg = redeem(sale_id.clone(), sign(&sale_id));
+        let err = execute(deps.as_mut(), mock_env(), cw20.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidTokenId {}));
+
+        // An overpayment is refunded and the events report the voucher price.
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(7),
+            msg: to_binary(&Cw20HookMsg::RedeemVoucher {
+                voucher: voucher.clone(),
+                signature: sign(&voucher),
+            })
+            .unwrap(),
+        });
+        // Unless payments must be exact.
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+        config.strict_payment = true;
+        CONFIG.save(&mut deps.storage, &config).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), cw20.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::WrongPaymentAmount {}));
+        config.strict_payment = false;
+        CONFIG.save(&mut deps.storage, &config).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), cw20.clone(), msg.clone()).unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        let mint = cw721_mint_msg(
+            &config,
+            voucher.token_id.clone(),
+            &Addr::unchecked("friend"),
+            voucher.token_uri,
+            None,
+        )
+        .unwrap();
+        let refund = cw20_transfer_msg(
+            &Addr::unchecked(MOCK_CONTRACT_ADDR),
+            "buyer",
+            Uint128::new(2),
+        )
+        .unwrap();
+        assert_eq!(res.messages, vec![SubMsg::new(mint), SubMsg::new(refund)]);
+        assert_eq!(
+            res.events,
+            vec![mint_event(
+                &voucher.token_id,
+                "buyer",
+                &Addr::unchecked("friend"),
+                Uint128::new(5),
+                None
+            )]
+        );
+
+        let err = execute(deps.as_mut(), mock_env(), cw20, msg).unwrap_err();
+        assert!(matches!(err, ContractError::VoucherRedeemed {}));
+    }
+
+    #[test]
+    fn anti_bot_limits() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 10;
+        msg.max_mints_per_tx = Some(2);
+        msg.max_mints_per_block = Some(3);
+        setup_contract(deps.as_mut(), msg);
+
+        let receive = |quantity| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from("bot"),
+                amount: Uint128::new(quantity as u128),
+                msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                    quantity: Some(quantity),
+                    ..MintOptions::default()
+                }))
+                .unwrap(),
+            })
+        };
+        let cw20 = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let err = execute(deps.as_mut(), mock_env(), cw20.clone(), receive(3)).unwrap_err();
+        assert!(matches!(err, ContractError::TooManyMintsPerTx {}));
+
+        execute(deps.as_mut(), mock_env(), cw20.clone(), receive(2)).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), cw20.clone(), receive(2)).unwrap_err();
+        assert!(matches!(err, ContractError::BlockMintLimitReached {}));
+        execute(deps.as_mut(), mock_env(), cw20.clone(), receive(1)).unwrap();
+
+        let mut env = mock_env();
+        env.block.height += 1;
+        execute(deps.as_mut(), env, cw20, receive(2)).unwrap();
+    }
+
+    #[test]
+    fn factory_collections() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+
+        let create = ExecuteMsg::CreateCollection {
+            name: String::from("Second"),
+            symbol: String::from("SEC"),
+            unit_price: Uint128::new(3),
+            max_tokens: 2,
+            token_uri: String::from("ipfs://QmSecond"),
+            token_code_id: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), create).unwrap();
+        let reply_id = res.messages[0].id;
+        assert_eq!(reply_id, INSTANTIATE_COLLECTION_REPLY_ID);
+
+        let instantiate_reply = MsgInstantiateContractResponse {
+            contract_address: String::from("second_nft"),
+            data: vec![],
+        };
+        let mut encoded_instantiate_reply =
+            Vec::<u8>::with_capacity(instantiate_reply.encoded_len());
+        instantiate_reply
+            .encode(&mut encoded_instantiate_reply)
+            .unwrap();
+        let reply_msg = Reply {
+            id: reply_id,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(encoded_instantiate_reply.into()),
+            }),
+        };
+        reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(6),
+            msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                quantity: Some(2),
+                collection_id: Some(0),
+                ..MintOptions::default()
+            }))
+            .unwrap(),
+        });
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            receive,
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert!(matches!(
+            &res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) if contract_addr == "second_nft"
+        ));
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Collections {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+        let collections: CollectionsResponse = from_binary(&res).unwrap();
+        assert_eq!(collections.collections.len(), 1);
+        assert_eq!(collections.collections[0].collection.minted, 2);
+        // The main collection is untouched.
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().unused_token_id, 0);
+    }
+
+    #[test]
+    fn instantiate2_with_salt() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.cw721_salt = Some(Binary::from(b"moni-drop-1"));
+        let res = instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].id, INSTANTIATE_TOKEN_REPLY_ID);
+        assert!(matches!(
+            &res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Instantiate2 { code_id: 10, salt, .. })
+                if salt.as_slice() == b"moni-drop-1"
+        ));
+    }
+
+    #[test]
+    fn cw721_submsg_gas_limit_and_reply() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.cw721_gas_limit = Some(500_000);
+        msg.cw721_reply_on = Some(ReplyOn::Success);
+        let res = instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        assert_eq!(res.messages[0].gas_limit, Some(500_000));
+        assert_eq!(res.messages[0].reply_on, ReplyOn::Success);
+
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.cw721_reply_on = Some(ReplyOn::Error);
+        let err = instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidReplyOn {}));
+    }
+
+    #[test]
+    fn cw721_admin_label_and_funds() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.cw721_admin = Some(String::from("owner"));
+        msg.cw721_label = Some(String::from("moni drop"));
+        msg.cw721_funds = Some(coins(10, "ujuno"));
+        let res = instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        assert!(matches!(
+            &res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Instantiate { admin: Some(admin), label, funds, .. })
+                if admin == "owner" && label == "moni drop" && *funds == coins(10, "ujuno")
+        ));
+    }
+
+    #[test]
+    fn soulbound_child_has_contract_as_admin() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.soulbound = Some(true);
+        let res = instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+        let expected = to_binary(&NonTransferableInstantiateMsg {
+            admin: Some(String::from(MOCK_CONTRACT_ADDR)),
+            name: String::from("FirstFT"),
+            symbol: String::from("FFT"),
+            minter: String::from(MOCK_CONTRACT_ADDR),
+        })
+        .unwrap();
+        assert!(matches!(
+            &res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Instantiate { msg, .. }) if *msg == expected
+        ));
+        assert!(CONFIG.load(&deps.storage).unwrap().soulbound);
+    }
+
+    #[test]
+    fn nft_rentals() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+
+        let offer = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: String::from("lender"),
+            token_id: String::from("2"),
+            msg: to_binary(&Cw721HookMsg::OfferRental {
+                daily_price: Uint128::new(10),
+            })
+            .unwrap(),
+        });
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(NFT_CONTRACT_ADDR, &[]),
+            offer,
+        )
+        .unwrap();
+
+        let rent = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("renter"),
+            amount: Uint128::new(30),
+            msg: to_binary(&Cw20HookMsg::Rent {
+                token_id: String::from("2"),
+                days: 3,
+            })
+            .unwrap(),
+        });
+        let cw20 = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let res = execute(deps.as_mut(), mock_env(), cw20.clone(), rent.clone()).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(
+                cw20_transfer_msg(
+                    &Addr::unchecked(MOCK_CONTRACT_ADDR),
+                    "lender",
+                    Uint128::new(30)
+                )
+                .unwrap()
+            )]
+        );
+        let err = execute(deps.as_mut(), mock_env(), cw20, rent).unwrap_err();
+        assert!(matches!(err, ContractError::TokenRented {}));
+
+        let active = |deps: Deps, env: Env| {
+            let msg = QueryMsg::ActiveRentals {
+                start_after: None,
+                limit: None,
+            };
+            let res: RentalsResponse = from_binary(&query(deps, env, msg).unwrap()).unwrap();
+            res.rentals
+        };
+        assert_eq!(
+            active(deps.as_ref(), mock_env())[0].renter,
+            Some(Addr::unchecked("renter"))
+        );
+
+        let withdraw = ExecuteMsg::WithdrawRental {
+            token_id: String::from("2"),
+        };
+        let lender = mock_info("lender", &[]);
+        let err = execute(deps.as_mut(), mock_env(), lender.clone(), withdraw.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::TokenRented {}));
+
+        let mut expired = mock_env();
+        expired.block.time = expired.block.time.plus_seconds(3 * 86_400);
+        assert!(active(deps.as_ref(), expired.clone()).is_empty());
+        execute(deps.as_mut(), expired, lender, withdraw).unwrap();
+    }
+
+    #[test]
+    fn cw721_query_passthrough() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == NFT_CONTRACT_ADDR => {
+                match from_binary(msg).unwrap() {
+                    Cw721QueryMsg::NumTokens {} => SystemResult::Ok(ContractResult::Ok(
+                        to_binary(&cw721::NumTokensResponse { count: 7 }).unwrap(),
+                    )),
+                    _ => SystemResult::Ok(ContractResult::Err(String::from("unsupported"))),
+                }
+            }
+            _ => SystemResult::Err(SystemError::NoSuchContract {
+                addr: String::from("unknown"),
+            }),
+        });
+
+        let msg = QueryMsg::Cw721 {
+            msg: to_binary(&Cw721QueryMsg::NumTokens {}).unwrap(),
+        };
+        let res: cw721::NumTokensResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.count, 7);
+
+        let msg = QueryMsg::Cw721 {
+            msg: to_binary(&Cw721QueryMsg::ContractInfo {}).unwrap(),
+        };
+        query(deps.as_ref(), mock_env(), msg).unwrap_err();
+    }
+
+    #[test]
+    fn config_response_has_derived_fields() {
+        let mut deps = mock_dependencies();
+        let now = mock_env().block.time;
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 10;
+        msg.unit_price = Uint128::new(100);
+        setup_contract(deps.as_mut(), msg);
+
+        let config = |deps: Deps| -> ConfigResponse {
+            from_binary(&query(deps, mock_env(), QueryMsg::GetConfig {}).unwrap()).unwrap()
+        };
+        let res = config(deps.as_ref());
+        assert_eq!(res.tokens_sold, 0);
+        assert_eq!(res.tokens_remaining, 10);
+        assert_eq!(res.active_phase, None);
+        assert_eq!(res.current_price, Some(Uint128::new(100)));
+
+        let phase = Phase {
+            name: String::from("public"),
+            start_time: now,
+            end_time: None,
+            price: Some(Uint128::new(50)),
+            per_wallet_cap: None,
+            allowlist_only: false,
+            free_supply: None,
+        };
+        let msg = ExecuteMsg::SetPhase {
+            id: 0,
+            phase: phase.clone(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        let res = config(deps.as_ref());
+        assert_eq!(res.active_phase, Some(PhaseInfo { id: 0, phase }));
+        assert_eq!(res.current_price, Some(Uint128::new(50)));
+    }
+
+    #[test]
+    fn ibc_mint_packets() {
+        use crate::ibc::{ibc_channel_connect, ibc_channel_open, ibc_packet_receive, IBC_VERSION};
+        use crate::msg::{MintAck, MintPacket};
+        use cosmwasm_std::testing::{
+            mock_ibc_channel_connect_ack, mock_ibc_channel_open_try, mock_ibc_packet_recv,
+        };
+        use cosmwasm_std::IbcOrder;
+
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 3;
+        setup_contract(deps.as_mut(), msg);
+
+        let open = mock_ibc_channel_open_try("channel-1", IbcOrder::Unordered, IBC_VERSION);
+        let err = ibc_channel_open(deps.as_mut(), mock_env(), open.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidIbcChannel {}));
+
+        let counterparty = |connection_id: &str, channel_id: &str| ExecuteMsg::SetIbcCounterparty {
+            port_id: Some(String::from("their_port")),
+            connection_id: Some(connection_id.to_string()),
+            channel_id: Some(channel_id.to_string()),
+        };
+        let msg = ExecuteMsg::SetIbcCounterparty {
+            port_id: Some(String::from("their_port")),
+            connection_id: None,
+            channel_id: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidIbcChannel {}));
+        // Same port name, different chain.
+        let msg = counterparty("connection-9", "channel-7");
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        let err = ibc_channel_open(deps.as_mut(), mock_env(), open.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidIbcChannel {}));
+        let msg = counterparty("connection-2", "channel-8");
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        let err = ibc_channel_open(deps.as_mut(), mock_env(), open.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidIbcChannel {}));
+        let msg = counterparty("connection-2", "channel-7");
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        ibc_channel_open(deps.as_mut(), mock_env(), open).unwrap();
+        let ordered = mock_ibc_channel_open_try("channel-1", IbcOrder::Ordered, IBC_VERSION);
+        ibc_channel_open(deps.as_mut(), mock_env(), ordered).unwrap_err();
+        let connect = mock_ibc_channel_connect_ack("channel-1", IbcOrder::Unordered, IBC_VERSION);
+        ibc_channel_connect(deps.as_mut(), mock_env(), connect).unwrap();
+
+        let packet = MintPacket {
+            recipient: String::from("alice"),
+            quantity: 2,
+            paid: Uint128::new(2),
+        };
+        let recv = mock_ibc_packet_recv("channel-1", &packet).unwrap();
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), recv).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        // Nothing is acknowledged until both child mints have replied.
+        assert!(res.acknowledgement.is_empty());
+        let minted = Reply {
+            id: MINT_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        };
+        let res = reply(deps.as_mut(), mock_env(), minted).unwrap();
+        assert_eq!(res.data, None);
+        // The counterparty took the payment, so a failed child mint refunds nothing here and
+        // is left out of the acknowledgement instead.
+        let failed = Reply {
+            id: MINT_REPLY_ID + 1,
+            result: SubMsgResult::Err(String::from("token_id already claimed")),
+        };
+        let res = reply(deps.as_mut(), mock_env(), failed).unwrap();
+        assert!(res.messages.is_empty());
+        let ack: MintAck = from_binary(&res.data.unwrap()).unwrap();
+        assert_eq!(
+            ack,
+            MintAck::Result {
+                token_ids: vec![String::from("0")]
+            }
+        );
+
+        let packet = MintPacket {
+            recipient: String::from("alice"),
+            quantity: 1,
+            paid: Uint128::new(1),
+        };
+        let recv = mock_ibc_packet_recv("channel-1", &packet).unwrap();
+        ibc_packet_receive(deps.as_mut(), mock_env(), recv.clone()).unwrap();
+        let failed = Reply {
+            id: MINT_REPLY_ID + 2,
+            result: SubMsgResult::Err(String::from("token_id already claimed")),
+        };
+        let res = reply(deps.as_mut(), mock_env(), failed).unwrap();
+        let ack: MintAck = from_binary(&res.data.unwrap()).unwrap();
+        assert_eq!(
+            ack,
+            MintAck::Error(String::from("token_id already claimed"))
+        );
+        assert_eq!(
+            MINTS_PER_ADDRESS
+                .load(&deps.storage, &Addr::unchecked("alice"))
+                .unwrap(),
+            1
+        );
+
+        // Repinning the counterparty cuts off channels opened before.
+        let msg = counterparty("connection-2", "channel-8");
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), recv.clone()).unwrap();
+        let ack: MintAck = from_binary(&res.acknowledgement).unwrap();
+        assert_eq!(ack, MintAck::Error(String::from("InvalidIbcChannel")));
+        let msg = counterparty("connection-2", "channel-7");
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), recv).unwrap();
+        assert!(res.messages.is_empty());
+        let ack: MintAck = from_binary(&res.acknowledgement).unwrap();
+        assert_eq!(ack, MintAck::Error(String::from("SoldOut")));
+    }
+
+    #[test]
+    fn mint_forwards_over_ics721() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1),
+            msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                remote_recipient: Some(String::from("stars1receiver")),
+                ..MintOptions::default()
+            }))
+            .unwrap(),
+        });
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), receive.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Ics721Disabled {}));
+
+        let msg = ExecuteMsg::SetIcs721Bridge {
+            bridge: Some(Ics721BridgeMsg {
+                bridge: String::from("ics721"),
+                channel_id: String::from("channel-0"),
+                timeout_seconds: 600,
+            }),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+        let res = execute(deps.as_mut(), mock_env(), info, receive).unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(
+            res.messages[1],
+            SubMsg::new(
+                ics721_send_msg(
+                    &mock_env(),
+                    &config,
+                    config.ics721_bridge.as_ref().unwrap(),
+                    "stars1receiver",
+                    "0",
+                )
+                .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn shutdown_closes_sale_and_pays_out() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, .. } if contract_addr == MOCK_CONTRACT_ADDR => {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&Cw20BalanceResponse {
+                        balance: Uint128::new(25),
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => SystemResult::Err(SystemError::NoSuchContract {
+                addr: String::from("unknown"),
+            }),
+        });
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::Shutdown {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let owner = mock_info("owner", &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            ExecuteMsg::Shutdown {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(
+                cw20_transfer_msg(
+                    &Addr::unchecked(MOCK_CONTRACT_ADDR),
+                    "owner",
+                    Uint128::new(25)
+                )
+                .unwrap()
+            )]
+        );
+
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1),
+            msg: Binary::default(),
+        });
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, receive).unwrap_err();
+        assert!(matches!(err, ContractError::SaleClosed {}));
+        let airdrop = ExecuteMsg::Airdrop {
+            recipients: vec![String::from("alice")],
+        };
+        let err = execute(deps.as_mut(), mock_env(), owner, airdrop).unwrap_err();
+        assert!(matches!(err, ContractError::SaleClosed {}));
+    }
+
+    #[test]
+    fn mint_with_custom_metadata() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.onchain_metadata = Some(true);
+        setup_contract(deps.as_mut(), msg);
+
+        let metadata = Metadata {
+            name: Some(String::from("Campaign badge")),
+            attributes: Some(vec![Trait {
+                display_type: None,
+                trait_type: String::from("team"),
+                value: String::from("blue"),
+            }]),
+            ..Metadata::default()
+        };
+        let receive = |metadata: &Metadata| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from("buyer"),
+                amount: Uint128::new(1),
+                msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                    metadata: Some(Box::new(metadata.clone())),
+                    ..MintOptions::default()
+                }))
+                .unwrap(),
+            })
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), receive(&metadata)).unwrap_err();
+        assert!(matches!(err, ContractError::CustomMetadataDisabled {}));
+
+        let allow = ExecuteMsg::SetCustomMetadata { allowed: true };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), allow).unwrap();
+
+        let oversized = Metadata {
+            description: Some("x".repeat(MAX_METADATA_FIELD_LENGTH + 1)),
+            ..metadata.clone()
+        };
+        let err =
+            execute(deps.as_mut(), mock_env(), info.clone(), receive(&oversized)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMetadata {}));
+
+        let res = execute(deps.as_mut(), mock_env(), info, receive(&metadata)).unwrap();
+        let mint = Cw721ExecuteMsg::<Option<Metadata>, Empty>::Mint(MintMsg {
+            token_id: String::from("0"),
+            owner: String::from("buyer"),
+            token_uri: Some(String::from("https://ipfs.io/ipfs/Q")),
+            extension: Some(metadata),
+        });
+        assert_eq!(
+            res.messages[0],
+            SubMsg::reply_always(
+                WasmMsg::Execute {
+                    contract_addr: NFT_CONTRACT_ADDR.to_string(),
+                    msg: to_binary(&mint).unwrap(),
+                    funds: vec![],
+                },
+                MINT_REPLY_ID,
+            )
+        );
+    }
+
+    #[test]
+    fn timed_open_edition_finalizes_after_deadline() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 0;
+        msg.open_edition = Some(true);
+        msg.end_time = Some(mock_env().block.time.plus_seconds(100));
+        setup_contract(deps.as_mut(), msg);
+
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(2),
+            msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                quantity: Some(2),
+                ..MintOptions::default()
+            }))
+            .unwrap(),
+        });
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        execute(deps.as_mut(), mock_env(), info.clone(), receive.clone()).unwrap();
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::Finalize {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::MintNotEnded {}));
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), receive.clone()).unwrap();
+        assert_eq!(res.attributes[0].value, "finalize");
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("buyer"),
+                    amount: Uint128::new(2),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::SupplyInfo {}).unwrap();
+        let supply: SupplyInfoResponse = from_binary(&res).unwrap();
+        assert_eq!(supply.final_supply, Some(2));
+        assert!(supply.sold_out);
+
+        let err = execute(deps.as_mut(), env.clone(), info, receive).unwrap_err();
+        assert!(matches!(err, ContractError::MintEnded {}));
+        let err = execute(
+            deps.as_mut(),
+            env,
+            mock_info("anyone", &[]),
+            ExecuteMsg::Finalize {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::EditionFinalized {}));
+    }
+
+    #[test]
+    fn reduce_supply_caps_collection() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 10;
+        setup_contract(deps.as_mut(), msg);
+
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(2),
+            msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                quantity: Some(2),
+                ..MintOptions::default()
+            }))
+            .unwrap(),
+        });
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        execute(deps.as_mut(), mock_env(), info, receive).unwrap();
+
+        let reduce = |new_max, freeze| ExecuteMsg::ReduceSupply { new_max, freeze };
+        let owner = mock_info("owner", &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            reduce(5, None),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        for new_max in [1, 11] {
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                owner.clone(),
+                reduce(new_max, None),
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::InvalidMaxTokens {}));
+        }
+
+        execute(deps.as_mut(), mock_env(), owner.clone(), reduce(5, None)).unwrap();
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            reduce(2, Some(true)),
+        )
+        .unwrap();
+        assert_eq!(res.attributes[0].value, "reduce_supply");
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.max_tokens, 2);
+        assert!(config.frozen);
+        assert_eq!(config.remaining_supply(), 0);
+
+        let err = execute(deps.as_mut(), mock_env(), owner, reduce(2, None)).unwrap_err();
+        assert!(matches!(err, ContractError::ConfigFrozen {}));
+    }
+
+    #[test]
+    fn only_governor_increases_supply() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 5;
+        msg.supply_governor = Some(String::from("dao"));
+        setup_contract(deps.as_mut(), msg);
+
+        let increase = |new_max| ExecuteMsg::IncreaseSupply { new_max };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            increase(10),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("dao", &[]),
+            increase(5),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMaxTokens {}));
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("dao", &[]),
+            increase(10),
+        )
+        .unwrap();
+        assert_eq!(res.attributes.last().unwrap().value, "governor");
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().max_tokens, 10);
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::ReduceSupply {
+                new_max: 11,
+                freeze: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMaxTokens {}));
+    }
+
+    #[test]
+    fn waitlist_after_sell_out() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 1;
+        msg.waitlist = Some(true);
+        msg.supply_governor = Some(String::from("dao"));
+        setup_contract(deps.as_mut(), msg);
+
+        let mint = |sender: &str, amount| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(amount),
+                msg: to_binary(&Cw20HookMsg::Mint(MintOptions::default())).unwrap(),
+            })
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        execute(deps.as_mut(), mock_env(), info.clone(), mint("first", 1)).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), mint("second", 3)).unwrap();
+        assert_eq!(res.attributes[0].value, "join_waitlist");
+        assert_eq!(res.attributes[3].value, "1");
+        assert_eq!(res.messages.len(), 1);
+        execute(deps.as_mut(), mock_env(), info.clone(), mint("third", 1)).unwrap();
+        execute(deps.as_mut(), mock_env(), info, mint("fourth", 1)).unwrap();
+        assert_eq!(
+            CONFIG.load(&deps.storage).unwrap().waitlist_escrow,
+            Uint128::new(3)
+        );
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("second", &[]),
+            ExecuteMsg::CancelWaitlist { position: 1 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("third", &[]),
+            ExecuteMsg::CancelWaitlist { position: 1 },
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 1);
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("dao", &[]),
+            ExecuteMsg::IncreaseSupply { new_max: 2 },
+        )
+        .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::FillWaitlist { limit: None },
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.attributes.last().unwrap().value, "1");
+        assert_eq!(
+            MINTS_PER_ADDRESS
+                .load(&deps.storage, &Addr::unchecked("second"))
+                .unwrap(),
+            1
+        );
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Waitlist {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+        let waitlist: WaitlistResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            waitlist.entries,
+            vec![WaitlistInfo {
+                position: 2,
+                buyer: Addr::unchecked("fourth"),
+                quantity: 1,
+                paid: Uint128::new(1),
+            }]
+        );
+        assert_eq!(
+            CONFIG.load(&deps.storage).unwrap().waitlist_escrow,
+            Uint128::new(1)
+        );
+    }
+
+    #[test]
+    fn native_ibc_denom_payments() {
+        let usdc = "ibc/D189335C6E4A68B513C10AB227BF1C1D38C746766278BA3EEB4FB14124F1D858";
+        let mut deps = mock_dependencies();
+        for denom in [
+            "ibc/d189335c6e4a68b513c10ab227bf1c1d38c746766278ba3eeb4fb14124f1d858",
+            "ibc/D189335C6E4A68B513C10AB227BF1C1D",
+            "1usdc",
+        ] {
+            let mut msg = default_instantiate_msg();
+            msg.native_denom = Some(denom.to_string());
+            let err =
+                instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap_err();
+            assert!(matches!(err, ContractError::InvalidDenom {}));
+        }
+
+        let mut msg = default_instantiate_msg();
+        msg.unit_price = Uint128::new(100);
+        msg.native_denom = Some(usdc.to_string());
+        setup_contract(deps.as_mut(), msg);
+        let config: ConfigResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap())
+                .unwrap();
+        assert_eq!(config.native_denom.as_deref(), Some(usdc));
+
+        let mint = ExecuteMsg::MintNative { quantity: None };
+        let lookalike = usdc.replace("D858", "D859");
+        for funds in [
+            coins(100, lookalike.as_str()),
+            coins(100, "uusdc"),
+            vec![Coin::new(100, usdc), Coin::new(1, "ujuno")],
+        ] {
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("buyer", &funds),
+                mint.clone(),
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::InvalidDenom {}));
+        }
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("buyer", &coins(150, usdc)),
+            mint,
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("buyer"),
+                amount: coins(50, usdc),
+            })
+        );
+
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(100, usdc));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::Withdraw {
+                amount: None,
+                recipient: None,
+                token: Some(usdc.to_string()),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("owner"),
+                amount: coins(100, usdc),
+            })
+        );
+    }
+
+    #[test]
+    fn per_currency_prices() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.native_denom = Some(String::from("uatom"));
+        setup_contract(deps.as_mut(), msg);
+
+        let update = |currency, price| ExecuteMsg::UpdatePrices {
+            prices: vec![CurrencyPrice {
+                currency,
+                price: Uint128::new(price),
+            }],
+        };
+        let owner = mock_info("owner", &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            update(Currency::Native(String::from("uosmo")), 5),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PaymentTokenNotAccepted {}));
+
+        let msg = ExecuteMsg::UpdatePrices {
+            prices: vec![
+                CurrencyPrice {
+                    currency: Currency::Cw20(Addr::unchecked(MOCK_CONTRACT_ADDR)),
+                    price: Uint128::new(3),
+                },
+                CurrencyPrice {
+                    currency: Currency::Cw20(Addr::unchecked("stable")),
+                    price: Uint128::new(25),
+                },
+                CurrencyPrice {
+                    currency: Currency::Native(String::from("uatom")),
+                    price: Uint128::new(7),
+                },
+            ],
+        };
+        execute(deps.as_mut(), mock_env(), owner, msg.clone()).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Prices {}).unwrap();
+        let prices: PricesResponse = from_binary(&res).unwrap();
+        let ExecuteMsg::UpdatePrices { prices: expected } = msg else {
+            unreachable!()
+        };
+        assert_eq!(prices.prices, expected);
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("buyer", &coins(3, "uatom")),
+            ExecuteMsg::MintNative { quantity: None },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::WrongPaymentAmount {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("buyer", &coins(7, "uatom")),
+            ExecuteMsg::MintNative { quantity: None },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn withdraw_credits_creator_shares() {
+        let mut deps = mock_dependencies();
+        let creator = |address: &str, bps| CreatorMsg {
+            address: address.to_string(),
+            bps,
+        };
+        let mut msg = default_instantiate_msg();
+        msg.creators = Some(vec![creator("alice", 7_000), creator("bob", 2_000)]);
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidCreators {}));
+
+        msg.creators = Some(vec![creator("alice", 7_000), creator("bob", 3_000)]);
+        setup_contract(deps.as_mut(), msg);
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { .. } => SystemResult::Ok(ContractResult::Ok(
+                to_binary(&Cw20BalanceResponse {
+                    balance: Uint128::new(101),
+                })
+                .unwrap(),
+            )),
+            _ => SystemResult::Err(SystemError::Unknown {}),
+        });
+
+        let withdraw = ExecuteMsg::Withdraw {
+            amount: None,
+            recipient: None,
+            token: None,
+        };
+        let owner = mock_info("owner", &[]);
+        let res = execute(deps.as_mut(), mock_env(), owner.clone(), withdraw.clone()).unwrap();
+        assert!(res.messages.is_empty());
+        // Everything is owed to the creators now.
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), withdraw).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToWithdraw {}));
+        let withdraw_some = ExecuteMsg::Withdraw {
+            amount: Some(Uint128::one()),
+            recipient: None,
+            token: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), owner, withdraw_some).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToWithdraw {}));
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Claims {
+                address: String::from("alice"),
+            },
+        )
+        .unwrap();
+        let claims: ClaimsResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            claims.claims,
+            vec![CreatorClaim {
+                currency: Currency::Cw20(Addr::unchecked(MOCK_CONTRACT_ADDR)),
+                amount: Uint128::new(71),
+            }]
+        );
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            ExecuteMsg::ClaimCreatorShare {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("bob"),
+                    amount: Uint128::new(30),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob", &[]),
+            ExecuteMsg::ClaimCreatorShare {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NothingToWithdraw {}));
+    }
+
+    #[test]
+    fn per_phase_allowlists() {
+        let mut deps = mock_dependencies();
+        let now = mock_env().block.time;
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 10;
+        msg.unit_price = Uint128::new(100);
+        setup_contract(deps.as_mut(), msg);
+
+        let owner = mock_info("owner", &[]);
+        let entries = vec![
+            PhaseAllowlistEntryMsg {
+                address: String::from("alice"),
+                price: Some(Uint128::new(40)),
+                mint_limit: Some(2),
+            },
+            PhaseAllowlistEntryMsg {
+                address: String::from("bob"),
+                price: None,
+                mint_limit: None,
+            },
+        ];
+        let add = ExecuteMsg::AddToPhaseAllowlist {
+            phase_id: 0,
+            entries,
+        };
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), add.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::PhaseNotFound {}));
+
+        let phase = Phase {
+            name: String::from("og"),
+            start_time: now,
+            end_time: None,
+            price: Some(Uint128::new(50)),
+            per_wallet_cap: None,
+            allowlist_only: true,
+            free_supply: None,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            ExecuteMsg::SetPhase { id: 0, phase },
+        )
+        .unwrap();
+        execute(deps.as_mut(), mock_env(), owner, add).unwrap();
+
+        let receive = |sender: &str, amount| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(amount),
+                msg: Binary::default(),
+            })
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            receive("carol", 50),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotAllowlisted {}));
+        execute(deps.as_mut(), mock_env(), info.clone(), receive("bob", 50)).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            receive("alice", 40),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            receive("alice", 40),
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), mock_env(), info, receive("alice", 40)).unwrap_err();
+        assert!(matches!(err, ContractError::MintLimitReached {}));
+
+        let page = |start_after: Option<&str>| QueryMsg::PhaseAllowlist {
+            phase_id: 0,
+            start_after: start_after.map(String::from),
+            limit: Some(1),
+            order: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), page(None)).unwrap();
+        let first: PhaseAllowlistResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            first.entries,
+            vec![PhaseAllowlistInfo {
+                address: Addr::unchecked("alice"),
+                price: Some(Uint128::new(40)),
+                mint_limit: Some(2),
+            }]
+        );
+        let res = query(deps.as_ref(), mock_env(), page(Some("alice"))).unwrap();
+        let second: PhaseAllowlistResponse = from_binary(&res).unwrap();
+        assert_eq!(second.entries[0].address, Addr::unchecked("bob"));
+    }
+
+    #[test]
+    fn allowlist_status_precheck() {
+        let mut deps = mock_dependencies();
+        let now = mock_env().block.time;
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 10;
+        msg.unit_price = Uint128::new(100);
+        setup_contract(deps.as_mut(), msg);
+
+        let owner = mock_info("owner", &[]);
+        let phase = Phase {
+            name: String::from("og"),
+            start_time: now.plus_seconds(60),
+            end_time: None,
+            price: Some(Uint128::new(50)),
+            per_wallet_cap: Some(3),
+            allowlist_only: true,
+            free_supply: None,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            ExecuteMsg::SetPhase { id: 4, phase },
+        )
+        .unwrap();
+        let add = ExecuteMsg::AddToPhaseAllowlist {
+            phase_id: 4,
+            entries: vec![PhaseAllowlistEntryMsg {
+                address: String::from("alice"),
+                price: Some(Uint128::new(40)),
+                mint_limit: Some(2),
+            }],
+        };
+        execute(deps.as_mut(), mock_env(), owner, add).unwrap();
+
+        let status = |address: &str| -> AllowlistStatusResponse {
+            let msg = QueryMsg::AllowlistStatus {
+                address: address.to_string(),
+            };
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+        };
+        assert_eq!(
+            status("alice"),
+            AllowlistStatusResponse {
+                phase_id: Some(4),
+                phase_active: false,
+                allowlisted: true,
+                remaining: Some(2),
+                price: Uint128::new(40),
+                expires: None,
+            }
+        );
+        assert_eq!(
+            status("bob"),
+            AllowlistStatusResponse {
+                phase_id: Some(4),
+                phase_active: false,
+                allowlisted: false,
+                remaining: Some(0),
+                price: Uint128::new(100),
+                expires: None,
+            }
+        );
+    }
+
+    #[test]
+    fn batch_update_allowlist() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 10;
+        msg.presale = Some(Presale {
+            end_time: mock_env().block.time.plus_seconds(60),
+            price: None,
+        });
+        setup_contract(deps.as_mut(), msg);
+
+        let owner = mock_info("owner", &[]);
+        let update = |add: &[(&str, u32)], remove: &[&str]| ExecuteMsg::UpdateAllowlist {
+            add: add.iter().map(|(a, n)| (a.to_string(), *n)).collect(),
+            remove: remove.iter().map(|a| a.to_string()).collect(),
+        };
+        let batch = update(&[("alice", 1), ("bob", 2), ("carol", 1)], &[]);
+        execute(deps.as_mut(), mock_env(), owner.clone(), batch).unwrap();
+        let batch = update(&[("bob", 3)], &["carol", "dave"]);
+        let res = execute(deps.as_mut(), mock_env(), owner.clone(), batch).unwrap();
+        assert_eq!(res.attributes.last().unwrap().value, "2");
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().allowlist_count, 2);
+        for batch in [
+            update(&[("erin", 1), ("erin", 2)], &[]),
+            update(&[("bob", 1)], &["bob"]),
+        ] {
+            let err = execute(deps.as_mut(), mock_env(), owner.clone(), batch).unwrap_err();
+            assert!(matches!(err, ContractError::DuplicateAllowlistEntry {}));
+        }
+
+        let receive = |sender: &str| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(1),
+                msg: Binary::default(),
+            })
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        execute(deps.as_mut(), mock_env(), info.clone(), receive("alice")).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), receive("alice")).unwrap_err();
+        assert!(matches!(err, ContractError::MintLimitReached {}));
+        let err = execute(deps.as_mut(), mock_env(), info, receive("carol")).unwrap_err();
+        assert!(matches!(err, ContractError::NotAllowlisted {}));
+    }
+
+    #[test]
+    fn merkle_root_rotation() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 10;
+        msg.presale = Some(Presale {
+            end_time: mock_env().block.time.plus_seconds(60),
+            price: None,
+        });
+        setup_contract(deps.as_mut(), msg);
+
+        let leaf = |address: &str| -> [u8; 32] { Sha256::digest(address.as_bytes()).into() };
+        let (alice, bob) = (leaf("alice"), leaf("bob"));
+        let (first, second) = if alice <= bob {
+            (alice, bob)
+        } else {
+            (bob, alice)
+        };
+        let root = Sha256::new()
+            .chain_update(first)
+            .chain_update(second)
+            .finalize();
+
+        let owner = mock_info("owner", &[]);
+        let update = |root: &[u8]| ExecuteMsg::UpdateMerkleRoot {
+            root: Some(Binary::from(root)),
+        };
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), update(&[0; 31])).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMerkleRoot {}));
+        let res = execute(deps.as_mut(), mock_env(), owner.clone(), update(&root)).unwrap();
+        assert_eq!(
+            res.attributes[1].value,
+            Binary::from(root.as_slice()).to_base64()
+        );
+        assert_eq!(res.attributes[2].value, "1");
+
+        let mint = |sender: &str, root_version, sibling: &[u8]| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(1),
+                msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                    merkle_proof: Some(MerkleProof {
+                        root_version,
+                        proof: vec![Binary::from(sibling)],
+                    }),
+                    ..MintOptions::default()
+                }))
+                .unwrap(),
+            })
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            mint("carol", 1, &bob),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMerkleProof {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            mint("alice", 1, &bob),
+        )
+        .unwrap();
+
+        // Re-publishing the same root still invalidates proofs for the old version.
+        execute(deps.as_mut(), mock_env(), owner, update(&root)).unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            mint("bob", 1, &alice),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::StaleMerkleProof {}));
+        execute(deps.as_mut(), mock_env(), info, mint("bob", 2, &alice)).unwrap();
+    }
+
+    #[test]
+    fn partner_collection_discount() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.unit_price = Uint128::new(100);
+        msg.partner_discount = Some(PartnerDiscountMsg {
+            collection: String::from("partner"),
+            discount_bps: 2_500,
+        });
+        setup_contract(deps.as_mut(), msg);
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "partner" => {
+                let tokens = match from_binary(msg).unwrap() {
+                    Cw721QueryMsg::Tokens { owner, .. } if owner == "holder" => {
+                        vec![String::from("7")]
+                    }
+                    _ => vec![],
+                };
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&TokensResponse { tokens }).unwrap(),
+                ))
+            }
+            _ => SystemResult::Err(SystemError::Unknown {}),
+        });
+
+        let receive = |sender: &str, amount| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(amount),
+                msg: Binary::default(),
+            })
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            receive("stranger", 75),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::WrongPaymentAmount {}));
+        let res = execute(deps.as_mut(), mock_env(), info, receive("holder", 75)).unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "partner_holder" && attr.value == "true"));
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "price" && attr.value == "75"));
+    }
+
+    #[test]
+    fn token_gated_minting() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.mint_gate = Some(MintGateMsg::Cw20 {
+            token: String::from("governance"),
+            min_balance: Uint128::new(10),
+        });
+        setup_contract(deps.as_mut(), msg);
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "governance" => {
+                let balance = match from_binary(msg).unwrap() {
+                    Cw20QueryMsg::Balance { address } if address == "whale" => 10,
+                    _ => 9,
+                };
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&Cw20BalanceResponse {
+                        balance: Uint128::new(balance),
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => SystemResult::Err(SystemError::Unknown {}),
+        });
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::MintGate {}).unwrap();
+        let gate: MintGateResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            gate.gate,
+            Some(MintGate::Cw20 {
+                token: Addr::unchecked("governance"),
+                min_balance: Uint128::new(10),
+            })
+        );
+
+        let receive = |sender: &str| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(1),
+                msg: Binary::default(),
+            })
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), receive("minnow")).unwrap_err();
+        assert!(matches!(err, ContractError::NotEligible {}));
+        execute(deps.as_mut(), mock_env(), info, receive("whale")).unwrap();
+    }
+
+    #[test]
+    fn nft_staking_rewards() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        let stake = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: String::from("holder"),
+            token_id: String::from("3"),
+            msg: to_binary(&Cw721HookMsg::Stake {}).unwrap(),
+        });
+        let nft = mock_info(NFT_CONTRACT_ADDR, &[]);
+        let err = execute(deps.as_mut(), mock_env(), nft.clone(), stake.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::StakingDisabled {}));
+
+        let set_rewards = ExecuteMsg::SetStakingRewards {
+            reward_token: String::from("reward"),
+            reward_per_block: Uint128::new(5),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            set_rewards,
+        )
+        .unwrap();
+        let fund = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("owner"),
+            amount: Uint128::new(40),
+            msg: to_binary(&Cw20HookMsg::FundStakingRewards {}).unwrap(),
+        });
+        execute(deps.as_mut(), mock_env(), mock_info("reward", &[]), fund).unwrap();
+        execute(deps.as_mut(), mock_env(), nft, stake).unwrap();
+
+        let mut later = mock_env();
+        later.block.height += 4;
+        let msg = QueryMsg::Stake {
+            token_id: String::from("3"),
+        };
+        let info: StakeInfo =
+            from_binary(&query(deps.as_ref(), later.clone(), msg).unwrap()).unwrap();
+        assert_eq!(info.pending, Uint128::new(20));
+
+        let claim = ExecuteMsg::ClaimStakingRewards {
+            token_ids: vec![String::from("3")],
+        };
+        let holder = mock_info("holder", &[]);
+        let res = execute(deps.as_mut(), later.clone(), holder.clone(), claim).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(
+                cw20_transfer_msg(&Addr::unchecked("reward"), "holder", Uint128::new(20)).unwrap()
+            )]
+        );
+
+        // Only 20 is left in the pool, so the remaining 30 accrued are forfeited.
+        later.block.height += 6;
+        let unstake = ExecuteMsg::Unstake {
+            token_id: String::from("3"),
+        };
+        let err = execute(
+            deps.as_mut(),
+            later.clone(),
+            mock_info("other", &[]),
+            unstake.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let res = execute(deps.as_mut(), later, holder, unstake).unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(cw721_transfer_msg(&config, &Addr::unchecked("holder"), "3").unwrap()),
+                SubMsg::new(
+                    cw20_transfer_msg(&Addr::unchecked("reward"), "holder", Uint128::new(20))
+                        .unwrap()
+                ),
+            ]
+        );
+        assert!(config.staking_pool.is_zero());
+        assert!(!STAKES.has(&deps.storage, "3"));
+    }
+
+    #[test]
+    fn vested_withdrawals() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.vesting = Some(VestingMsg {
+            start: None,
+            cliff_seconds: 100,
+            duration_seconds: 1_000,
+        });
+        setup_contract(deps.as_mut(), msg);
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { .. } => SystemResult::Ok(ContractResult::Ok(
+                to_binary(&Cw20BalanceResponse {
+                    balance: Uint128::new(1_000),
+                })
+                .unwrap(),
+            )),
+            _ => SystemResult::Ok(ContractResult::Err(String::from("unsupported"))),
+        });
+
+        let owner = mock_info("owner", &[]);
+        let withdraw = ExecuteMsg::Withdraw {
+            amount: None,
+            recipient: None,
+            token: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), withdraw.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToWithdraw {}));
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(250);
+        let res = execute(deps.as_mut(), env.clone(), owner.clone(), withdraw).unwrap();
+        assert!(res.attributes.contains(&attr("amount", "250")));
+
+        // The mocked balance does not drop, so 1_250 counts as collected from here on.
+        let status = |deps: Deps, env: Env| -> VestingResponse {
+            from_binary(&query(deps, env, QueryMsg::Vesting { token: None }).unwrap()).unwrap()
+        };
+        let res = status(deps.as_ref(), env.clone());
+        assert_eq!(res.withdrawn, Uint128::new(250));
+        assert_eq!(res.vested, Uint128::new(312));
+        assert_eq!(res.claimable, Uint128::new(62));
+
+        let too_much = ExecuteMsg::Withdraw {
+            amount: Some(Uint128::new(100)),
+            recipient: None,
+            token: None,
+        };
+        let err = execute(deps.as_mut(), env, owner, too_much).unwrap_err();
+        assert!(matches!(err, ContractError::NotVested {}));
+    }
+
+    #[test]
+    fn escrow_refundable_until_reveal() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.placeholder_uri = Some(String::from("ipfs://placeholder.json"));
+        msg.reveal_escrow = Some(true);
+        msg.max_tokens = 2;
+        setup_contract(deps.as_mut(), msg);
+
+        let mint = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(2),
+            msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                quantity: Some(2),
+                ..MintOptions::default()
+            }))
+            .unwrap(),
+        });
+        let cw20 = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        execute(deps.as_mut(), mock_env(), cw20, mint).unwrap();
+        assert_eq!(
+            ESCROW_HELD.load(&deps.storage, MOCK_CONTRACT_ADDR).unwrap(),
+            Uint128::new(2)
+        );
+
+        let refund = |token_id: &str| {
+            ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+                sender: String::from("holder"),
+                token_id: token_id.to_string(),
+                msg: to_binary(&Cw721HookMsg::RefundEscrow {}).unwrap(),
+            })
+        };
+        let nft = mock_info(NFT_CONTRACT_ADDR, &[]);
+        let res = execute(deps.as_mut(), mock_env(), nft.clone(), refund("0")).unwrap();
+        assert_eq!(
+            res.messages[1],
+            SubMsg::new(
+                cw20_transfer_msg(
+                    &Addr::unchecked(MOCK_CONTRACT_ADDR),
+                    "holder",
+                    Uint128::one()
+                )
+                .unwrap()
+            )
+        );
+        let err = execute(deps.as_mut(), mock_env(), nft.clone(), refund("0")).unwrap_err();
+        assert!(matches!(err, ContractError::EscrowNotFound {}));
+
+        let reveal = ExecuteMsg::Reveal {
+            base_uri: String::from("ipfs://revealed/"),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), reveal).unwrap();
+        let msg = QueryMsg::Escrow {
+            token_id: String::from("1"),
+        };
+        let res: EscrowResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert!(res.payment.is_some() && !res.refundable);
+        let err = execute(deps.as_mut(), mock_env(), nft, refund("1")).unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyRevealed {}));
+    }
+
+    #[test]
+    fn token_id_template() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.token_id_template = Some(String::from("MONI-{n:04}"));
+        msg.refund_bps = Some(10_000);
+        setup_contract(deps.as_mut(), msg);
+
+        let mint = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1),
+            msg: Binary::default(),
+        });
+        let cw20 = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let res = execute(deps.as_mut(), mock_env(), cw20, mint).unwrap();
+        assert!(res.attributes.contains(&attr("token_id", "MONI-0000")));
+
+        let msg = QueryMsg::Provenance {
+            token_id: String::from("MONI-0000"),
+        };
+        query(deps.as_ref(), mock_env(), msg).unwrap();
+        let refund = |token_id: &str| {
+            ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+                sender: String::from("buyer"),
+                token_id: token_id.to_string(),
+                msg: to_binary(&Cw721HookMsg::Refund {}).unwrap(),
+            })
+        };
+        let nft = mock_info(NFT_CONTRACT_ADDR, &[]);
+        let err = execute(deps.as_mut(), mock_env(), nft.clone(), refund("MONI-0")).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidTokenId {}));
+        execute(deps.as_mut(), mock_env(), nft, refund("MONI-0000")).unwrap();
+
+        for template in ["MONI", "{n:0}", "{n:x}", "{n}-{n}"] {
+            let mut msg = default_instantiate_msg();
+            msg.token_id_template = Some(template.to_string());
+            let info = mock_info("owner", &[]);
+            let err = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+            assert!(matches!(err, ContractError::InvalidTokenIdTemplate {}));
+        }
+    }
+
+    #[test]
+    fn traits_assigned_at_mint() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.onchain_metadata = Some(true);
+        setup_contract(deps.as_mut(), msg);
+
+        let owner = mock_info("owner", &[]);
+        let set_traits = |values: Vec<WeightedValue>| ExecuteMsg::SetTraitTable {
+            traits: vec![TraitCategory {
+                trait_type: String::from("background"),
+                values,
+            }],
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            set_traits(vec![WeightedValue {
+                value: String::from("red"),
+                weight: 0,
+            }]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidTraitTable {}));
+        let values = vec![
+            WeightedValue {
+                value: String::from("red"),
+                weight: 1,
+            },
+            WeightedValue {
+                value: String::from("blue"),
+                weight: 3,
+            },
+        ];
+        execute(deps.as_mut(), mock_env(), owner, set_traits(values)).unwrap();
+
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+        let buyer = Addr::unchecked("buyer");
+        let env = mock_env();
+        let mint = |deps: &mut OwnedDeps<_, _, _>, config: &mut Config| {
+            mint_next(
+                &mut deps.storage,
+                &env,
+                config,
+                &buyer,
+                &buyer,
+                Uint128::one(),
+                None,
+            )
+            .unwrap()
+            .1
+            .remove(0)
+            .msg
+        };
+        let first = mint(&mut deps, &mut config);
+        config.unused_token_id = 0;
+        assert_eq!(mint(&mut deps, &mut config), first);
+
+        let CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) = first else {
+            panic!("expected a cw721 mint");
+        };
+        let Cw721ExecuteMsg::<Option<Metadata>, Empty>::Mint(mint) = from_binary(&msg).unwrap()
+        else {
+            panic!("expected a cw721 mint");
+        };
+        let attributes = mint.extension.unwrap().attributes.unwrap();
+        assert_eq!(attributes.len(), 1);
+        assert_eq!(attributes[0].trait_type, "background");
+    }
+
+    #[test]
+    fn mints_wait_for_randomness() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        let set = ExecuteMsg::SetRandomness {
+            randomness: Some(RandomnessMsg {
+                proxy: String::from("nois"),
+                fee: coins(5, "unois"),
+            }),
+        };
+        let owner = mock_info("owner", &[]);
+        execute(deps.as_mut(), mock_env(), owner.clone(), set).unwrap();
+
+        let mint = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1),
+            msg: Binary::default(),
+        });
+        let cw20 = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let res = execute(deps.as_mut(), mock_env(), cw20.clone(), mint.clone()).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("nois"),
+                msg: to_binary(&NoisProxyExecuteMsg::GetNextRandomness {
+                    job_id: String::from("mint-0"),
+                })
+                .unwrap(),
+                funds: coins(5, "unois"),
+            })]
+        );
+        // The only token is reserved for the pending job.
+        let err = execute(deps.as_mut(), mock_env(), cw20, mint).unwrap_err();
+        assert!(matches!(err, ContractError::SoldOut {}));
+        let clear = ExecuteMsg::SetRandomness { randomness: None };
+        let err = execute(deps.as_mut(), mock_env(), owner, clear).unwrap_err();
+        assert!(matches!(err, ContractError::RandomnessPending {}));
+
+        let callback = ExecuteMsg::NoisReceive {
+            callback: NoisCallback {
+                job_id: String::from("mint-0"),
+                published: mock_env().block.time,
+                randomness: HexBinary::from(vec![7u8; 32]),
+            },
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("buyer", &[]),
+            callback.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let nois = mock_info("nois", &[]);
+        let res = execute(deps.as_mut(), mock_env(), nois.clone(), callback.clone()).unwrap();
+        assert!(res.attributes.contains(&attr("token_id", "0")));
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!((config.unused_token_id, config.pending_mints), (1, 0));
+        let err = execute(deps.as_mut(), mock_env(), nois, callback).unwrap_err();
+        assert!(matches!(err, ContractError::RandomnessJobNotFound {}));
+    }
+
+    #[test]
+    fn collection_stats() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 3;
+        setup_contract(deps.as_mut(), msg);
+        let cw20 = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        for buyer in ["alice", "alice", "bob"] {
+            let mint = ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: buyer.to_string(),
+                amount: Uint128::new(1),
+                msg: Binary::default(),
+            });
+            execute(deps.as_mut(), mock_env(), cw20.clone(), mint).unwrap();
+        }
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { msg, .. } => match from_binary(msg).unwrap() {
+                Cw721QueryMsg::NumTokens {} => SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&cw721::NumTokensResponse { count: 2 }).unwrap(),
+                )),
+                _ => SystemResult::Ok(ContractResult::Err(String::from("unsupported"))),
+            },
+            _ => SystemResult::Ok(ContractResult::Err(String::from("unsupported"))),
+        });
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::CollectionStats {}).unwrap();
+        let stats: CollectionStatsResponse = from_binary(&res).unwrap();
+        assert_eq!(stats.minted, 3);
+        assert_eq!(stats.burned, Some(1));
+        assert_eq!(stats.unique_holders, 2);
+        assert_eq!(stats.sale_status, SaleStatus::SoldOut);
+        assert_eq!(stats.name, None);
+    }
+
+    #[test]
+    fn mint_hooks() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 2;
+        setup_contract(deps.as_mut(), msg);
+        let owner = mock_info("owner", &[]);
+
+        let add = |addr: &str| ExecuteMsg::AddMintHook {
+            addr: addr.to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), owner.clone(), add("loyalty")).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), add("loyalty")).unwrap_err();
+        assert!(matches!(err, ContractError::MintHookAlreadyRegistered {}));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            add("x"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let res: MintHooksResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::MintHooks {}).unwrap())
+                .unwrap();
+        assert_eq!(res.hooks, vec![Addr::unchecked("loyalty")]);
+
+        let mint = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1),
+            msg: Binary::default(),
+        });
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            mint,
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 1);
+        let confirmed = Reply {
+            id: MINT_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        };
+        let res = reply(deps.as_mut(), mock_env(), confirmed.clone()).unwrap();
+        let hook = MintHookMsg {
+            token_id: String::from("0"),
+            buyer: String::from("buyer"),
+            price: Uint128::new(1),
+        }
+        .into_cosmos_msg("loyalty")
+        .unwrap();
+        assert!(res.messages.iter().any(|sub| sub.msg == hook));
+
+        let remove = ExecuteMsg::RemoveMintHook {
+            addr: String::from("loyalty"),
+        };
+        execute(deps.as_mut(), mock_env(), owner.clone(), remove.clone()).unwrap();
+        let res = reply(deps.as_mut(), mock_env(), confirmed).unwrap();
+        assert!(res.messages.is_empty());
+
+        let err = execute(deps.as_mut(), mock_env(), owner, remove).unwrap_err();
+        assert!(matches!(err, ContractError::MintHookNotFound {}));
+    }
+
+    #[test]
+    fn lifecycle_events() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        deps.querier.update_wasm(cw20_with_balance(100));
+        let owner = mock_info("owner", &[]);
+        let attr = |event: &Event, key: &str| {
+            event
+                .attributes
+                .iter()
+                .find(|attr| attr.key == key)
+                .map(|attr| attr.value.clone())
+        };
+
+        let mint = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1),
+            msg: Binary::default(),
+        });
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            mint,
+        )
+        .unwrap();
+        assert_eq!(res.events.len(), 1);
+        let event = &res.events[0];
+        assert_eq!(event.ty, "moni/mint");
+        assert_eq!(attr(event, "token_id").as_deref(), Some("0"));
+        assert_eq!(attr(event, "buyer").as_deref(), Some("buyer"));
+        assert_eq!(attr(event, "price").as_deref(), Some("1"));
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            ExecuteMsg::Pause {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.events,
+            vec![Event::new("moni/phase_change").add_attribute("change", "paused")]
+        );
+
+        let update = ExecuteMsg::UpdateUnitPrice {
+            price: Uint128::new(5),
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner.clone(), update).unwrap();
+        assert_eq!(
+            res.events,
+            vec![Event::new("moni/config_update")
+                .add_attribute("field", "unit_price")
+                .add_attribute("value", "5")]
+        );
+
+        let withdraw = ExecuteMsg::Withdraw {
+            amount: Some(Uint128::new(1)),
+            recipient: None,
+            token: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner, withdraw).unwrap();
+        assert_eq!(res.events[0].ty, "moni/withdraw");
+        assert_eq!(attr(&res.events[0], "amount").as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn update_token_uri() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        let owner = mock_info("owner", &[]);
+        let update = |token_id: &str| ExecuteMsg::UpdateTokenUri {
+            token_id: token_id.to_string(),
+            uri: String::from("ipfs://fixed/0.json"),
+        };
+
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), update("0")).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidTokenId {}));
+
+        let mint = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1),
+            msg: Binary::default(),
+        });
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            mint,
+        )
+        .unwrap();
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("buyer", &[]),
+            update("0"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let res = execute(deps.as_mut(), mock_env(), owner.clone(), update("0")).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: NFT_CONTRACT_ADDR.to_string(),
+                msg: to_binary(
+                    &Cw721ExecuteMsg::<Extension, Cw721UriExtensionMsg>::Extension {
+                        msg: Cw721UriExtensionMsg::UpdateTokenUri {
+                            token_id: String::from("0"),
+                            token_uri: String::from("ipfs://fixed/0.json"),
+                        },
+                    }
+                )
+                .unwrap(),
+                funds: vec![],
+            })]
+        );
+
+        let query_msg = QueryMsg::Provenance {
+            token_id: String::from("0"),
+        };
+        let provenance: ProvenanceResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), query_msg).unwrap()).unwrap();
+        assert_eq!(provenance.token_uri.as_deref(), Some("ipfs://fixed/0.json"));
+        assert_eq!(provenance.uri_updated, Some(mock_env().block.time));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            ExecuteMsg::Freeze {},
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), mock_env(), owner, update("0")).unwrap_err();
+        assert!(matches!(err, ContractError::ConfigFrozen {}));
+    }
+
+    #[test]
+    fn cw721_execute_passthrough() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        let burn = ExecuteMsg::Cw721Execute {
+            msg: to_binary(&Cw721ExecuteMsg::<Extension, Empty>::Burn {
+                token_id: String::from("0"),
+            })
+            .unwrap(),
+        };
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            burn.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), burn).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: NFT_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&Cw721ExecuteMsg::<Extension, Empty>::Burn {
+                    token_id: String::from("0"),
+                })
+                .unwrap(),
+                funds: vec![],
+            })]
+        );
+
+        let mint = ExecuteMsg::Cw721Execute {
+            msg: to_binary(&Cw721ExecuteMsg::<Extension, Empty>::Mint(MintMsg {
+                token_id: String::from("free"),
+                owner: String::from("owner"),
+                token_uri: None,
+                extension: None,
+            }))
+            .unwrap(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), mint).unwrap_err();
+        assert!(matches!(err, ContractError::MintPassthroughNotAllowed {}));
+
+        // Tokens held for users stay out of reach.
+        let set_rewards = ExecuteMsg::SetStakingRewards {
+            reward_token: String::from("reward"),
+            reward_per_block: Uint128::new(5),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            set_rewards,
+        )
+        .unwrap();
+        let stake = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: String::from("holder"),
+            token_id: String::from("3"),
+            msg: to_binary(&Cw721HookMsg::Stake {}).unwrap(),
+        });
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(NFT_CONTRACT_ADDR, &[]),
+            stake,
+        )
+        .unwrap();
+        for msg in [
+            Cw721ExecuteMsg::<Extension, Empty>::TransferNft {
+                recipient: String::from("owner"),
+                token_id: String::from("3"),
+            },
+            Cw721ExecuteMsg::ApproveAll {
+                operator: String::from("owner"),
+                expires: None,
+            },
+        ] {
+            let passthrough = ExecuteMsg::Cw721Execute {
+                msg: to_binary(&msg).unwrap(),
+            };
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("owner", &[]),
+                passthrough,
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::TokenInCustody {}));
+        }
+    }
+
+    #[test]
+    fn transfer_minter_retires_sale() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.cw721_version = Some(Cw721Version::Ownable);
+        setup_contract(deps.as_mut(), msg);
+        let transfer = ExecuteMsg::TransferMinter {
+            new_minter: String::from("sale_v2"),
+            expiry: None,
+        };
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            transfer.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            transfer.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: NFT_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&Cw721OwnershipMsg::UpdateOwnership(
+                    Cw721OwnershipAction::TransferOwnership {
+                        new_owner: String::from("sale_v2"),
+                        expiry: None,
+                    },
+                ))
+                .unwrap(),
+                funds: vec![],
+            })]
+        );
+        assert!(CONFIG.load(&deps.storage).unwrap().retired);
+
+        let mint = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1),
+            msg: Binary::default(),
+        });
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            mint,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Retired {}));
+
+        let err =
+            execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), transfer).unwrap_err();
+        assert!(matches!(err, ContractError::Retired {}));
+    }
+
+    #[test]
+    fn renounce_ownership() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.treasury = Some(String::from("treasury"));
+        setup_contract(deps.as_mut(), msg);
+        deps.querier.update_wasm(cw20_with_balance(100));
+        let owner = mock_info("owner", &[]);
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::RenounceOwnership {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            ExecuteMsg::RenounceOwnership {},
+        )
+        .unwrap();
+
+        let res: ConfigResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap())
+                .unwrap();
+        assert!(res.renounced);
+
+        let update = ExecuteMsg::UpdateUnitPrice {
+            price: Uint128::new(5),
+        };
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), update).unwrap_err();
+        assert!(matches!(err, ContractError::OwnershipRenounced {}));
+
+        let withdraw = |recipient: Option<&str>| ExecuteMsg::Withdraw {
+            amount: Some(Uint128::new(1)),
+            recipient: recipient.map(String::from),
+            token: None,
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            withdraw(None),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("treasury"),
+                    amount: Uint128::new(1),
+                })
+                .unwrap(),
+                funds: vec![],
+            })]
+        );
+        let err = execute(deps.as_mut(), mock_env(), owner, withdraw(Some("owner"))).unwrap_err();
+        assert!(matches!(err, ContractError::OwnershipRenounced {}));
+    }
+
+    #[test]
+    fn operators_get_delegated_actions_only() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        let operator = mock_info("operator", &[]);
+        let set = ExecuteMsg::SetOperator {
+            address: String::from("operator"),
+            perms: OperatorPerms {
+                can_pause: true,
+                ..OperatorPerms::default()
+            },
+        };
+        let err = execute(deps.as_mut(), mock_env(), operator.clone(), set.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), set).unwrap();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            operator.clone(),
+            ExecuteMsg::Pause {},
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            operator.clone(),
+            ExecuteMsg::Resume {},
+        )
+        .unwrap();
+
+        let query_msg = QueryMsg::Operators {
+            start_after: None,
+            limit: None,
+        };
+        let res: OperatorsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), query_msg).unwrap()).unwrap();
+        assert_eq!(res.operators.len(), 1);
+        assert!(res.operators[0].perms.can_pause);
+
+        let airdrop = ExecuteMsg::Airdrop {
+            recipients: vec![String::from("fan")],
+        };
+        let err = execute(deps.as_mut(), mock_env(), operator.clone(), airdrop).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let withdraw = ExecuteMsg::Withdraw {
+            amount: Some(Uint128::new(1)),
+            recipient: None,
+            token: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), operator.clone(), withdraw).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let remove = ExecuteMsg::RemoveOperator {
+            address: String::from("operator"),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), remove).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), operator, ExecuteMsg::Pause {}).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+    }
+
+    #[test]
+    fn operator_permission_flags() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.treasury = Some(String::from("treasury"));
+        setup_contract(deps.as_mut(), msg);
+        deps.querier.update_wasm(cw20_with_balance(100));
+        let operator = mock_info("operator", &[]);
+        let set = ExecuteMsg::SetOperator {
+            address: String::from("operator"),
+            perms: OperatorPerms {
+                can_update_price: true,
+                can_withdraw: true,
+                ..OperatorPerms::default()
+            },
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), set).unwrap();
+
+        let permissions = |deps: &OwnedDeps<_, _, _>, address: &str| -> PermissionsResponse {
+            let query_msg = QueryMsg::Permissions {
+                address: address.to_string(),
+            };
+            from_binary(&query(deps.as_ref(), mock_env(), query_msg).unwrap()).unwrap()
+        };
+        let owner = permissions(&deps, "owner");
+        assert!(owner.is_owner);
+        assert_eq!(owner.perms, OperatorPerms::all());
+        let res = permissions(&deps, "operator");
+        assert!(!res.is_owner);
+        assert!(res.perms.can_update_price && res.perms.can_withdraw && !res.perms.can_pause);
+
+        let update = ExecuteMsg::UpdateUnitPrice {
+            price: Uint128::new(5),
+        };
+        execute(deps.as_mut(), mock_env(), operator.clone(), update).unwrap();
+        assert_eq!(
+            CONFIG.load(&deps.storage).unwrap().unit_price,
+            Uint128::new(5)
+        );
+
+        let withdraw = |recipient: Option<&str>| ExecuteMsg::Withdraw {
+            amount: Some(Uint128::new(1)),
+            recipient: recipient.map(String::from),
+            token: None,
+        };
+        execute(deps.as_mut(), mock_env(), operator.clone(), withdraw(None)).unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            operator.clone(),
+            withdraw(Some("operator")),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let err = execute(deps.as_mut(), mock_env(), operator, ExecuteMsg::Pause {}).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+    }
+
+    #[test]
+    fn update_payment_token_after_timelock() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        let update = ExecuteMsg::UpdatePaymentToken {
+            address: String::from("new_token"),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            update.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), update).unwrap();
+
+        let res: PendingPaymentTokenResponse = from_binary(
+            &query(deps.as_ref(), mock_env(), QueryMsg::PendingPaymentToken {}).unwrap(),
+        )
+        .unwrap();
+        let effective_at = mock_env()
+            .block
+            .time
+            .plus_seconds(PAYMENT_TOKEN_TIMELOCK_SECONDS);
+        assert_eq!(res.address, Some(Addr::unchecked("new_token")));
+        assert_eq!(res.effective_at, Some(effective_at));
+
+        // The old token still mints during the timelock.
+        let mint = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1),
+            msg: Binary::default(),
+        });
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            mint,
+        )
+        .unwrap();
+
+        let mut env = mock_env();
+        env.block.time = effective_at;
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::ApplyPaymentToken {},
+        )
+        .unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.cw20_address, Addr::unchecked("new_token"));
+
+        let err = execute(
+            deps.as_mut(),
+            env,
+            mock_info("anyone", &[]),
+            ExecuteMsg::ApplyPaymentToken {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoPendingPaymentToken {}));
+    }
+
+    #[test]
+    fn apply_payment_token_waits_for_timelock() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        let update = ExecuteMsg::UpdatePaymentToken {
+            address: String::from("new_token"),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), update).unwrap();
+
+        let mut env = mock_env();
+        env.block.time = env
+            .block
+            .time
+            .plus_seconds(PAYMENT_TOKEN_TIMELOCK_SECONDS - 1);
+        let err = execute(
+            deps.as_mut(),
+            env,
+            mock_info("anyone", &[]),
+            ExecuteMsg::ApplyPaymentToken {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PaymentTokenTimelocked {}));
+    }
+
+    #[test]
+    fn blocklisted_addresses_cannot_mint() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 2;
+        setup_contract(deps.as_mut(), msg);
+        let update = ExecuteMsg::UpdateBlocklist {
+            add: vec![String::from("exploiter")],
+            remove: vec![],
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            update.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), update).unwrap();
+
+        let query_msg = QueryMsg::Blocklist {
+            start_after: None,
+            limit: None,
+        };
+        let res: BlocklistResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), query_msg).unwrap()).unwrap();
+        assert_eq!(res.addresses, vec![Addr::unchecked("exploiter")]);
+
+        let mint = |sender: &str, recipient: Option<&str>| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(1),
+                msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                    recipient: recipient.map(String::from),
+                    ..MintOptions::default()
+                }))
+                .unwrap(),
+            })
+        };
+        let contract = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            contract.clone(),
+            mint("exploiter", None),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Blocklisted {}));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            contract.clone(),
+            mint("buyer", Some("exploiter")),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Blocklisted {}));
+
+        let update = ExecuteMsg::UpdateBlocklist {
+            add: vec![],
+            remove: vec![String::from("exploiter")],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), update).unwrap();
+        execute(deps.as_mut(), mock_env(), contract, mint("exploiter", None)).unwrap();
+    }
+
+    #[test]
+    fn failed_child_mint_refunds_buyer() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        let mint = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1),
+            msg: Binary::default(),
+        });
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            mint,
+        )
+        .unwrap();
+        assert_eq!(res.messages[0].id, MINT_REPLY_ID);
+        assert_eq!(res.messages[0].reply_on, ReplyOn::Always);
+
+        let failed = Reply {
+            id: MINT_REPLY_ID,
+            result: SubMsgResult::Err(String::from("token_id already claimed")),
+        };
+        let res = reply(deps.as_mut(), mock_env(), failed.clone()).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("buyer"),
+                    amount: Uint128::new(1),
+                })
+                .unwrap(),
+                funds: vec![],
+            })]
+        );
+        assert!(!mints().has(&deps.storage, 0));
+        assert!(!RECEIPTS.has(&deps.storage, 0));
+        assert_eq!(
+            MINTS_PER_ADDRESS
+                .load(&deps.storage, &Addr::unchecked("buyer"))
+                .unwrap(),
+            0
+        );
+        // The buyer's only mint failed, so they are no longer counted as a minter.
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().unique_minters, 0);
+        let res: SupplyInfoResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::SupplyInfo {}).unwrap())
+                .unwrap();
+        assert_eq!(res.minted, 0);
+        assert_eq!(res.failed, 1);
+        assert_eq!(res.minted + res.failed + res.remaining, res.max_tokens);
+
+        // Each failure is refunded once.
+        reply(deps.as_mut(), mock_env(), failed).unwrap_err();
+    }
+
+    #[test]
+    fn tokens_by_buyer_uses_buyer_index() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 4;
+        setup_contract(deps.as_mut(), msg);
+
+        let airdrop = ExecuteMsg::Airdrop {
+            recipients: vec![
+                String::from("alice"),
+                String::from("bob"),
+                String::from("alice"),
+                String::from("alice"),
+            ],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), airdrop).unwrap();
+
+        let token_ids = |start_after, limit, order| {
+            let query_msg = QueryMsg::TokensByBuyer {
+                address: String::from("alice"),
+                start_after,
+                limit,
+                order,
+            };
+            let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+            let mints: MintsResponse = from_binary(&res).unwrap();
+            mints
+                .mints
+                .into_iter()
+                .map(|mint| mint.token_id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(token_ids(None, None, None), vec!["0", "2", "3"]);
+        assert_eq!(token_ids(Some(0), Some(1), None), vec!["2"]);
+        assert_eq!(
+            token_ids(Some(3), None, Some(SortOrder::Descending)),
+            vec!["2", "0"]
+        );
+    }
+
+    #[test]
+    fn price_quote_matches_presale_pricing() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 10;
+        msg.unit_price = Uint128::new(100);
+        msg.presale = Some(Presale {
+            end_time: mock_env().block.time.plus_seconds(60),
+            price: Some(Uint128::new(80)),
+        });
+        setup_contract(deps.as_mut(), msg);
+
+        let add = ExecuteMsg::AddToAllowlist {
+            entries: vec![
+                AllowlistEntryMsg {
+                    address: String::from("friend"),
+                    price: None,
+                    expires: None,
+                },
+                AllowlistEntryMsg {
+                    address: String::from("vip"),
+                    price: Some(Uint128::new(50)),
+                    expires: None,
+                },
+            ],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), add).unwrap();
+
+        let quote = |address: &str, env: Env| {
+            let query_msg = QueryMsg::PriceQuote {
+                address: address.to_string(),
+                quantity: 2,
+            };
+            query(deps.as_ref(), env, query_msg)
+                .map(|res| from_binary::<PriceQuoteResponse>(&res).unwrap().price)
+        };
+        assert_eq!(quote("friend", mock_env()).unwrap(), Uint128::new(160));
+        assert_eq!(quote("vip", mock_env()).unwrap(), Uint128::new(100));
+        quote("stranger", mock_env()).unwrap_err();
+
+        let mut later = mock_env();
+        later.block.time = later.block.time.plus_seconds(60);
+        assert_eq!(quote("stranger", later).unwrap(), Uint128::new(200));
+    }
+
+    #[test]
+    fn can_mint_reports_first_blocker() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 2;
+        msg.presale = Some(Presale {
+            end_time: mock_env().block.time.plus_seconds(60),
+            price: None,
+        });
+        setup_contract(deps.as_mut(), msg);
+
+        let add = ExecuteMsg::AddToAllowlist {
+            entries: vec![AllowlistEntryMsg {
+                address: String::from("friend"),
+                price: None,
+                expires: None,
+            }],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), add).unwrap();
+
+        let can_mint = |deps: Deps, address: &str, quantity| {
+            let query_msg = QueryMsg::CanMint {
+                address: address.to_string(),
+                quantity,
+            };
+            let res: CanMintResponse =
+                from_binary(&query(deps, mock_env(), query_msg).unwrap()).unwrap();
+            assert_eq!(res.can_mint, res.blocker.is_none());
+            res.blocker
+        };
+        assert_eq!(can_mint(deps.as_ref(), "friend", 2), None);
+        assert_eq!(
+            can_mint(deps.as_ref(), "stranger", 1),
+            Some(MintBlocker::NotAllowlisted)
+        );
+        assert_eq!(
+            can_mint(deps.as_ref(), "friend", 3),
+            Some(MintBlocker::SoldOut)
+        );
+        assert_eq!(
+            can_mint(deps.as_ref(), "friend", 0),
+            Some(MintBlocker::InvalidQuantity)
+        );
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::Pause {},
+        )
+        .unwrap();
+        assert_eq!(
+            can_mint(deps.as_ref(), "friend", 1),
+            Some(MintBlocker::Paused)
+        );
+    }
+
+    #[test]
+    fn free_phase_claims() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 5;
+        setup_contract(deps.as_mut(), msg);
+
+        let mut phase = Phase {
+            name: String::from("free"),
+            start_time: mock_env().block.time,
+            end_time: None,
+            price: None,
+            per_wallet_cap: Some(1),
+            allowlist_only: false,
+            free_supply: Some(2),
+        };
+        let owner = mock_info("owner", &[]);
+        let set = |phase: &Phase| ExecuteMsg::SetPhase {
+            id: 0,
+            phase: phase.clone(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), set(&phase)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPhase {}));
+        phase.price = Some(Uint128::zero());
+        execute(deps.as_mut(), mock_env(), owner, set(&phase)).unwrap();
+
+        let claim = |deps: DepsMut, sender: &str| {
+            execute(
+                deps,
+                mock_env(),
+                mock_info(sender, &[]),
+                ExecuteMsg::Claim {},
+            )
+        };
+        let res = claim(deps.as_mut(), "alice").unwrap();
+        assert_eq!(res.messages.len(), 1);
+        let err = claim(deps.as_mut(), "alice").unwrap_err();
+        assert!(matches!(err, ContractError::MintLimitReached {}));
+        claim(deps.as_mut(), "bob").unwrap();
+        let err = claim(deps.as_mut(), "carol").unwrap_err();
+        assert!(matches!(err, ContractError::FreeSupplyExhausted {}));
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from("carol"),
+                amount: Uint128::new(1),
+                msg: Binary::default(),
+            }),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::FreePhase {}));
+        assert_eq!(PHASE_CLAIMED.load(&deps.storage, 0).unwrap(), 2);
+        assert_eq!(
+            MINTS_PER_ADDRESS
+                .may_load(&deps.storage, &Addr::unchecked("alice"))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn drop_rounds_extend_the_collection() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+
+        let add = ExecuteMsg::AddDrop {
+            name: String::from("round two"),
+            start_time: mock_env().block.time,
+            end_time: None,
+            unit_price: Uint128::new(5),
+            supply: 2,
+            per_wallet_cap: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("buyer", &[]),
+            add.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), add).unwrap();
+
+        let receive = |amount, drop_id| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from("buyer"),
+                amount: Uint128::new(amount),
+                msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                    quantity: Some(2),
+                    drop_id,
+                    ..MintOptions::default()
+                }))
+                .unwrap(),
+            })
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        // The main sale only has its own token left.
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), receive(2, None)).unwrap_err();
+        assert!(matches!(err, ContractError::SoldOut {}));
+        let err =
+            execute(deps.as_mut(), mock_env(), info.clone(), receive(9, Some(0))).unwrap_err();
+        assert!(matches!(err, ContractError::WrongPaymentAmount {}));
+        let res = execute(deps.as_mut(), mock_env(), info, receive(10, Some(0))).unwrap();
+        assert_eq!(res.messages.len(), 2);
+
+        let query_msg = QueryMsg::Drops {
+            start_after: None,
+            limit: None,
+            order: None,
+        };
+        let drops: DropsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), query_msg).unwrap()).unwrap();
+        assert_eq!(drops.drops.len(), 1);
+        assert_eq!(drops.drops[0].round.minted, 2);
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.max_tokens, 3);
+        assert_eq!(config.drop_reserved, 0);
+        assert_eq!(config.remaining_supply(), 1);
+    }
+
+    #[test]
+    fn protocol_fee_is_forwarded_at_mint() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.unit_price = Uint128::new(100);
+        msg.protocol_fee = Some(ProtocolFeeMsg {
+            collector: String::from("platform"),
+            bps: 500,
+        });
+        setup_contract(deps.as_mut(), msg);
+
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(100),
+            msg: Binary::default(),
+        });
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, receive).unwrap();
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("platform"),
+                    amount: Uint128::new(5),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+
+        let update = ExecuteMsg::UpdateProtocolFee { fee: None };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            update.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("platform", &[]),
+            update,
+        )
+        .unwrap();
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().protocol_fee, None);
+    }
+
+    #[test]
+    fn burn_bps_burns_part_of_each_payment() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.unit_price = Uint128::new(100);
+        msg.burn_bps = Some(1_000);
+        setup_contract(deps.as_mut(), msg);
+
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(100),
+            msg: Binary::default(),
+        });
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, receive).unwrap();
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::new(10),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Burned {}).unwrap();
+        let burned: BurnedResponse = from_binary(&res).unwrap();
+        assert_eq!(burned.burned, Uint128::new(10));
+    }
+
+    #[test]
+    fn instantiate_requires_a_cw20_payment_token() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.cw20_address = Addr::unchecked("notatoken");
+        let err = instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPaymentToken {}));
+
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap();
+        let config: ConfigResponse = from_binary(&res).unwrap();
+        assert_eq!(config.cw20_symbol, Some(String::from("PAY")));
+        assert_eq!(config.cw20_decimals, Some(6));
+    }
+
+    #[test]
+    fn mint_max_buys_what_the_amount_allows() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 5;
+        msg.unit_price = Uint128::new(3);
+        msg.mint_limit_per_address = Some(3);
+        setup_contract(deps.as_mut(), msg);
+
+        let receive = |sender: &str, amount| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(amount),
+                msg: to_binary(&Cw20HookMsg::MintMax(MintOptions::default())).unwrap(),
+            })
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), receive("alice", 7)).unwrap();
+        assert_eq!(res.messages.len(), 3);
+        assert_eq!(
+            res.messages[2].msg,
+            cw20_transfer_msg(
+                &Addr::unchecked(MOCK_CONTRACT_ADDR),
+                "alice",
+                Uint128::new(1)
+            )
+            .unwrap()
+        );
+
+        // Capped by the per-address limit.
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), receive("alice", 9)).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(
+            res.messages[1].msg,
+            cw20_transfer_msg(
+                &Addr::unchecked(MOCK_CONTRACT_ADDR),
+                "alice",
+                Uint128::new(6)
+            )
+            .unwrap()
+        );
+
+        let err = execute(deps.as_mut(), mock_env(), info, receive("bob", 2)).unwrap_err();
+        assert!(matches!(err, ContractError::WrongPaymentAmount {}));
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().minted(), 3);
+    }
+
+    #[test]
+    fn commit_reveal_mint() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.commit_reveal_blocks = Some(2);
+        setup_contract(deps.as_mut(), msg);
+
+        let receive = |hook: &Cw20HookMsg| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from("buyer"),
+                amount: Uint128::new(1),
+                msg: to_binary(hook).unwrap(),
+            })
+        };
+        let reveal = |secret: &[u8]| {
+            receive(&Cw20HookMsg::RevealMint {
+                secret: Binary::from(secret),
+                options: MintOptions::default(),
+            })
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let mint = receive(&Cw20HookMsg::Mint(MintOptions::default()));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), mint).unwrap_err();
+        assert!(matches!(err, ContractError::CommitmentRequired {}));
+
+        let commitment: [u8; 32] = Sha256::digest(b"buyersecret").into();
+        let commit = ExecuteMsg::CommitMint {
+            commitment: HexBinary::from(commitment),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("buyer", &[]), commit).unwrap();
+
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), reveal(b"secret")).unwrap_err();
+        assert!(matches!(err, ContractError::CommitmentNotMatured {}));
+        let mut later = mock_env();
+        later.block.height += 2;
+        let err =
+            execute(deps.as_mut(), later.clone(), info.clone(), reveal(b"guess")).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidCommitment {}));
+        let res = execute(
+            deps.as_mut(),
+            later.clone(),
+            info.clone(),
+            reveal(b"secret"),
+        )
+        .unwrap();
+        assert_eq!(res.attributes[0].value, "mint");
+
+        let err = execute(deps.as_mut(), later, info, reveal(b"secret")).unwrap_err();
+        assert!(matches!(err, ContractError::NoCommitment {}));
+    }
+
+    #[test]
+    fn gatekeeper_permits_are_required_once() {
+        use k256::ecdsa::signature::Signer;
+        use k256::ecdsa::{Signature, SigningKey};
+        use k256::elliptic_curve::sec1::ToEncodedPoint;
+
+        let key = SigningKey::from_bytes(&[9u8; 32]).unwrap();
+        let pubkey = key.verifying_key().to_encoded_point(true);
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 3;
+        msg.gatekeeper = Some(Binary::from(pubkey.as_bytes()));
+        setup_contract(deps.as_mut(), msg);
+
+        let sign = |permit: MintPermit| {
+            let mut payload = MOCK_CONTRACT_ADDR.as_bytes().to_vec();
+            payload.extend_from_slice(&to_binary(&permit).unwrap());
+            let signature: Signature = key.sign(&payload);
+            SignedMintPermit {
+                permit,
+                signature: Binary::from(signature.as_ref()),
+            }
+        };
+        let receive = |permit: Option<SignedMintPermit>| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from("buyer"),
+                amount: Uint128::new(1),
+                msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                    permit,
+                    ..MintOptions::default()
+                }))
+                .unwrap(),
+            })
+        };
+        let permit = MintPermit {
+            address: String::from("buyer"),
+            nonce: 1,
+            expiry: mock_env().block.time.plus_seconds(60),
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), receive(None)).unwrap_err();
+        assert!(matches!(err, ContractError::PermitRequired {}));
+        let other = sign(MintPermit {
+            address: String::from("other"),
+            ..permit.clone()
+        });
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            receive(Some(other)),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPermit {}));
+
+        let signed = sign(permit);
+        let msg = receive(Some(signed.clone()));
+        execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::PermitUsed {}));
+        let used = |nonce| {
+            let res: NonceUsedResponse = from_binary(
+                &query(
+                    deps.as_ref(),
+                    mock_env(),
+                    QueryMsg::PermitNonceUsed {
+                        address: String::from("buyer"),
+                        nonce,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            res.used
+        };
+        assert!(used(1));
+        assert!(!used(2));
+
+        let mut later = mock_env();
+        later.block.time = later.block.time.plus_seconds(60);
+        let expired = sign(MintPermit {
+            nonce: 2,
+            ..signed.permit
+        });
+        let err = execute(deps.as_mut(), later, info, receive(Some(expired))).unwrap_err();
+        assert!(matches!(err, ContractError::PermitExpired {}));
+    }
+
+    #[test]
+    fn relayers_mint_signed_intents() {
+        use k256::ecdsa::signature::Signer;
+        use k256::ecdsa::{Signature, SigningKey};
+        use k256::elliptic_curve::sec1::ToEncodedPoint;
+
+        let key = SigningKey::from_bytes(&[7u8; 32]).unwrap();
+        let pubkey = key.verifying_key().to_encoded_point(true);
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 3;
+        setup_contract(deps.as_mut(), msg);
+
+        let msg = ExecuteMsg::UpdateRelayers {
+            add: vec![String::from("relayer")],
+            remove: vec![],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        let msg = ExecuteMsg::SetIntentKey {
+            pubkey: Some(Binary::from(pubkey.as_bytes())),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("user", &[]), msg).unwrap();
+
+        let intent = MintIntent {
+            recipient: String::from("user"),
+            quantity: 2,
+            nonce: 1,
+        };
+        let mut payload = MOCK_CONTRACT_ADDR.as_bytes().to_vec();
+        payload.extend_from_slice(&to_binary(&intent).unwrap());
+        let signature: Signature = key.sign(&payload);
+        let receive = |sender: &str, intent: MintIntent| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from(sender),
+                amount: Uint128::new(2),
+                msg: to_binary(&Cw20HookMsg::RelayMint {
+                    intent,
+                    signature: Binary::from(signature.as_ref()),
+                })
+                .unwrap(),
+            })
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+
+        let msg = receive("stranger", intent.clone());
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let tampered = MintIntent {
+            quantity: 3,
+            ..intent.clone()
+        };
+        let msg = receive("relayer", tampered);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidIntentSignature {}));
+
+        let msg = receive("relayer", intent);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(res.events.len(), 2);
+        assert_eq!(
+            MINTS_PER_ADDRESS
+                .load(&deps.storage, &Addr::unchecked("user"))
+                .unwrap(),
+            2
+        );
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::IntentUsed {}));
+        let res: NonceUsedResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::IntentNonceUsed {
+                    address: String::from("user"),
+                    nonce: 1,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(res.used);
+
+        let res: RelayerResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Relayer {
+                    address: String::from("relayer"),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(res.relayer);
+    }
+
+    #[test]
+    fn signed_admin_actions_run_as_owner() {
+        use k256::ecdsa::signature::Signer;
+        use k256::ecdsa::{Signature, SigningKey};
+        use k256::elliptic_curve::sec1::ToEncodedPoint;
+
+        let key = SigningKey::from_bytes(&[5u8; 32]).unwrap();
+        let pubkey = key.verifying_key().to_encoded_point(true);
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+
+        let sign = |payload: &AdminPayload| {
+            let mut data = MOCK_CONTRACT_ADDR.as_bytes().to_vec();
+            data.extend_from_slice(&to_binary(payload).unwrap());
+            let signature: Signature = key.sign(&data);
+            Binary::from(signature.as_ref())
+        };
+        let payload = AdminPayload {
+            msg: SignedAdminMsg::UpdateUnitPrice {
+                price: Uint128::new(7),
+            },
+            nonce: 1,
+            expiry: mock_env().block.time.plus_seconds(60),
+        };
+        let msg = ExecuteMsg::ExecuteSigned {
+            payload: payload.clone(),
+            signature: sign(&payload),
+        };
+        let info = mock_info("operator", &[]);
+
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::NoOwnerKey {}));
+        let set_key = ExecuteMsg::SetOwnerKey {
+            pubkey: Some(Binary::from(pubkey.as_bytes())),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), set_key.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), set_key).unwrap();
+
+        let forged = ExecuteMsg::ExecuteSigned {
+            payload: AdminPayload {
+                nonce: 2,
+                ..payload.clone()
+            },
+            signature: sign(&payload),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), forged).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAdminSignature {}));
+
+        execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.unit_price, Uint128::new(7));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::AdminNonceUsed {}));
+
+        let mut later = mock_env();
+        later.block.time = later.block.time.plus_seconds(60);
+        let payload = AdminPayload {
+            nonce: 3,
+            ..payload
+        };
+        let msg = ExecuteMsg::ExecuteSigned {
+            signature: sign(&payload),
+            payload,
+        };
+        let err = execute(deps.as_mut(), later, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::AdminPayloadExpired {}));
+    }
+
+    #[test]
+    fn renewals_extend_token_expiry() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.renewal = Some(Renewal {
+            price: Uint128::new(5),
+            period: 100,
+            revocable: true,
+        });
+        setup_contract(deps.as_mut(), msg);
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let receive = |amount: u128, hook: Cw20HookMsg| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from("buyer"),
+                amount: Uint128::new(amount),
+                msg: to_binary(&hook).unwrap(),
+            })
+        };
+        let msg = receive(1, Cw20HookMsg::Mint(MintOptions::default()));
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let start = mock_env().block.time;
+        let expiry_of = |deps: Deps, env: Env| -> TokenExpiryResponse {
+            let msg = QueryMsg::TokenExpiry {
+                token_id: String::from("0"),
+            };
+            from_binary(&query(deps, env, msg).unwrap()).unwrap()
+        };
+        assert_eq!(
+            expiry_of(deps.as_ref(), mock_env()).expiry,
+            Some(start.plus_seconds(100))
+        );
+
+        let renew = |amount| {
+            receive(
+                amount,
+                Cw20HookMsg::Renew {
+                    token_id: String::from("0"),
+                },
+            )
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), renew(4)).unwrap_err();
+        assert!(matches!(err, ContractError::WrongPaymentAmount {}));
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), renew(7)).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(
+            expiry_of(deps.as_ref(), mock_env()).expiry,
+            Some(start.plus_seconds(200))
+        );
+
+        let revoke = ExecuteMsg::RevokeExpired {
+            token_ids: vec![String::from("0")],
+        };
+        let owner = mock_info("owner", &[]);
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), revoke.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::TokenNotExpired {}));
+
+        let mut later = mock_env();
+        later.block.time = start.plus_seconds(200);
+        let res: ExpiredTokensResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                later.clone(),
+                QueryMsg::ExpiredTokens {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.token_ids, vec![String::from("0")]);
+        execute(deps.as_mut(), later.clone(), owner, revoke).unwrap();
+        assert!(expiry_of(deps.as_ref(), later.clone()).revoked);
+        let err = execute(deps.as_mut(), later, info, renew(5)).unwrap_err();
+        assert!(matches!(err, ContractError::TokenRevoked {}));
+    }
+
+    #[test]
+    fn contract_info_reports_child_version() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        let info = |deps: Deps| -> crate::msg::ContractInfoResponse {
+            from_binary(&query(deps, mock_env(), QueryMsg::ContractInfo {}).unwrap()).unwrap()
+        };
+
+        let res = info(deps.as_ref());
+        assert_eq!(res.contract.version, CONTRACT_VERSION);
+        assert_eq!(res.cw721_version, None);
+
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Raw { contract_addr, key } if contract_addr == NFT_CONTRACT_ADDR => {
+                assert_eq!(key.as_slice(), b"contract_info");
+                let version = ContractVersion {
+                    contract: String::from("crates.io:cw721-base"),
+                    version: String::from("0.15.0"),
+                };
+                SystemResult::Ok(ContractResult::Ok(to_binary(&version).unwrap()))
+            }
+            WasmQuery::ContractInfo { contract_addr } if contract_addr == NFT_CONTRACT_ADDR => {
+                let mut info = cosmwasm_std::ContractInfoResponse::default();
+                info.code_id = 7;
+                SystemResult::Ok(ContractResult::Ok(to_binary(&info).unwrap()))
+            }
+            _ => cw20_token_info(query),
+        });
+        let res = info(deps.as_ref());
+        assert_eq!(res.cw721_code_id, Some(7));
+        assert_eq!(res.cw721_version.unwrap().version, "0.15.0");
+    }
+
+    #[test]
+    fn owner_migrates_child_cw721() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        let msg = ExecuteMsg::MigrateCw721 {
+            new_code_id: 42,
+            msg: to_binary(&Empty {}).unwrap(),
+        };
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Migrate {
+                contract_addr: String::from(NFT_CONTRACT_ADDR),
+                new_code_id: 42,
+                msg: to_binary(&Empty {}).unwrap(),
+            })
+        );
+    }
+
+    #[test]
+    fn metadata_code_id_selects_onchain_child() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.metadata_code_id = Some(20);
+        msg.soulbound = Some(true);
+        let info = mock_info("owner", &[]);
+        let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::ConflictingCw721Variant {}));
+
+        msg.soulbound = None;
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert!(matches!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Instantiate { code_id: 20, .. })
+        ));
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert!(config.onchain_metadata);
+    }
+
+    #[test]
+    fn token_code_id_updates_are_recorded() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        let update = |code_id| ExecuteMsg::UpdateTokenCodeId { code_id };
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            update(11),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            update(11),
+        )
+        .unwrap();
+
+        let create = ExecuteMsg::CreateCollection {
+            name: String::from("Second"),
+            symbol: String::from("SEC"),
+            unit_price: Uint128::new(1),
+            max_tokens: 5,
+            token_uri: String::from("https://ipfs.io/ipfs/S"),
+            token_code_id: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), create).unwrap();
+        assert!(matches!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Instantiate { code_id: 11, .. })
+        ));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            update(12),
+        )
+        .unwrap();
+        let res: TokenCodeIdsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::TokenCodeIds {}).unwrap())
+                .unwrap();
+        assert_eq!(res.current, 12);
+        assert_eq!(res.history, vec![10, 11, 12]);
+    }
+
+    #[test]
+    fn mints_write_receipts() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 2;
+        setup_contract(deps.as_mut(), msg);
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(2),
+            msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                quantity: Some(2),
+                recipient: Some(String::from("friend")),
+                ..MintOptions::default()
+            }))
+            .unwrap(),
+        });
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            receive,
+        )
+        .unwrap();
+
+        let receipt: ReceiptInfo = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Receipt {
+                    token_id: String::from("1"),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            receipt,
+            ReceiptInfo {
+                token_id: String::from("1"),
+                payer: Addr::unchecked("buyer"),
+                recipient: Addr::unchecked("friend"),
+                amount: Uint128::new(1),
+                currency: Currency::Cw20(Addr::unchecked(MOCK_CONTRACT_ADDR)),
+                phase_id: None,
+                height: mock_env().block.height,
+            }
+        );
+
+        let msg = QueryMsg::Receipts {
+            start_after: Some(0),
+            limit: None,
+        };
+        let res: ReceiptsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.receipts, vec![receipt]);
+    }
+
+    #[test]
+    fn trade_ins_pay_for_mints() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 2;
+        setup_contract(deps.as_mut(), msg);
+        let set = ExecuteMsg::SetTradeIn {
+            trade_in: Some(TradeInMsg {
+                collection: String::from("legacy"),
+                tokens_per_mint: 2,
+                burn: true,
+            }),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), set).unwrap();
+
+        let trade_in = |token_id: &str| {
+            ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+                sender: String::from("holder"),
+                token_id: token_id.to_string(),
+                msg: Binary::default(),
+            })
+        };
+        let legacy = mock_info("legacy", &[]);
+        let res = execute(deps.as_mut(), mock_env(), legacy.clone(), trade_in("a")).unwrap();
+        assert!(res.messages.is_empty());
+
+        let res = execute(deps.as_mut(), mock_env(), legacy.clone(), trade_in("b")).unwrap();
+        assert_eq!(res.messages.len(), 3);
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("legacy"),
+                msg: to_binary(&Cw721ExecuteMsg::<Extension, Empty>::Burn {
+                    token_id: String::from("a"),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+        let holder = Addr::unchecked("holder");
+        assert_eq!(MINTS_PER_ADDRESS.load(&deps.storage, &holder).unwrap(), 1);
+
+        execute(deps.as_mut(), mock_env(), legacy, trade_in("c")).unwrap();
+        let cancel = ExecuteMsg::CancelTradeIn {
+            collection: String::from("legacy"),
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("holder", &[]),
+            cancel.clone(),
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 1);
+        let err = execute(deps.as_mut(), mock_env(), mock_info("holder", &[]), cancel).unwrap_err();
+        assert!(matches!(err, ContractError::NothingDeposited {}));
+    }
+
+    #[test]
+    fn raffle_winners_mint_and_losers_are_refunded() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 3;
+        setup_contract(deps.as_mut(), msg);
+        let end_time = mock_env().block.time.plus_seconds(100);
+        let start = ExecuteMsg::StartRaffle {
+            end_time,
+            ticket_price: Uint128::new(10),
+            winners: 2,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), start).unwrap();
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().remaining_supply(), 1);
+
+        let buy = |sender: &str, quantity: u32| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from(sender),
+                amount: Uint128::new(10 * quantity as u128),
+                msg: to_binary(&Cw20HookMsg::BuyTickets { quantity }).unwrap(),
+            })
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        execute(deps.as_mut(), mock_env(), info.clone(), buy("alice", 3)).unwrap();
+        execute(deps.as_mut(), mock_env(), info.clone(), buy("bob", 2)).unwrap();
+        assert_eq!(
+            CONFIG.load(&deps.storage).unwrap().raffle_escrow,
+            Uint128::new(50)
+        );
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::DrawRaffle {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::RaffleOpen {}));
+        let mut later = mock_env();
+        later.block.time = end_time;
+        let err = execute(deps.as_mut(), later.clone(), info.clone(), buy("carol", 1)).unwrap_err();
+        assert!(matches!(err, ContractError::MintEnded {}));
+        let err = execute(
+            deps.as_mut(),
+            later.clone(),
+            info,
+            ExecuteMsg::DrawRaffle {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            later.clone(),
+            mock_info("owner", &[]),
+            ExecuteMsg::DrawRaffle {},
+        )
+        .unwrap();
+
+        let raffle = RAFFLE.load(&deps.storage).unwrap();
+        assert_eq!((0..5).filter(|ticket| raffle.is_winner(*ticket)).count(), 2);
+
+        let mut wins = 0;
+        for holder in ["alice", "bob"] {
+            let tickets: RaffleTicketsResponse = from_binary(
+                &query(
+                    deps.as_ref(),
+                    later.clone(),
+                    QueryMsg::RaffleTickets {
+                        address: String::from(holder),
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            let won = tickets.winning.unwrap().len();
+            wins += won;
+
+            let res = execute(
+                deps.as_mut(),
+                later.clone(),
+                mock_info(holder, &[]),
+                ExecuteMsg::ClaimRaffle {},
+            )
+            .unwrap();
+            let refunded = tickets.tickets.len() - won;
+            assert_eq!(res.messages.len(), won + (refunded > 0) as usize);
+        }
+        assert_eq!(wins, 2);
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.raffle_escrow, Uint128::zero());
+        assert_eq!(config.raffle_reserved, 0);
+        assert_eq!(config.remaining_supply(), 1);
+
+        let err = execute(
+            deps.as_mut(),
+            later,
+            mock_info("alice", &[]),
+            ExecuteMsg::ClaimRaffle {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoTickets {}));
+    }
+
+    #[test]
+    fn auction_refunds_outbid_bidders_and_mints_to_winner() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        let end_time = mock_env().block.time.plus_seconds(100);
+        let create = |token_id: &str| ExecuteMsg::CreateAuction {
+            token_id: String::from(token_id),
+            token_uri: String::from("https://ipfs.io/ipfs/one"),
+            reserve_price: Uint128::new(10),
+            end_time,
+        };
+        let owner = mock_info("owner", &[]);
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), create("0")).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidTokenId {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            create("one-of-one"),
+        )
+        .unwrap();
+
+        let bid = |sender: &str, amount: u128| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from(sender),
+                amount: Uint128::new(amount),
+                msg: to_binary(&Cw20HookMsg::Bid {
+                    token_id: String::from("one-of-one"),
+                })
+                .unwrap(),
+            })
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), bid("alice", 9)).unwrap_err();
+        assert!(matches!(err, ContractError::BidTooLow {}));
+        execute(deps.as_mut(), mock_env(), info.clone(), bid("alice", 10)).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), bid("bob", 10)).unwrap_err();
+        assert!(matches!(err, ContractError::BidTooLow {}));
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), bid("bob", 15)).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            cw20_transfer_msg(
+                &Addr::unchecked(MOCK_CONTRACT_ADDR),
+                "alice",
+                Uint128::new(10)
+            )
+            .unwrap()
+        );
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.auction_escrow, Uint128::new(15));
+
+        let settle = ExecuteMsg::SettleAuction {
+            token_id: String::from("one-of-one"),
+        };
+        let err = execute(deps.as_mut(), mock_env(), owner, settle.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::AuctionOpen {}));
+        let mut later = mock_env();
+        later.block.time = end_time;
+        let err = execute(deps.as_mut(), later.clone(), info, bid("carol", 20)).unwrap_err();
+        assert!(matches!(err, ContractError::AuctionEnded {}));
+        let err = execute(
+            deps.as_mut(),
+            later.clone(),
+            mock_info("alice", &[]),
+            settle.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let res = execute(
+            deps.as_mut(),
+            later.clone(),
+            mock_info("bob", &[]),
+            settle.clone(),
+        )
+        .unwrap();
+        let mint = Cw721ExecuteMsg::<Extension, Empty>::Mint(MintMsg {
+            token_id: String::from("one-of-one"),
+            owner: String::from("bob"),
+            token_uri: Some(String::from("https://ipfs.io/ipfs/one")),
+            extension: None,
+        });
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from(NFT_CONTRACT_ADDR),
+                msg: to_binary(&mint).unwrap(),
+                funds: vec![],
+            })
+        );
+        assert_eq!(
+            CONFIG.load(&deps.storage).unwrap().auction_escrow,
+            Uint128::zero()
+        );
+        let err = execute(deps.as_mut(), later, mock_info("bob", &[]), settle).unwrap_err();
+        assert!(matches!(err, ContractError::AuctionSettled {}));
+    }
+
+    #[test]
+    fn scheduled_drops_open_in_turn() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        let week = 7 * 86_400;
+        let first_start = mock_env().block.time.plus_seconds(60);
+        let schedule = ExecuteMsg::ScheduleDrops {
+            name: String::from("weekly"),
+            first_start,
+            interval: week,
+            duration: 86_400,
+            rounds: 3,
+            unit_price: Uint128::new(2),
+            supply: 100,
+            per_wallet_cap: None,
+        };
+        // Rounds that would end past the last representable time are refused, not a panic.
+        let mut overflowing = schedule.clone();
+        if let ExecuteMsg::ScheduleDrops {
+            interval, duration, ..
+        } = &mut overflowing
+        {
+            *interval = u64::MAX / 2;
+            *duration = u64::MAX / 2;
+        }
+        let info = mock_info("owner", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), overflowing).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidDrop {}));
+        execute(deps.as_mut(), mock_env(), info, schedule).unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.max_tokens, 301);
+        assert_eq!(config.drop_reserved, 300);
+
+        let schedule_at = |deps: Deps, seconds: u64| -> DropScheduleResponse {
+            let mut env = mock_env();
+            env.block.time = first_start.plus_seconds(seconds);
+            from_binary(&query(deps, env, QueryMsg::DropSchedule {}).unwrap()).unwrap()
+        };
+        let res = schedule_at(deps.as_ref(), 0);
+        assert_eq!(res.current.unwrap().round.name, "weekly #1");
+        assert_eq!(res.next.unwrap().id, 1);
+
+        let res = schedule_at(deps.as_ref(), 86_400);
+        assert_eq!(res.current, None);
+        let next = res.next.unwrap();
+        assert_eq!(next.round.start_time, first_start.plus_seconds(week));
+
+        let res = schedule_at(deps.as_ref(), 2 * week + 10);
+        assert_eq!(res.current.unwrap().id, 2);
+        assert_eq!(res.next, None);
+    }
+
+    #[test]
+    fn token_prices_override_unit_price() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 3;
+        setup_contract(deps.as_mut(), msg);
+
+        let update = ExecuteMsg::UpdateTokenPrices {
+            set: vec![
+                (String::from("1"), Uint128::new(50)),
+                (String::from("2"), Uint128::new(30)),
+            ],
+            unset: vec![],
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("buyer", &[]),
+            update.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), update).unwrap();
+        let unset = ExecuteMsg::UpdateTokenPrices {
+            set: vec![],
+            unset: vec![String::from("2")],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), unset).unwrap();
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::TokenPrices {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+        let prices: TokenPricesResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            prices.prices,
+            vec![TokenPriceInfo {
+                token_id: String::from("1"),
+                price: Uint128::new(50),
+            }]
+        );
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(
+            price_for(&deps.storage, &config, 3).unwrap(),
+            config.unit_price + Uint128::new(50) + config.unit_price
+        );
+    }
+
+    #[test]
+    fn collection_info_until_freeze() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.royalty = Some(RoyaltyMsg {
+            payment_address: String::from("artist"),
+            bps: 500,
+        });
+        setup_contract(deps.as_mut(), msg);
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::CollectionInfo {}).unwrap();
+        let info: CollectionInfoResponse = from_binary(&res).unwrap();
+        assert_eq!(info.creator, "owner");
+        assert_eq!(info.description, None);
+        assert_eq!(info.royalty_info.unwrap().share, Decimal::percent(5));
+
+        let update = |description: &str| ExecuteMsg::UpdateCollectionInfo {
+            collection_info: CollectionInfoMsg {
+                creator: Some(String::from("artist")),
+                description: Some(description.to_string()),
+                image: Some(String::from("ipfs://collection.png")),
+                external_link: None,
+                explicit_content: false,
+            },
+            royalty: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            update(""),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidCollectionInfo {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            update("Moni genesis"),
+        )
+        .unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::CollectionInfo {}).unwrap();
+        let info: CollectionInfoResponse = from_binary(&res).unwrap();
+        assert_eq!(info.creator, "artist");
+        assert_eq!(info.description.as_deref(), Some("Moni genesis"));
+        assert_eq!(info.royalty_info, None);
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::Freeze {},
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            update("changed"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ConfigFrozen {}));
+    }
+
+    #[test]
+    fn batch_mint_tracks_each_token() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 3;
+        setup_contract(deps.as_mut(), msg);
+
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(3),
+            msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                quantity: Some(3),
+                ..MintOptions::default()
+            }))
+            .unwrap(),
+        });
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, receive).unwrap();
+
+        let reply_ids: Vec<u64> = res.messages.iter().map(|msg| msg.id).collect();
+        assert_eq!(
+            reply_ids,
+            vec![MINT_REPLY_ID, MINT_REPLY_ID + 1, MINT_REPLY_ID + 2]
+        );
+        let token_ids: Vec<&str> = res
+            .attributes
+            .iter()
+            .filter(|attr| attr.key == "token_id")
+            .map(|attr| attr.value.as_str())
+            .collect();
+        assert_eq!(token_ids, vec!["0", "1", "2"]);
+        assert_eq!(res.events.len(), 3);
+        for token_id in 0..3 {
+            assert_eq!(
+                RECEIPTS.load(&deps.storage, token_id).unwrap().payer,
+                "buyer"
+            );
+        }
+    }
+
+    #[test]
+    fn mints_between_times() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 4;
+        setup_contract(deps.as_mut(), msg);
+
+        let day = 86_400;
+        let start = mock_env().block.time;
+        for days in [0, 1, 1, 3] {
+            let mut env = mock_env();
+            env.block.time = start.plus_seconds(days * day);
+            let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from("buyer"),
+                amount: Uint128::new(1),
+                msg: to_binary(&Cw20HookMsg::Mint(MintOptions::default())).unwrap(),
+            });
+            execute(
+                deps.as_mut(),
+                env,
+                mock_info(MOCK_CONTRACT_ADDR, &[]),
+                receive,
+            )
+            .unwrap();
+        }
+
+        let between = |from: u64, to: u64, start_after, limit| -> Vec<String> {
+            let query_msg = QueryMsg::MintsBetween {
+                start: start.plus_seconds(from),
+                end: start.plus_seconds(to),
+                start_after,
+                limit,
+            };
+            let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+            let mints: MintsResponse = from_binary(&res).unwrap();
+            mints.mints.into_iter().map(|mint| mint.token_id).collect()
+        };
+        assert_eq!(between(day, 2 * day, None, None), vec!["1", "2"]);
+        assert_eq!(between(day, 3 * day, None, Some(1)), vec!["1"]);
+        assert_eq!(between(day, 3 * day, Some(1), None), vec!["2", "3"]);
+        assert!(between(4 * day, 5 * day, None, None).is_empty());
+    }
+
+    #[test]
+    fn mints_only_write_counters() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 2;
+        setup_contract(deps.as_mut(), msg);
+        let stored = deps.storage.get(b"config").unwrap();
+
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1),
+            msg: to_binary(&Cw20HookMsg::Mint(MintOptions::default())).unwrap(),
+        });
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            receive,
+        )
+        .unwrap();
+        assert_eq!(deps.storage.get(b"config").unwrap(), stored);
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!((config.unused_token_id, config.unique_minters), (1, 1));
+
+        // Configs stored before the split keep their inline counters until saved again.
+        let stored = String::from_utf8(stored).unwrap();
+        let legacy = format!(r#"{{"unused_token_id":7,{}"#, &stored[1..]);
+        deps.storage.set(b"config", legacy.as_bytes());
+        deps.storage.remove(b"mint_counters");
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().unused_token_id, 7);
+    }
+
+    #[test]
+    fn extension_entry_points_re_encode_mints() {
+        use crate::extension::{self, MintExtension};
+
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Badge {
+            title: Option<String>,
+            level: u8,
+        }
+
+        impl MintExtension for Badge {
+            fn from_metadata(metadata: Option<Metadata>) -> Self {
+                Badge {
+                    title: metadata.and_then(|metadata| metadata.name),
+                    level: 1,
+                }
+            }
+        }
+
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.onchain_metadata = Some(true);
+        setup_contract(deps.as_mut(), msg);
+
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1),
+            msg: to_binary(&Cw20HookMsg::Mint(MintOptions::default())).unwrap(),
+        });
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let res = extension::execute::<Badge>(deps.as_mut(), mock_env(), info, receive).unwrap();
+
+        let mint = res
+            .messages
+            .iter()
+            .find_map(|sub_msg| match &sub_msg.msg {
+                CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => {
+                    match from_binary::<Cw721ExecuteMsg<Badge, Empty>>(msg) {
+                        Ok(Cw721ExecuteMsg::Mint(mint)) => Some(mint),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(mint.extension.level, 1);
+        assert!(mint.extension.title.is_some());
+    }
+
+    #[test]
+    fn linked_cw721_version_is_detected() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr.ends_with("nft") => {
+                let res = match from_binary::<Cw721OwnershipQueryMsg>(msg) {
+                    Ok(_) if contract_addr == "legacy_nft" => {
+                        return SystemResult::Ok(ContractResult::Err(String::from("unknown")));
+                    }
+                    Ok(_) => to_binary(&Cw721OwnershipResponse {
+                        owner: Some(String::from(MOCK_CONTRACT_ADDR)),
+                        pending_owner: None,
+                        pending_expiry: None,
+                    }),
+                    Err(_) => to_binary(&ContractInfoResponse {
+                        name: String::from("FirstFT"),
+                        symbol: String::from("FFT"),
+                    }),
+                };
+                SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+            }
+            _ => cw20_token_info(query),
+        });
+        let transfer = ExecuteMsg::TransferMinter {
+            new_minter: String::from("sale_v2"),
+            expiry: None,
+        };
+
+        let mut msg = default_instantiate_msg();
+        msg.cw721_address = Some(String::from("legacy_nft"));
+        msg.cw721_version = Some(Cw721Version::Ownable);
+        instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.cw721_version, Cw721Version::Legacy);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            transfer.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Cw721OwnershipUnsupported {}));
+
+        let mut msg = default_instantiate_msg();
+        msg.cw721_address = Some(String::from("ownable_nft"));
+        instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.cw721_version, Cw721Version::Ownable);
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), transfer).unwrap();
+        assert!(CONFIG.load(&deps.storage).unwrap().retired);
+    }
+
+    #[test]
+    fn mints_return_token_ids_as_data() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 3;
+        setup_contract(deps.as_mut(), msg);
+        let mint = |quantity| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from("buyer"),
+                amount: Uint128::from(quantity),
+                msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                    quantity: Some(quantity),
+                    ..MintOptions::default()
+                }))
+                .unwrap(),
+            })
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), mint(1)).unwrap();
+        let data: MintResponse = from_binary(&res.data.unwrap()).unwrap();
+        assert_eq!(
+            data,
+            MintResponse {
+                token_id: String::from("0"),
+                token_ids: vec![String::from("0")],
+                recipient: String::from("buyer"),
+            }
+        );
+
+        let res = execute(deps.as_mut(), mock_env(), info, mint(2)).unwrap();
+        let data: MintResponse = from_binary(&res.data.unwrap()).unwrap();
+        assert_eq!(data.token_id, "1");
+        assert_eq!(data.token_ids, vec!["1", "2"]);
+    }
+
+    #[test]
+    fn reply_data_carries_cw721_address() {
+        let mut deps = mock_dependencies();
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            default_instantiate_msg(),
+        )
+        .unwrap();
+
+        let instantiate_reply = MsgInstantiateContractResponse {
+            contract_address: NFT_CONTRACT_ADDR.to_string(),
+            data: vec![],
+        };
+        let reply_msg = Reply {
+            id: INSTANTIATE_TOKEN_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(instantiate_reply.encode_to_vec().into()),
+            }),
+        };
+        let res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+        let data: InstantiateData = from_binary(&res.data.unwrap()).unwrap();
+        assert_eq!(data.cw721_address, NFT_CONTRACT_ADDR);
+    }
+
+    #[test]
+    fn mint_building_blocks() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 2;
+        msg.token_uri_mode = Some(TokenUriMode::Base);
+        setup_contract(deps.as_mut(), msg);
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+
+        validate_payment(&config, Uint128::new(2), Uint128::new(1)).unwrap();
+        let err = validate_payment(&config, Uint128::zero(), Uint128::new(1)).unwrap_err();
+        assert!(matches!(err, ContractError::WrongPaymentAmount {}));
+        config.strict_payment = true;
+        let err = validate_payment(&config, Uint128::new(2), Uint128::new(1)).unwrap_err();
+        assert!(matches!(err, ContractError::WrongPaymentAmount {}));
+
+        let buyer = Addr::unchecked("buyer");
+        let env = mock_env();
+        assert_eq!(
+            assign_token_id(&mut deps.storage, &env, &mut config, &buyer).unwrap(),
+            0
+        );
+        assert_eq!(
+            assign_token_id(&mut deps.storage, &env, &mut config, &buyer).unwrap(),
+            1
+        );
+        assert_eq!(config.unused_token_id, 2);
+
+        let mint = build_mint_msg(&config, 1, &buyer, None).unwrap();
+        assert_eq!(
+            mint,
+            cw721_mint_msg(
+                &config,
+                String::from("1"),
+                &buyer,
+                String::from("https://ipfs.io/ipfs/Q/1.json"),
+                None,
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn nft_details_merges_child_info() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, .. } if contract_addr == NFT_CONTRACT_ADDR => {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&AllNftInfoResponse::<Option<Metadata>> {
+                        access: cw721::OwnerOfResponse {
+                            owner: String::from("holder"),
+                            approvals: vec![],
+                        },
+                        info: cw721::NftInfoResponse {
+                            token_uri: Some(String::from("https://ipfs.io/ipfs/Q")),
+                            extension: None,
+                        },
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => cw20_token_info(query),
+        });
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1),
+            msg: to_binary(&Cw20HookMsg::Mint(MintOptions::default())).unwrap(),
+        });
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            receive,
+        )
+        .unwrap();
+
+        let details: NftDetailsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::NftDetails {
+                    token_id: String::from("0"),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(details.owner, "holder");
+        assert_eq!(details.provenance.buyer, "buyer");
+        assert_eq!(details.receipt.unwrap().payer, "buyer");
+        assert_eq!(details.token_uri.as_deref(), Some("https://ipfs.io/ipfs/Q"));
+
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::NftDetails {
+                token_id: String::from("1"),
+            },
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn cw4_gated_minting() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 2;
+        msg.mint_gate = Some(MintGateMsg::Cw4 {
+            group: String::from("dao"),
+            min_weight: Some(5),
+        });
+        setup_contract(deps.as_mut(), msg);
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "dao" => {
+                let weight = match from_binary(msg).unwrap() {
+                    Cw4QueryMsg::Member { addr, .. } if addr == "member" => Some(5),
+                    Cw4QueryMsg::Member { addr, .. } if addr == "light" => Some(4),
+                    Cw4QueryMsg::Member { .. } => None,
+                };
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&Cw4MemberResponse { weight }).unwrap(),
+                ))
+            }
+            _ => SystemResult::Err(SystemError::Unknown {}),
+        });
+
+        let receive = |sender: &str| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(1),
+                msg: Binary::default(),
+            })
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        for outsider in ["stranger", "light"] {
+            let err =
+                execute(deps.as_mut(), mock_env(), info.clone(), receive(outsider)).unwrap_err();
+            assert!(matches!(err, ContractError::NotEligible {}));
+        }
+        execute(deps.as_mut(), mock_env(), info, receive("member")).unwrap();
+    }
+
+    #[test]
+    fn mints_grant_membership_weight() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 3;
+        msg.membership = Some(MembershipMsg {
+            group: String::from("dao"),
+            weight_per_mint: 10,
+        });
+        setup_contract(deps.as_mut(), msg);
+
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(2),
+            msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                quantity: Some(2),
+                ..MintOptions::default()
+            }))
+            .unwrap(),
+        });
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        execute(deps.as_mut(), mock_env(), info, receive).unwrap();
+
+        let confirmed = Reply {
+            id: MINT_REPLY_ID + 1,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        };
+        let res = reply(deps.as_mut(), mock_env(), confirmed).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("dao"),
+                msg: to_binary(&Cw4GroupExecuteMsg::UpdateMembers {
+                    remove: vec![],
+                    add: vec![Cw4Member {
+                        addr: String::from("buyer"),
+                        weight: 20,
+                    }],
+                })
+                .unwrap(),
+                funds: vec![],
+            })]
+        );
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::SetMembership {
+                membership: Some(MembershipMsg {
+                    group: String::from("dao"),
+                    weight_per_mint: 0,
+                }),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMembership {}));
     }
 
     #[test]
-    fn initialization() {
+    fn withdraw_swaps_through_router() {
         let mut deps = mock_dependencies();
-        let msg = InstantiateMsg {
-            owner: Addr::unchecked("owner"),
-            max_tokens: 1,
-            unit_price: Uint128::new(1),
-            name: String::from("FirstFT"),
-            symbol: String::from("FFT"),
-            token_code_id: 10u64,
-            cw20_address: Addr::unchecked(MOCK_CONTRACT_ADDR),
-            token_uri: String::from("https://ipfs.io/ipfs/Q"),
-            extension: None,
+        let mut msg = default_instantiate_msg();
+        msg.treasury = Some(String::from("treasury"));
+        setup_contract(deps.as_mut(), msg);
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, .. } if contract_addr == "router" => {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&SimulateSwapOperationsResponse {
+                        amount: Uint128::new(200),
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => cw20_with_balance(7)(query),
+        });
+        let owner = mock_info("owner", &[]);
+        let route = |max_slippage_bps| ExecuteMsg::SetSwapRoute {
+            route: Some(SwapRouteMsg {
+                router: String::from("router"),
+                ask: Currency::Native(String::from("usdc")),
+                max_slippage_bps,
+            }),
         };
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), route(10_001)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSwapRoute {}));
+        execute(deps.as_mut(), mock_env(), owner.clone(), route(100)).unwrap();
 
-        let info = mock_info("owner", &[]);
-        let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        let withdraw = ExecuteMsg::Withdraw {
+            amount: Some(Uint128::new(7)),
+            recipient: None,
+            token: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner, withdraw).unwrap();
+        let swap = SwapRouterMsg::ExecuteSwapOperations {
+            operations: vec![SwapOperation::OraiSwap {
+                offer_asset_info: Currency::Cw20(Addr::unchecked(MOCK_CONTRACT_ADDR)).into(),
+                ask_asset_info: Currency::Native(String::from("usdc")).into(),
+            }],
+            minimum_receive: Some(Uint128::new(198)),
+            to: Some(String::from("treasury")),
+        };
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Send {
+                    contract: String::from("router"),
+                    amount: Uint128::new(7),
+                    msg: to_binary(&swap).unwrap(),
+                })
+                .unwrap(),
+                funds: vec![],
+            })]
+        );
+    }
 
-        instantiate(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
+    #[test]
+    fn ai_oracle_generates_token_uri() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.ai_oracle = Some(AiOracleMsg {
+            contract: String::from("aioracle"),
+            service: String::from("nft_art"),
+            threshold: 1,
+        });
+        setup_contract(deps.as_mut(), msg);
 
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1),
+            msg: to_binary(&Cw20HookMsg::Mint(MintOptions::default())).unwrap(),
+        });
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            receive,
+        )
+        .unwrap();
+        let confirmed = Reply {
+            id: MINT_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        };
+        let res = reply(deps.as_mut(), mock_env(), confirmed).unwrap();
         assert_eq!(
             res.messages,
-            vec![SubMsg {
-                msg: WasmMsg::Instantiate {
-                    code_id: msg.token_code_id,
-                    msg: to_binary(&Cw721InstantiateMsg {
-                        name: msg.name.clone(),
-                        symbol: msg.symbol.clone(),
-                        minter: MOCK_CONTRACT_ADDR.to_string(),
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("aioracle"),
+                msg: to_binary(&AiOracleExecuteMsg::Request {
+                    service: String::from("nft_art"),
+                    input: Some(String::from("0")),
+                    threshold: 1,
+                })
+                .unwrap(),
+                funds: vec![],
+            })]
+        );
+
+        let status = |deps: Deps| -> AiMetadataResponse {
+            let msg = QueryMsg::AiMetadata {
+                token_id: String::from("0"),
+            };
+            from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap()
+        };
+        assert_eq!(
+            status(deps.as_ref()).status,
+            Some(AiMetadataStatus::Pending)
+        );
+
+        let callback = ExecuteMsg::AiOracleCallback {
+            token_id: String::from("0"),
+            token_uri: String::from("ipfs://generated/0.json"),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            callback.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let oracle = mock_info("aioracle", &[]);
+        let res = execute(deps.as_mut(), mock_env(), oracle.clone(), callback.clone()).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        let completed = status(deps.as_ref());
+        assert_eq!(completed.status, Some(AiMetadataStatus::Completed));
+        assert_eq!(
+            completed.token_uri.as_deref(),
+            Some("ipfs://generated/0.json")
+        );
+
+        let err = execute(deps.as_mut(), mock_env(), oracle, callback).unwrap_err();
+        assert!(matches!(err, ContractError::AiMetadataNotPending {}));
+    }
+
+    #[test]
+    fn holder_snapshot_pages_through_child_tokens() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == NFT_CONTRACT_ADDR => {
+                let tokens = ["0", "1", "2"];
+                let res = match from_binary(msg).unwrap() {
+                    Cw721QueryMsg::AllTokens { start_after, limit } => to_binary(&TokensResponse {
+                        tokens: tokens
+                            .iter()
+                            .filter(|id| start_after.as_deref().is_none_or(|after| **id > after))
+                            .take(limit.unwrap() as usize)
+                            .map(|id| id.to_string())
+                            .collect(),
+                    }),
+                    Cw721QueryMsg::OwnerOf { token_id, .. } => to_binary(&cw721::OwnerOfResponse {
+                        owner: String::from(if token_id == "1" { "bob" } else { "alice" }),
+                        approvals: vec![],
+                    }),
+                    _ => panic!("unexpected query"),
+                };
+                SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+            }
+            _ => cw20_token_info(query),
+        });
+        let snapshot = |restart| ExecuteMsg::SnapshotHolders {
+            restart,
+            limit: Some(2),
+        };
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("buyer", &[]),
+            snapshot(false),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            snapshot(false),
+        )
+        .unwrap();
+        let res: HolderSnapshotResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::HolderSnapshot {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.height, Some(mock_env().block.height));
+        assert!(!res.complete);
+        assert_eq!(res.holders.len(), 2);
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            snapshot(false),
+        )
+        .unwrap();
+        let res: HolderSnapshotResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::HolderSnapshot {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(res.complete);
+        assert_eq!(
+            res.holders,
+            vec![
+                SnapshotHolder {
+                    address: Addr::unchecked("alice"),
+                    tokens: 2,
+                },
+                SnapshotHolder {
+                    address: Addr::unchecked("bob"),
+                    tokens: 1,
+                },
+            ]
+        );
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            snapshot(false),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SnapshotComplete {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            snapshot(true),
+        )
+        .unwrap();
+        let res: HolderSnapshotResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::HolderSnapshot {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(!res.complete);
+        assert_eq!(res.holders[0].tokens, 1);
+    }
+
+    #[test]
+    fn sweep_stuck_funds() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(50, "uatom"));
+        let sweep = |asset| ExecuteMsg::SweepFunds {
+            asset,
+            recipient: String::from("rescuer"),
+        };
+        let atom = Currency::Native(String::from("uatom"));
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("buyer", &[]),
+            sweep(atom.clone()),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let payment = Currency::Cw20(Addr::unchecked(MOCK_CONTRACT_ADDR));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            sweep(payment),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotSweepable {}));
+        let osmo = Currency::Native(String::from("uosmo"));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            sweep(osmo),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NothingToWithdraw {}));
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            sweep(atom),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("rescuer"),
+                amount: coins(50, "uatom"),
+            })
+        );
+    }
+
+    #[test]
+    fn clone_collection_starts_new_round() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, .. } if contract_addr == NFT_CONTRACT_ADDR => {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&AllNftInfoResponse::<Option<Metadata>> {
+                        access: cw721::OwnerOfResponse {
+                            owner: String::from("buyer"),
+                            approvals: vec![],
+                        },
+                        info: cw721::NftInfoResponse {
+                            token_uri: None,
+                            extension: None,
+                        },
                     })
                     .unwrap(),
-                    funds: vec![],
-                    admin: None,
-                    label: String::from("Instantiate fixed price NFT contract"),
-                }
-                .into(),
-                id: INSTANTIATE_TOKEN_REPLY_ID,
-                gas_limit: None,
-                reply_on: ReplyOn::Success,
-            }]
-        );
+                ))
+            }
+            _ => cw20_token_info(query),
+        });
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(1),
+            msg: to_binary(&Cw20HookMsg::Mint(MintOptions::default())).unwrap(),
+        });
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        execute(deps.as_mut(), mock_env(), info.clone(), receive.clone()).unwrap();
 
-        let instantiate_reply = MsgInstantiateContractResponse {
-            contract_address: "nftcontract".to_string(),
-            data: vec![2u8; 32769],
+        let clone = ExecuteMsg::CloneCollection {
+            max_tokens: 2,
+            unit_price: Some(Uint128::new(5)),
+            token_uri: None,
         };
-        let mut encoded_instantiate_reply =
-            Vec::<u8>::with_capacity(instantiate_reply.encoded_len());
-        instantiate_reply
-            .encode(&mut encoded_instantiate_reply)
-            .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("buyer", &[]),
+            clone.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), clone).unwrap();
+        assert_eq!(res.messages[0].id, INSTANTIATE_TOKEN_REPLY_ID);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), receive).unwrap_err();
+        assert!(matches!(err, ContractError::Uninitialized {}));
 
+        let instantiate_reply = MsgInstantiateContractResponse {
+            contract_address: String::from("nftround1"),
+            data: vec![],
+        };
         let reply_msg = Reply {
             id: INSTANTIATE_TOKEN_REPLY_ID,
             result: SubMsgResult::Ok(SubMsgResponse {
                 events: vec![],
-                data: Some(encoded_instantiate_reply.into()),
+                data: Some(instantiate_reply.encode_to_vec().into()),
             }),
         };
         reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.cw721_address, Some(Addr::unchecked("nftround1")));
+        assert_eq!(config.remaining_supply(), 2);
+        assert_eq!(config.unit_price, Uint128::new(5));
 
-        let query_msg = QueryMsg::GetConfig {};
-        let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
-        let config: Config = from_binary(&res).unwrap();
+        let res: ArchivedRoundsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::ArchivedRounds {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.rounds.len(), 1);
+        let round = &res.rounds[0].round;
+        assert_eq!(round.cw721_address, NFT_CONTRACT_ADDR);
+        assert_eq!((round.first_token, round.end_token), (0, 1));
+        assert_eq!(round.unit_price, Uint128::new(1));
+
+        let details: NftDetailsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::NftDetails {
+                    token_id: String::from("0"),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(details.owner, "buyer");
+    }
+
+    #[test]
+    fn buyer_token_uri_within_allowed_prefixes() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 2;
+        setup_contract(deps.as_mut(), msg);
+
+        let receive = |token_uri: &str| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from("buyer"),
+                amount: Uint128::new(1),
+                msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                    token_uri: Some(token_uri.to_string()),
+                    ..MintOptions::default()
+                }))
+                .unwrap(),
+            })
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            receive("ipfs://QmAllowed/alice.json"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::TokenUriNotAllowed {}));
+
+        let set = ExecuteMsg::SetAllowedUriPrefixes {
+            prefixes: vec![String::from("ipfs://QmAllowed/")],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), set).unwrap();
+        for token_uri in [
+            "ipfs://QmOther/alice.json",
+            "ipfs://QmAllowed/../QmOther/a.json",
+        ] {
+            let err =
+                execute(deps.as_mut(), mock_env(), info.clone(), receive(token_uri)).unwrap_err();
+            assert!(matches!(err, ContractError::TokenUriNotAllowed {}));
+        }
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            receive("ipfs://QmAllowed/alice.json"),
+        )
+        .unwrap();
+        let mint = Cw721ExecuteMsg::<Extension, Empty>::Mint(MintMsg {
+            token_id: String::from("0"),
+            owner: String::from("buyer"),
+            token_uri: Some(String::from("ipfs://QmAllowed/alice.json")),
+            extension: None,
+        });
         assert_eq!(
-            config,
-            Config {
-                owner: Addr::unchecked("owner"),
-                cw20_address: msg.cw20_address,
-                cw721_address: Some(Addr::unchecked(NFT_CONTRACT_ADDR)),
-                max_tokens: msg.max_tokens,
-                unit_price: msg.unit_price,
-                name: msg.name,
-                symbol: msg.symbol,
-                token_uri: msg.token_uri,
-                extension: None,
-                unused_token_id: 0
-            }
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: NFT_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&mint).unwrap(),
+                funds: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn mint_credits_redeem_for_free_tokens() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 5;
+        setup_contract(deps.as_mut(), msg);
+
+        let grant = ExecuteMsg::GrantCredits {
+            address: String::from("partner"),
+            amount: 2,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("partner", &[]),
+            grant.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), grant).unwrap();
+
+        let redeem = |quantity| ExecuteMsg::RedeemCredits { quantity };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("partner", &[]),
+            redeem(3),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientCredits {}));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("partner", &[]),
+            redeem(1),
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 1);
+        let record = mints().load(&deps.storage, 0).unwrap();
+        assert_eq!(record.buyer, "partner");
+        assert!(record.price.is_zero());
+
+        let credits = |deps: Deps| -> u32 {
+            let res: MintCreditsResponse = from_binary(
+                &query(
+                    deps,
+                    mock_env(),
+                    QueryMsg::MintCredits {
+                        address: String::from("partner"),
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            res.credits
+        };
+        assert_eq!(credits(deps.as_ref()), 1);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("partner", &[]),
+            redeem(1),
+        )
+        .unwrap();
+        assert_eq!(credits(deps.as_ref()), 0);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("partner", &[]),
+            redeem(1),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientCredits {}));
+    }
+
+    #[test]
+    fn deposit_then_mint_from_balance() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 5;
+        msg.unit_price = Uint128::new(10);
+        setup_contract(deps.as_mut(), msg);
+
+        let deposit = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(25),
+            msg: to_binary(&Cw20HookMsg::Deposit {}).unwrap(),
+        });
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            deposit,
+        )
+        .unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(
+            CONFIG.load(&deps.storage).unwrap().deposit_escrow,
+            Uint128::new(25)
+        );
+
+        let mint = |quantity| ExecuteMsg::MintFromBalance { quantity };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("buyer", &[]), mint(3)).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientDeposit {}));
+        let res = execute(deps.as_mut(), mock_env(), mock_info("buyer", &[]), mint(2)).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(mints().load(&deps.storage, 1).unwrap().buyer, "buyer");
+
+        let balance = |deps: Deps| -> Uint128 {
+            let res: DepositResponse = from_binary(
+                &query(
+                    deps,
+                    mock_env(),
+                    QueryMsg::Deposit {
+                        address: String::from("buyer"),
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            res.balance
+        };
+        assert_eq!(balance(deps.as_ref()), Uint128::new(5));
+
+        let refund = ExecuteMsg::RefundDeposit { amount: None };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("buyer", &[]), refund).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            cw20_transfer_msg(
+                &Addr::unchecked(MOCK_CONTRACT_ADDR),
+                "buyer",
+                Uint128::new(5)
+            )
+            .unwrap()
+        );
+        assert_eq!(balance(deps.as_ref()), Uint128::zero());
+        assert!(CONFIG.load(&deps.storage).unwrap().deposit_escrow.is_zero());
+    }
+
+    #[test]
+    fn withdraw_leaves_auction_bids_escrowed() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        let create = ExecuteMsg::CreateAuction {
+            token_id: String::from("one-of-one"),
+            token_uri: String::from("https://ipfs.io/ipfs/one"),
+            reserve_price: Uint128::new(10),
+            end_time: mock_env().block.time.plus_seconds(100),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), create).unwrap();
+        let bid = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("alice"),
+            amount: Uint128::new(15),
+            msg: to_binary(&Cw20HookMsg::Bid {
+                token_id: String::from("one-of-one"),
+            })
+            .unwrap(),
+        });
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            bid,
+        )
+        .unwrap();
+        deps.querier.update_wasm(cw20_with_balance(20));
+
+        let withdraw = |amount| ExecuteMsg::Withdraw {
+            amount: Some(Uint128::new(amount)),
+            recipient: None,
+            token: None,
+        };
+        let owner = mock_info("owner", &[]);
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(15)).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToWithdraw {}));
+        let res = execute(deps.as_mut(), mock_env(), owner, withdraw(5)).unwrap();
+        assert!(res.attributes.contains(&attr("amount", "5")));
+    }
+
+    #[test]
+    fn withdraw_leaves_raffle_tickets_escrowed() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 3;
+        setup_contract(deps.as_mut(), msg);
+        let start = ExecuteMsg::StartRaffle {
+            end_time: mock_env().block.time.plus_seconds(100),
+            ticket_price: Uint128::new(10),
+            winners: 1,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), start).unwrap();
+        let buy = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("alice"),
+            amount: Uint128::new(50),
+            msg: to_binary(&Cw20HookMsg::BuyTickets { quantity: 5 }).unwrap(),
+        });
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            buy,
+        )
+        .unwrap();
+        deps.querier.update_wasm(cw20_with_balance(60));
+
+        let withdraw = |amount| ExecuteMsg::Withdraw {
+            amount: Some(Uint128::new(amount)),
+            recipient: None,
+            token: None,
+        };
+        let owner = mock_info("owner", &[]);
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(11)).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToWithdraw {}));
+        execute(deps.as_mut(), mock_env(), owner, withdraw(10)).unwrap();
+    }
+
+    #[test]
+    fn withdraw_leaves_staking_pool_funded() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        let set_rewards = ExecuteMsg::SetStakingRewards {
+            reward_token: String::from(MOCK_CONTRACT_ADDR),
+            reward_per_block: Uint128::new(5),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            set_rewards,
+        )
+        .unwrap();
+        let fund = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("owner"),
+            amount: Uint128::new(40),
+            msg: to_binary(&Cw20HookMsg::FundStakingRewards {}).unwrap(),
+        });
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            fund,
+        )
+        .unwrap();
+        deps.querier.update_wasm(cw20_with_balance(50));
+
+        let withdraw = |amount| ExecuteMsg::Withdraw {
+            amount: Some(Uint128::new(amount)),
+            recipient: None,
+            token: None,
+        };
+        let owner = mock_info("owner", &[]);
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(11)).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToWithdraw {}));
+        execute(deps.as_mut(), mock_env(), owner, withdraw(10)).unwrap();
+    }
+
+    #[test]
+    fn withdraw_leaves_reveal_escrow_held() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.placeholder_uri = Some(String::from("ipfs://placeholder.json"));
+        msg.reveal_escrow = Some(true);
+        msg.max_tokens = 2;
+        setup_contract(deps.as_mut(), msg);
+        let mint = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(2),
+            msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                quantity: Some(2),
+                ..MintOptions::default()
+            }))
+            .unwrap(),
+        });
+        let cw20 = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        execute(deps.as_mut(), mock_env(), cw20, mint).unwrap();
+        deps.querier.update_wasm(cw20_with_balance(5));
+
+        let withdraw = |amount| ExecuteMsg::Withdraw {
+            amount: Some(Uint128::new(amount)),
+            recipient: None,
+            token: None,
+        };
+        let owner = mock_info("owner", &[]);
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(4)).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToWithdraw {}));
+        execute(deps.as_mut(), mock_env(), owner, withdraw(3)).unwrap();
+    }
+
+    #[test]
+    fn failed_crowdfund_mint_drops_its_pledge() {
+        let mut deps = mock_dependencies();
+        let now = mock_env().block.time;
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 5;
+        msg.crowdfund = Some(Crowdfund {
+            soft_cap: 2,
+            deadline: now.plus_seconds(100),
+        });
+        setup_contract(deps.as_mut(), msg);
+        let mint = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("alice"),
+            amount: Uint128::new(2),
+            msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                quantity: Some(2),
+                ..MintOptions::default()
+            }))
+            .unwrap(),
+        });
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            mint,
+        )
+        .unwrap();
+
+        let failed = Reply {
+            id: MINT_REPLY_ID,
+            result: SubMsgResult::Err(String::from("token_id already claimed")),
+        };
+        let res = reply(deps.as_mut(), mock_env(), failed).unwrap();
+        assert!(res.attributes.contains(&attr("refund", "1")));
+        let alice = Addr::unchecked("alice");
+        let pledge = PLEDGES.load(&deps.storage, &alice).unwrap();
+        assert_eq!(pledge.token_ids, vec![1]);
+        assert_eq!(pledge.paid, Uint128::one());
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.crowdfund_sold, 1);
+        assert_eq!(config.unique_minters, 1);
+        assert_eq!(MINTS_PER_ADDRESS.load(&deps.storage, &alice).unwrap(), 1);
+        assert!(!mints().has(&deps.storage, 0));
+        assert!(mints().has(&deps.storage, 1));
+
+        // Only the token that was minted is burned and refunded.
+        let mut late = mock_env();
+        late.block.time = now.plus_seconds(100);
+        let res = execute(
+            deps.as_mut(),
+            late,
+            mock_info("alice", &[]),
+            ExecuteMsg::ClaimPledge {},
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert!(res.attributes.contains(&attr("refund", "1")));
+        assert!(!PLEDGES.has(&deps.storage, &alice));
+    }
+
+    #[test]
+    fn failed_mint_releases_reveal_escrow() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 2;
+        msg.placeholder_uri = Some(String::from("ipfs://placeholder.json"));
+        msg.reveal_escrow = Some(true);
+        msg.renewal = Some(Renewal {
+            price: Uint128::new(5),
+            period: 100,
+            revocable: false,
+        });
+        setup_contract(deps.as_mut(), msg);
+        let mint = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("alice"),
+            amount: Uint128::new(2),
+            msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                quantity: Some(2),
+                ..MintOptions::default()
+            }))
+            .unwrap(),
+        });
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            mint,
+        )
+        .unwrap();
+        let held = |deps: Deps| {
+            ESCROW_HELD
+                .load(deps.storage, MOCK_CONTRACT_ADDR)
+                .unwrap_or_default()
+        };
+        assert_eq!(held(deps.as_ref()), Uint128::new(2));
+
+        let failed = Reply {
+            id: MINT_REPLY_ID + 1,
+            result: SubMsgResult::Err(String::from("token_id already claimed")),
+        };
+        let res = reply(deps.as_mut(), mock_env(), failed).unwrap();
+        assert!(res.attributes.contains(&attr("refund", "1")));
+        assert!(!ESCROWED_PAYMENTS.has(&deps.storage, 1));
+        assert!(!TOKEN_EXPIRY.has(&deps.storage, 1));
+        assert!(!RECEIPTS.has(&deps.storage, 1));
+        assert_eq!(held(deps.as_ref()), Uint128::one());
+        // The token that went through stays escrowed until reveal.
+        assert!(ESCROWED_PAYMENTS.has(&deps.storage, 0));
+        assert!(TOKEN_EXPIRY.has(&deps.storage, 0));
+        let alice = Addr::unchecked("alice");
+        assert_eq!(MINTS_PER_ADDRESS.load(&deps.storage, &alice).unwrap(), 1);
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().unique_minters, 1);
+    }
+
+    #[test]
+    fn reduce_supply_keeps_reserved_tokens() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 3;
+        setup_contract(deps.as_mut(), msg);
+        let start = ExecuteMsg::StartRaffle {
+            end_time: mock_env().block.time.plus_seconds(100),
+            ticket_price: Uint128::new(10),
+            winners: 2,
+        };
+        let owner = mock_info("owner", &[]);
+        execute(deps.as_mut(), mock_env(), owner.clone(), start).unwrap();
+
+        let reduce = |new_max| ExecuteMsg::ReduceSupply {
+            new_max,
+            freeze: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), reduce(1)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMaxTokens {}));
+        execute(deps.as_mut(), mock_env(), owner, reduce(2)).unwrap();
+        let res: SupplyInfoResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::SupplyInfo {}).unwrap())
+                .unwrap();
+        assert_eq!(res.remaining, 0);
+        assert!(res.sold_out);
+    }
+
+    #[test]
+    fn partial_fill_mints_what_is_left() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 2;
+        msg.unit_price = Uint128::new(10);
+        setup_contract(deps.as_mut(), msg);
+        let mint = |amount, quantity, allow_partial| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from("buyer"),
+                amount: Uint128::new(amount),
+                msg: to_binary(&Cw20HookMsg::Mint(MintOptions {
+                    quantity: Some(quantity),
+                    allow_partial,
+                    ..MintOptions::default()
+                }))
+                .unwrap(),
+            })
+        };
+        let cw20 = mock_info(MOCK_CONTRACT_ADDR, &[]);
+
+        let err = execute(deps.as_mut(), mock_env(), cw20.clone(), mint(30, 3, None)).unwrap_err();
+        assert!(matches!(err, ContractError::SoldOut {}));
+        // The two tokens left still have to be paid in full.
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            cw20.clone(),
+            mint(19, 3, Some(true)),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::WrongPaymentAmount {}));
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            cw20.clone(),
+            mint(30, 3, Some(true)),
+        )
+        .unwrap();
+        assert!(res.attributes.contains(&attr("partial_fill", "2")));
+        assert!(res.attributes.contains(&attr("refund", "10")));
+        assert_eq!(
+            res.messages.last().unwrap().msg,
+            cw20_transfer_msg(
+                &Addr::unchecked(MOCK_CONTRACT_ADDR),
+                "buyer",
+                Uint128::new(10)
+            )
+            .unwrap()
         );
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().remaining_supply(), 0);
+
+        let err = execute(deps.as_mut(), mock_env(), cw20, mint(10, 1, Some(true))).unwrap_err();
+        assert!(matches!(err, ContractError::SoldOut {}));
+    }
+
+    #[test]
+    fn nonce_used_queries() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        let user = Addr::unchecked("user");
+        USED_INTENTS
+            .save(&mut deps.storage, (&user, 3), &Empty {})
+            .unwrap();
+        USED_PERMITS
+            .save(&mut deps.storage, (&user, 4), &Empty {})
+            .unwrap();
+
+        let used = |deps: Deps, msg| -> bool {
+            let res: NonceUsedResponse =
+                from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.used
+        };
+        let intent = |nonce| QueryMsg::IntentNonceUsed {
+            address: String::from("user"),
+            nonce,
+        };
+        let permit = |nonce| QueryMsg::PermitNonceUsed {
+            address: String::from("user"),
+            nonce,
+        };
+        assert!(used(deps.as_ref(), intent(3)));
+        assert!(!used(deps.as_ref(), intent(4)));
+        assert!(used(deps.as_ref(), permit(4)));
+        assert!(!used(deps.as_ref(), permit(3)));
+
+        let invalid = QueryMsg::IntentNonceUsed {
+            address: String::new(),
+            nonce: 3,
+        };
+        query(deps.as_ref(), mock_env(), invalid).unwrap_err();
+    }
+
+    #[test]
+    fn schedule_counts_down_between_phases() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut(), default_instantiate_msg());
+        let now = mock_env().block.time;
+        let schedule = |deps: Deps, env| -> ScheduleResponse {
+            from_binary(&query(deps, env, QueryMsg::Schedule {}).unwrap()).unwrap()
+        };
+
+        let res = schedule(deps.as_ref(), mock_env());
+        assert!(res.current.is_none() && res.next.is_none());
+        assert_eq!(res.seconds_until_next, None);
+
+        let phase = |start: u64, end: u64| Phase {
+            name: format!("phase-{start}"),
+            start_time: now.plus_seconds(start),
+            end_time: Some(now.plus_seconds(end)),
+            price: None,
+            per_wallet_cap: None,
+            allowlist_only: false,
+            free_supply: None,
+        };
+        let owner = mock_info("owner", &[]);
+        for (id, phase) in [(0, phase(100, 200)), (1, phase(300, 400))] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                owner.clone(),
+                ExecuteMsg::SetPhase { id, phase },
+            )
+            .unwrap();
+        }
+
+        // Before the first phase only the next one is reported.
+        let res = schedule(deps.as_ref(), mock_env());
+        assert!(res.current.is_none());
+        assert_eq!(res.next.unwrap().id, 0);
+        assert_eq!(res.seconds_until_next, Some(100));
+        assert_eq!(res.seconds_until_end, None);
+
+        // Between phases, and after the last one.
+        let mut env = mock_env();
+        env.block.time = now.plus_seconds(250);
+        let res = schedule(deps.as_ref(), env.clone());
+        assert!(res.current.is_none());
+        assert_eq!(res.next.unwrap().id, 1);
+        assert_eq!(res.seconds_until_next, Some(50));
+        env.block.time = now.plus_seconds(400);
+        let res = schedule(deps.as_ref(), env);
+        assert!(res.current.is_none() && res.next.is_none());
+    }
+
+    #[test]
+    fn allowlist_entries_lapse_at_expiry() {
+        let mut deps = mock_dependencies();
+        let now = mock_env().block.time;
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 10;
+        msg.presale = Some(Presale {
+            end_time: now.plus_seconds(60),
+            price: None,
+        });
+        setup_contract(deps.as_mut(), msg);
+        let add = |expires| ExecuteMsg::AddToAllowlist {
+            entries: vec![AllowlistEntryMsg {
+                address: String::from("friend"),
+                price: None,
+                expires,
+            }],
+        };
+        let owner = mock_info("owner", &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            add(Some(now.plus_seconds(30))),
+        )
+        .unwrap();
+
+        let mint = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("friend"),
+            amount: Uint128::one(),
+            msg: Binary::default(),
+        });
+        let status = |deps: Deps, env| -> AllowlistStatusResponse {
+            let msg = QueryMsg::AllowlistStatus {
+                address: String::from("friend"),
+            };
+            from_binary(&query(deps, env, msg).unwrap()).unwrap()
+        };
+        let cw20 = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let mut env = mock_env();
+        env.block.time = now.plus_seconds(29);
+        assert!(status(deps.as_ref(), env.clone()).allowlisted);
+        execute(deps.as_mut(), env.clone(), cw20.clone(), mint.clone()).unwrap();
+
+        env.block.time = now.plus_seconds(30);
+        assert!(!status(deps.as_ref(), env.clone()).allowlisted);
+        let err = execute(deps.as_mut(), env.clone(), cw20.clone(), mint.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::NotAllowlisted {}));
+
+        // Re-adding the address without an expiry lets it mint again.
+        execute(deps.as_mut(), env.clone(), owner, add(None)).unwrap();
+        let res = status(deps.as_ref(), env.clone());
+        assert!(res.allowlisted);
+        assert_eq!(res.expires, None);
+        execute(deps.as_mut(), env, cw20, mint).unwrap();
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn provenance_records_mint_height() {
+        let mut deps = mock_dependencies();
+        let mut msg = default_instantiate_msg();
+        msg.max_tokens = 2;
+        setup_contract(deps.as_mut(), msg);
+        let mut env = mock_env();
+        env.block.height = 4_242;
+        let mint = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::one(),
+            msg: Binary::default(),
+        });
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            mint,
+        )
+        .unwrap();
+
+        let provenance = |token_id: &str| {
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Provenance {
+                    token_id: token_id.to_string(),
+                },
+            )
+        };
+        let res: ProvenanceResponse = from_binary(&provenance("0").unwrap()).unwrap();
+        assert_eq!(res.buyer, "buyer");
+        assert_eq!(res.height, 4_242);
+        assert_eq!(res.time, env.block.time);
+        assert_eq!(res.price, Uint128::one());
+
+        // Not minted yet, and not a sale token id at all.
+        provenance("1").unwrap_err();
+        provenance("vip-1").unwrap_err();
+    }
+}
@@ -1,73 +0,0 @@
-/**
-* This file was automatically generated by @oraichain/ts-codegen@0.35.9.
-* DO NOT MODIFY IT BY HAND. Instead, modify the source JSONSchema file,
-* and run the @oraichain/ts-codegen generate command to regenerate this file.
-*/
-
-import { CosmWasmClient, SigningCosmWasmClient, ExecuteResult } from "@cosmjs/cosmwasm-stargate";
-import { Coin, StdFee } from "@cosmjs/amino";
-import {Addr, Uint128, InstantiateMsg, Empty, ExecuteMsg, Binary, Cw20ReceiveMsg, QueryMsg, ConfigResponse} from "./Moni.types";
-export interface MoniReadOnlyInterface {
-  contractAddress: string;
-  getConfig: () => Promise<GetConfigResponse>;
-}
-export class MoniQueryClient implements MoniReadOnlyInterface {
-  client: CosmWasmClient;
-  contractAddress: string;
-
-  constructor(client: CosmWasmClient, contractAddress: string) {
-    this.client = client;
-    this.contractAddress = contractAddress;
-    this.getConfig = this.getConfig.bind(this);
-  }
-
-  getConfig = async (): Promise<GetConfigResponse> => {
-    return this.client.queryContractSmart(this.contractAddress, {
-      get_config: {}
-    });
-  };
-}
-export interface MoniInterface extends MoniReadOnlyInterface {
-  contractAddress: string;
-  sender: string;
-  receive: ({
-    amount,
-    msg,
-    sender
-  }: {
-    amount: Uint128;
-    msg: Binary;
-    sender: string;
-  }, _fee?: number | StdFee | "auto", _memo?: string, _funds?: Coin[]) => Promise<ExecuteResult>;
-}
-export class MoniClient extends MoniQueryClient implements MoniInterface {
-  client: SigningCosmWasmClient;
-  sender: string;
-  contractAddress: string;
-
-  constructor(client: SigningCosmWasmClient, sender: string, contractAddress: string) {
-    super(client, contractAddress);
-    this.client = client;
-    this.sender = sender;
-    this.contractAddress = contractAddress;
-    this.receive = this.receive.bind(this);
-  }
-
-  receive = async ({
-    amount,
-    msg,
-    sender
-  }: {
-    amount: Uint128;
-    msg: Binary;
-    sender: string;
-  }, _fee: number | StdFee | "auto" = "auto", _memo?: string, _funds?: Coin[]): Promise<ExecuteResult> => {
-    return await this.client.execute(this.sender, this.contractAddress, {
-      receive: {
-        amount,
-        msg,
-        sender
-      }
-    }, _fee, _memo, _funds);
-  };
-}
\ No newline at end of file
@@ -1,2 +0,0 @@
-export * as MoniTypes from './Moni.types';
-export * from './Moni.client';
\ No newline at end of file
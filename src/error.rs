@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{OverflowError, StdError};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,12 +6,15 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
     #[error("Custom Error val: {val:?}")]
     CustomError { val: String },
-    
+
     #[error("InvalidUnitPrice")]
     InvalidUnitPrice {},
 
@@ -38,4 +41,496 @@ pub enum ContractError {
 
     #[error("Cw721NotLinked")]
     Cw721NotLinked {},
+
+    #[error("NoPendingOwner")]
+    NoPendingOwner {},
+
+    #[error("OwnershipProposalExpired")]
+    OwnershipProposalExpired {},
+
+    #[error("InvalidExpiration")]
+    InvalidExpiration {},
+
+    #[error("NothingToWithdraw")]
+    NothingToWithdraw {},
+
+    #[error("CannotMigrate from {previous_contract}")]
+    CannotMigrate { previous_contract: String },
+
+    #[error("CannotMigrateVersion from {previous_version}")]
+    CannotMigrateVersion { previous_version: String },
+
+    #[error("ContractPaused")]
+    ContractPaused {},
+
+    #[error("InvalidMintWindow")]
+    InvalidMintWindow {},
+
+    #[error("MintNotStarted")]
+    MintNotStarted {},
+
+    #[error("MintEnded")]
+    MintEnded {},
+
+    #[error("InvalidQuantity")]
+    InvalidQuantity {},
+
+    #[error("InvalidReservedSupply")]
+    InvalidReservedSupply {},
+
+    #[error("ReservedSupplyExceeded")]
+    ReservedSupplyExceeded {},
+
+    #[error("TooManyRecipients (max {max})")]
+    TooManyRecipients { max: usize },
+
+    #[error("InvalidCw721")]
+    InvalidCw721 {},
+
+    #[error("InvalidHookMsg")]
+    InvalidHookMsg {},
+
+    #[error("MintLimitReached")]
+    MintLimitReached {},
+
+    #[error("InvalidRoyalty")]
+    InvalidRoyalty {},
+
+    #[error("InvalidPriceTiers")]
+    InvalidPriceTiers {},
+
+    #[error("InvalidPresale")]
+    InvalidPresale {},
+
+    #[error("NotAllowlisted")]
+    NotAllowlisted {},
+
+    #[error("InvalidReferralBps")]
+    InvalidReferralBps {},
+
+    #[error("SelfReferral")]
+    SelfReferral {},
+
+    #[error("InvalidPaymentSplits")]
+    InvalidPaymentSplits {},
+
+    #[error("PaymentSplitsConfigured")]
+    PaymentSplitsConfigured {},
+
+    #[error("InvalidPhase")]
+    InvalidPhase {},
+
+    #[error("PhaseNotFound")]
+    PhaseNotFound {},
+
+    #[error("NoActivePhase")]
+    NoActivePhase {},
+
+    #[error("OnchainMetadataDisabled")]
+    OnchainMetadataDisabled {},
+
+    #[error("InvalidTokenId")]
+    InvalidTokenId {},
+
+    #[error("TokenAlreadyMinted")]
+    TokenAlreadyMinted {},
+
+    #[error("RevealNotConfigured")]
+    RevealNotConfigured {},
+
+    #[error("AlreadyRevealed")]
+    AlreadyRevealed {},
+
+    #[error("ConfigFrozen")]
+    ConfigFrozen {},
+
+    #[error("InvalidRefundBps")]
+    InvalidRefundBps {},
+
+    #[error("RefundDisabled")]
+    RefundDisabled {},
+
+    #[error("UpgradeDisabled")]
+    UpgradeDisabled {},
+
+    #[error("InvalidUpgradeRule")]
+    InvalidUpgradeRule {},
+
+    #[error("NothingDeposited")]
+    NothingDeposited {},
+
+    #[error("InvalidMarketFee")]
+    InvalidMarketFee {},
+
+    #[error("InvalidListingPrice")]
+    InvalidListingPrice {},
+
+    #[error("ListingNotFound")]
+    ListingNotFound {},
+
+    #[error("InvalidCrowdfund")]
+    InvalidCrowdfund {},
+
+    #[error("CrowdfundPending")]
+    CrowdfundPending {},
+
+    #[error("CrowdfundTargetNotMet")]
+    CrowdfundTargetNotMet {},
+
+    #[error("PaymentTokenNotAccepted")]
+    PaymentTokenNotAccepted {},
+
+    #[error("InvalidPriceOracle")]
+    InvalidPriceOracle {},
+
+    #[error("StalePrice")]
+    StalePrice {},
+
+    #[error("RecipientNotSupported")]
+    RecipientNotSupported {},
+
+    #[error("MemoTooLong")]
+    MemoTooLong {},
+
+    #[error("VouchersDisabled")]
+    VouchersDisabled {},
+
+    #[error("InvalidVoucherSignature")]
+    InvalidVoucherSignature {},
+
+    #[error("VoucherExpired")]
+    VoucherExpired {},
+
+    #[error("VoucherRedeemed")]
+    VoucherRedeemed {},
+
+    #[error("TooManyMintsPerTx")]
+    TooManyMintsPerTx {},
+
+    #[error("BlockMintLimitReached")]
+    BlockMintLimitReached {},
+
+    #[error("CollectionNotFound")]
+    CollectionNotFound {},
+
+    #[error("InvalidReplyOn")]
+    InvalidReplyOn {},
+
+    #[error("TokenRented")]
+    TokenRented {},
+
+    #[error("RentalNotFound")]
+    RentalNotFound {},
+
+    #[error("InvalidIbcChannel")]
+    InvalidIbcChannel {},
+
+    #[error("InvalidIbcVersion")]
+    InvalidIbcVersion {},
+
+    #[error("Ics721Disabled")]
+    Ics721Disabled {},
+
+    #[error("InvalidIcs721Bridge")]
+    InvalidIcs721Bridge {},
+
+    #[error("SaleClosed")]
+    SaleClosed {},
+
+    #[error("CustomMetadataDisabled")]
+    CustomMetadataDisabled {},
+
+    #[error("InvalidMetadata")]
+    InvalidMetadata {},
+
+    #[error("MintNotEnded")]
+    MintNotEnded {},
+
+    #[error("EditionFinalized")]
+    EditionFinalized {},
+
+    #[error("WaitlistEntryNotFound")]
+    WaitlistEntryNotFound {},
+
+    #[error("NativePaymentsDisabled")]
+    NativePaymentsDisabled {},
+
+    #[error("InvalidDenom")]
+    InvalidDenom {},
+
+    #[error("InvalidCreators")]
+    InvalidCreators {},
+
+    #[error("DuplicateAllowlistEntry")]
+    DuplicateAllowlistEntry {},
+
+    #[error("InvalidMerkleRoot")]
+    InvalidMerkleRoot {},
+
+    #[error("InvalidMerkleProof")]
+    InvalidMerkleProof {},
+
+    #[error("StaleMerkleProof")]
+    StaleMerkleProof {},
+
+    #[error("InvalidPartnerDiscount")]
+    InvalidPartnerDiscount {},
+
+    #[error("InvalidMintGate")]
+    InvalidMintGate {},
+
+    #[error("NotEligible")]
+    NotEligible {},
+
+    #[error("StakingDisabled")]
+    StakingDisabled {},
+
+    #[error("StakeNotFound")]
+    StakeNotFound {},
+
+    #[error("InvalidStakingConfig")]
+    InvalidStakingConfig {},
+
+    #[error("InvalidVesting")]
+    InvalidVesting {},
+
+    #[error("NotVested")]
+    NotVested {},
+
+    #[error("EscrowNotFound")]
+    EscrowNotFound {},
+
+    #[error("InvalidTokenIdTemplate")]
+    InvalidTokenIdTemplate {},
+
+    #[error("InvalidTraitTable")]
+    InvalidTraitTable {},
+
+    #[error("RandomMintUnsupported")]
+    RandomMintUnsupported {},
+
+    #[error("RandomnessPending")]
+    RandomnessPending {},
+
+    #[error("RandomnessJobNotFound")]
+    RandomnessJobNotFound {},
+
+    #[error("MintHookAlreadyRegistered")]
+    MintHookAlreadyRegistered {},
+
+    #[error("MintHookNotFound")]
+    MintHookNotFound {},
+
+    #[error("TooManyMintHooks")]
+    TooManyMintHooks {},
+
+    #[error("InvalidTokenUri")]
+    InvalidTokenUri {},
+
+    #[error("MintPassthroughNotAllowed")]
+    MintPassthroughNotAllowed {},
+
+    #[error("Retired")]
+    Retired {},
+
+    #[error("OwnershipRenounced")]
+    OwnershipRenounced {},
+
+    #[error("OperatorNotFound")]
+    OperatorNotFound {},
+
+    #[error("PaymentTokenTimelocked")]
+    PaymentTokenTimelocked {},
+
+    #[error("NoPendingPaymentToken")]
+    NoPendingPaymentToken {},
+
+    #[error("PaymentTokenInUse")]
+    PaymentTokenInUse {},
+
+    #[error("Blocklisted")]
+    Blocklisted {},
+
+    #[error("NoActiveClaim")]
+    NoActiveClaim {},
+
+    #[error("FreePhase")]
+    FreePhase {},
+
+    #[error("FreeSupplyExhausted")]
+    FreeSupplyExhausted {},
+
+    #[error("InvalidDrop")]
+    InvalidDrop {},
+
+    #[error("DropNotFound")]
+    DropNotFound {},
+
+    #[error("DropNotActive")]
+    DropNotActive {},
+
+    #[error("InvalidProtocolFee")]
+    InvalidProtocolFee {},
+
+    #[error("InvalidBurnBps")]
+    InvalidBurnBps {},
+
+    #[error("InvalidPaymentToken")]
+    InvalidPaymentToken {},
+
+    #[error("InvalidCommitRevealBlocks")]
+    InvalidCommitRevealBlocks {},
+
+    #[error("CommitRevealDisabled")]
+    CommitRevealDisabled {},
+
+    #[error("CommitmentRequired")]
+    CommitmentRequired {},
+
+    #[error("NoCommitment")]
+    NoCommitment {},
+
+    #[error("CommitmentNotMatured")]
+    CommitmentNotMatured {},
+
+    #[error("InvalidCommitment")]
+    InvalidCommitment {},
+
+    #[error("PermitRequired")]
+    PermitRequired {},
+
+    #[error("InvalidPermit")]
+    InvalidPermit {},
+
+    #[error("PermitExpired")]
+    PermitExpired {},
+
+    #[error("PermitUsed")]
+    PermitUsed {},
+
+    #[error("NoIntentKey")]
+    NoIntentKey {},
+
+    #[error("InvalidIntentSignature")]
+    InvalidIntentSignature {},
+
+    #[error("IntentUsed")]
+    IntentUsed {},
+
+    #[error("NoOwnerKey")]
+    NoOwnerKey {},
+
+    #[error("InvalidAdminSignature")]
+    InvalidAdminSignature {},
+
+    #[error("AdminPayloadExpired")]
+    AdminPayloadExpired {},
+
+    #[error("AdminNonceUsed")]
+    AdminNonceUsed {},
+
+    #[error("InvalidRenewal")]
+    InvalidRenewal {},
+
+    #[error("RenewalDisabled")]
+    RenewalDisabled {},
+
+    #[error("RenewalNotRevocable")]
+    RenewalNotRevocable {},
+
+    #[error("TokenRevoked")]
+    TokenRevoked {},
+
+    #[error("TokenNotExpired")]
+    TokenNotExpired {},
+
+    #[error("ConflictingCw721Variant")]
+    ConflictingCw721Variant {},
+
+    #[error("InvalidTradeIn")]
+    InvalidTradeIn {},
+
+    #[error("InvalidRaffle")]
+    InvalidRaffle {},
+
+    #[error("RaffleExists")]
+    RaffleExists {},
+
+    #[error("NoRaffle")]
+    NoRaffle {},
+
+    #[error("RaffleOpen")]
+    RaffleOpen {},
+
+    #[error("RaffleDrawn")]
+    RaffleDrawn {},
+
+    #[error("RaffleNotDrawn")]
+    RaffleNotDrawn {},
+
+    #[error("NoTickets")]
+    NoTickets {},
+
+    #[error("InvalidAuction")]
+    InvalidAuction {},
+
+    #[error("AuctionNotFound")]
+    AuctionNotFound {},
+
+    #[error("AuctionEnded")]
+    AuctionEnded {},
+
+    #[error("AuctionOpen")]
+    AuctionOpen {},
+
+    #[error("AuctionSettled")]
+    AuctionSettled {},
+
+    #[error("BidTooLow")]
+    BidTooLow {},
+
+    #[error("InvalidTokenPrice")]
+    InvalidTokenPrice {},
+
+    #[error("TokenPricesUnsupported")]
+    TokenPricesUnsupported {},
+
+    #[error("InvalidCollectionInfo")]
+    InvalidCollectionInfo {},
+
+    #[error("Cw721OwnershipUnsupported")]
+    Cw721OwnershipUnsupported {},
+
+    #[error("InvalidMembership")]
+    InvalidMembership {},
+
+    #[error("InvalidSwapRoute")]
+    InvalidSwapRoute {},
+
+    #[error("AiMetadataNotPending")]
+    AiMetadataNotPending {},
+
+    #[error("SnapshotComplete")]
+    SnapshotComplete {},
+
+    #[error("NotSweepable")]
+    NotSweepable {},
+
+    #[error("MintsPending")]
+    MintsPending {},
+
+    #[error("TokenUriNotAllowed")]
+    TokenUriNotAllowed {},
+
+    #[error("InsufficientCredits")]
+    InsufficientCredits {},
+
+    #[error("InsufficientDeposit")]
+    InsufficientDeposit {},
+
+    #[error("TokenInCustody")]
+    TokenInCustody {},
+
+    #[error("UnknownCodeId")]
+    UnknownCodeId {},
 }
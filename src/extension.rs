@@ -0,0 +1,117 @@
+//! Entry points for crates embedding this contract (with the `library` feature) whose child
+//! cw721 uses its own metadata extension. The contract itself tracks on-chain metadata as
+//! `Metadata`; these wrappers run the regular entry points and re-encode every mint sent to
+//! the linked cw721 with the embedder's extension type.
+
+use cosmwasm_std::{
+    from_binary, to_binary, Binary, CosmosMsg, Deps, DepsMut, Empty, Env, IbcPacketReceiveMsg,
+    IbcReceiveResponse, MessageInfo, Never, Reply, Response, StdResult, SubMsg, WasmMsg,
+};
+use cw721_base::{ExecuteMsg as Cw721ExecuteMsg, Extension, MintMsg};
+use serde::Serialize;
+
+use crate::ibc::ack_error;
+use crate::msg::{ExecuteMsg, SudoMsg};
+use crate::state::{Metadata, CONFIG};
+use crate::ContractError;
+
+/// Metadata extension minted into the linked cw721. `metadata` is what the contract would
+/// mint with `Option<Metadata>`: `None` unless `onchain_metadata` is set.
+pub trait MintExtension: Serialize {
+    fn from_metadata(metadata: Option<Metadata>) -> Self;
+}
+
+impl MintExtension for Option<Metadata> {
+    fn from_metadata(metadata: Option<Metadata>) -> Self {
+        metadata
+    }
+}
+
+impl MintExtension for Extension {
+    fn from_metadata(_metadata: Option<Metadata>) -> Self {
+        None
+    }
+}
+
+pub fn execute<E: MintExtension>(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    let mut res = crate::contract::execute(deps.branch(), env, info, msg)?;
+    convert_mint_msgs::<E>(deps.as_ref(), &mut res.messages)?;
+    Ok(res)
+}
+
+pub fn reply<E: MintExtension>(
+    mut deps: DepsMut,
+    env: Env,
+    msg: Reply,
+) -> Result<Response, ContractError> {
+    let mut res = crate::contract::reply(deps.branch(), env, msg)?;
+    convert_mint_msgs::<E>(deps.as_ref(), &mut res.messages)?;
+    Ok(res)
+}
+
+pub fn sudo<E: MintExtension>(
+    mut deps: DepsMut,
+    env: Env,
+    msg: SudoMsg,
+) -> Result<Response, ContractError> {
+    let mut res = crate::contract::sudo(deps.branch(), env, msg)?;
+    convert_mint_msgs::<E>(deps.as_ref(), &mut res.messages)?;
+    Ok(res)
+}
+
+/// Failed re-encodings are acknowledged as errors, like any other failed packet.
+pub fn ibc_packet_receive<E: MintExtension>(
+    mut deps: DepsMut,
+    env: Env,
+    msg: IbcPacketReceiveMsg,
+) -> Result<IbcReceiveResponse, Never> {
+    let mut res = crate::ibc::ibc_packet_receive(deps.branch(), env, msg)?;
+    if let Err(err) = convert_mint_msgs::<E>(deps.as_ref(), &mut res.messages) {
+        return Ok(IbcReceiveResponse::new()
+            .set_ack(ack_error(err.to_string()))
+            .add_attribute("action", "ibc_mint")
+            .add_attribute("error", err.to_string()));
+    }
+    Ok(res)
+}
+
+/// Rewrites the extension of every `Mint` in `msgs` addressed to the linked cw721.
+pub fn convert_mint_msgs<E: MintExtension>(
+    deps: Deps,
+    msgs: &mut [SubMsg],
+) -> Result<(), ContractError> {
+    let cw721 = match CONFIG.load(deps.storage)?.cw721_address {
+        Some(cw721) => cw721,
+        None => return Ok(()),
+    };
+    for sub_msg in msgs {
+        if let CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr, msg, ..
+        }) = &mut sub_msg.msg
+        {
+            if *contract_addr == cw721 {
+                convert_mint::<E>(msg)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn convert_mint<E: MintExtension>(msg: &mut Binary) -> StdResult<()> {
+    if let Ok(Cw721ExecuteMsg::Mint(mint)) =
+        from_binary::<Cw721ExecuteMsg<Option<Metadata>, Empty>>(msg)
+    {
+        *msg = to_binary(&Cw721ExecuteMsg::<E, Empty>::Mint(MintMsg {
+            token_id: mint.token_id,
+            owner: mint.owner,
+            token_uri: mint.token_uri,
+            extension: E::from_metadata(mint.extension),
+        }))?;
+    }
+    Ok(())
+}
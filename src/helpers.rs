@@ -0,0 +1,91 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    to_binary, Addr, CosmosMsg, CustomQuery, QuerierWrapper, StdResult, Uint128, WasmMsg,
+};
+use serde::de::DeserializeOwned;
+
+use crate::msg::{
+    ConfigResponse, CurrentPriceResponse, Cw20ExecuteMsg, Cw20HookMsg, ExecuteMsg, MintOptions,
+    MintsOfResponse, QueryMsg, SupplyInfoResponse,
+};
+
+/// Wrapper around a deployed fixed price contract that builds messages and runs typed
+/// queries for other contracts, in the spirit of cw721-base's `Cw721Contract`.
+#[cw_serde]
+pub struct FixedPriceContract(pub Addr);
+
+impl FixedPriceContract {
+    pub fn addr(&self) -> Addr {
+        self.0.clone()
+    }
+
+    pub fn call<T: Into<ExecuteMsg>>(&self, msg: T) -> StdResult<CosmosMsg> {
+        let msg = to_binary(&msg.into())?;
+        Ok(WasmMsg::Execute {
+            contract_addr: self.addr().into(),
+            msg,
+            funds: vec![],
+        }
+        .into())
+    }
+
+    /// Cw20 `Send` of `amount` of `payment_token` that mints through the receive hook.
+    /// The calling contract must hold the tokens.
+    pub fn mint_msg(
+        &self,
+        payment_token: impl Into<String>,
+        amount: Uint128,
+        options: MintOptions,
+    ) -> StdResult<CosmosMsg> {
+        let msg = Cw20ExecuteMsg::Send {
+            contract: self.addr().into(),
+            amount,
+            msg: to_binary(&Cw20HookMsg::Mint(options))?,
+        };
+        Ok(WasmMsg::Execute {
+            contract_addr: payment_token.into(),
+            msg: to_binary(&msg)?,
+            funds: vec![],
+        }
+        .into())
+    }
+
+    pub fn query<C: CustomQuery, T: DeserializeOwned>(
+        &self,
+        querier: &QuerierWrapper<C>,
+        req: QueryMsg,
+    ) -> StdResult<T> {
+        querier.query_wasm_smart(self.addr(), &req)
+    }
+
+    pub fn config<C: CustomQuery>(&self, querier: &QuerierWrapper<C>) -> StdResult<ConfigResponse> {
+        self.query(querier, QueryMsg::GetConfig {})
+    }
+
+    pub fn supply_info<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+    ) -> StdResult<SupplyInfoResponse> {
+        self.query(querier, QueryMsg::SupplyInfo {})
+    }
+
+    pub fn current_price<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+    ) -> StdResult<CurrentPriceResponse> {
+        self.query(querier, QueryMsg::CurrentPrice {})
+    }
+
+    pub fn mints_of<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+        address: impl Into<String>,
+    ) -> StdResult<MintsOfResponse> {
+        self.query(
+            querier,
+            QueryMsg::MintsOf {
+                address: address.into(),
+            },
+        )
+    }
+}
@@ -0,0 +1,267 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    from_binary, to_binary, Binary, DepsMut, Env, IbcBasicResponse, IbcChannel, IbcChannelCloseMsg,
+    IbcChannelConnectMsg, IbcChannelOpenMsg, IbcChannelOpenResponse, IbcEndpoint, IbcOrder,
+    IbcPacket, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse,
+    Never, StdResult, Storage, Uint128,
+};
+
+use crate::contract::{
+    assert_not_blocklisted, mint_batch, price_for, record_address_mints, MAX_BATCH_MINTS,
+};
+use crate::msg::{MintAck, MintPacket};
+use crate::state::{
+    Config, IbcPendingAck, CONFIG, IBC_CHANNELS, IBC_PENDING_ACK, MINTS_PER_ADDRESS,
+};
+use crate::ContractError;
+
+pub const IBC_VERSION: &str = "moni-mint-1";
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_open(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelOpenMsg,
+) -> Result<IbcChannelOpenResponse, ContractError> {
+    validate_channel(deps, msg.channel(), msg.counterparty_version())?;
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_connect(
+    mut deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelConnectMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel = msg.channel();
+    validate_channel(deps.branch(), channel, msg.counterparty_version())?;
+    IBC_CHANNELS.save(
+        deps.storage,
+        &channel.endpoint.channel_id,
+        &channel.counterparty_endpoint,
+    )?;
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_connect")
+        .add_attribute("channel_id", &channel.endpoint.channel_id))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_close(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelCloseMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel = msg.channel();
+    IBC_CHANNELS.remove(deps.storage, &channel.endpoint.channel_id);
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_close")
+        .add_attribute("channel_id", &channel.endpoint.channel_id))
+}
+
+/// Mints for a `MintPacket`. Failures are returned as an error acknowledgement so the
+/// counterparty can refund the user. A packet that passes the checks is acknowledged only
+/// once its child mints have replied, see `settle_ibc_mint`.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_receive(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketReceiveMsg,
+) -> Result<IbcReceiveResponse, Never> {
+    match receive_mint(deps, env, msg.packet) {
+        Ok(res) => Ok(res),
+        Err(err) => Ok(IbcReceiveResponse::new()
+            .set_ack(ack_error(err.to_string()))
+            .add_attribute("action", "ibc_mint")
+            .add_attribute("error", err.to_string())),
+    }
+}
+
+/// This contract never sends packets.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_ack(
+    _deps: DepsMut,
+    _env: Env,
+    _msg: IbcPacketAckMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    Ok(IbcBasicResponse::new())
+}
+
+/// This contract never sends packets.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_timeout(
+    _deps: DepsMut,
+    _env: Env,
+    _msg: IbcPacketTimeoutMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    Ok(IbcBasicResponse::new())
+}
+
+fn validate_channel(
+    deps: DepsMut,
+    channel: &IbcChannel,
+    counterparty_version: Option<&str>,
+) -> Result<(), ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config.ibc_connection_id.as_ref() != Some(&channel.connection_id) {
+        return Err(ContractError::InvalidIbcChannel {});
+    }
+    let counterparty = &channel.counterparty_endpoint;
+    if config.ibc_counterparty_port.as_ref() != Some(&counterparty.port_id) {
+        return Err(ContractError::InvalidIbcChannel {});
+    }
+    // The counterparty channel id is still empty when this side initiates the handshake.
+    if !counterparty.channel_id.is_empty() && !is_counterparty(&config, counterparty) {
+        return Err(ContractError::InvalidIbcChannel {});
+    }
+    if channel.order != IbcOrder::Unordered {
+        return Err(ContractError::InvalidIbcChannel {});
+    }
+    if channel.version != IBC_VERSION || counterparty_version.is_some_and(|v| v != IBC_VERSION) {
+        return Err(ContractError::InvalidIbcVersion {});
+    }
+    Ok(())
+}
+
+/// Whether `endpoint` is the configured counterparty port and, if pinned, channel.
+fn is_counterparty(config: &Config, endpoint: &IbcEndpoint) -> bool {
+    config.ibc_counterparty_port.as_ref() == Some(&endpoint.port_id)
+        && config
+            .ibc_counterparty_channel
+            .as_ref()
+            .is_none_or(|channel_id| *channel_id == endpoint.channel_id)
+}
+
+/// Counts toward the recipient's per-address total but skips the buyer-side checks (block and
+/// phase limits, allowlists), which the counterparty is expected to enforce.
+fn receive_mint(
+    deps: DepsMut,
+    env: Env,
+    packet: IbcPacket,
+) -> Result<IbcReceiveResponse, ContractError> {
+    let counterparty = IBC_CHANNELS
+        .may_load(deps.storage, &packet.dest.channel_id)?
+        .ok_or(ContractError::InvalidIbcChannel {})?;
+    let packet: MintPacket = from_binary(&packet.data)?;
+    let mut config = CONFIG.load(deps.storage)?;
+    // Channels opened before the counterparty was changed stop minting.
+    if !is_counterparty(&config, &counterparty) {
+        return Err(ContractError::InvalidIbcChannel {});
+    }
+
+    if config.retired {
+        return Err(ContractError::Retired {});
+    }
+    if config.closed {
+        return Err(ContractError::SaleClosed {});
+    }
+    if config.paused {
+        return Err(ContractError::ContractPaused {});
+    }
+    if config
+        .start_time
+        .is_some_and(|start| env.block.time < start)
+    {
+        return Err(ContractError::MintNotStarted {});
+    }
+    if config.end_time.is_some_and(|end| env.block.time >= end) {
+        return Err(ContractError::MintEnded {});
+    }
+    if config.cw721_address.is_none() {
+        return Err(ContractError::Uninitialized {});
+    }
+    if config.crowdfund.is_some() && !config.crowdfund_settled {
+        return Err(ContractError::CrowdfundPending {});
+    }
+    if packet.quantity == 0 || packet.quantity as usize > MAX_BATCH_MINTS {
+        return Err(ContractError::InvalidQuantity {});
+    }
+    if config.remaining_supply() < packet.quantity as u64 {
+        return Err(ContractError::SoldOut {});
+    }
+    let price = price_for(deps.storage, &config, packet.quantity)?;
+    if packet.paid < price {
+        return Err(ContractError::WrongPaymentAmount {});
+    }
+
+    assert_not_blocklisted(deps.as_ref(), &packet.recipient)?;
+    let recipient = deps.api.addr_validate(&packet.recipient)?;
+    let bought = MINTS_PER_ADDRESS
+        .may_load(deps.storage, &recipient)?
+        .unwrap_or_default();
+    if config
+        .mint_limit_per_address
+        .is_some_and(|limit| bought + packet.quantity > limit)
+    {
+        return Err(ContractError::MintLimitReached {});
+    }
+
+    // Payment stays with the counterparty, so the records carry no price and nothing is
+    // refunded here if the child mint fails; the acknowledgement leaves that token out.
+    let batch = mint_batch(
+        deps.storage,
+        &env,
+        &mut config,
+        &recipient,
+        &recipient,
+        Uint128::zero(),
+        None,
+        packet.quantity,
+    )?;
+    let pending = IbcPendingAck {
+        pending: batch.token_ids.clone(),
+        minted: vec![],
+    };
+    IBC_PENDING_ACK.save(deps.storage, &pending)?;
+    let res = IbcReceiveResponse::new()
+        .add_attribute("action", "ibc_mint")
+        .add_attribute("recipient", &recipient)
+        .add_attribute("quantity", packet.quantity.to_string())
+        .add_submessages(batch.msgs)
+        .add_events(batch.events)
+        .add_attributes(batch.attributes);
+    record_address_mints(
+        deps.storage,
+        &mut config,
+        &recipient,
+        bought,
+        packet.quantity,
+    )?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(res)
+}
+
+/// Settles the reply for `token_id` if it belongs to the packet being received, returning
+/// the acknowledgement once no child mint of that packet is left to reply.
+pub(crate) fn settle_ibc_mint(
+    storage: &mut dyn Storage,
+    config: &Config,
+    token_id: u64,
+    error: Option<&str>,
+) -> StdResult<Option<Binary>> {
+    let mut ack = match IBC_PENDING_ACK.may_load(storage)? {
+        Some(ack) if ack.pending.contains(&token_id) => ack,
+        _ => return Ok(None),
+    };
+    ack.pending.retain(|id| *id != token_id);
+    if error.is_none() {
+        ack.minted.push(token_id);
+    }
+    if !ack.pending.is_empty() {
+        IBC_PENDING_ACK.save(storage, &ack)?;
+        return Ok(None);
+    }
+    IBC_PENDING_ACK.remove(storage);
+    if ack.minted.is_empty() {
+        return Ok(Some(ack_error(error.unwrap_or_default().to_string())));
+    }
+    let token_ids = ack.minted.iter().map(|id| config.token_id(*id)).collect();
+    to_binary(&MintAck::Result { token_ids }).map(Some)
+}
+
+pub(crate) fn ack_error(err: String) -> Binary {
+    to_binary(&MintAck::Error(err)).unwrap_or_else(|_| Binary::from(b"error".to_vec()))
+}
@@ -1,6 +1,20 @@
 pub mod contract;
 mod error;
+pub mod ibc;
 pub mod msg;
+pub mod pagination;
 pub mod state;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+// Only needed by crates embedding or calling this contract, so kept out of the wasm build.
+#[cfg(any(test, feature = "library"))]
+pub mod extension;
+#[cfg(any(test, feature = "library"))]
+pub mod helpers;
 
 pub use crate::error::ContractError;
+
+/// Building blocks of the mint flow, for contracts composing it with their own checks.
+#[cfg(feature = "library")]
+pub use crate::contract::{assign_token_id, build_mint_msg, validate_payment};
@@ -1,13 +1,27 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
 
-use cosmwasm_std::{Addr, Uint128, Binary};
+use cosmwasm_std::{
+    to_binary, Addr, Binary, Coin, CosmosMsg, Decimal, HexBinary, IbcTimeout, ReplyOn, StdResult,
+    Timestamp, Uint128, WasmMsg,
+};
+use cw2::ContractVersion;
+use cw721::{Approval, Cw721ReceiveMsg};
 use cw721_base::Extension;
+use cw_utils::Expiration;
+
+use crate::state::{
+    AiMetadataStatus, AiOracle, ArchivedRound, Auction, Collection, Creator, CreatorClaim,
+    Crowdfund, Currency, Cw721Version, DropRound, EscrowedPayment, Ics721Bridge, Membership,
+    Metadata, MintGate, OperatorPerms, PartnerDiscount, PaymentSplit, PendingMint, Phase, Presale,
+    PriceCurve, PriceOracle, PriceTier, ProtocolFee, Raffle, Randomness, Renewal, Staking,
+    SwapRoute, TokenIdTemplate, TokenUriMode, TradeIn, TraitCategory, UpgradeRule, Vesting,
+};
 
 #[cw_serde]
 #[serde(rename_all = "snake_case")]
 pub struct InstantiateMsg {
     pub owner: Addr,
-    pub max_tokens: u32,
+    pub max_tokens: u64,
     pub unit_price: Uint128,
     pub name: String,
     pub symbol: String,
@@ -15,11 +29,1056 @@ pub struct InstantiateMsg {
     pub cw20_address: Addr,
     pub token_uri: String,
     pub extension: Extension,
+    /// Default destination for withdrawn proceeds. Falls back to the owner when unset.
+    pub treasury: Option<String>,
+    /// Mints are rejected before this time when set.
+    pub start_time: Option<Timestamp>,
+    /// Mints are rejected at or after this time when set.
+    pub end_time: Option<Timestamp>,
+    /// How `token_uri` is applied to minted tokens. Defaults to `Fixed`.
+    pub token_uri_mode: Option<TokenUriMode>,
+    /// Assign token ids in pseudo-random order instead of sequentially.
+    pub shuffle_token_ids: Option<bool>,
+    /// Upper bound on tokens the owner may mint for free via `ReserveMint`.
+    pub reserved_supply: Option<u64>,
+    /// Reject payments above `unit_price` instead of refunding the excess.
+    pub strict_payment: Option<bool>,
+    /// Link an existing cw721 collection instead of instantiating one from `token_code_id`.
+    /// This contract must be (or become) its minter.
+    pub cw721_address: Option<String>,
+    /// Maximum number of tokens a single address may buy.
+    pub mint_limit_per_address: Option<u32>,
+    /// Creator royalty reported to marketplaces through the `RoyaltyInfo` query.
+    pub royalty: Option<RoyaltyMsg>,
+    /// Raise the price as supply is consumed. `unit_price` is the price of the first token.
+    pub price_curve: Option<PriceCurve>,
+    /// Allowlist-only sale that runs from `start_time` until `presale.end_time`.
+    pub presale: Option<Presale>,
+    /// Share of each payment, in basis points, forwarded to the referrer named in the mint hook.
+    pub referral_bps: Option<u16>,
+    /// Mint with per-token on-chain `Metadata` extensions. The child cw721 must be a
+    /// metadata-onchain build when enabled.
+    pub onchain_metadata: Option<bool>,
+    /// Delayed reveal: every token is minted with this URI until the owner calls `Reveal`.
+    pub placeholder_uri: Option<String>,
+    /// Share of the mint price, in basis points, paid back to holders who return their token
+    /// for burning. Buy-back is disabled when unset.
+    pub refund_bps: Option<u16>,
+    /// Fee, in basis points, kept from every secondary sale made through `Buy`.
+    pub market_fee_bps: Option<u16>,
+    /// Escrow payments until `crowdfund.soft_cap` tokens are sold; refundable after the deadline
+    /// otherwise.
+    pub crowdfund: Option<Crowdfund>,
+    /// Charge a USD price converted through an oracle instead of `unit_price`.
+    pub price_oracle: Option<PriceOracleMsg>,
+    /// Compressed secp256k1 public key whose signed vouchers may be redeemed for mints.
+    pub voucher_signer: Option<Binary>,
+    /// Largest `quantity` accepted by a single public mint.
+    pub max_mints_per_tx: Option<u32>,
+    /// Tokens a single address may buy within one block.
+    pub max_mints_per_block: Option<u32>,
+    /// Instantiate the child cw721 with `Instantiate2` and this salt, so its address can be
+    /// derived before the transaction lands.
+    pub cw721_salt: Option<Binary>,
+    /// Gas limit for the child cw721 instantiation submessage.
+    pub cw721_gas_limit: Option<u64>,
+    /// `Always` (default) records a failed child instantiation for `RetryCw721Instantiate`;
+    /// `Success` makes it abort the whole transaction instead.
+    pub cw721_reply_on: Option<ReplyOn>,
+    /// Non-transferable collection. `token_code_id` must be a cw721-non-transferable build;
+    /// this contract becomes its admin, so holders cannot move their tokens.
+    pub soulbound: Option<bool>,
+    /// Wasm admin of the child cw721, allowed to migrate it. Defaults to none.
+    pub cw721_admin: Option<String>,
+    /// Label of the child cw721 instantiation.
+    pub cw721_label: Option<String>,
+    /// Native funds forwarded to the child cw721 instantiation, out of this contract's balance.
+    pub cw721_funds: Option<Vec<Coin>>,
+    /// Unlimited supply; `max_tokens` must be 0. Usually paired with a mint window.
+    pub open_edition: Option<bool>,
+    /// Contract (e.g. a DAO proposal module) allowed to raise `max_tokens` via `IncreaseSupply`.
+    /// The owner key can only ever lower the supply.
+    pub supply_governor: Option<String>,
+    /// Queue payments received after sell-out instead of rejecting them, to be filled in order if
+    /// supply is raised later.
+    pub waitlist: Option<bool>,
+    /// Native denom accepted through `MintNative`, e.g. the `ibc/<hash>` denom of axlUSDC. Only
+    /// this exact string is accepted.
+    pub native_denom: Option<String>,
+    /// Creators sharing the proceeds. When set, `Withdraw` credits each creator's claimable
+    /// balance instead of paying the treasury. Shares must add up to 10 000 bps.
+    pub creators: Option<Vec<CreatorMsg>>,
+    /// Discount for holders of a partner cw721 collection. Does not apply to oracle-priced mints.
+    pub partner_discount: Option<PartnerDiscountMsg>,
+    /// Only buyers holding the given cw721 or cw20 balance may mint.
+    pub mint_gate: Option<MintGateMsg>,
+    /// Releases proceeds for withdrawal gradually: nothing before the cliff, then linearly
+    /// until the end of the schedule.
+    pub vesting: Option<VestingMsg>,
+    /// Holds payments in escrow until `Reveal`. Until then holders can return a token for a
+    /// full refund. Requires `placeholder_uri`.
+    pub reveal_escrow: Option<bool>,
+    /// Token id format with one `{n}` placeholder for the mint index, e.g. `"MONI-{n}"`.
+    /// `{n:06}` zero-pads the index to six digits. Defaults to the bare index.
+    pub token_id_template: Option<String>,
+    /// Platform fee taken from every mint payment. Only the fee collector can change it later.
+    pub protocol_fee: Option<ProtocolFeeMsg>,
+    /// Basis points of every primary cw20 payment to `Burn` at mint time.
+    pub burn_bps: Option<u16>,
+    /// Requires two-step commit-reveal mints, revealed at least this many blocks after the commit.
+    pub commit_reveal_blocks: Option<u64>,
+    /// Secp256k1 public key of an off-chain gatekeeper. Paid mints then need a signed `MintPermit`.
+    pub gatekeeper: Option<Binary>,
+    /// Gives every minted token an expiry that can be extended with `Renew`.
+    pub renewal: Option<Renewal>,
+    /// Code id of a cw721-metadata-onchain build to instantiate in place of `token_code_id`.
+    /// Implies `onchain_metadata`; cannot be combined with `soulbound`.
+    pub metadata_code_id: Option<u64>,
+    /// Collection-level metadata reported through the `CollectionInfo` query.
+    pub collection_info: Option<CollectionInfoMsg>,
+    /// Release line of `token_code_id`. Defaults to `legacy`; ignored with `cw721_address`, whose
+    /// version is detected.
+    pub cw721_version: Option<Cw721Version>,
+    pub membership: Option<MembershipMsg>,
+    /// Requests every token's metadata from this AI oracle once its mint is confirmed.
+    pub ai_oracle: Option<AiOracleMsg>,
+}
+
+/// Direction a list query walks its keys in.
+#[cw_serde]
+#[derive(Copy, Default)]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+#[cw_serde]
+pub struct CollectionInfoMsg {
+    /// Defaults to the owner.
+    pub creator: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub external_link: Option<String>,
+    #[serde(default)]
+    pub explicit_content: bool,
+}
+
+#[cw_serde]
+pub struct RoyaltyMsg {
+    pub payment_address: String,
+    /// Royalty share in basis points (1/100th of a percent).
+    pub bps: u16,
+}
+
+#[cw_serde]
+pub struct Ics721BridgeMsg {
+    pub bridge: String,
+    pub channel_id: String,
+    pub timeout_seconds: u64,
+}
+
+/// `msg` of the `SendNft` into the ICS-721 bridge.
+#[cw_serde]
+pub struct Ics721OutgoingMsg {
+    pub receiver: String,
+    pub channel_id: String,
+    pub timeout: IbcTimeout,
+    pub memo: Option<String>,
+}
+
+#[cw_serde]
+pub struct PriceOracleMsg {
+    pub address: String,
+    pub usd_price: Uint128,
+    pub max_staleness: u64,
+    pub tolerance_bps: u16,
+}
+
+/// Short-lived authorization for `address` to mint, issued by the gatekeeper (e.g. after a
+/// captcha or KYC check). Each nonce is accepted once per address.
+#[cw_serde]
+pub struct MintPermit {
+    pub address: String,
+    pub nonce: u64,
+    pub expiry: Timestamp,
+}
+
+#[cw_serde]
+pub struct SignedMintPermit {
+    pub permit: MintPermit,
+    /// 64-byte secp256k1 signature by the gatekeeper over
+    /// `sha256(contract_address ++ json(permit))`.
+    pub signature: Binary,
+}
+
+/// A user's off-chain request to mint `quantity` tokens to themselves, signed with the intent
+/// key they registered and submitted by a relayer. Each nonce is accepted once per recipient.
+#[cw_serde]
+pub struct MintIntent {
+    pub recipient: String,
+    pub quantity: u32,
+    pub nonce: u64,
+}
+
+/// Admin actions that can be authorized offline with the owner key.
+#[cw_serde]
+pub enum SignedAdminMsg {
+    UpdateUnitPrice {
+        price: Uint128,
+    },
+    Withdraw {
+        amount: Option<Uint128>,
+        recipient: Option<String>,
+        token: Option<String>,
+    },
+}
+
+/// An admin action signed with the owner key. Each nonce is accepted once.
+#[cw_serde]
+pub struct AdminPayload {
+    pub msg: SignedAdminMsg,
+    pub nonce: u64,
+    pub expiry: Timestamp,
+}
+
+/// Off-chain authorization to mint a specific token, signed by the configured voucher signer.
+#[cw_serde]
+pub struct Voucher {
+    /// Must not collide with the numeric ids of the regular sale.
+    pub token_id: String,
+    pub token_uri: String,
+    pub price: Uint128,
+    pub recipient: String,
+    pub expiry: Timestamp,
+}
+
+#[cw_serde]
+pub struct AllowlistEntryMsg {
+    pub address: String,
+    pub price: Option<Uint128>,
+    /// The entry lapses at this time. Never when unset.
+    pub expires: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct PhaseAllowlistEntryMsg {
+    pub address: String,
+    pub price: Option<Uint128>,
+    pub mint_limit: Option<u32>,
+}
+
+#[cw_serde]
+pub struct RandomnessMsg {
+    pub proxy: String,
+    pub fee: Vec<Coin>,
+}
+
+/// Request understood by a Nois proxy.
+#[cw_serde]
+pub enum NoisProxyExecuteMsg {
+    GetNextRandomness { job_id: String },
+}
+
+/// Beacon delivered by a Nois proxy.
+#[cw_serde]
+pub struct NoisCallback {
+    pub job_id: String,
+    pub published: Timestamp,
+    pub randomness: HexBinary,
+}
+
+/// Payload sent to every registered mint hook.
+#[cw_serde]
+pub struct MintHookMsg {
+    pub token_id: String,
+    pub buyer: String,
+    pub price: Uint128,
+}
+
+impl MintHookMsg {
+    /// Serializes the message wrapped in `MintHookExecuteMsg`.
+    pub fn into_binary(self) -> StdResult<Binary> {
+        to_binary(&MintHookExecuteMsg::MintHook(self))
+    }
+
+    /// Creates a `WasmMsg::Execute` delivering this hook to `contract_addr`.
+    pub fn into_cosmos_msg<T: Into<String>>(self, contract_addr: T) -> StdResult<CosmosMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg: self.into_binary()?,
+            funds: vec![],
+        }
+        .into())
+    }
+}
+
+/// Execute message a mint hook contract must accept.
+#[cw_serde]
+pub enum MintHookExecuteMsg {
+    MintHook(MintHookMsg),
+}
+
+/// `Extension` message for child cw721s that let their minter rewrite token URIs.
+#[cw_serde]
+pub enum Cw721UriExtensionMsg {
+    UpdateTokenUri { token_id: String, token_uri: String },
+}
+
+/// Data this contract's instantiation resolves to once its cw721 is linked, so a parent
+/// factory can read the collection address from its own instantiate reply.
+#[cw_serde]
+pub struct InstantiateData {
+    pub cw721_address: Addr,
+}
+
+/// `Response.data` of every mint to a single recipient, so calling contracts learn the
+/// assigned ids without scanning events.
+#[cw_serde]
+pub struct MintResponse {
+    /// The first of `token_ids`.
+    pub token_id: String,
+    pub token_ids: Vec<String>,
+    pub recipient: String,
+}
+
+/// cw-ownable ownership update understood by cw721-base 0.16 and later.
+#[cw_serde]
+pub enum Cw721OwnershipMsg {
+    UpdateOwnership(Cw721OwnershipAction),
+}
+
+/// cw-ownable query answered by cw721-base 0.16 and later only.
+#[cw_serde]
+pub enum Cw721OwnershipQueryMsg {
+    Ownership {},
+}
+
+#[cw_serde]
+pub struct Cw721OwnershipResponse {
+    pub owner: Option<String>,
+    pub pending_owner: Option<String>,
+    pub pending_expiry: Option<Expiration>,
+}
+
+#[cw_serde]
+pub enum Cw721OwnershipAction {
+    TransferOwnership {
+        new_owner: String,
+        expiry: Option<Expiration>,
+    },
+}
+
+#[cw_serde]
+pub struct VestingMsg {
+    /// Defaults to the instantiation time.
+    pub start: Option<Timestamp>,
+    pub cliff_seconds: u64,
+    pub duration_seconds: u64,
+}
+
+#[cw_serde]
+pub enum MintGateMsg {
+    Cw721 {
+        collection: String,
+    },
+    Cw20 {
+        token: String,
+        min_balance: Uint128,
+    },
+    Cw4 {
+        group: String,
+        min_weight: Option<u64>,
+    },
+}
+
+#[cw_serde]
+pub struct AiOracleMsg {
+    pub contract: String,
+    pub service: String,
+    pub threshold: u64,
+}
+
+#[cw_serde]
+pub struct SwapRouteMsg {
+    pub router: String,
+    pub ask: Currency,
+    pub max_slippage_bps: u16,
+}
+
+#[cw_serde]
+pub struct MembershipMsg {
+    pub group: String,
+    pub weight_per_mint: u64,
+}
+
+#[cw_serde]
+pub struct PartnerDiscountMsg {
+    pub collection: String,
+    pub discount_bps: u16,
+}
+
+#[cw_serde]
+pub struct TradeInMsg {
+    pub collection: String,
+    pub tokens_per_mint: u32,
+    pub burn: bool,
+}
+
+#[cw_serde]
+pub struct ProtocolFeeMsg {
+    pub collector: String,
+    pub bps: u16,
+}
+
+#[cw_serde]
+pub struct CreatorMsg {
+    pub address: String,
+    pub bps: u16,
+}
+
+#[cw_serde]
+pub struct PaymentSplitMsg {
+    pub address: String,
+    pub weight: u32,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
     Receive(Cw20ReceiveMsg),
+    /// Owner or `can_update_price` operator. Changes the cw20 amount charged per token.
+    UpdateUnitPrice {
+        price: Uint128,
+    },
+    /// Owner only. Nominates a new owner, who must call `AcceptOwnership` before `expiry`.
+    ProposeNewOwner {
+        owner: String,
+        expiry: Option<Expiration>,
+    },
+    /// Owner only. Withdraws a pending ownership proposal.
+    CancelOwnershipProposal {},
+    /// Pending owner only. Completes the ownership transfer.
+    AcceptOwnership {},
+    /// Owner only. Schedules `address` to replace `cw20_address` once the payment token
+    /// timelock has passed, replacing any earlier schedule. Sends of the current token keep
+    /// minting until then.
+    UpdatePaymentToken {
+        address: String,
+    },
+    /// Anyone, once the timelock has passed. Switches to the scheduled payment token.
+    ApplyPaymentToken {},
+    /// Owner only, irreversible. Disables every owner-only message and supply changes. Anyone
+    /// may then trigger `Withdraw` to the configured payout destinations.
+    RenounceOwnership {},
+    /// Owner only. Sends collected proceeds to `recipient`, or to the treasury when unset;
+    /// `can_withdraw` operators may withdraw without a `recipient`. Withdraws the full balance
+    /// when `amount` is unset.
+    Withdraw {
+        amount: Option<Uint128>,
+        recipient: Option<String>,
+        /// Cw20 address or `native_denom` to withdraw. Defaults to `cw20_address`.
+        token: Option<String>,
+    },
+    /// Owner only. Sends the whole balance of an asset the sale does not accept, such as
+    /// tokens sent here by mistake, to `recipient`. Amounts owed to creators or set aside for
+    /// staking rewards stay behind. Payment currencies can only leave through `Withdraw`.
+    SweepFunds {
+        asset: Currency,
+        recipient: String,
+    },
+    /// Owner or `can_pause` operator. Rejects all mints until `Resume` is called.
+    Pause {},
+    /// Owner or `can_pause` operator. Re-enables minting after a `Pause`.
+    Resume {},
+    /// Owner or `can_airdrop` operator. Mints `quantity` tokens to `recipient` without payment. Counts against
+    /// `max_tokens` and, when configured, `reserved_supply`.
+    ReserveMint {
+        quantity: u32,
+        recipient: String,
+    },
+    /// Owner or `can_airdrop` operator. Mints one token to each recipient without payment. Counts against the
+    /// same supplies as `ReserveMint`; large drops must be split across transactions.
+    Airdrop {
+        recipients: Vec<String>,
+    },
+    /// Owner only. Retries the child cw721 instantiation after a failure, optionally with a
+    /// different code id. A config migrated without a child has none, so it must be given.
+    RetryCw721Instantiate {
+        code_id: Option<u64>,
+    },
+    /// Owner only. Links a cw721 instantiated out-of-band, e.g. after a lost reply. The
+    /// contract must be its minter. Fails once a collection is linked.
+    SetCw721Address {
+        address: String,
+    },
+    /// Mints `quantity` tokens to the sender, paying through a prior cw20 allowance granted
+    /// to this contract instead of a cw20 `Send`.
+    MintWithAllowance {
+        quantity: u32,
+    },
+    /// Owner or `can_update_price` operator. Replaces the supply-milestone price table. An empty list removes tiering.
+    SetPriceTiers {
+        tiers: Vec<PriceTier>,
+    },
+    /// Owner or `can_update_price` operator, until `Freeze`. Prices each `(token_id, price)` in
+    /// `set` explicitly and drops the explicit price of every id in `unset`. Only collections
+    /// with sequential token ids support per-token prices.
+    UpdateTokenPrices {
+        set: Vec<(String, Uint128)>,
+        unset: Vec<String>,
+    },
+    /// Owner or `can_manage_allowlist` operator. Adds or updates presale allowlist entries.
+    AddToAllowlist {
+        entries: Vec<AllowlistEntryMsg>,
+    },
+    /// Owner or `can_manage_allowlist` operator. Removes addresses from the presale allowlist.
+    RemoveFromAllowlist {
+        addresses: Vec<String>,
+    },
+    /// Owner or `can_manage_allowlist` operator. Adds `(address, mint_limit)` pairs to the
+    /// allowlist and removes `remove`.
+    /// Large lists can be split across transactions; an address may appear once per batch.
+    UpdateAllowlist {
+        add: Vec<(String, u32)>,
+        remove: Vec<String>,
+    },
+    /// Owner only. Changes the referral reward share. `None` disables referral rewards.
+    SetReferralBps {
+        bps: Option<u16>,
+    },
+    /// Owner only. Replaces the weighted list of withdrawal recipients. An empty list
+    /// restores withdrawals to the treasury.
+    UpdatePaymentSplits {
+        splits: Vec<PaymentSplitMsg>,
+    },
+    /// Owner only. Adds or replaces the phase with the given id. Phases may not overlap.
+    SetPhase {
+        id: u8,
+        phase: Phase,
+    },
+    /// Owner only. Removes a phase from the schedule.
+    RemovePhase {
+        id: u8,
+    },
+    /// Owner or `can_manage_allowlist` operator. Adds or updates allowlist entries for one phase.
+    AddToPhaseAllowlist {
+        phase_id: u8,
+        entries: Vec<PhaseAllowlistEntryMsg>,
+    },
+    /// Owner or `can_manage_allowlist` operator. Removes addresses from a phase's allowlist.
+    RemoveFromPhaseAllowlist {
+        phase_id: u8,
+        addresses: Vec<String>,
+    },
+    /// Owner only. Sets the on-chain metadata for a token that has not been minted yet.
+    /// Requires `onchain_metadata`.
+    SetTokenMetadata {
+        token_id: u64,
+        metadata: Metadata,
+    },
+    /// Owner only, until `Freeze`. Replaces the URI of a minted token. The child cw721 must
+    /// accept `Cw721UriExtensionMsg` as its `Extension` message; plain cw721-base does not.
+    UpdateTokenUri {
+        token_id: String,
+        uri: String,
+    },
+    /// Owner only. Ends a delayed reveal: tokens resolve to `{base_uri}/{token_id}.json` from
+    /// now on. cw721-base cannot rewrite the URI of tokens already minted, so clients resolve
+    /// those through the `Revealed` query.
+    Reveal {
+        base_uri: String,
+    },
+    /// Owner only. Permanently locks token URIs, metadata, prices and supply.
+    Freeze {},
+    /// Owner only. Permanently closes the sale and pays out every payment token balance.
+    /// cw721-base 0.15 has no minter transfer, so this contract stays the minter.
+    Shutdown {},
+    /// Entry point for tokens of this collection sent back with cw721 `SendNft`. The `msg`
+    /// must be a `Cw721HookMsg`.
+    ReceiveNft(Cw721ReceiveMsg),
+    /// Owner only. Changes the burn-to-refund share. `None` disables buy-back.
+    SetRefundBps {
+        bps: Option<u16>,
+    },
+    /// Owner only. Sets or removes the burn-to-upgrade recipe.
+    SetUpgradeRule {
+        rule: Option<UpgradeRule>,
+    },
+    /// Returns the tokens the sender has deposited towards an upgrade.
+    CancelUpgrade {},
+    /// Seller only. Withdraws a listing and returns the token.
+    CancelListing {
+        token_id: String,
+    },
+    /// Owner only. Ends a crowdfund that reached its soft cap, releasing escrowed payments
+    /// for withdrawal and letting buyers claim their tokens.
+    SettleCrowdfund {},
+    /// Claims the sender's escrowed tokens once the crowdfund is settled, or a full refund
+    /// (burning those tokens) once it has failed.
+    ClaimPledge {},
+    /// Owner only. Accepts `address` as payment for mints at a flat `price` per token, or
+    /// updates its price.
+    AddPaymentToken {
+        address: String,
+        price: Uint128,
+    },
+    /// Owner only. Stops accepting a token added with `AddPaymentToken`.
+    RemovePaymentToken {
+        address: String,
+    },
+    /// Owner only. Switches to oracle-based USD pricing, or back to `unit_price` with `None`.
+    SetPriceOracle {
+        oracle: Option<PriceOracleMsg>,
+    },
+    /// Owner only. Sets or clears the ICS-721 bridge used for `remote_recipient` mints.
+    SetIcs721Bridge {
+        bridge: Option<Ics721BridgeMsg>,
+    },
+    /// Owner only. Sets or clears the public key that signs mint vouchers.
+    SetVoucherSigner {
+        pubkey: Option<Binary>,
+    },
+    /// Owner only. Starts an additional fixed-price collection with its own cw721, instantiated
+    /// from `token_code_id` (defaults to the main collection's code id).
+    CreateCollection {
+        name: String,
+        symbol: String,
+        unit_price: Uint128,
+        max_tokens: u64,
+        token_uri: String,
+        token_code_id: Option<u64>,
+    },
+    /// Owner only. Archives the running round and starts a new one on a fresh child cw721
+    /// instantiated from `token_code_id`, selling `max_tokens` more tokens, optionally at a new
+    /// unit price and base URI. Mint indexes carry on from the previous round.
+    CloneCollection {
+        max_tokens: u64,
+        unit_price: Option<Uint128>,
+        token_uri: Option<String>,
+    },
+    /// Lender only. Takes back a deposited token once it is not rented out.
+    WithdrawRental {
+        token_id: String,
+    },
+    /// Owner only. Sets the counterparty allowed to open mint channels: its port, the
+    /// connection to its chain and, optionally, the one channel it may use. `paid` in a
+    /// `MintPacket` is taken on trust from the counterparty contract, which collects the
+    /// payment, so only pin a contract you control. `None` for `port_id` disables IBC minting.
+    SetIbcCounterparty {
+        port_id: Option<String>,
+        connection_id: Option<String>,
+        channel_id: Option<String>,
+    },
+    /// Owner only. Allows or disallows buyer-supplied metadata in `MintOptions`.
+    SetCustomMetadata {
+        allowed: bool,
+    },
+    /// Owner only. Replaces the prefixes a buyer-supplied `token_uri` must start with, e.g. an
+    /// IPFS directory such as `ipfs://<cid>/`. An empty list stops accepting them.
+    SetAllowedUriPrefixes {
+        prefixes: Vec<String>,
+    },
+    /// Closes a timed open edition once `end_time` has passed, recording its final supply.
+    /// Callable by anyone.
+    Finalize {},
+    /// Owner only. Lowers `max_tokens`, never below the ids already used, pending or reserved
+    /// for drops and raffle winners. `freeze`
+    /// also locks the config so the cap cannot be raised again.
+    ReduceSupply {
+        new_max: u64,
+        freeze: Option<bool>,
+    },
+    /// Supply governor only. Raises `max_tokens`; chain governance can do the same through
+    /// `SudoMsg::UpdateMaxTokens`.
+    IncreaseSupply {
+        new_max: u64,
+    },
+    /// Mints for the oldest waitlist entries while supply allows. Callable by anyone.
+    FillWaitlist {
+        limit: Option<u32>,
+    },
+    /// Leaves the waitlist and refunds the escrowed payment to the buyer.
+    CancelWaitlist {
+        position: u64,
+    },
+    /// Mints `quantity` tokens (default 1) to the sender, paid in `native_denom` funds at
+    /// `native_price`, or the public price when unset. Phase and allowlist prices do not apply.
+    MintNative {
+        quantity: Option<u32>,
+    },
+    /// Owner or `can_update_price` operator. Sets the per-token price of each listed currency: the primary cw20's
+    /// `unit_price`, an alternative cw20 (accepting it if new), or `native_denom`.
+    UpdatePrices {
+        prices: Vec<CurrencyPrice>,
+    },
+    /// Creator only. Pays out everything credited to the sender by `Withdraw`.
+    ClaimCreatorShare {},
+    /// Owner or `can_manage_allowlist` operator. Replaces the Merkle allowlist root, or removes
+    /// it with `None`. Proofs made
+    /// for earlier roots stop being accepted.
+    UpdateMerkleRoot {
+        root: Option<Binary>,
+    },
+    /// Owner only. Sets or clears the partner collection discount.
+    SetPartnerDiscount {
+        discount: Option<PartnerDiscountMsg>,
+    },
+    /// Owner only. Sets or clears the holding requirement for minting.
+    SetMintGate {
+        gate: Option<MintGateMsg>,
+    },
+    /// AI oracle only. Delivers the generated metadata URI of a token whose request is still
+    /// pending.
+    AiOracleCallback {
+        token_id: String,
+        token_uri: String,
+    },
+    /// Owner only. Sets or clears the router through which withdrawals to the treasury are
+    /// swapped into `ask`, receiving at least the simulated output less `max_slippage_bps`.
+    /// Withdrawals to a `recipient`, splits or creators are never swapped.
+    SetSwapRoute {
+        route: Option<SwapRouteMsg>,
+    },
+    /// Owner only. Sets or clears the cw4 group whose weights follow every buyer's mints.
+    /// Existing members keep their weight until they mint again.
+    SetMembership {
+        membership: Option<MembershipMsg>,
+    },
+    /// Owner only. Sets the per-block reward of each staked token. Rewards accrued so far are
+    /// kept at the old rate. The reward token can only change while the pool is empty.
+    SetStakingRewards {
+        reward_token: String,
+        reward_per_block: Uint128,
+    },
+    /// Staker only. Pays the pending rewards of `token_ids`.
+    ClaimStakingRewards {
+        token_ids: Vec<String>,
+    },
+    /// Staker only. Pays the pending rewards and returns the token.
+    Unstake {
+        token_id: String,
+    },
+    /// Owner only. Replaces the trait table used to assign traits at mint. Requires
+    /// `onchain_metadata`; an empty table stops trait assignment.
+    SetTraitTable {
+        traits: Vec<TraitCategory>,
+    },
+    /// Owner only. Sets the randomness provider that public mints wait for, or clears it once
+    /// no mints are pending.
+    SetRandomness {
+        randomness: Option<RandomnessMsg>,
+    },
+    /// Randomness proxy only. Mints the tokens of the job the beacon was requested for.
+    NoisReceive {
+        callback: NoisCallback,
+    },
+    /// Owner only. Registers a contract to receive a `MintHookExecuteMsg` after every mint.
+    /// A failing hook fails the mint.
+    AddMintHook {
+        addr: String,
+    },
+    /// Owner only. Unregisters a hook added with `AddMintHook`.
+    RemoveMintHook {
+        addr: String,
+    },
+    /// Owner only. Executes `msg` on the child cw721 as its minter, e.g. `Approve` or `Burn`
+    /// of a reserved token. `Mint` is rejected so supply accounting stays with this contract,
+    /// as are `ApproveAll` and messages touching tokens this contract holds for users.
+    Cw721Execute {
+        msg: Binary,
+    },
+    /// Owner only. Hands the child cw721's minter role to `new_minter` (e.g. a successor sale
+    /// contract) and retires this contract, refusing all further mints. Only `ownable`
+    /// children (cw721-base 0.16 and later) support it, through cw-ownable's
+    /// `UpdateOwnership`; `new_minter` then completes the handoff with `AcceptOwnership`.
+    TransferMinter {
+        new_minter: String,
+        expiry: Option<Expiration>,
+    },
+    /// Owner only. Grants `address` the given operator permissions, replacing any it had.
+    SetOperator {
+        address: String,
+        perms: OperatorPerms,
+    },
+    /// Owner only. Revokes all of `address`'s operator permissions.
+    RemoveOperator {
+        address: String,
+    },
+    /// Owner only. Bars `add` from buying or receiving mints and lifts the bar for `remove`.
+    UpdateBlocklist {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    /// Claims one token for free during a phase with a `free_supply`.
+    Claim {},
+    /// Mints `quantity` tokens as a primary cw20 `Mint` would, paid from the sender's deposit
+    /// at the current price.
+    MintFromBalance {
+        quantity: u32,
+    },
+    /// Returns `amount` of the sender's unused deposit, or all of it when unset.
+    RefundDeposit {
+        amount: Option<Uint128>,
+    },
+    /// Owner only. Gives `address` `amount` more mint credits.
+    GrantCredits {
+        address: String,
+        amount: u32,
+    },
+    /// Mints `quantity` tokens to the sender without payment, using up as many of its mint
+    /// credits.
+    RedeemCredits {
+        quantity: u32,
+    },
+    /// Owner only, until `Freeze`. Appends a drop round to the main collection, raising
+    /// `max_tokens` by `supply`. The round's tokens are only sold through `MintOptions::drop_id`.
+    AddDrop {
+        name: String,
+        start_time: Timestamp,
+        end_time: Option<Timestamp>,
+        unit_price: Uint128,
+        supply: u64,
+        per_wallet_cap: Option<u32>,
+    },
+    /// Owner only, until `Freeze`. Appends `rounds` drop rounds of `supply` tokens each, the
+    /// first opening at `first_start` and each following one `interval` seconds later. Every
+    /// round stays open for `duration` seconds.
+    ScheduleDrops {
+        name: String,
+        first_start: Timestamp,
+        interval: u64,
+        duration: u64,
+        rounds: u32,
+        unit_price: Uint128,
+        supply: u64,
+        per_wallet_cap: Option<u32>,
+    },
+    /// Owner only, until `Freeze`. Replaces the collection metadata and the royalty, which
+    /// `None` removes.
+    UpdateCollectionInfo {
+        collection_info: CollectionInfoMsg,
+        royalty: Option<RoyaltyMsg>,
+    },
+    /// Fee collector only. Replaces or, with `None`, removes the protocol fee.
+    UpdateProtocolFee {
+        fee: Option<ProtocolFeeMsg>,
+    },
+    /// Owner only, until `Freeze`. Sets the share of each primary cw20 payment that is burned;
+    /// `None` stops burning.
+    SetBurnBps {
+        bps: Option<u16>,
+    },
+    /// Commits to a mint under `commit_reveal_blocks`. `commitment` is
+    /// `sha256(sender ++ secret)`; a new commitment replaces the previous one.
+    CommitMint {
+        commitment: HexBinary,
+    },
+    /// Owner only. Sets or, with `None`, removes the gatekeeper key.
+    SetGatekeeper {
+        pubkey: Option<Binary>,
+    },
+    /// Owner only. Allows `add` to submit relayed mints and revokes `remove`.
+    UpdateRelayers {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    /// Sets or, with `None`, removes the compressed secp256k1 key that signs the sender's
+    /// relayed mint intents.
+    SetIntentKey {
+        pubkey: Option<Binary>,
+    },
+    /// Owner only, with a revocable `renewal`. Revokes expired tokens.
+    RevokeExpired {
+        token_ids: Vec<String>,
+    },
+    /// Owner only. Migrates the linked cw721 to `new_code_id`. Only succeeds when this
+    /// contract is the collection's wasm admin, e.g. with `cw721_admin` set to its address.
+    MigrateCw721 {
+        new_code_id: u64,
+        msg: Binary,
+    },
+    /// Owner only. Changes the cw721 code id that future children are instantiated from;
+    /// existing collections are unaffected.
+    UpdateTokenCodeId {
+        code_id: u64,
+    },
+    /// Owner only, until `Freeze`. Sets or, with `None`, stops accepting tokens of another
+    /// collection in exchange for mints.
+    SetTradeIn {
+        trade_in: Option<TradeInMsg>,
+    },
+    /// Returns the tokens of `collection` the sender has traded in towards a mint.
+    CancelTradeIn {
+        collection: String,
+    },
+    /// Owner only, until `Freeze`. Opens the contract's raffle, reserving `winners` tokens.
+    /// Tickets are bought with `Cw20HookMsg::BuyTickets` until `end_time`.
+    StartRaffle {
+        end_time: Timestamp,
+        ticket_price: Uint128,
+        winners: u32,
+    },
+    /// Draws the raffle once its entry window has closed, through the randomness provider
+    /// when one is configured. Without one, only the owner may draw.
+    DrawRaffle {},
+    /// Mints the sender's winning tickets and refunds the rest once the raffle is drawn.
+    ClaimRaffle {},
+    /// Owner only. Auctions `token_id`, which must not be an id of the regular sale, until
+    /// `end_time`. Bids are placed with `Cw20HookMsg::Bid`.
+    CreateAuction {
+        token_id: String,
+        token_uri: String,
+        reserve_price: Uint128,
+        end_time: Timestamp,
+    },
+    /// Owner or highest bidder, after the auction ends. Mints the token to the highest bidder
+    /// and releases their bid to the proceeds; without bids the auction just closes.
+    SettleAuction {
+        token_id: String,
+    },
+    /// Owner only. Sets or, with `None`, removes the owner key.
+    SetOwnerKey {
+        pubkey: Option<Binary>,
+    },
+    /// Runs `payload.msg` as the owner. `signature` is a 64-byte secp256k1 signature by the
+    /// owner key over `sha256(contract_address ++ json(payload))`.
+    ExecuteSigned {
+        payload: AdminPayload,
+        signature: Binary,
+    },
+    /// Owner only. Reads the next `limit` child tokens and their owners into the holder
+    /// snapshot, starting a new one when `restart` is set or none exists. Tokens moved between
+    /// pages are counted under whoever held them when their page was read.
+    SnapshotHolders {
+        restart: bool,
+        limit: Option<u32>,
+    },
+}
+
+/// Actions that can be embedded in the `msg` of a cw20 `Send` to this contract. An empty
+/// payload is treated as `Mint` with default options.
+#[cw_serde]
+pub enum Cw20HookMsg {
+    /// Mints tokens to the cw20 sender. The sent amount must cover the price of all of them.
+    Mint(MintOptions),
+    /// Reveals the secret of an aged `CommitMint` and mints as `Mint` would. Required instead
+    /// of `Mint` while `commit_reveal_blocks` is set.
+    RevealMint {
+        secret: Binary,
+        options: MintOptions,
+    },
+    /// Mints as many tokens as the sent amount pays for, within the remaining supply and the
+    /// sender's limits, and refunds the rest. `quantity` is ignored, and factory collections
+    /// and drop rounds are not supported.
+    MintMax(MintOptions),
+    /// Relayer only. Mints for a signed intent, paid for by the relayer. The intent's recipient
+    /// is the buyer for limits and refunds. `signature` is a 64-byte secp256k1 signature by
+    /// the recipient's intent key over `sha256(contract_address ++ json(intent))`.
+    RelayMint {
+        intent: MintIntent,
+        signature: Binary,
+    },
+    /// Buys a listed token. The sent amount must cover the listing price; any excess is
+    /// refunded.
+    Buy { token_id: String },
+    /// Redeems a signed voucher. `signature` is a 64-byte secp256k1 signature by the voucher
+    /// signer over `sha256(contract_address ++ json(voucher))`. Vouchers mint outside the
+    /// numbered supply and ignore the sale window, phases and per-address limits; the
+    /// voucher's own expiry is the only time check.
+    RedeemVoucher { voucher: Voucher, signature: Binary },
+    /// Bids the sent amount on an auction. It must reach the reserve price and beat the
+    /// highest bid, which is refunded.
+    Bid { token_id: String },
+    /// Buys `quantity` raffle tickets at the ticket price; any excess is refunded.
+    BuyTickets { quantity: u32 },
+    /// Extends the expiry of `token_id` by the renewal period, from now if it already expired.
+    /// The sent amount must cover the renewal price; any excess is refunded.
+    Renew { token_id: String },
+    /// Rents a deposited token for `days`. The payment goes to the lender.
+    Rent { token_id: String, days: u32 },
+    /// Adds the sent amount to the staking reward pool. Must be sent by the reward token.
+    FundStakingRewards {},
+    /// Credits the sent amount to the sender's deposit for `MintFromBalance`. Only the primary
+    /// cw20 is accepted.
+    Deposit {},
+}
+
+/// Actions that can be embedded in the `msg` of a cw721 `SendNft` to this contract.
+#[cw_serde]
+pub enum Cw721HookMsg {
+    /// Burns the token and pays `refund_bps` of its mint price back to the sender.
+    Refund {},
+    /// Deposits the token towards the configured `UpgradeRule`. Once enough tokens are
+    /// deposited they are all burned and one upgraded token is minted to the sender.
+    Upgrade {},
+    /// Lists the token for sale at `price` in the payment cw20.
+    List { price: Uint128 },
+    /// Deposits the token for rent at `daily_price` in the payment cw20.
+    OfferRental { daily_price: Uint128 },
+    /// Stakes the token. It earns the configured per-block reward until unstaked.
+    Stake {},
+    /// Burns the token and returns its full escrowed payment to the sender. Only before
+    /// `Reveal`, and only with `reveal_escrow`.
+    RefundEscrow {},
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct MintOptions {
+    /// Number of tokens to mint. Defaults to 1.
+    pub quantity: Option<u32>,
+    /// Address credited with a share of the payment when referral rewards are enabled.
+    pub referrer: Option<String>,
+    /// Address that receives the tokens. Defaults to the cw20 sender, who stays the buyer
+    /// for limits, referrals and refunds.
+    pub recipient: Option<String>,
+    /// Short message stored with every minted token, up to `MAX_MEMO_LENGTH` bytes. It is
+    /// returned by `GiftInfo` and `Provenance`.
+    pub memo: Option<String>,
+    /// Mint from a factory collection instead of the main one. Only `quantity` and
+    /// `recipient` apply to factory collections.
+    pub collection_id: Option<u32>,
+    /// Mint from a drop round of the main collection, at the round's price. Only `quantity`
+    /// and `recipient` apply to drop rounds.
+    pub drop_id: Option<u32>,
+    /// Receiver on another chain. The tokens are minted to this contract and sent on through
+    /// the configured ICS-721 bridge; cannot be combined with `recipient`.
+    pub remote_recipient: Option<String>,
+    /// On-chain metadata for every token of this mint, in place of the prepared or generated
+    /// metadata. Only accepted when the owner enabled custom metadata.
+    pub metadata: Option<Box<Metadata>>,
+    /// Proof of membership in the Merkle allowlist, accepted wherever the allowlist is.
+    pub merkle_proof: Option<MerkleProof>,
+    /// Gatekeeper permit for the buyer; required when a gatekeeper is configured.
+    pub permit: Option<SignedMintPermit>,
+    /// When fewer than `quantity` tokens are left, mint those instead of failing and refund
+    /// the rest of the payment.
+    pub allow_partial: Option<bool>,
+    /// Token URI for every token of this mint, in place of the collection's. Must start with
+    /// one of the owner's allowed URI prefixes.
+    pub token_uri: Option<String>,
+}
+
+#[cw_serde]
+pub struct MerkleProof {
+    /// `merkle_version` of the root the proof was generated against.
+    pub root_version: u32,
+    /// Sibling hashes from the leaf up to the root.
+    pub proof: Vec<Binary>,
+}
+
+/// Messages accepted through the `sudo` entry point, e.g. from chain governance.
+#[cw_serde]
+pub enum SudoMsg {
+    UpdateUnitPrice {
+        price: Uint128,
+    },
+    Pause {},
+    Resume {},
+    /// Changes the collection size. Cannot drop below the number of tokens already minted.
+    UpdateMaxTokens {
+        max_tokens: u64,
+    },
 }
 
 #[cw_serde]
@@ -29,14 +1088,435 @@ pub struct Cw20ReceiveMsg {
     pub msg: Binary,
 }
 
+/// The subset of the cw20 execute interface this contract and `FixedPriceContract` send.
+#[cw_serde]
+pub enum Cw20ExecuteMsg {
+    Transfer {
+        recipient: String,
+        amount: Uint128,
+    },
+    Send {
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    TransferFrom {
+        owner: String,
+        recipient: String,
+        amount: Uint128,
+    },
+    Burn {
+        amount: Uint128,
+    },
+}
+
+/// Query understood by the configured price oracle.
+#[cw_serde]
+pub enum OracleQueryMsg {
+    /// Exchange rate for the cw20 token at `denom` (its contract address).
+    Price { denom: String },
+}
+
+#[cw_serde]
+pub struct OraclePriceResponse {
+    /// Smallest units of the token per smallest USD unit.
+    pub rate: Decimal,
+    pub updated_at: Timestamp,
+}
+
+/// The subset of the cw20 query interface this contract makes.
+#[cw_serde]
+pub enum Cw20QueryMsg {
+    Balance { address: String },
+    TokenInfo {},
+}
+
+#[cw_serde]
+pub struct Cw20BalanceResponse {
+    pub balance: Uint128,
+}
+
+/// The subset of the cw4 query interface this contract makes.
+#[cw_serde]
+pub enum Cw4QueryMsg {
+    Member {
+        addr: String,
+        at_height: Option<u64>,
+    },
+}
+
+#[cw_serde]
+pub struct Cw4MemberResponse {
+    /// `None` for non-members.
+    pub weight: Option<u64>,
+}
+
+/// The subset of the cw4-group execute interface this contract uses.
+#[cw_serde]
+pub enum Cw4GroupExecuteMsg {
+    UpdateMembers {
+        remove: Vec<String>,
+        add: Vec<Cw4Member>,
+    },
+}
+
+#[cw_serde]
+pub struct Cw4Member {
+    pub addr: String,
+    pub weight: u64,
+}
+
+/// Asset as named by Oraiswap.
+#[cw_serde]
+pub enum SwapAssetInfo {
+    Token { contract_addr: Addr },
+    NativeToken { denom: String },
+}
+
+impl From<Currency> for SwapAssetInfo {
+    fn from(currency: Currency) -> Self {
+        match currency {
+            Currency::Cw20(contract_addr) => SwapAssetInfo::Token { contract_addr },
+            Currency::Native(denom) => SwapAssetInfo::NativeToken { denom },
+        }
+    }
+}
+
+#[cw_serde]
+pub enum SwapOperation {
+    OraiSwap {
+        offer_asset_info: SwapAssetInfo,
+        ask_asset_info: SwapAssetInfo,
+    },
+}
+
+/// The subset of the Oraiswap router interface this contract uses. Cw20 offers are sent with
+/// this as the `Send` hook; native offers execute it directly.
+#[cw_serde]
+pub enum SwapRouterMsg {
+    ExecuteSwapOperations {
+        operations: Vec<SwapOperation>,
+        minimum_receive: Option<Uint128>,
+        to: Option<String>,
+    },
+}
+
+#[cw_serde]
+pub enum SwapRouterQueryMsg {
+    SimulateSwapOperations {
+        offer_amount: Uint128,
+        operations: Vec<SwapOperation>,
+    },
+}
+
+#[cw_serde]
+pub struct SimulateSwapOperationsResponse {
+    pub amount: Uint128,
+}
+
+/// The subset of the Oraichain AI oracle interface this contract uses. `input` is the
+/// token id the generated metadata is for.
+#[cw_serde]
+pub enum AiOracleExecuteMsg {
+    Request {
+        service: String,
+        input: Option<String>,
+        threshold: u64,
+    },
+}
+
+#[cw_serde]
+pub struct Cw20TokenInfoResponse {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub total_supply: Uint128,
+}
+
+#[cw_serde]
+pub struct MigrateMsg {}
+
+/// Instantiate message of cw721-non-transferable, used for soulbound collections.
 #[cw_serde]
-pub enum MigrateMsg {}
+pub struct NonTransferableInstantiateMsg {
+    pub admin: Option<String>,
+    pub name: String,
+    pub symbol: String,
+    pub minter: String,
+}
 
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
     #[returns(ConfigResponse)]
     GetConfig {},
+    #[returns(PendingOwnerResponse)]
+    PendingOwner {},
+    #[returns(SupplyInfoResponse)]
+    SupplyInfo {},
+    #[returns(MintsOfResponse)]
+    MintsOf { address: String },
+    /// Unused deposit of an address.
+    #[returns(DepositResponse)]
+    Deposit { address: String },
+    /// Unredeemed mint credits of an address.
+    #[returns(MintCreditsResponse)]
+    MintCredits { address: String },
+    /// Original mint data of a token: first buyer, price paid, block height and time.
+    #[returns(ProvenanceResponse)]
+    Provenance { token_id: String },
+    /// `Provenance` and `Receipt` of a token together with its owner, approvals, URI and
+    /// extension as reported by the child cw721, in one query.
+    #[returns(NftDetailsResponse)]
+    NftDetails { token_id: String },
+    /// Progress of the AI oracle request for a token's metadata.
+    #[returns(AiMetadataResponse)]
+    AiMetadata { token_id: String },
+    /// Mint ledger in token id order, ascending unless `order` says otherwise. Entries are
+    /// never rewritten, so paging with `start_after` yields a stable snapshot for off-chain
+    /// tooling.
+    #[returns(MintsResponse)]
+    Mints {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        order: Option<SortOrder>,
+    },
+    /// cw2981-style royalty lookup. Returns a zero amount when no royalty is configured.
+    #[returns(RoyaltyInfoResponse)]
+    RoyaltyInfo {
+        token_id: String,
+        sale_price: Uint128,
+    },
+    #[returns(CurrentPriceResponse)]
+    CurrentPrice {},
+    #[returns(ActiveTierResponse)]
+    ActiveTier {},
+    #[returns(ReferrerResponse)]
+    Referrer { address: String },
+    /// All referrers ordered by address, for building leaderboards off-chain.
+    #[returns(ReferrersResponse)]
+    Referrers {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// The mint schedule ordered by phase id.
+    #[returns(PhasesResponse)]
+    Phases {},
+    #[returns(ActivePhaseResponse)]
+    ActivePhase {},
+    /// The phase running now, if any, and the next one to start, with the seconds left until
+    /// each boundary at the current block.
+    #[returns(ScheduleResponse)]
+    Schedule {},
+    #[returns(RevealedResponse)]
+    Revealed {},
+    #[returns(ListingInfo)]
+    Listing { token_id: String },
+    /// Open listings ordered by token id.
+    #[returns(ListingsResponse)]
+    Listings {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        order: Option<SortOrder>,
+    },
+    /// Accepted payment tokens, starting with `cw20_address` at its `unit_price`.
+    #[returns(PaymentTokensResponse)]
+    PaymentTokens {},
+    #[returns(GiftInfoResponse)]
+    GiftInfo { token_id: String },
+    #[returns(CollectionResponse)]
+    Collection { collection_id: u32 },
+    /// Factory collections ordered by id.
+    #[returns(CollectionsResponse)]
+    Collections {
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    /// Rounds archived by `CloneCollection`, oldest first.
+    #[returns(ArchivedRoundsResponse)]
+    ArchivedRounds {
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    /// Rental state of a deposited token.
+    #[returns(RentalInfo)]
+    Rental { token_id: String },
+    /// Tokens currently rented out, ordered by token id.
+    #[returns(RentalsResponse)]
+    ActiveRentals {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Forwards a raw query to the child cw721 and returns its response unchanged,
+    /// e.g. `OwnerOf`, `NftInfo` or `NumTokens`.
+    #[returns(Binary)]
+    Cw721 { msg: Binary },
+    /// Pending waitlist entries in the order they will be filled.
+    #[returns(WaitlistResponse)]
+    Waitlist {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Per-token price in every accepted currency, primary cw20 first.
+    #[returns(PricesResponse)]
+    Prices {},
+    /// Proceeds credited to a creator and not yet claimed, one entry per currency.
+    #[returns(ClaimsResponse)]
+    Claims { address: String },
+    /// Entries of a phase's allowlist, ordered by address.
+    #[returns(PhaseAllowlistResponse)]
+    PhaseAllowlist {
+        phase_id: u8,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        order: Option<SortOrder>,
+    },
+    /// Eligibility of `address` for the active phase, or the next scheduled one when none is
+    /// active.
+    #[returns(AllowlistStatusResponse)]
+    AllowlistStatus { address: String },
+    #[returns(MintGateResponse)]
+    MintGate {},
+    #[returns(StakeInfo)]
+    Stake { token_id: String },
+    /// Staked tokens in ascending token id order, optionally only those of `owner`.
+    #[returns(StakesResponse)]
+    Stakes {
+        owner: Option<String>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Vesting progress of the proceeds in `token`, which defaults to `cw20_address`.
+    #[returns(VestingResponse)]
+    Vesting { token: Option<String> },
+    #[returns(EscrowResponse)]
+    Escrow { token_id: String },
+    #[returns(TraitTableResponse)]
+    TraitTable {},
+    #[returns(PendingMintResponse)]
+    PendingMint { job_id: String },
+    /// Sale and supply figures combined with live queries to the child cw721.
+    #[returns(CollectionStatsResponse)]
+    CollectionStats {},
+    #[returns(MintHooksResponse)]
+    MintHooks {},
+    /// Permissions of one operator; all `false` for addresses that are not operators.
+    #[returns(OperatorPerms)]
+    Operator { address: String },
+    #[returns(OperatorsResponse)]
+    Operators {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// What `address` may do: everything for the owner, its operator flags otherwise.
+    #[returns(PermissionsResponse)]
+    Permissions { address: String },
+    #[returns(PendingPaymentTokenResponse)]
+    PendingPaymentToken {},
+    #[returns(BlocklistedResponse)]
+    Blocklisted { address: String },
+    #[returns(BlocklistResponse)]
+    Blocklist {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Mints whose block time falls between `start` and `end`, both inclusive, in time order.
+    /// Page with the last token id seen as `start_after`.
+    #[returns(MintsResponse)]
+    MintsBetween {
+        start: Timestamp,
+        end: Timestamp,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Tokens minted to `address` in token id order, from the contract's own
+    /// ledger. Transfers on the cw721 side don't affect the result.
+    #[returns(MintsResponse)]
+    TokensByBuyer {
+        address: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        order: Option<SortOrder>,
+    },
+    /// Exact amount of the primary cw20 `address` would pay for `quantity` tokens right now,
+    /// with phase, tier, allowlist and partner discounts applied. With a Merkle root set the
+    /// quote assumes the address holds a valid proof.
+    #[returns(PriceQuoteResponse)]
+    PriceQuote { address: String, quantity: u32 },
+    /// Whether `address` could buy `quantity` tokens with the primary cw20 right now, and if
+    /// not the first rule that would reject it. Payment amount is not checked; see
+    /// `PriceQuote`. With a Merkle root set, allowlist membership is assumed.
+    #[returns(CanMintResponse)]
+    CanMint { address: String, quantity: u32 },
+    /// Drop rounds ordered by id.
+    #[returns(DropsResponse)]
+    Drops {
+        start_after: Option<u32>,
+        limit: Option<u32>,
+        order: Option<SortOrder>,
+    },
+    /// Total of the payment cw20 burned so far.
+    #[returns(BurnedResponse)]
+    Burned {},
+    #[returns(RelayerResponse)]
+    Relayer { address: String },
+    /// Whether `address` has already used the relayed mint intent `nonce`.
+    #[returns(NonceUsedResponse)]
+    IntentNonceUsed { address: String, nonce: u64 },
+    /// Whether `address` has already used the gatekeeper permit `nonce`.
+    #[returns(NonceUsedResponse)]
+    PermitNonceUsed { address: String, nonce: u64 },
+    #[returns(TokenExpiryResponse)]
+    TokenExpiry { token_id: String },
+    /// Tokens whose expiry has passed, by index.
+    #[returns(ExpiredTokensResponse)]
+    ExpiredTokens {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// This contract's cw2 version alongside the linked cw721's code id and cw2 version.
+    #[returns(ContractInfoResponse)]
+    ContractInfo {},
+    #[returns(TokenCodeIdsResponse)]
+    TokenCodeIds {},
+    #[returns(ReceiptInfo)]
+    Receipt { token_id: String },
+    #[returns(ReceiptsResponse)]
+    Receipts {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Explicit per-token prices in ascending token id order.
+    #[returns(TokenPricesResponse)]
+    TokenPrices {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(RaffleResponse)]
+    Raffle {},
+    /// The address's unclaimed tickets and, once drawn, which of them won.
+    #[returns(RaffleTicketsResponse)]
+    RaffleTickets { address: String },
+    #[returns(AuctionInfo)]
+    Auction { token_id: String },
+    #[returns(AuctionsResponse)]
+    Auctions {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// The drop round open now, if any, and the next one to open.
+    #[returns(DropScheduleResponse)]
+    DropSchedule {},
+    /// sg721-style collection metadata. Before any is set, only the creator (the owner) is known.
+    #[returns(CollectionInfoResponse)]
+    CollectionInfo {},
+    /// Holders recorded by `SnapshotHolders` in address order, with the number of tokens each
+    /// held.
+    #[returns(HolderSnapshotResponse)]
+    HolderSnapshot {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
 
 #[cw_serde]
@@ -44,11 +1524,710 @@ pub struct ConfigResponse {
     pub owner: Addr,
     pub cw20_address: Addr,
     pub cw721_address: Option<Addr>,
-    pub max_tokens: u32,
+    pub max_tokens: u64,
     pub unit_price: Uint128,
     pub name: String,
     pub symbol: String,
     pub token_uri: String,
     pub extension: Extension,
-    pub unused_token_id: u32,
-}
\ No newline at end of file
+    pub unused_token_id: u64,
+    pub treasury: Option<Addr>,
+    pub paused: bool,
+    pub start_time: Option<Timestamp>,
+    pub end_time: Option<Timestamp>,
+    pub token_uri_mode: TokenUriMode,
+    pub shuffle_token_ids: bool,
+    pub reserved_supply: Option<u64>,
+    pub reserved_minted: u64,
+    pub strict_payment: bool,
+    pub mint_limit_per_address: Option<u32>,
+    pub token_code_id: u64,
+    pub cw721_instantiate_error: Option<String>,
+    pub price_curve: Option<PriceCurve>,
+    pub price_tiers: Vec<PriceTier>,
+    pub presale: Option<Presale>,
+    pub referral_bps: Option<u16>,
+    pub payment_splits: Vec<PaymentSplit>,
+    pub onchain_metadata: bool,
+    pub placeholder_uri: Option<String>,
+    pub revealed: bool,
+    pub frozen: bool,
+    pub refund_bps: Option<u16>,
+    pub total_refunded: Uint128,
+    pub upgrade_rule: Option<UpgradeRule>,
+    pub upgrades_minted: u32,
+    pub market_fee_bps: u16,
+    pub crowdfund: Option<Crowdfund>,
+    pub crowdfund_sold: u64,
+    pub crowdfund_settled: bool,
+    pub price_oracle: Option<PriceOracle>,
+    pub voucher_signer: Option<Binary>,
+    pub max_mints_per_tx: Option<u32>,
+    pub max_mints_per_block: Option<u32>,
+    pub cw721_salt: Option<Binary>,
+    pub cw721_gas_limit: Option<u64>,
+    pub cw721_reply_on: ReplyOn,
+    /// Tokens bought through the sale, excluding reserved mints.
+    pub tokens_sold: u64,
+    pub tokens_remaining: u64,
+    pub active_phase: Option<PhaseInfo>,
+    /// Public price of the next token in the payment cw20, before any oracle conversion.
+    /// `None` while phases are scheduled but none is active.
+    pub current_price: Option<Uint128>,
+    pub ibc_counterparty_port: Option<String>,
+    pub ibc_connection_id: Option<String>,
+    pub ibc_counterparty_channel: Option<String>,
+    pub ics721_bridge: Option<Ics721Bridge>,
+    pub soulbound: bool,
+    pub cw721_admin: Option<Addr>,
+    pub cw721_label: String,
+    pub cw721_funds: Vec<Coin>,
+    pub closed: bool,
+    pub custom_metadata: bool,
+    pub open_edition: bool,
+    pub final_supply: Option<u64>,
+    pub supply_governor: Option<Addr>,
+    pub waitlist: bool,
+    pub waitlist_escrow: Uint128,
+    pub native_denom: Option<String>,
+    pub native_price: Option<Uint128>,
+    pub creators: Vec<Creator>,
+    pub allowlist_count: u32,
+    pub merkle_root: Option<Binary>,
+    pub merkle_version: u32,
+    pub partner_discount: Option<PartnerDiscount>,
+    pub mint_gate: Option<MintGate>,
+    pub staking: Option<Staking>,
+    pub staking_pool: Uint128,
+    pub vesting: Option<Vesting>,
+    pub reveal_escrow: bool,
+    pub token_id_template: Option<TokenIdTemplate>,
+    pub randomness: Option<Randomness>,
+    pub pending_mints: u64,
+    pub unique_minters: u64,
+    pub retired: bool,
+    pub renounced: bool,
+    pub failed_mints: u64,
+    pub drop_reserved: u64,
+    pub protocol_fee: Option<ProtocolFee>,
+    pub burn_bps: Option<u16>,
+    pub cw20_symbol: Option<String>,
+    pub cw20_decimals: Option<u8>,
+    pub commit_reveal_blocks: Option<u64>,
+    pub gatekeeper: Option<Binary>,
+    pub owner_key: Option<Binary>,
+    pub renewal: Option<Renewal>,
+    pub trade_in: Option<TradeIn>,
+    pub raffle_reserved: u64,
+    pub raffle_escrow: Uint128,
+    pub auction_escrow: Uint128,
+    pub cw721_version: Cw721Version,
+    pub membership: Option<Membership>,
+    pub swap_route: Option<SwapRoute>,
+    pub ai_oracle: Option<AiOracle>,
+    pub allowed_uri_prefixes: Vec<String>,
+    pub deposit_escrow: Uint128,
+}
+
+#[cw_serde]
+pub struct PendingOwnerResponse {
+    pub pending_owner: Option<Addr>,
+    pub expiry: Option<Expiration>,
+}
+
+#[cw_serde]
+pub enum SaleStatus {
+    NotStarted,
+    Active,
+    Paused,
+    SoldOut,
+    Ended,
+    Closed,
+}
+
+#[cw_serde]
+pub struct CollectionStatsResponse {
+    pub minted: u64,
+    /// Minted tokens no longer in the child cw721. `None` when it could not be queried.
+    pub burned: Option<u64>,
+    /// Live cw721 `NumTokens`.
+    pub num_tokens: Option<u64>,
+    /// Distinct addresses that minted, as cached at mint time. Later transfers are not
+    /// tracked.
+    pub unique_holders: u64,
+    pub sale_status: SaleStatus,
+    pub cw20_address: Addr,
+    pub cw721_address: Option<Addr>,
+    /// Live cw721 `ContractInfo`.
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+}
+
+#[cw_serde]
+pub struct SupplyInfoResponse {
+    pub minted: u64,
+    /// Ids whose child mint failed. They are never reissued, so they come out of `max_tokens`
+    /// like `minted` does.
+    pub failed: u64,
+    pub remaining: u64,
+    /// Zero for open editions.
+    pub max_tokens: u64,
+    pub sold_out: bool,
+    pub open_edition: bool,
+    /// Set once a timed open edition has closed.
+    pub final_supply: Option<u64>,
+}
+
+#[cw_serde]
+pub struct DepositResponse {
+    pub address: Addr,
+    pub balance: Uint128,
+}
+
+#[cw_serde]
+pub struct MintCreditsResponse {
+    pub address: Addr,
+    pub credits: u32,
+}
+
+#[cw_serde]
+pub struct MintsOfResponse {
+    pub address: Addr,
+    pub minted: u32,
+    /// How many more tokens the address may buy; `None` when there is no per-address cap.
+    pub remaining: Option<u32>,
+}
+
+#[cw_serde]
+pub struct MintInfo {
+    pub token_id: String,
+    pub buyer: Addr,
+    pub time: Timestamp,
+    pub price: Uint128,
+}
+
+#[cw_serde]
+pub struct ProvenanceResponse {
+    pub token_id: String,
+    pub buyer: Addr,
+    pub price: Uint128,
+    /// Cw20 the token was paid in; `None` for the primary payment token.
+    pub payment_token: Option<Addr>,
+    pub height: u64,
+    pub time: Timestamp,
+    /// URI set with `UpdateTokenUri`, replacing the one the token was minted with.
+    pub token_uri: Option<String>,
+    pub uri_updated: Option<Timestamp>,
+    pub memo: Option<String>,
+}
+
+#[cw_serde]
+pub struct NftDetailsResponse {
+    pub provenance: ProvenanceResponse,
+    /// `None` for tokens minted without a receipt, e.g. before receipts were recorded.
+    pub receipt: Option<ReceiptInfo>,
+    pub owner: String,
+    pub approvals: Vec<Approval>,
+    pub token_uri: Option<String>,
+    pub extension: Option<Metadata>,
+}
+
+#[cw_serde]
+pub struct AiMetadataResponse {
+    pub token_id: String,
+    /// `None` when no request was made for the token.
+    pub status: Option<AiMetadataStatus>,
+    /// The current URI override, set by the oracle once completed.
+    pub token_uri: Option<String>,
+}
+
+#[cw_serde]
+pub struct MintsResponse {
+    pub mints: Vec<MintInfo>,
+}
+
+#[cw_serde]
+pub struct RoyaltyInfoResponse {
+    pub address: String,
+    pub royalty_amount: Uint128,
+}
+
+#[cw_serde]
+pub struct CurrentPriceResponse {
+    /// Price of the next token to be minted.
+    pub current_price: Uint128,
+    /// Price of the token after that.
+    pub next_price: Uint128,
+}
+
+#[cw_serde]
+pub struct ActiveTierResponse {
+    /// Position of the active tier in the tier table; `None` once all tiers are sold.
+    pub index: Option<u32>,
+    pub tier: Option<PriceTier>,
+    /// Tokens left at the active tier's price.
+    pub remaining_in_tier: u64,
+}
+
+#[cw_serde]
+pub struct ReferrerResponse {
+    pub address: Addr,
+    /// Tokens minted through this referrer.
+    pub referrals: u32,
+    /// Total cw20 rewards paid to this referrer.
+    pub earned: Uint128,
+}
+
+#[cw_serde]
+pub struct ReferrersResponse {
+    pub referrers: Vec<ReferrerResponse>,
+}
+
+#[cw_serde]
+pub struct PhaseInfo {
+    pub id: u8,
+    pub phase: Phase,
+}
+
+#[cw_serde]
+pub struct PhasesResponse {
+    pub phases: Vec<PhaseInfo>,
+}
+
+#[cw_serde]
+pub struct ActivePhaseResponse {
+    /// `None` when no phase is active, including when no schedule is configured.
+    pub phase: Option<PhaseInfo>,
+}
+
+#[cw_serde]
+pub struct ScheduleResponse {
+    pub current: Option<PhaseInfo>,
+    /// End of the current phase. `None` when it is open-ended or no phase is active.
+    pub current_ends_at: Option<Timestamp>,
+    pub next: Option<PhaseInfo>,
+    pub next_starts_at: Option<Timestamp>,
+    pub seconds_until_end: Option<u64>,
+    pub seconds_until_next: Option<u64>,
+}
+
+#[cw_serde]
+pub struct RevealedResponse {
+    pub revealed: bool,
+    /// Base of the real token URIs once revealed; each token lives at `{base_uri}/{token_id}.json`.
+    pub base_uri: Option<String>,
+}
+
+#[cw_serde]
+pub struct ListingInfo {
+    pub token_id: String,
+    pub seller: Addr,
+    pub price: Uint128,
+}
+
+#[cw_serde]
+pub struct ListingsResponse {
+    pub listings: Vec<ListingInfo>,
+}
+
+#[cw_serde]
+pub struct PaymentTokenInfo {
+    pub address: Addr,
+    pub unit_price: Uint128,
+}
+
+#[cw_serde]
+pub struct PaymentTokensResponse {
+    pub tokens: Vec<PaymentTokenInfo>,
+}
+
+#[cw_serde]
+pub struct GiftInfoResponse {
+    pub token_id: String,
+    /// Buyer who paid for the token; `None` when it was not minted with a memo.
+    pub from: Option<Addr>,
+    pub memo: Option<String>,
+}
+
+#[cw_serde]
+pub struct CollectionResponse {
+    pub collection_id: u32,
+    pub collection: Collection,
+}
+
+#[cw_serde]
+pub struct CollectionsResponse {
+    pub collections: Vec<CollectionResponse>,
+}
+
+#[cw_serde]
+pub struct ArchivedRoundInfo {
+    pub round_id: u32,
+    pub round: ArchivedRound,
+}
+
+#[cw_serde]
+pub struct ArchivedRoundsResponse {
+    pub rounds: Vec<ArchivedRoundInfo>,
+}
+
+#[cw_serde]
+pub struct RentalInfo {
+    pub token_id: String,
+    pub lender: Addr,
+    pub daily_price: Uint128,
+    /// Current renter; `None` when the token is available.
+    pub renter: Option<Addr>,
+    pub expires: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct RentalsResponse {
+    pub rentals: Vec<RentalInfo>,
+}
+
+/// Packet sent by the counterparty contract after it has collected `paid` (in units of the
+/// payment cw20) from a user on its chain.
+#[cw_serde]
+pub struct MintPacket {
+    /// Address on this chain that receives the tokens.
+    pub recipient: String,
+    pub quantity: u32,
+    pub paid: Uint128,
+}
+
+/// Written once every child mint of the packet has replied. `Result` lists the tokens that
+/// were minted; the counterparty refunds any of the `quantity` missing from it.
+#[cw_serde]
+pub enum MintAck {
+    Result { token_ids: Vec<String> },
+    Error(String),
+}
+
+#[cw_serde]
+pub struct WaitlistInfo {
+    pub position: u64,
+    pub buyer: Addr,
+    pub quantity: u32,
+    pub paid: Uint128,
+}
+
+#[cw_serde]
+pub struct WaitlistResponse {
+    pub entries: Vec<WaitlistInfo>,
+}
+
+#[cw_serde]
+pub struct CurrencyPrice {
+    pub currency: Currency,
+    pub price: Uint128,
+}
+
+#[cw_serde]
+pub struct PricesResponse {
+    pub prices: Vec<CurrencyPrice>,
+}
+
+#[cw_serde]
+pub struct ClaimsResponse {
+    pub address: Addr,
+    pub claims: Vec<CreatorClaim>,
+}
+
+#[cw_serde]
+pub struct PhaseAllowlistInfo {
+    pub address: Addr,
+    pub price: Option<Uint128>,
+    pub mint_limit: Option<u32>,
+}
+
+#[cw_serde]
+pub struct PhaseAllowlistResponse {
+    pub phase_id: u8,
+    pub entries: Vec<PhaseAllowlistInfo>,
+}
+
+#[cw_serde]
+pub struct AllowlistStatusResponse {
+    /// Phase the status refers to; `None` without a phase schedule.
+    pub phase_id: Option<u8>,
+    pub phase_active: bool,
+    /// On the global allowlist or the phase's own allowlist.
+    pub allowlisted: bool,
+    /// Tokens the address may still buy; `None` when uncapped. Zero when the sale is restricted
+    /// to an allowlist the address is not on.
+    pub remaining: Option<u32>,
+    /// Unit price the address would pay.
+    pub price: Uint128,
+    /// When the address's global allowlist entry lapses, including once it has.
+    pub expires: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct MintGateResponse {
+    /// `None` when anyone may mint.
+    pub gate: Option<MintGate>,
+}
+
+#[cw_serde]
+pub struct StakeInfo {
+    pub token_id: String,
+    pub owner: Addr,
+    pub staked_height: u64,
+    /// Rewards that a claim would pay now, capped at the pool.
+    pub pending: Uint128,
+}
+
+#[cw_serde]
+pub struct StakesResponse {
+    pub stakes: Vec<StakeInfo>,
+}
+
+#[cw_serde]
+pub struct VestingResponse {
+    /// `None` when proceeds are not vested.
+    pub vesting: Option<Vesting>,
+    pub token: String,
+    /// Proceeds released so far, including those already withdrawn.
+    pub vested: Uint128,
+    pub withdrawn: Uint128,
+    /// Proceeds that can be withdrawn now.
+    pub claimable: Uint128,
+}
+
+#[cw_serde]
+pub struct EscrowResponse {
+    pub token_id: String,
+    /// `None` when nothing is escrowed for the token.
+    pub payment: Option<EscrowedPayment>,
+    /// Whether the payment can still be refunded, i.e. the collection is not revealed.
+    pub refundable: bool,
+}
+
+#[cw_serde]
+pub struct TraitTableResponse {
+    pub traits: Vec<TraitCategory>,
+}
+
+#[cw_serde]
+pub struct PendingMintResponse {
+    pub job_id: String,
+    /// `None` once the beacon arrived and the tokens were minted.
+    pub pending: Option<PendingMint>,
+}
+
+#[cw_serde]
+pub struct MintHooksResponse {
+    pub hooks: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct OperatorInfo {
+    pub address: Addr,
+    pub perms: OperatorPerms,
+}
+
+#[cw_serde]
+pub struct OperatorsResponse {
+    pub operators: Vec<OperatorInfo>,
+}
+
+#[cw_serde]
+pub struct PermissionsResponse {
+    pub is_owner: bool,
+    pub perms: OperatorPerms,
+}
+
+#[cw_serde]
+pub struct PendingPaymentTokenResponse {
+    pub address: Option<Addr>,
+    pub effective_at: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct BlocklistedResponse {
+    pub blocklisted: bool,
+}
+
+#[cw_serde]
+pub struct BlocklistResponse {
+    pub addresses: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct SnapshotHolder {
+    pub address: Addr,
+    pub tokens: u32,
+}
+
+#[cw_serde]
+pub struct HolderSnapshotResponse {
+    /// Block height at which the snapshot was started, `None` before the first one.
+    pub height: Option<u64>,
+    /// Whether every child token has been read.
+    pub complete: bool,
+    pub holders: Vec<SnapshotHolder>,
+}
+
+#[cw_serde]
+pub struct PriceQuoteResponse {
+    /// Total to send, after any partner discount.
+    pub price: Uint128,
+    /// Phase the quote was priced in, if any.
+    pub phase_id: Option<u8>,
+    /// Amount taken off for holding a partner collection token; zero otherwise.
+    pub partner_discount: Uint128,
+}
+
+#[cw_serde]
+pub struct CanMintResponse {
+    pub can_mint: bool,
+    /// Why the mint would fail; `None` when `can_mint` is true.
+    pub blocker: Option<MintBlocker>,
+}
+
+#[cw_serde]
+pub enum MintBlocker {
+    /// Closed, retired or the child cw721 is not instantiated yet.
+    Closed,
+    Paused,
+    NotStarted,
+    Ended,
+    SoldOut,
+    InvalidQuantity,
+    Blocklisted,
+    /// The address fails the configured mint gate.
+    NotEligible,
+    NotAllowlisted,
+    /// A per-address, per-phase, per-transaction or per-block limit would be exceeded.
+    CapReached,
+    /// Phases are scheduled but none is active, or the active one is claimed with `Claim {}`.
+    WrongPhase,
+}
+
+#[cw_serde]
+pub struct DropInfo {
+    pub id: u32,
+    pub round: DropRound,
+}
+
+#[cw_serde]
+pub struct DropsResponse {
+    pub drops: Vec<DropInfo>,
+}
+
+#[cw_serde]
+pub struct BurnedResponse {
+    pub burned: Uint128,
+}
+
+#[cw_serde]
+pub struct RelayerResponse {
+    pub relayer: bool,
+}
+
+#[cw_serde]
+pub struct NonceUsedResponse {
+    pub used: bool,
+}
+
+#[cw_serde]
+pub struct TokenExpiryResponse {
+    /// `None` for tokens minted without `renewal`.
+    pub expiry: Option<Timestamp>,
+    pub expired: bool,
+    pub revoked: bool,
+}
+
+#[cw_serde]
+pub struct ExpiredTokensResponse {
+    pub token_ids: Vec<String>,
+}
+
+#[cw_serde]
+pub struct ContractInfoResponse {
+    pub contract: ContractVersion,
+    /// `None` without a linked cw721 or when it does not answer.
+    pub cw721_code_id: Option<u64>,
+    /// `None` without a linked cw721 or when it stores no cw2 version.
+    pub cw721_version: Option<ContractVersion>,
+}
+
+#[cw_serde]
+pub struct TokenCodeIdsResponse {
+    pub current: u64,
+    /// Every value `token_code_id` has held, oldest first, ending with `current`.
+    pub history: Vec<u64>,
+}
+
+#[cw_serde]
+pub struct ReceiptInfo {
+    pub token_id: String,
+    pub payer: Addr,
+    pub recipient: Addr,
+    pub amount: Uint128,
+    pub currency: Currency,
+    pub phase_id: Option<u8>,
+    pub height: u64,
+}
+
+#[cw_serde]
+pub struct ReceiptsResponse {
+    pub receipts: Vec<ReceiptInfo>,
+}
+
+#[cw_serde]
+pub struct TokenPriceInfo {
+    pub token_id: String,
+    pub price: Uint128,
+}
+
+#[cw_serde]
+pub struct TokenPricesResponse {
+    pub prices: Vec<TokenPriceInfo>,
+}
+
+#[cw_serde]
+pub struct RaffleResponse {
+    pub raffle: Option<Raffle>,
+}
+
+#[cw_serde]
+pub struct RaffleTicketsResponse {
+    pub tickets: Vec<u32>,
+    /// `None` until the raffle is drawn.
+    pub winning: Option<Vec<u32>>,
+}
+
+#[cw_serde]
+pub struct AuctionInfo {
+    pub token_id: String,
+    pub auction: Auction,
+}
+
+#[cw_serde]
+pub struct AuctionsResponse {
+    pub auctions: Vec<AuctionInfo>,
+}
+
+#[cw_serde]
+pub struct DropScheduleResponse {
+    pub current: Option<DropInfo>,
+    pub next: Option<DropInfo>,
+}
+
+#[cw_serde]
+pub struct CollectionRoyaltyInfo {
+    pub payment_address: Addr,
+    pub share: Decimal,
+}
+
+#[cw_serde]
+pub struct CollectionInfoResponse {
+    pub creator: Addr,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub external_link: Option<String>,
+    pub explicit_content: bool,
+    pub royalty_info: Option<CollectionRoyaltyInfo>,
+}
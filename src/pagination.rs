@@ -0,0 +1,52 @@
+//! `start_after`/`limit` handling shared by the list queries.
+
+use cosmwasm_std::Order;
+use cw_storage_plus::{Bound, PrimaryKey};
+
+use crate::msg::SortOrder;
+
+pub const DEFAULT_LIMIT: u32 = 10;
+pub const MAX_LIMIT: u32 = 30;
+
+/// Page size for a requested `limit`: `DEFAULT_LIMIT` when unset, never above `MAX_LIMIT`.
+pub fn clamp_limit(limit: Option<u32>) -> usize {
+    limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize
+}
+
+/// Range bounds resuming after `start_after`, walking the keys in `order` (ascending by
+/// default). Pass the result straight to `range(storage, min, max, order)`.
+pub fn range_bounds<'a, K: PrimaryKey<'a>>(
+    start_after: Option<K>,
+    order: Option<SortOrder>,
+) -> (Option<Bound<'a, K>>, Option<Bound<'a, K>>, Order) {
+    let start = start_after.map(Bound::exclusive);
+    match order.unwrap_or_default() {
+        SortOrder::Ascending => (start, None, Order::Ascending),
+        SortOrder::Descending => (None, start, Order::Descending),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limit_is_clamped() {
+        assert_eq!(clamp_limit(None), DEFAULT_LIMIT as usize);
+        assert_eq!(clamp_limit(Some(5)), 5);
+        assert_eq!(clamp_limit(Some(MAX_LIMIT + 1)), MAX_LIMIT as usize);
+    }
+
+    #[test]
+    fn descending_pages_end_before_start_after() {
+        let (min, max, order) = range_bounds(Some(7u64), Some(SortOrder::Descending));
+        assert!(min.is_none());
+        assert!(matches!(max, Some(Bound::Exclusive((7, _)))));
+        assert!(matches!(order, Order::Descending));
+
+        let (min, max, order) = range_bounds(Some(7u64), None);
+        assert!(matches!(min, Some(Bound::Exclusive((7, _)))));
+        assert!(max.is_none());
+        assert!(matches!(order, Order::Ascending));
+    }
+}
@@ -1,13 +1,617 @@
+use std::fmt;
+
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cw721_base::Extension;
 
-use cosmwasm_std::{Addr, Uint128};
-use cw_storage_plus::Item;
+use cosmwasm_std::{
+    to_vec, Addr, Binary, Coin, Empty, HexBinary, IbcEndpoint, ReplyOn, StdError, StdResult,
+    Storage, Timestamp, Uint128,
+};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+use cw_utils::Expiration;
+
+/// How the configured `token_uri` is turned into a minted token's URI.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenUriMode {
+    /// Every token gets `token_uri` verbatim.
+    #[default]
+    Fixed,
+    /// `token_uri` is a base; each token gets `{token_uri}/{token_id}.json`.
+    Base,
+}
+
+/// cw721-base release line of the child collection, which decides how its minter is managed.
+/// Both encode `Mint` and `InstantiateMsg` the same way.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw721Version {
+    /// cw721-base 0.15 and earlier: the minter is fixed at instantiation.
+    #[default]
+    Legacy,
+    /// cw721-base 0.16 and later: the minter is the collection's cw-ownable owner.
+    Ownable,
+}
+
+/// Bonding curve applied on top of `unit_price`, indexed by the number of tokens minted.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceCurve {
+    /// `unit_price + increment * minted`
+    Linear { increment: Uint128 },
+    /// `unit_price * (1 + growth_bps / 10_000) ^ minted`
+    Exponential { growth_bps: u16 },
+}
+
+/// `supply` consecutive tokens sold at `price`. Tiers apply in order, starting from the first
+/// token.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceTier {
+    pub supply: u64,
+    pub price: Uint128,
+}
+
+/// Allowlist-only sale window preceding the public sale.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Presale {
+    pub end_time: Timestamp,
+    /// Price for allowlisted buyers without an entry-specific price. Falls back to the
+    /// public price when unset.
+    pub price: Option<Uint128>,
+}
+
+/// Soft cap and deadline for a crowdfunded drop. Until the owner settles it, tokens mint into
+/// this contract's custody and payments stay escrowed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Crowdfund {
+    /// Tokens that must be sold for the drop to go ahead.
+    pub soft_cap: u64,
+    /// Sales stop at this time; buyers may claim refunds afterwards if `soft_cap` was missed.
+    pub deadline: Timestamp,
+}
+
+/// Holding requirement for minting at all.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MintGate {
+    /// At least one token of `collection`.
+    Cw721 { collection: Addr },
+    /// A `token` balance of at least `min_balance`.
+    Cw20 { token: Addr, min_balance: Uint128 },
+    /// Membership of the cw4 `group`, with a weight of at least `min_weight` when set.
+    Cw4 {
+        group: Addr,
+        min_weight: Option<u64>,
+    },
+}
+
+/// cw4 group whose members get `weight_per_mint` voting weight for every token they bought.
+/// This contract must be the group's admin.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Membership {
+    pub group: Addr,
+    pub weight_per_mint: u64,
+}
+
+/// Oraiswap-compatible router that treasury withdrawals are swapped through into `ask`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapRoute {
+    pub router: Addr,
+    pub ask: Currency,
+    /// Shortfall from the router's simulated output a swap may end up with.
+    pub max_slippage_bps: u16,
+}
+
+/// Oraichain AI oracle asked to generate every minted token's metadata. Its callback sets
+/// the token URI.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AiOracle {
+    pub contract: Addr,
+    pub service: String,
+    /// Executors that must agree on a result.
+    pub threshold: u64,
+}
+
+/// Discount for buyers holding at least one token of another cw721 collection.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PartnerDiscount {
+    pub collection: Addr,
+    pub discount_bps: u16,
+}
+
+/// Share of each mint payment forwarded to `collector`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProtocolFee {
+    pub collector: Addr,
+    pub bps: u16,
+}
+
+/// A pending `CommitMint`: `sha256(address ++ secret)` and the height it was made at.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintCommitment {
+    pub commitment: HexBinary,
+    pub height: u64,
+}
+
+/// Latest commitment of each address. Revealing consumes it.
+pub const MINT_COMMITMENTS: Map<&Addr, MintCommitment> = Map::new("mint_commitments");
+
+/// Gatekeeper permit nonces already used, per address.
+pub const USED_PERMITS: Map<(&Addr, u64), Empty> = Map::new("used_permits");
+
+/// Addresses allowed to submit relayed mints.
+pub const RELAYERS: Map<&Addr, Empty> = Map::new("relayers");
+
+/// Public key each address signs its relayed mint intents with.
+pub const INTENT_KEYS: Map<&Addr, Binary> = Map::new("intent_keys");
+
+/// Relayed intent nonces already used, per recipient.
+pub const USED_INTENTS: Map<(&Addr, u64), Empty> = Map::new("used_intents");
+
+/// Nonces of the signed admin payloads already executed.
+pub const USED_ADMIN_NONCES: Map<u64, Empty> = Map::new("used_admin_nonces");
+
+/// Membership-style expiry for tokens of the main sale. Each token starts with `period`
+/// seconds and `Renew` adds another `period` for `price` of the payment cw20.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Renewal {
+    pub price: Uint128,
+    pub period: u64,
+    /// Lets the owner revoke expired tokens, which can then no longer be renewed.
+    pub revocable: bool,
+}
+
+/// Expiry of each token minted while `renewal` is set.
+pub const TOKEN_EXPIRY: Map<u64, Timestamp> = Map::new("token_expiry");
+
+pub const REVOKED_TOKENS: Map<u64, Empty> = Map::new("revoked_tokens");
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AiMetadataStatus {
+    Pending,
+    Completed,
+}
+
+/// Tokens whose metadata was requested from the AI oracle.
+pub const AI_METADATA: Map<u64, AiMetadataStatus> = Map::new("ai_metadata");
+
+/// Every value `token_code_id` has held, oldest first. Unset until it first changes.
+pub const TOKEN_CODE_IDS: Item<Vec<u64>> = Item::new("token_code_ids");
+
+/// What was paid for a sale mint, by whom and for whom, kept for accounting.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Receipt {
+    pub payer: Addr,
+    pub recipient: Addr,
+    pub amount: Uint128,
+    pub currency: Currency,
+    pub phase_id: Option<u8>,
+    pub height: u64,
+}
+
+pub const RECEIPTS: Map<u64, Receipt> = Map::new("receipts");
+
+/// Explicit prices for individual sale indexes, charged instead of the default pricing.
+pub const TOKEN_PRICES: Map<u64, Uint128> = Map::new("token_prices");
+
+/// Another cw721 collection whose tokens are accepted in exchange for mints: every
+/// `tokens_per_mint` tokens sent to this contract mint one token to the sender.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TradeIn {
+    pub collection: Addr,
+    pub tokens_per_mint: u32,
+    /// Burn the traded-in tokens instead of forwarding them to the treasury (or the owner).
+    pub burn: bool,
+}
+
+/// Traded-in tokens not yet exchanged, keyed by collection and holder.
+pub const TRADE_IN_DEPOSITS: Map<(&Addr, &Addr), Vec<String>> = Map::new("trade_in_deposits");
+
+/// Ticket sale for the chance to mint. After `end_time` a seed is drawn, which decides the
+/// `winners` tickets that mint at `ticket_price`; every other ticket is refunded.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Raffle {
+    pub end_time: Timestamp,
+    pub ticket_price: Uint128,
+    pub winners: u32,
+    pub tickets_sold: u32,
+    /// Set while the draw waits for its randomness beacon.
+    pub drawing: bool,
+    pub seed: Option<HexBinary>,
+}
+
+impl Raffle {
+    /// Winners are the tickets a seeded permutation of all sold tickets moves into the first
+    /// `winners` slots, so each ticket wins with the same odds and can be checked on its own.
+    pub fn is_winner(&self, ticket: u32) -> bool {
+        let seed = match &self.seed {
+            Some(seed) if seed.len() >= 16 => seed,
+            _ => return false,
+        };
+        let sold = self.tickets_sold as u64;
+        if sold == 0 {
+            return false;
+        }
+        let offset = u64::from_be_bytes(seed[0..8].try_into().unwrap()) % sold;
+        let mut stride = (u64::from_be_bytes(seed[8..16].try_into().unwrap()) % sold).max(1);
+        while gcd(stride, sold) != 1 {
+            stride += 1;
+        }
+        let slot = (ticket as u128 * stride as u128 + offset as u128) % sold as u128;
+        slot < self.winners as u128
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+pub const RAFFLE: Item<Raffle> = Item::new("raffle");
+
+/// Tickets bought by each address, until claimed.
+pub const RAFFLE_TICKETS: Map<&Addr, Vec<u32>> = Map::new("raffle_tickets");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuctionBid {
+    pub bidder: Addr,
+    pub amount: Uint128,
+}
+
+/// English auction of a single token outside the sale's id sequence, paid in the primary
+/// cw20. Each new bid must beat the highest one, which is refunded.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Auction {
+    pub token_uri: String,
+    pub reserve_price: Uint128,
+    pub end_time: Timestamp,
+    pub highest_bid: Option<AuctionBid>,
+    pub settled: bool,
+}
+
+pub const AUCTIONS: Map<&str, Auction> = Map::new("auctions");
+
+/// Total of the primary cw20 burned through `burn_bps`.
+pub const BURNED: Item<Uint128> = Item::new("burned");
+
+/// Creator entitled to `bps` of every withdrawal.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Creator {
+    pub address: Addr,
+    pub bps: u16,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PaymentSplit {
+    pub address: Addr,
+    pub weight: u32,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
+    pub owner: Addr,
+    pub cw20_address: Addr,
+    pub cw721_address: Option<Addr>,
+    pub max_tokens: u64,
+    pub unit_price: Uint128,
+    pub name: String,
+    pub symbol: String,
+    pub token_uri: String,
+    pub extension: Extension,
+    /// Mint counter. This and the other `MintCounters` fields live in `MINT_COUNTERS`.
+    #[serde(default, skip_serializing)]
+    pub unused_token_id: u64,
+    pub treasury: Option<Addr>,
+    pub paused: bool,
+    pub start_time: Option<Timestamp>,
+    pub end_time: Option<Timestamp>,
+    pub token_uri_mode: TokenUriMode,
+    pub shuffle_token_ids: bool,
+    pub reserved_supply: Option<u64>,
+    #[serde(default, skip_serializing)]
+    pub reserved_minted: u64,
+    pub strict_payment: bool,
+    pub mint_limit_per_address: Option<u32>,
+    /// Code id used to instantiate the child cw721. Unknown (0) for instances migrated from 0.1.
+    pub token_code_id: u64,
+    /// Error returned by the last failed child cw721 instantiation, if any.
+    pub cw721_instantiate_error: Option<String>,
+    pub price_curve: Option<PriceCurve>,
+    /// Supply milestones with their own price. Tokens past the last tier use `unit_price`.
+    pub price_tiers: Vec<PriceTier>,
+    pub presale: Option<Presale>,
+    pub referral_bps: Option<u16>,
+    /// Weighted recipients of withdrawn proceeds. Replaces the treasury when non-empty.
+    pub payment_splits: Vec<PaymentSplit>,
+    pub onchain_metadata: bool,
+    pub placeholder_uri: Option<String>,
+    pub revealed: bool,
+    /// Set by `Freeze`. Locks token URIs, extensions, prices and supply for good.
+    pub frozen: bool,
+    pub refund_bps: Option<u16>,
+    /// Cw20 paid out through burn-to-refund.
+    pub total_refunded: Uint128,
+    pub upgrade_rule: Option<UpgradeRule>,
+    pub upgrades_minted: u32,
+    pub market_fee_bps: u16,
+    pub crowdfund: Option<Crowdfund>,
+    /// Tokens currently escrowed or claimed under the crowdfund.
+    pub crowdfund_sold: u64,
+    pub crowdfund_settled: bool,
+    pub price_oracle: Option<PriceOracle>,
+    pub voucher_signer: Option<Binary>,
+    pub max_mints_per_tx: Option<u32>,
+    pub max_mints_per_block: Option<u32>,
+    pub cw721_salt: Option<Binary>,
+    pub cw721_gas_limit: Option<u64>,
+    pub cw721_reply_on: ReplyOn,
+    /// Only channels opened with this counterparty port may send mint packets; `None` disables IBC minting.
+    pub ibc_counterparty_port: Option<String>,
+    /// Connection whose light client tracks the counterparty chain; required with the port.
+    #[serde(default)]
+    pub ibc_connection_id: Option<String>,
+    /// Counterparty channel mint packets must come from, when pinned to one.
+    #[serde(default)]
+    pub ibc_counterparty_channel: Option<String>,
+    pub ics721_bridge: Option<Ics721Bridge>,
+    pub soulbound: bool,
+    pub cw721_admin: Option<Addr>,
+    pub cw721_label: String,
+    pub cw721_funds: Vec<Coin>,
+    /// Set by `Shutdown`; no further mints are accepted.
+    pub closed: bool,
+    /// Buyers may pass their own `Metadata` in `MintOptions`. Requires `onchain_metadata`.
+    pub custom_metadata: bool,
+    /// Unlimited supply: `max_tokens` is 0 and only the mint counter advances.
+    pub open_edition: bool,
+    /// Minted count recorded when a timed open edition closes at `end_time`.
+    pub final_supply: Option<u64>,
+    pub supply_governor: Option<Addr>,
+    pub waitlist: bool,
+    /// Primary-token payments held for waitlisted buyers; never withdrawn as proceeds.
+    pub waitlist_escrow: Uint128,
+    pub native_denom: Option<String>,
+    /// Flat per-token price in `native_denom`. `MintNative` charges the public price when unset.
+    pub native_price: Option<Uint128>,
+    pub creators: Vec<Creator>,
+    /// Number of addresses on the global `ALLOWLIST`.
+    pub allowlist_count: u32,
+    /// Root of the Merkle allowlist. Leaves are `sha256(address)`, pairs are hashed sorted.
+    pub merkle_root: Option<Binary>,
+    /// Bumped on every `UpdateMerkleRoot`; proofs must name the current version.
+    pub merkle_version: u32,
+    pub partner_discount: Option<PartnerDiscount>,
+    pub mint_gate: Option<MintGate>,
+    pub staking: Option<Staking>,
+    /// Unpaid staking rewards held by this contract.
+    pub staking_pool: Uint128,
+    pub vesting: Option<Vesting>,
+    pub reveal_escrow: bool,
+    pub token_id_template: Option<TokenIdTemplate>,
+    /// Defers public mints until the provider's beacon arrives.
+    pub randomness: Option<Randomness>,
+    /// Tokens paid for but waiting for a randomness beacon.
+    #[serde(default, skip_serializing)]
+    pub pending_mints: u64,
+    /// Distinct addresses that have minted.
+    #[serde(default, skip_serializing)]
+    pub unique_minters: u64,
+    /// Set by `TransferMinter`; the child cw721 has a new minter and this contract no longer mints.
+    pub retired: bool,
+    /// Set by `RenounceOwnership`; every owner-only path is disabled for good.
+    pub renounced: bool,
+    /// Child mints rejected and refunded in `reply_mint`. Their ids are not reissued.
+    #[serde(default, skip_serializing)]
+    pub failed_mints: u64,
+    /// Unsold supply of drop rounds, kept out of the main sale.
+    pub drop_reserved: u64,
+    /// Cut of every non-escrowed payment forwarded to the hosting platform at mint time.
+    pub protocol_fee: Option<ProtocolFee>,
+    /// Share of every primary cw20 payment burned at mint time instead of accrued.
+    pub burn_bps: Option<u16>,
+    /// `TokenInfo` of `cw20_address`, read when it is set; `None` for older configs.
+    pub cw20_symbol: Option<String>,
+    pub cw20_decimals: Option<u8>,
+    /// Blocks a `CommitMint` must age before it can be revealed. When set, mints only go
+    /// through `CommitMint` and the `RevealMint` hook.
+    pub commit_reveal_blocks: Option<u64>,
+    /// Compressed secp256k1 key whose `MintPermit` every paid mint needs, when set.
+    pub gatekeeper: Option<Binary>,
+    /// Compressed secp256k1 key whose signed `AdminPayload`s anyone may broadcast. Cleared
+    /// when ownership changes.
+    pub owner_key: Option<Binary>,
+    pub renewal: Option<Renewal>,
+    pub trade_in: Option<TradeIn>,
+    /// Supply held back for raffle winners until they claim.
+    pub raffle_reserved: u64,
+    /// Ticket payments of the primary cw20 held until their holders claim.
+    pub raffle_escrow: Uint128,
+    /// Highest auction bids held until their auctions settle.
+    pub auction_escrow: Uint128,
+    /// Detected for linked collections; configures `TransferMinter`.
+    #[serde(default)]
+    pub cw721_version: Cw721Version,
+    pub membership: Option<Membership>,
+    /// Set with `SetSwapRoute`; treasury withdrawals of other tokens are swapped into its `ask`.
+    pub swap_route: Option<SwapRoute>,
+    pub ai_oracle: Option<AiOracle>,
+    /// Prefixes a buyer-supplied `MintOptions::token_uri` must start with. Empty disables them.
+    #[serde(default)]
+    pub allowed_uri_prefixes: Vec<String>,
+    /// Primary-token deposits held for `MintFromBalance`; never withdrawn as proceeds.
+    #[serde(default)]
+    pub deposit_escrow: Uint128,
+}
+
+impl Config {
+    /// The cw721 token id of mint `index`.
+    pub fn token_id(&self, index: u64) -> String {
+        match &self.token_id_template {
+            Some(template) => template.format(index),
+            None => index.to_string(),
+        }
+    }
+
+    pub fn parse_token_id(&self, token_id: &str) -> Option<u64> {
+        match &self.token_id_template {
+            Some(template) => template.parse(token_id),
+            None => token_id.parse().ok(),
+        }
+    }
+
+    /// Tokens the child cw721 actually minted from the sale's id sequence.
+    pub fn minted(&self) -> u64 {
+        self.unused_token_id - self.failed_mints
+    }
+
+    /// Tokens still available. Open editions are only bounded by the id space until finalized.
+    /// Ids of failed child mints are never reissued, so they count as burned supply.
+    pub fn remaining_supply(&self) -> u64 {
+        if self.final_supply.is_some() {
+            0
+        } else if self.open_edition {
+            u64::MAX.saturating_sub(self.unused_token_id + self.pending_mints)
+        } else {
+            self.max_tokens.saturating_sub(
+                self.unused_token_id
+                    + self.pending_mints
+                    + self.drop_reserved
+                    + self.raffle_reserved,
+            )
+        }
+    }
+}
+
+/// Format of cw721 token ids: `prefix`, the mint index zero-padded to `width` digits, then
+/// `suffix`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenIdTemplate {
+    pub prefix: String,
+    pub suffix: String,
+    pub width: u8,
+}
+
+impl TokenIdTemplate {
+    pub fn format(&self, index: u64) -> String {
+        format!(
+            "{}{:0width$}{}",
+            self.prefix,
+            index,
+            self.suffix,
+            width = self.width as usize
+        )
+    }
+
+    /// The mint index of `token_id`, if it is exactly what `format` produces for it.
+    pub fn parse(&self, token_id: &str) -> Option<u64> {
+        let digits = token_id
+            .strip_prefix(&self.prefix)?
+            .strip_suffix(&self.suffix)?;
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let index = digits.parse().ok()?;
+        (self.format(index) == token_id).then_some(index)
+    }
+}
+
+pub const CONFIG: ConfigStore = ConfigStore::new("config", "mint_counters");
+
+/// `Config` fields every mint bumps. They are stored in their own item, so a plain mint
+/// rewrites these few bytes instead of the whole `Config`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct MintCounters {
+    pub unused_token_id: u64,
+    pub reserved_minted: u64,
+    pub pending_mints: u64,
+    pub unique_minters: u64,
+    pub failed_mints: u64,
+}
+
+/// Storage for `Config` with the `MintCounters` split out. The rest of the configuration is
+/// only written when it actually changed. Configs saved before the split still carry the
+/// counters inline; they are read from there until the first save.
+pub struct ConfigStore<'a> {
+    config: Item<'a, Config>,
+    counters: Item<'a, MintCounters>,
+}
+
+impl<'a> ConfigStore<'a> {
+    pub const fn new(config_key: &'a str, counters_key: &'a str) -> Self {
+        ConfigStore {
+            config: Item::new(config_key),
+            counters: Item::new(counters_key),
+        }
+    }
+
+    pub fn load(&self, storage: &dyn Storage) -> StdResult<Config> {
+        let mut config = self.config.load(storage)?;
+        if let Some(counters) = self.counters.may_load(storage)? {
+            config.unused_token_id = counters.unused_token_id;
+            config.reserved_minted = counters.reserved_minted;
+            config.pending_mints = counters.pending_mints;
+            config.unique_minters = counters.unique_minters;
+            config.failed_mints = counters.failed_mints;
+        }
+        Ok(config)
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
+        let counters = MintCounters {
+            unused_token_id: config.unused_token_id,
+            reserved_minted: config.reserved_minted,
+            pending_mints: config.pending_mints,
+            unique_minters: config.unique_minters,
+            failed_mints: config.failed_mints,
+        };
+        self.counters.save(storage, &counters)?;
+        let data = to_vec(config)?;
+        if storage.get(self.config.as_slice()).as_deref() != Some(data.as_slice()) {
+            storage.set(self.config.as_slice(), &data);
+        }
+        Ok(())
+    }
+
+    pub fn update<E>(
+        &self,
+        storage: &mut dyn Storage,
+        action: impl FnOnce(Config) -> Result<Config, E>,
+    ) -> Result<Config, E>
+    where
+        E: From<StdError>,
+    {
+        let config = action(self.load(storage)?)?;
+        self.save(storage, &config)?;
+        Ok(config)
+    }
+}
+
+/// `Config` as stored by 0.1.x releases, before the treasury was introduced.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigV0_1 {
+    pub owner: Addr,
+    pub cw20_address: Addr,
+    pub cw721_address: Option<Addr>,
+    pub max_tokens: u32,
+    pub unit_price: Uint128,
+    pub name: String,
+    pub symbol: String,
+    pub token_uri: String,
+    pub extension: Extension,
+    pub unused_token_id: u32,
+}
+
+pub const CONFIG_V0_1: Item<ConfigV0_1> = Item::new("config");
+
+/// `Config` as stored by 0.2.x releases: the 0.1 fields plus the treasury.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigV0_2 {
     pub owner: Addr,
     pub cw20_address: Addr,
     pub cw721_address: Option<Addr>,
@@ -18,6 +622,650 @@ pub struct Config {
     pub token_uri: String,
     pub extension: Extension,
     pub unused_token_id: u32,
+    pub treasury: Option<Addr>,
+}
+
+pub const CONFIG_V0_2: Item<ConfigV0_2> = Item::new("config");
+
+// Maps keyed by token id as stored by 0.2.x releases, before token ids were widened to u64.
+pub const SHUFFLED_IDS_V0_2: Map<u32, u32> = Map::new("shuffled_ids");
+pub const MINTS_V0_2: Map<u32, MintRecord> = Map::new("mints");
+pub const TOKEN_METADATA_V0_2: Map<u32, Metadata> = Map::new("token_metadata");
+pub const GIFTS_V0_2: Map<u32, Gift> = Map::new("gifts");
+pub const ESCROWED_PAYMENTS_V0_2: Map<u32, EscrowedPayment> = Map::new("escrowed_payments");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOwner {
+    pub owner: Addr,
+    pub expiry: Option<Expiration>,
+}
+
+pub const PENDING_OWNER: Item<PendingOwner> = Item::new("pending_owner");
+
+/// A replacement `cw20_address` scheduled by `UpdatePaymentToken`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingPaymentToken {
+    pub address: Addr,
+    pub effective_at: Timestamp,
+}
+
+pub const PENDING_PAYMENT_TOKEN: Item<PendingPaymentToken> = Item::new("pending_payment_token");
+
+/// Sparse Fisher-Yates table for shuffled token ids. Slot `i` of the unminted id pool
+/// holds `i` unless an entry says otherwise.
+pub const SHUFFLED_IDS: Map<u64, u64> = Map::new("shuffled_ids");
+
+/// Number of tokens bought by each address.
+pub const MINTS_PER_ADDRESS: Map<&Addr, u32> = Map::new("mints_per_address");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintRecord {
+    pub buyer: Addr,
+    pub time: Timestamp,
+    /// Block height of the mint; zero for records written before it was tracked.
+    #[serde(default)]
+    pub height: u64,
+    /// What the buyer paid for this token; zero for owner and IBC mints.
+    #[serde(default)]
+    pub price: Uint128,
+    /// Part of `price` already paid out as protocol fee, burn or referral reward, and so kept
+    /// back when a failed mint is refunded.
+    #[serde(default)]
+    pub paid_out: Uint128,
+    /// Cw20 the token was paid in, when other than `cw20_address`.
+    #[serde(default)]
+    pub payment_token: Option<Addr>,
+    /// Native denom the token was paid in, for `MintNative` mints.
+    #[serde(default)]
+    pub denom: Option<String>,
+    /// Replacement URI set with `UpdateTokenUri`, if any.
+    #[serde(default)]
+    pub token_uri: Option<String>,
+    #[serde(default)]
+    pub uri_updated: Option<Timestamp>,
+    /// Memo the buyer attached to the mint, e.g. to keep it as a donation receipt.
+    #[serde(default)]
+    pub memo: Option<String>,
+}
+
+impl MintRecord {
+    /// What the buyer paid in, given the sale's primary cw20.
+    pub fn currency(&self, cw20_address: &Addr) -> Currency {
+        match (&self.payment_token, &self.denom) {
+            (Some(token), _) => Currency::Cw20(token.clone()),
+            (None, Some(denom)) => Currency::Native(denom.clone()),
+            (None, None) => Currency::Cw20(cw20_address.clone()),
+        }
+    }
+}
+
+pub struct MintIndexes<'a> {
+    pub buyer: MultiIndex<'a, Addr, MintRecord, u64>,
+    /// Block time of the mint, in seconds.
+    pub time: MultiIndex<'a, u64, MintRecord, u64>,
+}
+
+impl<'a> IndexList<MintRecord> for MintIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<MintRecord>> + '_> {
+        let v: Vec<&dyn Index<MintRecord>> = vec![&self.buyer, &self.time];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Every mint performed by this contract, keyed by token id and indexed by buyer and time.
+/// The buyer is whoever the token was minted to, regardless of later cw721 transfers.
+pub fn mints<'a>() -> IndexedMap<'a, u64, MintRecord, MintIndexes<'a>> {
+    let indexes = MintIndexes {
+        buyer: MultiIndex::new(|_pk, record| record.buyer.clone(), "mints", "mints__buyer"),
+        time: MultiIndex::new(|_pk, record| record.time.seconds(), "mints", "mints__time"),
+    };
+    IndexedMap::new("mints", indexes)
+}
+
+/// Largest royalty that can be configured, in basis points.
+pub const MAX_ROYALTY_BPS: u16 = 10_000;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Royalty {
+    pub payment_address: Addr,
+    pub bps: u16,
+}
+
+pub const ROYALTY: Item<Royalty> = Item::new("royalty");
+
+/// Collection-level metadata for marketplaces, modelled on sg721's `CollectionInfo`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollectionInfo {
+    pub creator: Addr,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub external_link: Option<String>,
+    pub explicit_content: bool,
+}
+
+pub const COLLECTION_INFO: Item<CollectionInfo> = Item::new("collection_info");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllowlistEntry {
+    /// Discounted unit price for this address during the presale.
+    pub price: Option<Uint128>,
+    /// Total tokens this address may buy while sales are restricted to the allowlist.
+    #[serde(default)]
+    pub mint_limit: Option<u32>,
+    /// The entry no longer grants access or discounts from this time on.
+    #[serde(default)]
+    pub expires: Option<Timestamp>,
+}
+
+impl AllowlistEntry {
+    pub fn is_expired(&self, time: Timestamp) -> bool {
+        self.expires.is_some_and(|expires| time >= expires)
+    }
+}
+
+pub const ALLOWLIST: Map<&Addr, AllowlistEntry> = Map::new("allowlist");
+
+/// Allowlist entry scoped to one phase.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PhaseAllowlistEntry {
+    /// Unit price for this address during the phase, overriding the phase price.
+    pub price: Option<Uint128>,
+    /// Tokens this address may buy during the phase. The phase's wallet cap still applies.
+    pub mint_limit: Option<u32>,
+}
+
+/// Per-phase allowlists, keyed by phase id and address. Entries admit the address to an
+/// allowlist-only phase alongside the global `ALLOWLIST`.
+pub const PHASE_ALLOWLISTS: Map<(u8, &Addr), PhaseAllowlistEntry> = Map::new("phase_allowlists");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct ReferralStats {
+    pub referrals: u32,
+    pub earned: Uint128,
+}
+
+pub const REFERRALS: Map<&Addr, ReferralStats> = Map::new("referrals");
+
+/// A scheduled sale window with its own price and per-wallet cap.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Phase {
+    pub name: String,
+    pub start_time: Timestamp,
+    /// Open-ended when unset.
+    pub end_time: Option<Timestamp>,
+    /// Unit price during this phase. Falls back to the regular pricing when unset.
+    pub price: Option<Uint128>,
+    /// Tokens each address may buy during this phase.
+    pub per_wallet_cap: Option<u32>,
+    /// Only addresses on the allowlist may mint during this phase.
+    pub allowlist_only: bool,
+    /// Makes this a free phase: up to this many tokens in total may be claimed with
+    /// `Claim {}`, and paid mints are refused. Requires a zero `price`; `per_wallet_cap`
+    /// then limits claims per address.
+    #[serde(default)]
+    pub free_supply: Option<u64>,
+}
+
+impl Phase {
+    pub fn is_active(&self, time: Timestamp) -> bool {
+        self.start_time <= time && self.end_time.is_none_or(|end| time < end)
+    }
+}
+
+/// Mint schedule. When any phase is defined, mints are only accepted while one is active.
+pub const PHASES: Map<u8, Phase> = Map::new("phases");
+
+/// Number of tokens bought by each address, per phase.
+pub const PHASE_MINTS: Map<(u8, &Addr), u32> = Map::new("phase_mints");
+
+/// Tokens claimed for free during each free phase. Claims are tracked separately from paid
+/// mints and don't count toward `mint_limit_per_address`.
+pub const PHASE_CLAIMED: Map<u8, u64> = Map::new("phase_claimed");
+
+/// A single trait in the cw721 metadata-onchain `Metadata` extension.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Trait {
+    pub display_type: Option<String>,
+    pub trait_type: String,
+    pub value: String,
+}
+
+/// The cw721 metadata-onchain extension, mirrored here so this contract can mint into such
+/// collections without depending on that crate.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct Metadata {
+    pub image: Option<String>,
+    pub image_data: Option<String>,
+    pub external_url: Option<String>,
+    pub description: Option<String>,
+    pub name: Option<String>,
+    pub attributes: Option<Vec<Trait>>,
+    pub background_color: Option<String>,
+    pub animation_url: Option<String>,
+    pub youtube_url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WeightedValue {
+    pub value: String,
+    pub weight: u32,
+}
+
+/// A trait drawn for every mint: one of `values`, with probability proportional to its
+/// weight.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TraitCategory {
+    pub trait_type: String,
+    pub values: Vec<WeightedValue>,
+}
+
+/// Randomness provider (a Nois proxy) drawing the beacon for deferred mints. `fee` is paid
+/// from this contract's balance with every request.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Randomness {
+    pub proxy: Addr,
+    pub fee: Vec<Coin>,
+}
+
+/// A paid mint waiting for its randomness beacon.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingMint {
+    pub buyer: Addr,
+    pub recipient: Addr,
+    pub quantity: u32,
+    pub unit_paid: Uint128,
+    /// Cw20 the mint was paid in, when other than `cw20_address`.
+    pub payment_token: Option<Addr>,
+    /// Protocol fee, burn and referral reward already paid out of the payment.
+    #[serde(default)]
+    pub paid_out: Uint128,
+}
+
+/// Mints waiting for their beacon, keyed by randomness job id.
+pub const PENDING_MINTS: Map<&str, PendingMint> = Map::new("pending_mints");
+
+pub const RANDOMNESS_JOB_COUNT: Item<u64> = Item::new("randomness_job_count");
+
+/// The most recent beacon. Token id shuffling and trait draws mix it into their seed.
+pub const LATEST_RANDOMNESS: Item<HexBinary> = Item::new("latest_randomness");
+
+/// Traits assigned at mint in `onchain_metadata` collections. Empty when unset.
+pub const TRAIT_TABLE: Item<Vec<TraitCategory>> = Item::new("trait_table");
+
+/// Metadata prepared by the owner ahead of minting, keyed by token id. Tokens without an
+/// entry get generated metadata when `onchain_metadata` is enabled.
+pub const TOKEN_METADATA: Map<u64, Metadata> = Map::new("token_metadata");
+
+/// Burn-to-upgrade recipe: holders who return `tokens_required` tokens receive one
+/// upgraded token.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UpgradeRule {
+    pub tokens_required: u32,
+    pub token_uri: String,
+    /// Extension for upgraded tokens in `onchain_metadata` collections.
+    pub metadata: Option<Metadata>,
+}
+
+/// Tokens returned towards an upgrade, per holder, until enough are collected.
+pub const UPGRADE_DEPOSITS: Map<&Addr, Vec<String>> = Map::new("upgrade_deposits");
+
+/// A token held by this contract and offered for sale by its previous owner.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Listing {
+    pub seller: Addr,
+    pub price: Uint128,
+}
+
+/// Secondary-market listings keyed by token id.
+pub const LISTINGS: Map<&str, Listing> = Map::new("listings");
+
+/// Tokens held in custody for a buyer while a crowdfund is unsettled, with what they paid.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct Pledge {
+    pub token_ids: Vec<u64>,
+    pub paid: Uint128,
+}
+
+pub const PLEDGES: Map<&Addr, Pledge> = Map::new("pledges");
+
+/// Cw20 tokens accepted for mints besides `cw20_address`, with their flat unit price.
+pub const PAYMENT_TOKENS: Map<&Addr, Uint128> = Map::new("payment_tokens");
+
+/// Addresses barred from buying or receiving mints.
+pub const BLOCKLIST: Map<&Addr, Empty> = Map::new("blocklist");
+
+/// Contracts notified with a `MintHookExecuteMsg` after every mint.
+pub const MINT_HOOKS: Map<&Addr, Empty> = Map::new("mint_hooks");
+
+/// Upper bound on `MINT_HOOKS`, keeping the per-mint message count bounded.
+pub const MAX_MINT_HOOKS: usize = 10;
+
+/// Day-to-day actions the owner has delegated to an operator key. Operators can never
+/// change ownership or the owner-only configuration.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct OperatorPerms {
+    /// `Pause` and `Resume`.
+    pub can_pause: bool,
+    /// Presale, phase and Merkle allowlist updates.
+    pub can_manage_allowlist: bool,
+    /// `Airdrop` and `ReserveMint`.
+    pub can_airdrop: bool,
+    /// `UpdateUnitPrice`, `SetPriceTiers` and `UpdatePrices`.
+    #[serde(default)]
+    pub can_update_price: bool,
+    /// `Withdraw` to the configured payout destinations; choosing a `recipient` stays with
+    /// the owner.
+    #[serde(default)]
+    pub can_withdraw: bool,
+}
+
+impl OperatorPerms {
+    pub fn all() -> Self {
+        OperatorPerms {
+            can_pause: true,
+            can_manage_allowlist: true,
+            can_airdrop: true,
+            can_update_price: true,
+            can_withdraw: true,
+        }
+    }
+
+    pub fn allows(&self, permission: Permission) -> bool {
+        match permission {
+            Permission::Pause => self.can_pause,
+            Permission::ManageAllowlist => self.can_manage_allowlist,
+            Permission::Airdrop => self.can_airdrop,
+            Permission::UpdatePrice => self.can_update_price,
+            Permission::Withdraw => self.can_withdraw,
+        }
+    }
+}
+
+/// A delegable action, as granted by the matching `OperatorPerms` flag.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Permission {
+    Pause,
+    ManageAllowlist,
+    Airdrop,
+    UpdatePrice,
+    Withdraw,
+}
+
+pub const OPERATORS: Map<&Addr, OperatorPerms> = Map::new("operators");
+
+/// USD-denominated pricing resolved through an on-chain price feed at mint time.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceOracle {
+    pub address: Addr,
+    /// Price per token in the feed's smallest USD unit.
+    pub usd_price: Uint128,
+    /// Rates older than this many seconds are rejected.
+    pub max_staleness: u64,
+    /// Accepted deviation of the paid amount from the quoted price, in basis points.
+    pub tolerance_bps: u16,
+}
+
+/// ICS-721 bridge that freshly minted tokens can be forwarded through to another chain.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Ics721Bridge {
+    pub bridge: Addr,
+    pub channel_id: String,
+    /// Packet timeout, relative to the mint block.
+    pub timeout_seconds: u64,
+}
+
+/// Most attributes accepted in buyer-supplied metadata.
+pub const MAX_METADATA_ATTRIBUTES: usize = 32;
+
+/// Longest string field accepted in buyer-supplied metadata, in bytes.
+pub const MAX_METADATA_FIELD_LENGTH: usize = 512;
+
+/// Longest gift memo accepted by `Mint`, in bytes.
+pub const MAX_MEMO_LENGTH: usize = 256;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Gift {
+    pub from: Addr,
+    pub memo: String,
+}
+
+/// Gift memos keyed by token id.
+pub const GIFTS: Map<u64, Gift> = Map::new("gifts");
+
+/// Voucher token ids already redeemed, with the time of redemption.
+pub const REDEEMED_VOUCHERS: Map<&str, Timestamp> = Map::new("redeemed_vouchers");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BlockMints {
+    pub height: u64,
+    pub count: u32,
+}
+
+/// Tokens bought by each address in the last block it minted in. Entries from earlier
+/// blocks are stale and simply overwritten.
+pub const BLOCK_MINTS: Map<&Addr, BlockMints> = Map::new("block_mints");
+
+/// An additional fixed-price drop run by this contract in factory mode, with its own cw721.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Collection {
+    pub name: String,
+    pub symbol: String,
+    pub cw721_address: Option<Addr>,
+    /// Paid in `cw20_address`.
+    pub unit_price: Uint128,
+    pub max_tokens: u64,
+    pub minted: u64,
+    pub token_uri: String,
+}
+
+/// Factory collections keyed by id. The collection configured at instantiation is not part
+/// of this map.
+pub const COLLECTIONS: Map<u32, Collection> = Map::new("collections");
+
+pub const COLLECTION_COUNT: Item<u32> = Item::new("collection_count");
+
+/// An earlier round of the main collection, archived by `CloneCollection`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ArchivedRound {
+    pub cw721_address: Addr,
+    /// Mint indexes `first_token..end_token` were minted on `cw721_address`.
+    pub first_token: u64,
+    pub end_token: u64,
+    pub unit_price: Uint128,
+    pub token_uri: String,
+    pub archived_at: Timestamp,
+}
+
+/// Archived rounds keyed by id, oldest first. The running round is not part of this map.
+pub const ARCHIVED_ROUNDS: Map<u32, ArchivedRound> = Map::new("archived_rounds");
+
+/// Mint credits granted by the owner and not redeemed yet. Credits cannot be transferred.
+pub const MINT_CREDITS: Map<&Addr, u32> = Map::new("mint_credits");
+
+/// Primary-token balances deposited for `MintFromBalance`, per depositor.
+pub const DEPOSITS: Map<&Addr, Uint128> = Map::new("deposits");
+
+/// An extra tranche of the main collection, sold at its own price during its own window.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DropRound {
+    pub name: String,
+    pub start_time: Timestamp,
+    /// Open-ended when unset.
+    pub end_time: Option<Timestamp>,
+    /// Paid in `cw20_address`.
+    pub unit_price: Uint128,
+    pub supply: u64,
+    pub minted: u64,
+    /// Tokens each address may buy in this round.
+    pub per_wallet_cap: Option<u32>,
+}
+
+impl DropRound {
+    pub fn is_active(&self, time: Timestamp) -> bool {
+        self.start_time <= time && self.end_time.is_none_or(|end| time < end)
+    }
+}
+
+/// Drop rounds keyed by id, in the order they were added.
+pub const DROP_ROUNDS: Map<u32, DropRound> = Map::new("drop_rounds");
+
+pub const DROP_ROUND_COUNT: Item<u32> = Item::new("drop_round_count");
+
+/// Number of tokens bought by each address, per drop round.
+pub const DROP_ROUND_MINTS: Map<(u32, &Addr), u32> = Map::new("drop_round_mints");
+
+/// A token deposited by `lender` for rent. `renter` holds usage rights until `expires`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Rental {
+    pub lender: Addr,
+    pub daily_price: Uint128,
+    pub renter: Option<Addr>,
+    pub expires: Option<Timestamp>,
+}
+
+impl Rental {
+    pub fn is_rented(&self, time: Timestamp) -> bool {
+        self.expires.is_some_and(|expires| time < expires)
+    }
+}
+
+/// Open mint channels with their counterparty endpoint.
+pub const IBC_CHANNELS: Map<&str, IbcEndpoint> = Map::new("ibc_channels");
+
+/// Child mints of the `MintPacket` being received. The reply of the last one writes the
+/// acknowledgement.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IbcPendingAck {
+    /// Tokens whose child mint has not replied yet.
+    pub pending: Vec<u64>,
+    /// Tokens whose child mint went through.
+    pub minted: Vec<u64>,
+}
+
+pub const IBC_PENDING_ACK: Item<IbcPendingAck> = Item::new("ibc_pending_ack");
+
+/// Rentable tokens held by this contract, keyed by token id.
+pub const RENTALS: Map<&str, Rental> = Map::new("rentals");
+
+/// Per-block rewards for staked tokens, paid in `reward_token` from the funded pool.
+/// `reward_index` is the reward accrued by one token staked since the start, as of
+/// `index_height`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Staking {
+    pub reward_token: Addr,
+    pub reward_per_block: Uint128,
+    pub reward_index: Uint128,
+    pub index_height: u64,
+}
+
+impl Staking {
+    pub fn index_at(&self, height: u64) -> StdResult<Uint128> {
+        let blocks = height.saturating_sub(self.index_height);
+        Ok(self.reward_index + self.reward_per_block.checked_mul(Uint128::from(blocks))?)
+    }
+}
+
+/// A token staked by `owner`. Rewards accrue from the `reward_index` of the last claim.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Stake {
+    pub owner: Addr,
+    pub staked_height: u64,
+    pub reward_index: Uint128,
+}
+
+/// Release schedule for withdrawals of proceeds. Nothing is released before `start` plus
+/// `cliff_seconds`; everything is released after `start` plus `duration_seconds`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Vesting {
+    pub start: Timestamp,
+    pub cliff_seconds: u64,
+    pub duration_seconds: u64,
+}
+
+impl Vesting {
+    /// The part of `total` released at `time`.
+    pub fn vested(&self, total: Uint128, time: Timestamp) -> Uint128 {
+        let elapsed = time.seconds().saturating_sub(self.start.seconds());
+        if elapsed < self.cliff_seconds {
+            Uint128::zero()
+        } else if elapsed >= self.duration_seconds {
+            total
+        } else {
+            total.multiply_ratio(elapsed, self.duration_seconds)
+        }
+    }
+}
+
+/// Proceeds withdrawn under the vesting schedule, keyed by currency.
+pub const VESTING_WITHDRAWN: Map<&str, Uint128> = Map::new("vesting_withdrawn");
+
+/// Payment for a token held until reveal, refundable to its holder until then.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EscrowedPayment {
+    pub buyer: Addr,
+    pub currency: Currency,
+    pub amount: Uint128,
+}
+
+/// Payments escrowed until reveal, keyed by token id.
+pub const ESCROWED_PAYMENTS: Map<u64, EscrowedPayment> = Map::new("escrowed_payments");
+
+/// Total escrowed until reveal, keyed by currency.
+pub const ESCROW_HELD: Map<&str, Uint128> = Map::new("escrow_held");
+
+/// Staked tokens held by this contract, keyed by token id.
+pub const STAKES: Map<&str, Stake> = Map::new("stakes");
+
+/// Buyer queued for `quantity` tokens after sell-out, with `paid` held in escrow.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WaitlistEntry {
+    pub buyer: Addr,
+    pub quantity: u32,
+    pub paid: Uint128,
+}
+
+/// Waitlist entries keyed by position. Lower positions are filled first.
+pub const WAITLIST: Map<u64, WaitlistEntry> = Map::new("waitlist");
+pub const WAITLIST_NEXT: Item<u64> = Item::new("waitlist_next");
+
+/// A payment asset held by this contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Currency {
+    Cw20(Addr),
+    Native(String),
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Currency::Cw20(address) => write!(f, "{address}"),
+            Currency::Native(denom) => write!(f, "{denom}"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CreatorClaim {
+    pub currency: Currency,
+    pub amount: Uint128,
+}
+
+/// Withdrawn proceeds not yet claimed, keyed by creator and currency.
+pub const CREATOR_CLAIMS: Map<(&Addr, &str), CreatorClaim> = Map::new("creator_claims");
+/// Sum of `CREATOR_CLAIMS` per currency. Excluded from withdrawable balances.
+pub const CLAIMS_OWED: Map<&str, Uint128> = Map::new("claims_owed");
+
+/// Progress of the holder snapshot taken by `SnapshotHolders`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HolderSnapshot {
+    /// Block height at which the snapshot was started.
+    pub height: u64,
+    /// Last child token id read, where the next page resumes.
+    pub last_token: Option<String>,
+    pub complete: bool,
 }
 
-pub const CONFIG: Item<Config> = Item::new("config");
\ No newline at end of file
+pub const HOLDER_SNAPSHOT: Item<HolderSnapshot> = Item::new("holder_snapshot");
+/// Tokens held per address in the holder snapshot.
+pub const SNAPSHOT_HOLDERS: Map<&Addr, u32> = Map::new("snapshot_holders");
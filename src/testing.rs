@@ -0,0 +1,412 @@
+//! cw-multi-test harness running this contract against real cw20-base and cw721-base code.
+
+use anyhow::Result as AnyResult;
+use cosmwasm_std::{
+    to_binary, Addr, DepsMut, Empty, Env, MessageInfo, Response, StdResult, Uint128,
+};
+use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg};
+use cw721::{Cw721QueryMsg, OwnerOfResponse};
+use cw_multi_test::{App, AppResponse, Contract, ContractWrapper, Executor};
+use serde::de::DeserializeOwned;
+
+use crate::msg::{ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MintOptions, QueryMsg};
+
+pub fn contract_moni() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    )
+    .with_reply(crate::contract::reply)
+    .with_sudo(crate::contract::sudo)
+    .with_migrate(crate::contract::migrate);
+    Box::new(contract)
+}
+
+pub fn contract_cw20() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    );
+    Box::new(contract)
+}
+
+pub fn contract_cw721() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        cw721_base::entry::execute,
+        cw721_base::entry::instantiate,
+        cw721_base::entry::query,
+    );
+    Box::new(contract)
+}
+
+/// cw721-base that refuses to mint token "1", as a child holding that id already would.
+pub fn contract_cw721_rejecting_token_1() -> Box<dyn Contract<Empty>> {
+    fn execute(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: cw721_base::ExecuteMsg<cw721_base::Extension, Empty>,
+    ) -> Result<Response, cw721_base::ContractError> {
+        match &msg {
+            cw721_base::ExecuteMsg::Mint(mint) if mint.token_id == "1" => {
+                Err(cw721_base::ContractError::Claimed {})
+            }
+            _ => cw721_base::entry::execute(deps, env, info, msg),
+        }
+    }
+    let contract = ContractWrapper::new(
+        execute,
+        cw721_base::entry::instantiate,
+        cw721_base::entry::query,
+    );
+    Box::new(contract)
+}
+
+/// Minimal `InstantiateMsg` selling `max_tokens` tokens at `unit_price` of `cw20_address`.
+pub fn instantiate_msg(
+    owner: &Addr,
+    cw20_address: &Addr,
+    token_code_id: u64,
+    max_tokens: u64,
+    unit_price: u128,
+) -> InstantiateMsg {
+    InstantiateMsg {
+        owner: owner.clone(),
+        max_tokens,
+        unit_price: Uint128::new(unit_price),
+        name: String::from("Moni"),
+        symbol: String::from("MONI"),
+        token_code_id,
+        cw20_address: cw20_address.clone(),
+        token_uri: String::from("https://ipfs.io/ipfs/Q"),
+        extension: None,
+        treasury: None,
+        start_time: None,
+        end_time: None,
+        token_uri_mode: None,
+        shuffle_token_ids: None,
+        reserved_supply: None,
+        strict_payment: None,
+        cw721_address: None,
+        mint_limit_per_address: None,
+        royalty: None,
+        price_curve: None,
+        presale: None,
+        referral_bps: None,
+        onchain_metadata: None,
+        placeholder_uri: None,
+        refund_bps: None,
+        market_fee_bps: None,
+        crowdfund: None,
+        price_oracle: None,
+        voucher_signer: None,
+        max_mints_per_tx: None,
+        max_mints_per_block: None,
+        cw721_salt: None,
+        cw721_gas_limit: None,
+        cw721_reply_on: None,
+        soulbound: None,
+        cw721_admin: None,
+        cw721_label: None,
+        cw721_funds: None,
+        open_edition: None,
+        supply_governor: None,
+        waitlist: None,
+        native_denom: None,
+        creators: None,
+        partner_discount: None,
+        mint_gate: None,
+        vesting: None,
+        reveal_escrow: None,
+        token_id_template: None,
+        protocol_fee: None,
+        burn_bps: None,
+        commit_reveal_blocks: None,
+        gatekeeper: None,
+        renewal: None,
+        metadata_code_id: None,
+        collection_info: None,
+        cw721_version: None,
+        membership: None,
+        ai_oracle: None,
+    }
+}
+
+type Configure = Box<dyn FnOnce(&mut InstantiateMsg)>;
+
+pub struct SuiteBuilder {
+    max_tokens: u64,
+    unit_price: u128,
+    balances: Vec<Cw20Coin>,
+    configure: Option<Configure>,
+    cw721: Option<Box<dyn Contract<Empty>>>,
+}
+
+impl Default for SuiteBuilder {
+    fn default() -> Self {
+        Self {
+            max_tokens: 10,
+            unit_price: 100,
+            balances: vec![],
+            configure: None,
+            cw721: None,
+        }
+    }
+}
+
+impl SuiteBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_tokens(mut self, max_tokens: u64) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    pub fn with_unit_price(mut self, unit_price: u128) -> Self {
+        self.unit_price = unit_price;
+        self
+    }
+
+    /// Gives `address` a starting balance of the payment cw20.
+    pub fn with_balance(mut self, address: &str, amount: u128) -> Self {
+        self.balances.push(Cw20Coin {
+            address: address.to_string(),
+            amount: Uint128::new(amount),
+        });
+        self
+    }
+
+    /// Adjusts the `InstantiateMsg` before the contract is instantiated.
+    pub fn with_instantiate_msg(
+        mut self,
+        configure: impl FnOnce(&mut InstantiateMsg) + 'static,
+    ) -> Self {
+        self.configure = Some(Box::new(configure));
+        self
+    }
+
+    /// Instantiates the child from `contract` instead of plain cw721-base.
+    pub fn with_cw721(mut self, contract: Box<dyn Contract<Empty>>) -> Self {
+        self.cw721 = Some(contract);
+        self
+    }
+
+    pub fn build(self) -> Suite {
+        let mut app = App::default();
+        let owner = Addr::unchecked("owner");
+
+        let cw20_code_id = app.store_code(contract_cw20());
+        let cw721_code_id = app.store_code(self.cw721.unwrap_or_else(contract_cw721));
+        let moni_code_id = app.store_code(contract_moni());
+
+        let cw20 = app
+            .instantiate_contract(
+                cw20_code_id,
+                owner.clone(),
+                &cw20_base::msg::InstantiateMsg {
+                    name: String::from("Payment token"),
+                    symbol: String::from("PAY"),
+                    decimals: 6,
+                    initial_balances: self.balances,
+                    mint: None,
+                    marketing: None,
+                },
+                &[],
+                "cw20",
+                None,
+            )
+            .unwrap();
+
+        let mut msg = instantiate_msg(
+            &owner,
+            &cw20,
+            cw721_code_id,
+            self.max_tokens,
+            self.unit_price,
+        );
+        if let Some(configure) = self.configure {
+            configure(&mut msg);
+        }
+        let moni = app
+            .instantiate_contract(moni_code_id, owner.clone(), &msg, &[], "moni", None)
+            .unwrap();
+
+        let config: ConfigResponse = app
+            .wrap()
+            .query_wasm_smart(&moni, &QueryMsg::GetConfig {})
+            .unwrap();
+
+        Suite {
+            app,
+            owner,
+            cw20,
+            moni,
+            cw721: config.cw721_address,
+        }
+    }
+}
+
+pub struct Suite {
+    pub app: App,
+    pub owner: Addr,
+    pub cw20: Addr,
+    pub moni: Addr,
+    /// `None` when the child instantiation failed.
+    pub cw721: Option<Addr>,
+}
+
+impl Suite {
+    /// Buys one token by sending `amount` of the payment cw20 from `sender`.
+    pub fn mint_as(&mut self, sender: &str, amount: u128) -> AnyResult<AppResponse> {
+        self.mint_with(sender, amount, MintOptions::default())
+    }
+
+    pub fn mint_with(
+        &mut self,
+        sender: &str,
+        amount: u128,
+        options: MintOptions,
+    ) -> AnyResult<AppResponse> {
+        let msg = Cw20ExecuteMsg::Send {
+            contract: self.moni.to_string(),
+            amount: Uint128::new(amount),
+            msg: to_binary(&Cw20HookMsg::Mint(options))?,
+        };
+        self.app
+            .execute_contract(Addr::unchecked(sender), self.cw20.clone(), &msg, &[])
+    }
+
+    pub fn execute(&mut self, sender: &str, msg: &ExecuteMsg) -> AnyResult<AppResponse> {
+        self.app
+            .execute_contract(Addr::unchecked(sender), self.moni.clone(), msg, &[])
+    }
+
+    pub fn query<T: DeserializeOwned>(&self, msg: &QueryMsg) -> StdResult<T> {
+        self.app.wrap().query_wasm_smart(&self.moni, msg)
+    }
+
+    pub fn config(&self) -> StdResult<ConfigResponse> {
+        self.query(&QueryMsg::GetConfig {})
+    }
+
+    pub fn owner_of(&self, token_id: &str) -> StdResult<String> {
+        let cw721 = self.cw721.as_ref().expect("cw721 not instantiated");
+        let res: OwnerOfResponse = self.app.wrap().query_wasm_smart(
+            cw721,
+            &Cw721QueryMsg::OwnerOf {
+                token_id: token_id.to_string(),
+                include_expired: None,
+            },
+        )?;
+        Ok(res.owner)
+    }
+
+    pub fn balance(&self, address: &str) -> StdResult<u128> {
+        let res: BalanceResponse = self.app.wrap().query_wasm_smart(
+            &self.cw20,
+            &cw20::Cw20QueryMsg::Balance {
+                address: address.to_string(),
+            },
+        )?;
+        Ok(res.balance.u128())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msg::{ProtocolFeeMsg, SupplyInfoResponse};
+
+    #[test]
+    fn mint_end_to_end() {
+        let mut suite = SuiteBuilder::new()
+            .with_max_tokens(2)
+            .with_balance("buyer", 150)
+            .build();
+        assert!(suite.cw721.is_some());
+
+        suite.mint_as("buyer", 120).unwrap();
+        assert_eq!(suite.owner_of("0").unwrap(), "buyer");
+        assert_eq!(suite.balance("buyer").unwrap(), 50);
+        assert_eq!(suite.config().unwrap().tokens_sold, 1);
+
+        suite.mint_as("buyer", 10).unwrap_err();
+    }
+
+    #[test]
+    fn fixed_price_contract_helper() {
+        use crate::helpers::FixedPriceContract;
+
+        let mut suite = SuiteBuilder::new().with_balance("buyer", 100).build();
+        let moni = FixedPriceContract(suite.moni.clone());
+
+        let msg = moni
+            .mint_msg(
+                suite.cw20.clone(),
+                Uint128::new(100),
+                MintOptions::default(),
+            )
+            .unwrap();
+        suite.app.execute(Addr::unchecked("buyer"), msg).unwrap();
+
+        let querier = suite.app.wrap();
+        assert_eq!(moni.supply_info(&querier).unwrap().minted, 1);
+        assert_eq!(moni.mints_of(&querier, "buyer").unwrap().minted, 1);
+        assert_eq!(moni.config(&querier).unwrap().tokens_remaining, 9);
+    }
+
+    #[test]
+    fn failed_child_mint_is_refunded() {
+        let mut suite = SuiteBuilder::new()
+            .with_max_tokens(3)
+            .with_balance("buyer", 300)
+            .with_cw721(contract_cw721_rejecting_token_1())
+            .build();
+
+        let options = MintOptions {
+            quantity: Some(2),
+            ..MintOptions::default()
+        };
+        suite.mint_with("buyer", 200, options).unwrap();
+        assert_eq!(suite.owner_of("0").unwrap(), "buyer");
+        suite.owner_of("1").unwrap_err();
+        assert_eq!(suite.balance("buyer").unwrap(), 200);
+        assert_eq!(suite.balance(suite.moni.as_str()).unwrap(), 100);
+
+        let supply: SupplyInfoResponse = suite.query(&QueryMsg::SupplyInfo {}).unwrap();
+        assert_eq!((supply.minted, supply.failed, supply.remaining), (1, 1, 1));
+    }
+
+    #[test]
+    fn failed_child_mint_keeps_fees_paid_out() {
+        let mut suite = SuiteBuilder::new()
+            .with_max_tokens(3)
+            .with_balance("buyer", 300)
+            .with_instantiate_msg(|msg| {
+                msg.referral_bps = Some(500);
+                msg.protocol_fee = Some(ProtocolFeeMsg {
+                    collector: String::from("collector"),
+                    bps: 1_000,
+                });
+            })
+            .with_cw721(contract_cw721_rejecting_token_1())
+            .build();
+
+        let options = MintOptions {
+            quantity: Some(2),
+            referrer: Some(String::from("referrer")),
+            ..MintOptions::default()
+        };
+        suite.mint_with("buyer", 200, options).unwrap();
+        suite.owner_of("1").unwrap_err();
+        // 20 went to the collector and 10 to the referrer, so the failed token refunds 85.
+        assert_eq!(suite.balance("collector").unwrap(), 20);
+        assert_eq!(suite.balance("referrer").unwrap(), 10);
+        assert_eq!(suite.balance("buyer").unwrap(), 185);
+        assert_eq!(suite.balance(suite.moni.as_str()).unwrap(), 85);
+    }
+}